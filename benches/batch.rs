@@ -0,0 +1,51 @@
+//! Compares per-operation edits with a single batch on a large array.
+//!
+//! Run with `cargo bench --bench batch`.
+
+use keyarray::KeyArray;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const KEYS: u32 = 100_000;
+const EDITS: u32 = 2_000;
+
+fn time(label: &str, f: impl Fn() -> KeyArray<u32>) -> Duration {
+    let mut best = Duration::MAX;
+    for _ in 0..5 {
+        let start = Instant::now();
+        black_box(f());
+        best = best.min(start.elapsed());
+    }
+    println!("{:<12} {:>10.2?}", label, best);
+    best
+}
+
+fn main() {
+    let base = KeyArray::new_with(0..KEYS, KEYS as usize / 2);
+
+    let sequential = time("sequential", || {
+        let mut ka = base.clone();
+        for i in 0..EDITS {
+            ka.remove((i * 37 % (KEYS - i)) as usize);
+            ka.insert((i * 11) as usize, KEYS + i);
+        }
+        ka
+    });
+
+    let batched = time("batched", || {
+        let mut ka = base.clone();
+        {
+            let mut batch = ka.batch();
+            for i in 0..EDITS {
+                batch.remove((i * 37) as usize);
+                batch.insert((i * 11) as usize, KEYS + i);
+            }
+        }
+        ka
+    });
+
+    println!(
+        "speedup      {:>9.1}x",
+        sequential.as_secs_f64() / batched.as_secs_f64()
+    );
+}
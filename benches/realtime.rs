@@ -0,0 +1,38 @@
+//! Cost of reading the current index once per sample, as a DSP loop would.
+//!
+//! Run with `cargo bench --bench realtime`.
+
+use keyarray::sync::SyncKeyArray;
+use keyarray::KeyArray;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const SAMPLES: usize = 10_000_000;
+const GAINS: [f32; 4] = [0.0, 0.25, 0.5, 1.0];
+
+fn time(label: &str, mut index: impl FnMut() -> usize) -> Duration {
+    let mut best = Duration::MAX;
+    for _ in 0..5 {
+        let start = Instant::now();
+        let mut sum = 0.0f32;
+        for _ in 0..SAMPLES {
+            sum += GAINS[index()];
+        }
+        black_box(sum);
+        best = best.min(start.elapsed());
+    }
+    println!("{:<18} {:>6.2} ns/sample", label, best.as_nanos() as f64 / SAMPLES as f64);
+    best
+}
+
+fn main() {
+    let mut keys = KeyArray::new_with(["Mute", "Quiet", "Half", "Full"], 2);
+    let shared = keys.share_index();
+    let mut reader = keys.publish_realtime(|_| 0);
+    let sync = SyncKeyArray::new(keys.clone());
+
+    time("KeyArray", || black_box(&keys).current_index());
+    time("SharedIndex", || black_box(&shared).current_index());
+    time("RtReader", || black_box(&mut reader).index());
+    time("SyncKeyArray", || black_box(&sync).current_index());
+}
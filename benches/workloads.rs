@@ -0,0 +1,17 @@
+//! The standard workloads of `keyarray::bench` at their default sizes.
+//!
+//! Run with `cargo bench --bench workloads --features bench`.
+
+use keyarray::bench::{measure_all, WorkloadConfig};
+
+fn main() {
+    for m in measure_all(&WorkloadConfig::default(), 5) {
+        println!(
+            "{:<18} {:>10.2?} best {:>10.2?} median {:>8.1} ns/op",
+            m.workload.name(),
+            m.best,
+            m.median,
+            m.ns_per_op()
+        );
+    }
+}
@@ -0,0 +1,232 @@
+//! How recently and how often the selection changed.
+//!
+//! Once [`track_activity`](KeyArray::track_activity) is called, every
+//! change of the current key (not of its index) is timestamped with the given
+//! [`Clock`], keeping the most recent ones, so dashboards and watchdogs
+//! can ask whether a selection is live without wrapping every change call.
+//!
+//! ```
+//! use keyarray::clock::MockClock;
+//! use keyarray::KeyArray;
+//! use std::sync::Arc;
+//! use std::time::Duration;
+//!
+//! let clock = Arc::new(MockClock::new());
+//! let mut source = KeyArray::new(["HDMI 1", "HDMI 2", "USB-C"]);
+//! source.track_activity(Arc::clone(&clock), 64);
+//! source.next();
+//! clock.advance(Duration::from_secs(50));
+//! source.next();
+//! clock.advance(Duration::from_secs(20));
+//!
+//! assert_eq!(source.changes_in(Duration::from_secs(60)), 1);
+//! assert!(source.is_idle(Duration::from_secs(15)));
+//! assert!(!source.is_idle(Duration::from_secs(30)));
+//!
+//! let stats = source.stats(Duration::from_secs(60));
+//! assert_eq!(stats.arrivals, [0, 0, 1]);
+//! assert_eq!(stats.per_minute(), 1.0);
+//! ```
+
+use crate::clock::Clock;
+use crate::id::KeyId;
+use crate::KeyArray;
+use std::collections::VecDeque;
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Clone)]
+pub(crate) struct Activity {
+    clock: Arc<dyn Clock + Send + Sync>,
+    capacity: usize,
+    started: Instant,
+    // the current key as last recorded
+    seen: Option<KeyId>,
+    // times of the latest changes and the key each landed on, oldest first
+    changes: VecDeque<(Instant, KeyId)>,
+}
+
+/// Selection changes within a rolling window, from
+/// [`stats`](KeyArray::stats).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeStats {
+    pub window: Duration,
+    /// Changes within the window.
+    pub changes: usize,
+    /// How many of them landed on each key, by current index. Keys removed
+    /// since are not counted.
+    pub arrivals: Vec<usize>,
+}
+
+impl ChangeStats {
+    /// Changes per minute over the window; 0 for an empty window.
+    pub fn per_minute(&self) -> f64 {
+        if self.window.is_zero() {
+            return 0.0;
+        }
+        self.changes as f64 * 60.0 / self.window.as_secs_f64()
+    }
+
+    /// Index of the key changed to most often, the first on a tie; `None`
+    /// if there were no changes.
+    pub fn busiest(&self) -> Option<usize> {
+        let most = *self.arrivals.iter().max().filter(|&&n| n > 0)?;
+        self.arrivals.iter().position(|&n| n == most)
+    }
+}
+
+impl Activity {
+    pub(crate) fn heap_bytes(&self) -> usize {
+        self.changes.capacity() * std::mem::size_of::<(Instant, KeyId)>()
+    }
+
+    // Timestamp the current key if it differs from the one last recorded.
+    // Keys moving to another index do not count.
+    pub(crate) fn record(&mut self, current: Option<KeyId>) {
+        if current == self.seen {
+            return;
+        }
+        self.seen = current;
+        let Some(id) = current else {
+            return;
+        };
+        if self.changes.len() == self.capacity {
+            self.changes.pop_front();
+        }
+        self.changes.push_back((self.clock.now(), id));
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Start timestamping selection changes with `clock`, keeping the
+    /// latest `capacity`. Replaces any earlier tracking. Panics if
+    /// `capacity` is zero.
+    pub fn track_activity(&mut self, clock: impl Clock + Send + Sync + 'static, capacity: usize) {
+        assert!(capacity > 0, "KeyArray::track_activity: capacity must be positive");
+        self.activity = Some(Box::new(Activity {
+            started: clock.now(),
+            clock: Arc::new(clock),
+            capacity,
+            seen: self.slots.get(self.idx).map(|s| s.id),
+            changes: VecDeque::new(),
+        }));
+    }
+
+    /// Stop timestamping and forget the recorded changes.
+    pub fn stop_tracking_activity(&mut self) {
+        self.activity = None;
+    }
+
+    /// When the current key last changed; `None` if it has not since
+    /// tracking started, or activity is not tracked.
+    pub fn last_changed_at(&self) -> Option<Instant> {
+        self.activity.as_ref()?.changes.back().map(|&(t, _)| t)
+    }
+
+    /// How many of the recorded changes happened within `window` of now.
+    /// At most the capacity given to
+    /// [`track_activity`](Self::track_activity); 0 if activity is not
+    /// tracked.
+    pub fn changes_in(&self, window: Duration) -> usize {
+        self.recent_changes(window).count()
+    }
+
+    /// How often the selection changed within `window` of now, overall and
+    /// per key, e.g. to spot a mode that keeps flapping. Counts only the
+    /// changes kept by [`track_activity`](Self::track_activity).
+    pub fn stats(&self, window: Duration) -> ChangeStats {
+        let mut stats = ChangeStats {
+            window,
+            changes: 0,
+            arrivals: vec![0; self.len()],
+        };
+        for id in self.recent_changes(window) {
+            stats.changes += 1;
+            if let Some(i) = self.index_of_id(id) {
+                stats.arrivals[i] += 1;
+            }
+        }
+        stats
+    }
+
+    // Every recorded change's key, oldest first.
+    #[cfg(feature = "predict")]
+    pub(crate) fn recorded_changes(&self) -> impl Iterator<Item = KeyId> + '_ {
+        let activity = self.activity.as_deref();
+        activity.into_iter().flat_map(|a| a.changes.iter().map(|&(_, id)| id))
+    }
+
+    // Keys changed to within `window` of now, newest first.
+    fn recent_changes(&self, window: Duration) -> impl Iterator<Item = KeyId> + '_ {
+        let activity = self.activity.as_deref();
+        let now = activity.map(|a| a.clock.now());
+        let recent =
+            move |t: Instant| now.is_some_and(|now| now.saturating_duration_since(t) <= window);
+        activity
+            .into_iter()
+            .flat_map(|a| a.changes.iter().rev())
+            .take_while(move |&&(t, _)| recent(t))
+            .map(|&(_, id)| id)
+    }
+
+    /// Whether the current key has not changed for at least `threshold`,
+    /// counting from when tracking started if it never has. False if
+    /// activity is not tracked.
+    pub fn is_idle(&self, threshold: Duration) -> bool {
+        let Some(activity) = &self.activity else {
+            return false;
+        };
+        let since = activity.changes.back().map_or(activity.started, |&(t, _)| t);
+        activity.clock.now().saturating_duration_since(since) >= threshold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+
+    #[test]
+    fn only_selection_changes_count() {
+        let clock = Arc::new(MockClock::new());
+        let mut ka = KeyArray::new(["a", "b"]);
+        assert!(!ka.is_idle(Duration::ZERO));
+        ka.track_activity(Arc::clone(&clock), 2);
+        clock.advance(Duration::from_secs(5));
+        assert!(ka.is_idle(Duration::from_secs(5)) && ka.last_changed_at().is_none());
+
+        ka.push("c");
+        ka.change(0);
+        assert_eq!(ka.changes_in(Duration::MAX), 0);
+        for _ in 0..3 {
+            ka.next();
+            clock.advance(Duration::from_secs(1));
+        }
+        assert_eq!(ka.changes_in(Duration::MAX), 2);
+        assert_eq!(ka.changes_in(Duration::from_secs(1)), 1);
+        assert_eq!(ka.last_changed_at(), Some(clock.now() - Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn stats_follow_keys() {
+        let clock = Arc::new(MockClock::new());
+        let mut ka = KeyArray::new(["a", "b", "c"]);
+        ka.track_activity(Arc::clone(&clock), 8);
+        for i in [1, 0, 1, 2] {
+            ka.change(i);
+            clock.advance(Duration::from_secs(10));
+        }
+        ka.insert(0, "z");
+        let stats = ka.stats(Duration::from_secs(30));
+        assert_eq!((stats.changes, stats.arrivals), (3, vec![0, 1, 1, 1]));
+        let stats = ka.stats(Duration::from_secs(60));
+        assert_eq!(stats.busiest(), Some(2));
+        assert_eq!(stats.per_minute(), 4.0);
+        ka.remove(2);
+        assert_eq!(ka.stats(Duration::from_secs(60)).arrivals, [0, 1, 1]);
+    }
+}
@@ -0,0 +1,180 @@
+//! Amortised bulk edits.
+//!
+//! Each `insert`/`remove` on a KeyArray shifts the tail of the list and fixes
+//! up the selection. A [`Batch`] records edits instead and applies them all
+//! in one merge pass when it is dropped, turning `m` edits on `n` keys from
+//! O(n·m) into O(n + m log m).
+//!
+//! All indices given to a batch refer to positions *before* the batch.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//!
+//! let mut ka = KeyArray::new_with(["a", "b", "c", "d"], 2);
+//! {
+//!     let mut batch = ka.batch();
+//!     batch.remove(0);
+//!     batch.remove(3);
+//!     batch.insert(2, "b2");
+//!     batch.push("e");
+//! }
+//! assert_eq!(ka.keys(), &["b", "b2", "c", "e"]);
+//! assert_eq!(ka.current(), &"c");
+//! ```
+
+use crate::{KeyArray, KeyId, Slot};
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+/// Pending edits against a [`KeyArray`], applied on drop.
+pub struct Batch<'a, K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    array: &'a mut KeyArray<K>,
+    removed: Vec<bool>,
+    // (position before the batch, key, slot); stable-sorted on apply so
+    // inserts at the same position keep their call order
+    inserts: Vec<(usize, K, Slot)>,
+}
+
+impl<K> Batch<'_, K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Queue `key` to go before the key currently at `i` (or at the end when
+    /// `i == len`). Panics if `i > len`.
+    pub fn insert(&mut self, i: usize, key: K) -> KeyId {
+        assert!(
+            i <= self.removed.len(),
+            "Batch::insert: index {} out of bounds",
+            i
+        );
+        let slot = self.array.new_slot();
+        let id = slot.id;
+        self.inserts.push((i, key, slot));
+        id
+    }
+
+    /// Queue `key` to be appended.
+    pub fn push(&mut self, key: K) -> KeyId {
+        let end = self.removed.len();
+        self.insert(end, key)
+    }
+
+    /// Queue removal of the key currently at `i`. Removing the same key
+    /// twice is a no-op. Panics if out of bounds.
+    pub fn remove(&mut self, i: usize) {
+        assert!(
+            i < self.removed.len(),
+            "Batch::remove: index {} out of bounds",
+            i
+        );
+        self.removed[i] = true;
+    }
+
+    /// Apply the queued edits now.
+    pub fn apply(self) {}
+}
+
+impl<K> Drop for Batch<'_, K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    fn drop(&mut self) {
+        if self.inserts.is_empty() && !self.removed.contains(&true) {
+            return;
+        }
+        let mut inserts = std::mem::take(&mut self.inserts);
+        inserts.sort_by_key(|(pos, _, _)| *pos);
+        let old_keys = std::mem::take(Arc::make_mut(&mut self.array.keys));
+        let old_slots = std::mem::take(Arc::make_mut(&mut self.array.slots));
+        let old_len = old_keys.len();
+        let current = self.array.idx;
+
+        let mut keys = Vec::with_capacity(old_len + inserts.len());
+        let mut slots = Vec::with_capacity(old_len + inserts.len());
+        let mut new_idx = None;
+        let mut pending = inserts.into_iter().peekable();
+        for (pos, (key, slot)) in old_keys.into_iter().zip(old_slots).enumerate() {
+            while let Some((_, k, s)) = pending.next_if(|(p, _, _)| *p == pos) {
+                keys.push(k);
+                slots.push(s);
+            }
+            if self.removed[pos] {
+                continue;
+            }
+            // the current key, or the first survivor after a removed one
+            if new_idx.is_none() && pos >= current {
+                new_idx = Some(keys.len());
+            }
+            keys.push(key);
+            slots.push(slot);
+        }
+        for (_, k, s) in pending {
+            keys.push(k);
+            slots.push(s);
+        }
+
+        self.array.idx = new_idx.unwrap_or(keys.len().saturating_sub(1));
+        self.array.keys = Arc::new(keys);
+        self.array.slots = Arc::new(slots);
+        self.array.touch();
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Start a batch of structural edits; see [`Batch`].
+    /// If the current key is removed, the next surviving key becomes current
+    /// (or the last key, if none follow).
+    pub fn batch(&mut self) -> Batch<'_, K> {
+        let removed = vec![false; self.keys.len()];
+        Batch {
+            array: self,
+            removed,
+            inserts: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_sequential_edits() {
+        let mut a = KeyArray::new_with(0..10, 5);
+        let mut b = a.clone();
+        {
+            let mut batch = a.batch();
+            for i in (0..10).step_by(2) {
+                batch.remove(i);
+            }
+            batch.insert(0, 100);
+            batch.insert(10, 101);
+        }
+        for i in (0..10).step_by(2).rev() {
+            b.remove(i);
+        }
+        b.insert(0, 100);
+        b.push(101);
+        assert_eq!(a.keys(), b.keys());
+        assert_eq!(a.current(), &5);
+        let ids: Vec<_> = (0..a.len()).map(|i| a.id_at(i)).collect();
+        assert_eq!(ids, (0..b.len()).map(|i| b.id_at(i)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn removing_current_moves_forward_or_clamps() {
+        let mut ka = KeyArray::new_with(["a", "b", "c"], 1);
+        ka.batch().remove(1);
+        assert_eq!(ka.current(), &"c");
+        let mut batch = ka.batch();
+        batch.remove(1);
+        batch.apply();
+        assert_eq!(ka.current(), &"a");
+    }
+}
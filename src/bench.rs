@@ -0,0 +1,204 @@
+//! Standard workloads for measuring performance on your own hardware.
+//!
+//! Each [`Workload`] exercises one pattern that later caches and storage
+//! backends are meant to speed up: churn of inserts and removals on a large
+//! list, change notifications fanned out to many observers, and selection
+//! by name and by id. [`measure`] runs one several times and reports the
+//! best and median time, so a CI job or a downstream crate can track
+//! regressions without a benchmarking framework. The workloads are
+//! deterministic and return a checksum, so the optimizer cannot skip them
+//! and two runs can be checked to have done the same work.
+//!
+//! ```
+//! use keyarray::bench::{measure, Workload, WorkloadConfig};
+//!
+//! let config = WorkloadConfig { keys: 1_000, ops: 200, observers: 8 };
+//! for workload in Workload::ALL {
+//!     let result = measure(workload, &config, 3);
+//!     assert!(result.best <= result.median);
+//!     println!("{:<18} {:>8.1} ns/op", workload.name(), result.ns_per_op());
+//! }
+//! ```
+//!
+//! `cargo bench --bench workloads --features bench` runs all of them at the
+//! default sizes.
+
+use crate::KeyArray;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// A standard workload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Workload {
+    /// Interleaved removals and inserts across a large list, keeping the
+    /// selection in place; see [`mutation_churn`].
+    MutationChurn,
+    /// Selection changes, each told to many observers; see
+    /// [`observer_fan_out`].
+    ObserverFanOut,
+    /// Selection by key name and lookups by id; see [`lookup_heavy`].
+    LookupHeavy,
+}
+
+impl Workload {
+    pub const ALL: [Workload; 3] = [
+        Workload::MutationChurn,
+        Workload::ObserverFanOut,
+        Workload::LookupHeavy,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Workload::MutationChurn => "mutation_churn",
+            Workload::ObserverFanOut => "observer_fan_out",
+            Workload::LookupHeavy => "lookup_heavy",
+        }
+    }
+
+    /// Run once, returning the checksum.
+    pub fn run(self, config: &WorkloadConfig) -> u64 {
+        match self {
+            Workload::MutationChurn => mutation_churn(config.keys, config.ops),
+            Workload::ObserverFanOut => observer_fan_out(config.keys, config.observers, config.ops),
+            Workload::LookupHeavy => lookup_heavy(config.keys, config.ops),
+        }
+    }
+}
+
+/// The size of a workload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WorkloadConfig {
+    /// Keys in the array.
+    pub keys: usize,
+    /// Operations per run.
+    pub ops: usize,
+    /// Observers registered for [`Workload::ObserverFanOut`].
+    pub observers: usize,
+}
+
+impl Default for WorkloadConfig {
+    fn default() -> Self {
+        WorkloadConfig {
+            keys: 10_000,
+            ops: 2_000,
+            observers: 64,
+        }
+    }
+}
+
+/// The timings of one workload, from [`measure`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Measurement {
+    pub workload: Workload,
+    pub ops: usize,
+    pub runs: usize,
+    pub best: Duration,
+    pub median: Duration,
+}
+
+impl Measurement {
+    /// Nanoseconds per operation of the best run.
+    pub fn ns_per_op(&self) -> f64 {
+        self.best.as_nanos() as f64 / self.ops.max(1) as f64
+    }
+}
+
+/// Run `workload` `runs` times, at least once, and time each run.
+pub fn measure(workload: Workload, config: &WorkloadConfig, runs: usize) -> Measurement {
+    let mut times: Vec<Duration> = (0..runs.max(1))
+        .map(|_| {
+            let start = Instant::now();
+            black_box(workload.run(black_box(config)));
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+    Measurement {
+        workload,
+        ops: config.ops,
+        runs: times.len(),
+        best: times[0],
+        median: times[times.len() / 2],
+    }
+}
+
+/// [`measure`] every workload.
+pub fn measure_all(config: &WorkloadConfig, runs: usize) -> Vec<Measurement> {
+    Workload::ALL.iter().map(|&w| measure(w, config, runs)).collect()
+}
+
+/// Remove a key and insert another at spread-out positions `ops` times on
+/// an array of `keys` keys, selected in the middle. Returns the final
+/// current index plus the sum of the keys.
+pub fn mutation_churn(keys: usize, ops: usize) -> u64 {
+    let keys = keys.max(1);
+    let mut ka = KeyArray::new_with(0..keys as u64, keys / 2);
+    for n in 0..ops {
+        let len = ka.len();
+        if len > 1 {
+            ka.remove(n * 37 % len);
+        }
+        ka.insert(n * 11 % (ka.len() + 1), (keys + n) as u64);
+    }
+    ka.current_index() as u64 + ka.iter().sum::<u64>()
+}
+
+/// Register `observers` observers on an array of `keys` keys and make
+/// `ops` changes spread over it. Returns how many notifications were
+/// delivered.
+pub fn observer_fan_out(keys: usize, observers: usize, ops: usize) -> u64 {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    let keys = keys.max(2);
+    let mut ka = KeyArray::new(0..keys as u64);
+    let delivered = Arc::new(AtomicU64::new(0));
+    for _ in 0..observers {
+        let delivered = Arc::clone(&delivered);
+        ka.on_change(move |_, _, _| {
+            delivered.fetch_add(1, Ordering::Relaxed);
+        });
+    }
+    for n in 0..ops {
+        ka.change((n * 7919 + 1) % keys);
+    }
+    delivered.load(Ordering::Relaxed)
+}
+
+/// Select keys by name `ops` times, each followed by a lookup of a key's
+/// index by its id, on an array of `keys` string keys. Returns the sum of
+/// the indices found.
+pub fn lookup_heavy(keys: usize, ops: usize) -> u64 {
+    let keys = keys.max(1);
+    let mut ka = KeyArray::new((0..keys).map(|i| format!("key-{}", i)));
+    let ids: Vec<_> = (0..keys).filter_map(|i| ka.id_at(i)).collect();
+    let names: Vec<String> = (0..ops).map(|n| format!("key-{}", n * 7919 % keys)).collect();
+    let mut sum = 0;
+    for (n, name) in names.iter().enumerate() {
+        let i = ka.keys().iter().position(|k| k == name).expect("every name exists");
+        ka.change(i);
+        sum += i as u64;
+        sum += ka.index_of_id(ids[n * 104_729 % keys]).expect("ids are kept") as u64;
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workloads_are_deterministic() {
+        let config = WorkloadConfig {
+            keys: 50,
+            ops: 40,
+            observers: 3,
+        };
+        for workload in Workload::ALL {
+            assert_eq!(workload.run(&config), workload.run(&config), "{}", workload.name());
+        }
+        assert_eq!(observer_fan_out(50, 3, 40), 3 * 40);
+        let all = measure_all(&config, 0);
+        assert_eq!((all.len(), all[0].runs), (3, 1));
+    }
+}
@@ -0,0 +1,156 @@
+//! Bevy ECS integration.
+//!
+//! `KeyArray<K>` is a [`Component`]. [`KeyArrayPlugin`] emits a
+//! [`KeyChanged`] message whenever an entity's selection moves, and steps the
+//! selection of entities that carry a [`KeyNavigation`] from keyboard input.
+//!
+//! ```no_run
+//! use bevy_app::App;
+//! use keyarray::bevy::{KeyArrayPlugin, KeyNavigation};
+//! use keyarray::KeyArray;
+//!
+//! App::new()
+//!     .add_plugins(KeyArrayPlugin::<&'static str>::default())
+//!     .world_mut()
+//!     .spawn((KeyArray::new(["Easy", "Normal", "Hard"]), KeyNavigation::default()));
+//! ```
+
+use crate::KeyArray;
+use bevy_app::{App, Plugin, Update};
+use bevy_ecs::prelude::*;
+use bevy_input::keyboard::KeyCode;
+use bevy_input::ButtonInput;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use std::marker::PhantomData;
+
+/// The selection of `entity` moved.
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct KeyChanged<K: Send + Sync + 'static> {
+    pub entity: Entity,
+    pub previous: usize,
+    pub current: usize,
+    /// The key that is now current.
+    pub key: K,
+}
+
+/// Keyboard bindings that step an entity's KeyArray.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyNavigation {
+    pub next: KeyCode,
+    pub prev: KeyCode,
+}
+
+impl Default for KeyNavigation {
+    /// Right/left arrows.
+    fn default() -> Self {
+        KeyNavigation {
+            next: KeyCode::ArrowRight,
+            prev: KeyCode::ArrowLeft,
+        }
+    }
+}
+
+/// Registers [`KeyChanged<K>`] and the navigation and change-detection
+/// systems for `KeyArray<K>` components. Add once per key type.
+pub struct KeyArrayPlugin<K>(PhantomData<fn() -> K>);
+
+impl<K> Default for KeyArrayPlugin<K> {
+    fn default() -> Self {
+        KeyArrayPlugin(PhantomData)
+    }
+}
+
+impl<K> Plugin for KeyArrayPlugin<K>
+where
+    K: Clone + PartialEq + Debug + Display + Send + Sync + 'static,
+{
+    fn build(&self, app: &mut App) {
+        app.add_message::<KeyChanged<K>>()
+            .add_systems(Update, (navigate::<K>, emit_changes::<K>).chain());
+    }
+}
+
+/// Step every [`KeyNavigation`] entity on a just-pressed binding.
+pub fn navigate<K>(
+    input: Option<Res<ButtonInput<KeyCode>>>,
+    mut arrays: Query<(&KeyNavigation, &mut KeyArray<K>)>,
+) where
+    K: Clone + PartialEq + Debug + Display + Send + Sync + 'static,
+{
+    let Some(input) = input else {
+        return;
+    };
+    for (nav, mut ka) in &mut arrays {
+        if input.just_pressed(nav.next) {
+            ka.next();
+        }
+        if input.just_pressed(nav.prev) {
+            ka.prev();
+        }
+    }
+}
+
+/// Write a [`KeyChanged`] for every entity whose current key moved since the
+/// last run. Newly spawned arrays are recorded without a message.
+pub fn emit_changes<K>(
+    arrays: Query<(Entity, Ref<KeyArray<K>>)>,
+    mut seen: Local<HashMap<Entity, usize>>,
+    mut out: MessageWriter<KeyChanged<K>>,
+) where
+    K: Clone + PartialEq + Debug + Display + Send + Sync + 'static,
+{
+    for (entity, ka) in &arrays {
+        if !ka.is_changed() || ka.is_empty() {
+            continue;
+        }
+        let current = ka.current_index();
+        match seen.insert(entity, current) {
+            Some(previous) if previous != current => {
+                out.write(KeyChanged {
+                    entity,
+                    previous,
+                    current,
+                    key: ka.current().clone(),
+                });
+            }
+            _ => {}
+        }
+    }
+    seen.retain(|e, _| arrays.contains(*e));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::message::Messages;
+
+    #[test]
+    fn navigation_emits_messages() {
+        let mut app = App::new();
+        app.add_plugins(KeyArrayPlugin::<&'static str>::default())
+            .init_resource::<ButtonInput<KeyCode>>();
+        let e = app
+            .world_mut()
+            .spawn((KeyArray::new(["A", "B", "C"]), KeyNavigation::default()))
+            .id();
+        app.update();
+
+        app.world_mut()
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .press(KeyCode::ArrowLeft);
+        app.update();
+
+        let messages = app.world().resource::<Messages<KeyChanged<&'static str>>>();
+        let got: Vec<_> = messages.iter_current_update_messages().cloned().collect();
+        assert_eq!(
+            got,
+            vec![KeyChanged {
+                entity: e,
+                previous: 0,
+                current: 2,
+                key: "C"
+            }]
+        );
+    }
+}
@@ -0,0 +1,89 @@
+//! Named selections to jump back to.
+//!
+//! A bookmark remembers the key's [`KeyId`], not its index, so it keeps
+//! pointing at the same key through inserts, removals and sorts:
+//!
+//! ```
+//! use keyarray::KeyArray;
+//!
+//! let mut preset = KeyArray::new(["Warm", "Neutral", "Cool"]);
+//! preset.change(1);
+//! preset.bookmark("before_experiment");
+//! preset.insert(0, "Vivid");
+//! preset.change(3);
+//! assert_eq!(preset.jump_to_bookmark("before_experiment"), Some(2));
+//! assert_eq!(preset.current(), &"Neutral");
+//! ```
+
+use crate::id::KeyId;
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Bookmark the current key as `name`, replacing any bookmark of that
+    /// name. Returns the bookmarked key's id.
+    pub fn bookmark(&mut self, name: impl Into<String>) -> KeyId {
+        let id = self.current_id();
+        Arc::make_mut(&mut self.bookmarks).insert(name.into(), id);
+        self.touch();
+        id
+    }
+
+    /// Index of the key bookmarked as `name`. `None` if there is no such
+    /// bookmark or its key was removed.
+    pub fn bookmark_index(&self, name: &str) -> Option<usize> {
+        self.index_of_id(*self.bookmarks.get(name)?)
+    }
+
+    /// Make the key bookmarked as `name` current and return the index now
+    /// current, which is its replacement's if it is deprecated under strict
+    /// mode. Leaves the selection untouched and returns `None` if the bookmark is
+    /// unknown or its key was removed or cannot be selected; the bookmark
+    /// itself is kept.
+    pub fn jump_to_bookmark(&mut self, name: &str) -> Option<usize> {
+        let i = self.bookmark_index(name)?;
+        self.try_change(i).ok()?;
+        Some(self.current_index())
+    }
+
+    /// Forget the bookmark `name`, returning the id it pointed at.
+    pub fn remove_bookmark(&mut self, name: &str) -> Option<KeyId> {
+        let id = Arc::make_mut(&mut self.bookmarks).remove(name)?;
+        self.touch();
+        Some(id)
+    }
+
+    /// All bookmarks by name, including ones whose key was removed.
+    pub fn bookmarks(&self) -> impl Iterator<Item = (&str, KeyId)> + '_ {
+        self.bookmarks.iter().map(|(name, id)| (name.as_str(), *id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bookmarks_follow_ids() {
+        let mut ka = KeyArray::new(["A", "B", "C"]);
+        let a = ka.bookmark("start");
+        ka.change(2);
+        ka.bookmark("end");
+        ka.sort_by(|x, y| y.cmp(x));
+        assert_eq!(ka.bookmark_index("start"), Some(2));
+        assert_eq!(ka.jump_to_bookmark("start"), Some(2));
+
+        ka.remove(0);
+        assert_eq!(ka.jump_to_bookmark("end"), None);
+        assert_eq!(ka.current(), &"A");
+        assert_eq!(ka.jump_to_bookmark("nope"), None);
+        let names: Vec<_> = ka.bookmarks().map(|(n, _)| n).collect();
+        assert_eq!(names, ["end", "start"]);
+        assert_eq!(ka.remove_bookmark("start"), Some(a));
+        assert_eq!(ka.remove_bookmark("start"), None);
+    }
+}
@@ -0,0 +1,154 @@
+//! KeyArray semantics over data you already own, without copying it.
+//!
+//! ```
+//! use keyarray::{AsKeyArray, SelectionCursor};
+//!
+//! let modes = vec!["On", "Off", "Auto"];
+//! let mut view = modes.with_selection(2);
+//! view.next();
+//! assert_eq!(view.current(), &"On");
+//! ```
+
+use crate::cursor::SelectionCursor;
+use core::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+/// A borrowed key list with its own selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyArrayRef<'a, K> {
+    keys: &'a [K],
+    idx: usize,
+}
+
+impl<'a, K> KeyArrayRef<'a, K> {
+    /// Select `idx` within `keys`. Panics if `keys` is empty or `idx` is out
+    /// of bounds.
+    pub fn new(keys: &'a [K], idx: usize) -> Self {
+        assert!(!keys.is_empty(), "KeyArrayRef::new: must supply keys");
+        assert!(
+            idx < keys.len(),
+            "KeyArrayRef::new: index {} out of bounds",
+            idx
+        );
+        KeyArrayRef { keys, idx }
+    }
+
+    /// Change the current key by zero-based index.
+    /// Panics if `i` is out of bounds.
+    pub fn change(&mut self, i: usize) {
+        assert!(
+            i < self.keys.len(),
+            "KeyArrayRef::change: index {} out of bounds",
+            i
+        );
+        self.idx = i;
+    }
+
+    /// Get a reference to the current key, borrowed from the underlying list.
+    pub fn current(&self) -> &'a K {
+        &self.keys[self.idx]
+    }
+
+    /// Get the index of the current key.
+    pub fn current_index(&self) -> usize {
+        self.idx
+    }
+
+    /// Get the underlying slice.
+    pub fn keys(&self) -> &'a [K] {
+        self.keys
+    }
+
+    /// Number of keys.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Always false: a `KeyArrayRef` cannot be empty.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+impl<K> SelectionCursor for KeyArrayRef<'_, K> {
+    type Item = K;
+
+    fn item_count(&self) -> usize {
+        self.keys.len()
+    }
+
+    fn cursor(&self) -> usize {
+        self.idx
+    }
+
+    fn set_cursor(&mut self, i: usize) {
+        self.idx = i;
+    }
+
+    fn item(&self, i: usize) -> Option<&K> {
+        self.keys.get(i)
+    }
+}
+
+impl<K> Display for KeyArrayRef<'_, K>
+where
+    K: Debug + Display,
+{
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "keys={:?}, current_idx={}, current={}",
+            self.keys,
+            self.idx,
+            self.current()
+        )
+    }
+}
+
+/// Lift a slice-like container into a [`KeyArrayRef`].
+pub trait AsKeyArray<K> {
+    /// Borrow `self` as a key list with `idx` selected. Panics if empty or
+    /// out of bounds.
+    fn with_selection(&self, idx: usize) -> KeyArrayRef<'_, K>;
+}
+
+impl<K> AsKeyArray<K> for [K] {
+    fn with_selection(&self, idx: usize) -> KeyArrayRef<'_, K> {
+        KeyArrayRef::new(self, idx)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K> AsKeyArray<K> for Vec<K> {
+    fn with_selection(&self, idx: usize) -> KeyArrayRef<'_, K> {
+        KeyArrayRef::new(self, idx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::string::String;
+    use std::vec::Vec;
+    use std::{format, vec};
+
+    #[test]
+    fn slice_and_vec() {
+        let arr = [1, 2, 3];
+        let mut view = arr[..].with_selection(0);
+        view.prev();
+        assert_eq!(view.current(), &3);
+        view.change(1);
+        assert_eq!(format!("{}", view), "keys=[1, 2, 3], current_idx=1, current=2");
+
+        let v: Vec<String> = vec!["x".into()];
+        assert_eq!(v.with_selection(0).current(), "x");
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn oob_selection_panics() {
+        Vec::from(["a"]).with_selection(1);
+    }
+}
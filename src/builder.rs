@@ -0,0 +1,258 @@
+//! Checked construction of a configured KeyArray.
+//!
+//! [`KeyArrayBuilder`] gathers the keys and settings first and checks them
+//! all in [`build`](KeyArrayBuilder::build), so a bad configuration is
+//! reported once, with a descriptive [`BuildError`], instead of panicking
+//! partway through a series of calls.
+//!
+//! ```
+//! # #[cfg(feature = "disable")] {
+//! use keyarray::builder::BuildError;
+//! use keyarray::{KeyArray, WrapMode};
+//!
+//! let quality = KeyArray::builder()
+//!     .keys(["Low", "Medium", "High", "Ultra"])
+//!     .start_key("High")
+//!     .unique_keys(true)
+//!     .disabled(3)
+//!     .wrap_mode(WrapMode::Saturate)
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(quality.current(), &"High");
+//!
+//! let err = KeyArray::builder()
+//!     .keys(["Low", "Low"])
+//!     .unique_keys(true)
+//!     .build()
+//!     .unwrap_err();
+//! assert_eq!(err, BuildError::Duplicate { first: 0, index: 1 });
+//! # }
+//! ```
+
+use crate::cursor::WrapMode;
+use crate::KeyArray;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+type Validator<K> = Box<dyn Fn(&K) -> bool>;
+
+enum Start<K> {
+    Index(usize),
+    Key(K),
+}
+
+/// Settings for a KeyArray, checked when it is built. Created by
+/// [`KeyArray::builder`].
+pub struct KeyArrayBuilder<K> {
+    keys: Vec<K>,
+    start: Start<K>,
+    unique: bool,
+    validators: Vec<Validator<K>>,
+    #[cfg(feature = "disable")]
+    disabled: Vec<usize>,
+    wrap: WrapMode,
+}
+
+/// Why [`build`](KeyArrayBuilder::build) refused the configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BuildError {
+    /// No keys were given.
+    Empty,
+    /// The start index is not below the number of keys, `len`.
+    StartOutOfBounds { index: usize, len: usize },
+    /// No key equals the start key, shown as `key`.
+    StartKeyMissing { key: String },
+    /// The start key is disabled.
+    StartDisabled { index: usize },
+    /// A key to disable is not below the number of keys, `len`.
+    DisabledOutOfBounds { index: usize, len: usize },
+    /// Keys `first` and `index` are equal, and keys must be unique.
+    Duplicate { first: usize, index: usize },
+    /// A validator rejected key `index`, shown as `key`.
+    Rejected { index: usize, key: String },
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            BuildError::Empty => f.write_str("must supply at least one key"),
+            BuildError::StartOutOfBounds { index, len } => {
+                write!(f, "start index {} out of bounds for {} keys", index, len)
+            }
+            BuildError::StartKeyMissing { key } => write!(f, "start key {:?} is not a key", key),
+            BuildError::StartDisabled { index } => write!(f, "start key {} is disabled", index),
+            BuildError::DisabledOutOfBounds { index, len } => {
+                write!(f, "cannot disable key {} of {}", index, len)
+            }
+            BuildError::Duplicate { first, index } => {
+                write!(f, "key {} duplicates key {}", index, first)
+            }
+            BuildError::Rejected { index, key } => {
+                write!(f, "key {} ({:?}) was rejected", index, key)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+impl<K> Default for KeyArrayBuilder<K> {
+    fn default() -> Self {
+        KeyArrayBuilder {
+            keys: Vec::new(),
+            start: Start::Index(0),
+            unique: false,
+            validators: Vec::new(),
+            #[cfg(feature = "disable")]
+            disabled: Vec::new(),
+            wrap: WrapMode::default(),
+        }
+    }
+}
+
+impl<K> KeyArrayBuilder<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append `keys` to the keys given so far.
+    pub fn keys(mut self, keys: impl IntoIterator<Item = K>) -> Self {
+        self.keys.extend(keys);
+        self
+    }
+
+    /// Append one key.
+    pub fn key(mut self, key: K) -> Self {
+        self.keys.push(key);
+        self
+    }
+
+    /// Start at key `i`. The default is the first key.
+    pub fn start_index(mut self, i: usize) -> Self {
+        self.start = Start::Index(i);
+        self
+    }
+
+    /// Start at the first key equal to `key`.
+    pub fn start_key(mut self, key: K) -> Self {
+        self.start = Start::Key(key);
+        self
+    }
+
+    /// Whether equal keys are an error. Off by default.
+    pub fn unique_keys(mut self, unique: bool) -> Self {
+        self.unique = unique;
+        self
+    }
+
+    /// Reject any key for which `valid` returns false. Every validator
+    /// must accept every key.
+    pub fn validator<F>(mut self, valid: F) -> Self
+    where
+        F: Fn(&K) -> bool + 'static,
+    {
+        self.validators.push(Box::new(valid));
+        self
+    }
+
+    /// [`disable`](KeyArray::disable) key `i`.
+    #[cfg(feature = "disable")]
+    pub fn disabled(mut self, i: usize) -> Self {
+        self.disabled.push(i);
+        self
+    }
+
+    pub fn wrap_mode(mut self, wrap: WrapMode) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    /// Check the configuration and create the KeyArray. Keys are checked in
+    /// order, each for duplicates before validators.
+    pub fn build(self) -> Result<KeyArray<K>, BuildError> {
+        let len = self.keys.len();
+        if len == 0 {
+            return Err(BuildError::Empty);
+        }
+        for (index, key) in self.keys.iter().enumerate() {
+            if self.unique {
+                if let Some(first) = self.keys[..index].iter().position(|k| k == key) {
+                    return Err(BuildError::Duplicate { first, index });
+                }
+            }
+            if !self.validators.iter().all(|valid| valid(key)) {
+                let key = key.to_string();
+                return Err(BuildError::Rejected { index, key });
+            }
+        }
+        #[cfg(feature = "disable")]
+        if let Some(&index) = self.disabled.iter().find(|&&i| i >= len) {
+            return Err(BuildError::DisabledOutOfBounds { index, len });
+        }
+        let start = match &self.start {
+            Start::Index(i) if *i < len => *i,
+            Start::Index(i) => return Err(BuildError::StartOutOfBounds { index: *i, len }),
+            Start::Key(key) => match self.keys.iter().position(|k| k == key) {
+                Some(i) => i,
+                None => return Err(BuildError::StartKeyMissing { key: key.to_string() }),
+            },
+        };
+        #[cfg(feature = "disable")]
+        if self.disabled.contains(&start) {
+            return Err(BuildError::StartDisabled { index: start });
+        }
+        let mut keys = KeyArray::new_with(self.keys, start);
+        keys.set_wrap_mode(self.wrap);
+        #[cfg(feature = "disable")]
+        for i in self.disabled {
+            keys.disable(i);
+        }
+        Ok(keys)
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Start configuring a KeyArray; see [`KeyArrayBuilder`].
+    pub fn builder() -> KeyArrayBuilder<K> {
+        KeyArrayBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_problem_is_reported() {
+        let base = || KeyArray::builder().keys([1, 2, 3]);
+        assert_eq!(KeyArray::<u8>::builder().build().unwrap_err(), BuildError::Empty);
+        assert_eq!(
+            base().validator(|k| k % 2 == 1).build().unwrap_err(),
+            BuildError::Rejected { index: 1, key: "2".into() }
+        );
+        assert_eq!(
+            base().start_key(7).build().unwrap_err().to_string(),
+            "start key \"7\" is not a key"
+        );
+        let oob = BuildError::StartOutOfBounds { index: 3, len: 3 };
+        assert_eq!(base().start_index(3).build().unwrap_err(), oob);
+    }
+
+    #[cfg(feature = "disable")]
+    #[test]
+    fn disabled_keys_are_checked() {
+        let base = || KeyArray::builder().keys([1, 2, 3]);
+        let oob = BuildError::DisabledOutOfBounds { index: 5, len: 3 };
+        assert_eq!(base().disabled(5).build().unwrap_err(), oob);
+        let disabled = base().key(2).start_key(2).disabled(1).build().unwrap_err();
+        assert_eq!(disabled, BuildError::StartDisabled { index: 1 });
+
+        let ka = base().key(1).start_index(2).disabled(0).build().unwrap();
+        assert_eq!((ka.len(), ka.current(), ka.is_enabled(0)), (4, &3, false));
+    }
+}
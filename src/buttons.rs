@@ -0,0 +1,242 @@
+//! Physical Next/Prev/Select buttons driving a KeyArray, or any other
+//! [`SelectionCursor`] such as a heap-free
+//! [`KeyArrayFixed`](crate::KeyArrayFixed).
+//!
+//! [`ButtonDriver`] owns three `embedded-hal` input pins. Call
+//! [`ButtonDriver::poll`] at a fixed rate (e.g. from a 1 kHz timer); all
+//! timing is counted in polls, so no clock is needed. Presses are debounced,
+//! and holding Next or Prev repeats the step after a delay.
+//!
+//! Firmware that already decodes buttons in an interrupt can skip the pins
+//! and feed a [`ButtonEvent`] to [`apply_event`] instead.
+
+use crate::cursor::SelectionCursor;
+use embedded_hal::digital::InputPin;
+
+/// A decoded button action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ButtonEvent {
+    Next,
+    Prev,
+    Select,
+}
+
+/// Apply `event` to `keys`. Returns the current index on
+/// [`ButtonEvent::Select`], `None` otherwise.
+pub fn apply_event<C>(keys: &mut C, event: ButtonEvent) -> Option<usize>
+where
+    C: SelectionCursor + ?Sized,
+{
+    match event {
+        ButtonEvent::Next => keys.next(),
+        ButtonEvent::Prev => keys.prev(),
+        ButtonEvent::Select => return Some(keys.cursor()),
+    }
+    None
+}
+
+/// Timing and wiring, in polls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ButtonConfig {
+    /// A raw level must hold this many consecutive polls to count.
+    pub debounce: u16,
+    /// Polls a press must be held before Next/Prev start repeating;
+    /// 0 disables repeat.
+    pub repeat_delay: u16,
+    /// Polls between repeats once repeating.
+    pub repeat_interval: u16,
+    /// Pins read low while pressed (pull-up wiring).
+    pub active_low: bool,
+}
+
+impl Default for ButtonConfig {
+    /// Tuned for a 1 kHz poll: 20 ms debounce, repeat after 500 ms, then
+    /// every 100 ms, pull-up wiring.
+    fn default() -> Self {
+        ButtonConfig {
+            debounce: 20,
+            repeat_delay: 500,
+            repeat_interval: 100,
+            active_low: true,
+        }
+    }
+}
+
+/// Debounce and repeat state of one button.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Button {
+    pressed: bool,
+    // polls the raw level has disagreed with `pressed`
+    unstable: u16,
+    // polls since the debounced press began
+    held: u32,
+}
+
+impl Button {
+    /// Feed one raw sample; returns true when the button fires (on press,
+    /// and on each repeat if `repeats`).
+    pub fn update(&mut self, raw_pressed: bool, config: &ButtonConfig, repeats: bool) -> bool {
+        if raw_pressed != self.pressed {
+            self.unstable += 1;
+            if self.unstable >= config.debounce.max(1) {
+                self.pressed = raw_pressed;
+                self.unstable = 0;
+                self.held = 0;
+                return raw_pressed;
+            }
+            return false;
+        }
+        self.unstable = 0;
+        if !self.pressed {
+            return false;
+        }
+        self.held = self.held.saturating_add(1);
+        let delay = config.repeat_delay as u32;
+        let interval = config.repeat_interval.max(1) as u32;
+        repeats && delay > 0 && self.held >= delay && (self.held - delay).is_multiple_of(interval)
+    }
+
+    /// Whether the debounced state is pressed.
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+}
+
+/// Reads three pins and turns them into [`ButtonEvent`]s.
+pub struct ButtonDriver<N, P, S> {
+    next: N,
+    prev: P,
+    select: S,
+    buttons: [Button; 3],
+    config: ButtonConfig,
+}
+
+impl<N, P, S, E> ButtonDriver<N, P, S>
+where
+    N: InputPin<Error = E>,
+    P: InputPin<Error = E>,
+    S: InputPin<Error = E>,
+{
+    pub fn new(next: N, prev: P, select: S, config: ButtonConfig) -> Self {
+        ButtonDriver {
+            next,
+            prev,
+            select,
+            buttons: [Button::default(); 3],
+            config,
+        }
+    }
+
+    /// Give the pins back.
+    pub fn release(self) -> (N, P, S) {
+        (self.next, self.prev, self.select)
+    }
+
+    /// Sample the pins once and return the event that fired, if any. When
+    /// several fire in the same poll, Select wins over Next over Prev.
+    pub fn poll_event(&mut self) -> Result<Option<ButtonEvent>, E> {
+        let low = self.config.active_low;
+        let raw = [
+            self.next.is_low()? == low,
+            self.prev.is_low()? == low,
+            self.select.is_low()? == low,
+        ];
+        let cfg = self.config;
+        let fired = [
+            self.buttons[0].update(raw[0], &cfg, true),
+            self.buttons[1].update(raw[1], &cfg, true),
+            self.buttons[2].update(raw[2], &cfg, false),
+        ];
+        Ok(if fired[2] {
+            Some(ButtonEvent::Select)
+        } else if fired[0] {
+            Some(ButtonEvent::Next)
+        } else if fired[1] {
+            Some(ButtonEvent::Prev)
+        } else {
+            None
+        })
+    }
+
+    /// [`poll_event`](Self::poll_event) and apply the result to `keys`.
+    /// Returns the event together with the selected index for Select.
+    pub fn poll<C>(&mut self, keys: &mut C) -> Result<Option<(ButtonEvent, Option<usize>)>, E>
+    where
+        C: SelectionCursor + ?Sized,
+    {
+        Ok(self
+            .poll_event()?
+            .map(|event| (event, apply_event(keys, event))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use crate::KeyArrayFixed;
+    use core::convert::Infallible;
+    use embedded_hal::digital::ErrorType;
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::vec::Vec;
+
+    // A pin whose level the test controls; reads low when "pressed".
+    #[derive(Clone, Default)]
+    struct FakePin(Rc<Cell<bool>>);
+
+    impl ErrorType for FakePin {
+        type Error = Infallible;
+    }
+
+    impl InputPin for FakePin {
+        fn is_high(&mut self) -> Result<bool, Infallible> {
+            Ok(!self.0.get())
+        }
+        fn is_low(&mut self) -> Result<bool, Infallible> {
+            Ok(self.0.get())
+        }
+    }
+
+    #[test]
+    fn debounce_and_repeat() {
+        let (next, prev, select) = (FakePin::default(), FakePin::default(), FakePin::default());
+        let config = ButtonConfig {
+            debounce: 3,
+            repeat_delay: 10,
+            repeat_interval: 5,
+            active_low: true,
+        };
+        let mut driver = ButtonDriver::new(next.clone(), prev.clone(), select.clone(), config);
+        let mut ka: KeyArrayFixed<u8, 10> = KeyArrayFixed::new(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+
+        // a 2-poll glitch is ignored
+        next.0.set(true);
+        driver.poll(&mut ka).unwrap();
+        driver.poll(&mut ka).unwrap();
+        next.0.set(false);
+        for _ in 0..5 {
+            driver.poll(&mut ka).unwrap();
+        }
+        assert_eq!(ka.current_index(), 0);
+
+        // hold for 3 + 20 polls: one press, then repeats at 10, 15, 20
+        next.0.set(true);
+        for _ in 0..23 {
+            driver.poll(&mut ka).unwrap();
+        }
+        assert_eq!(ka.current_index(), 4);
+        next.0.set(false);
+
+        for _ in 0..3 {
+            driver.poll(&mut ka).unwrap();
+        }
+        select.0.set(true);
+        let fired: Vec<_> = (0..30)
+            .filter_map(|_| driver.poll(&mut ka).unwrap())
+            .collect();
+        assert_eq!(fired, [(ButtonEvent::Select, Some(4))]);
+    }
+}
@@ -0,0 +1,176 @@
+//! A canonical text form for snapshot tests.
+//!
+//! [`to_canonical_string`](KeyArray::to_canonical_string) writes what an
+//! application observes of a KeyArray, one fact per line in a fixed order,
+//! so a snapshot (with `insta` or a plain golden file) only changes when
+//! the state does. Volatile data is left out: key ids, generation counters,
+//! expiry times, activity, history, observers and attached payloads (only
+//! their presence is noted). Keys are written with `{:?}`. Bookmarks are
+//! sorted by name, transition rules by index.
+//!
+//! ```
+//! # #[cfg(all(feature = "disable", feature = "transitions"))] {
+//! use keyarray::KeyArray;
+//!
+//! let mut input = KeyArray::new(["HDMI 1", "HDMI 2", "USB-C"]);
+//! input.change(1);
+//! input.disable(2);
+//! input.bookmark("console");
+//! input.deny_transition(1, 0);
+//! assert_eq!(
+//!     input.to_canonical_string(),
+//!     "\
+//! keys:
+//!   0: \"HDMI 1\"
+//!   1: \"HDMI 2\" (current)
+//!   2: \"USB-C\" (disabled)
+//! wrap: Wrap
+//! bookmarks:
+//!   console: 1
+//! transitions:
+//!   1 -> 0: deny
+//! "
+//! );
+//! # }
+//! ```
+
+#[cfg(feature = "provenance")]
+use crate::provenance::Source;
+use crate::KeyArray;
+use std::fmt::{Debug, Display, Write};
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// The state as deterministic, human-readable text; see the
+    /// [module docs](self).
+    pub fn to_canonical_string(&self) -> String {
+        let mut out = String::new();
+        self.write_canonical(&mut out).expect("writing to a String cannot fail");
+        out
+    }
+
+    #[cfg_attr(
+        not(any(
+            feature = "confirm",
+            feature = "deprecation",
+            feature = "disable",
+            feature = "expiry",
+            feature = "meta",
+            feature = "permission",
+            feature = "provenance",
+            feature = "tombstone",
+            feature = "ui"
+        )),
+        allow(unused_variables)
+    )]
+    fn write_canonical(&self, out: &mut String) -> std::fmt::Result {
+        out.push_str("keys:\n");
+        for (i, (key, slot)) in self.keys.iter().zip(self.slots.iter()).enumerate() {
+            write!(out, "  {}: {:?}", i, key)?;
+            let mut flags = Vec::new();
+            if i == self.idx {
+                flags.push("current".to_string());
+            }
+            let marks: &[(bool, &str)] = &[
+                #[cfg(feature = "disable")]
+                (slot.disabled, "disabled"),
+                #[cfg(feature = "tombstone")]
+                (slot.hidden, "hidden"),
+                #[cfg(feature = "confirm")]
+                (slot.confirm, "confirm"),
+                #[cfg(feature = "expiry")]
+                (slot.expires.is_some(), "expires"),
+                #[cfg(feature = "meta")]
+                (slot.meta.is_some(), "meta"),
+            ];
+            for &(set, flag) in marks {
+                if set {
+                    flags.push(flag.to_string());
+                }
+            }
+            #[cfg(feature = "deprecation")]
+            if slot.deprecation.is_some() {
+                flags.push("deprecated".to_string());
+            }
+            #[cfg(feature = "permission")]
+            if let Some(permission) = &slot.permission {
+                flags.push(format!("needs {:?}", permission));
+            }
+            #[cfg(feature = "provenance")]
+            match &slot.source {
+                Source::Defaults => {}
+                Source::Plugin(name) => flags.push(format!("from plugin {:?}", name)),
+                other => flags.push(format!("from {:?}", other)),
+            }
+            if !flags.is_empty() {
+                write!(out, " ({})", flags.join(", "))?;
+            }
+            out.push('\n');
+            #[cfg(feature = "ui")]
+            if let Some(ui) = &slot.ui {
+                writeln!(out, "    ui: {:?}", ui)?;
+            }
+        }
+        writeln!(out, "wrap: {:?}", self.wrap)?;
+        if !self.bookmarks.is_empty() {
+            out.push_str("bookmarks:\n");
+            for (name, &id) in self.bookmarks.iter() {
+                match self.index_of_id(id) {
+                    Some(i) => writeln!(out, "  {}: {}", name, i)?,
+                    None => writeln!(out, "  {}: removed", name)?,
+                }
+            }
+        }
+        #[cfg(feature = "transitions")]
+        self.write_transitions(out)?;
+        Ok(())
+    }
+
+    #[cfg(feature = "transitions")]
+    fn write_transitions(&self, out: &mut String) -> std::fmt::Result {
+        let mut rules: Vec<_> = self
+            .transitions
+            .describe()
+            .filter_map(|(from, to, kind)| {
+                Some((self.index_of_id(from)?, self.index_of_id(to)?, kind))
+            })
+            .collect();
+        rules.sort();
+        if !rules.is_empty() {
+            out.push_str("transitions:\n");
+            for (from, to, kind) in rules {
+                writeln!(out, "  {} -> {}: {}", from, to, kind)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "expiry", feature = "meta"))]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn leaves_out_volatile_data() {
+        let build = |offset: u64| {
+            let mut ka = KeyArray::new(["a", "b"]);
+            // different ids, generations and expiry times
+            for _ in 0..offset {
+                ka.push("x");
+                ka.remove(2);
+            }
+            ka.push_with_ttl("c", Duration::from_secs(offset + 1), Instant::now());
+            ka.set_meta(0, offset);
+            ka
+        };
+        let (a, b) = (build(0), build(3));
+        assert_eq!(a.to_canonical_string(), b.to_canonical_string());
+        assert_eq!(
+            a.to_canonical_string(),
+            "keys:\n  0: \"a\" (current, meta)\n  1: \"b\"\n  2: \"c\" (expires)\nwrap: Wrap\n"
+        );
+    }
+}
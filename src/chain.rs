@@ -0,0 +1,238 @@
+//! Several KeyArrays navigated as one list.
+//!
+//! A [`Chain`] puts its parts one after another, like "recent" above "all",
+//! and offers a single selection over them: global indices count the keys
+//! of every part in order, [`next`](Chain::next) and [`prev`](Chain::prev)
+//! cross from one part into the next, and a change is made on the part
+//! owning the key, which becomes the active one. Each part keeps its own
+//! observers, disabled keys and other settings. [`rows`](Chain::rows) lists
+//! what to draw, with a [`Row::Separator`] between non-empty parts.
+//!
+//! ```
+//! use keyarray::chain::{Chain, Row};
+//! use keyarray::KeyArray;
+//!
+//! let recent = KeyArray::new(["notes.md"]);
+//! let all = KeyArray::new(["main.rs", "notes.md", "lib.rs"]);
+//! let mut files = Chain::new([recent, all]);
+//! files.next();
+//! assert_eq!((files.current(), files.current_index()), (&"main.rs", 1));
+//! assert_eq!(files.active_part(), 1);
+//!
+//! files.change(3);
+//! assert_eq!(files.part(1).current(), &"lib.rs");
+//! assert_eq!(files.rows()[1], Row::Separator);
+//! ```
+
+use crate::error::KeyArrayError;
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+
+/// One line of a [`Chain`] as drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Row<'a, K> {
+    Key {
+        /// Global index, as taken by [`Chain::change`].
+        index: usize,
+        key: &'a K,
+        current: bool,
+    },
+    /// Between two non-empty parts; never selected.
+    Separator,
+}
+
+/// KeyArrays presented as one navigable list; see the
+/// [module docs](self).
+#[derive(Clone)]
+pub struct Chain<K> {
+    parts: Vec<KeyArray<K>>,
+    active: usize,
+}
+
+impl<K> Debug for Chain<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Chain")
+            .field("parts", &self.parts)
+            .field("active", &self.active)
+            .finish()
+    }
+}
+
+impl<K> Chain<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Chain `parts` in order; the first non-empty one is active. Panics if
+    /// every part is empty.
+    pub fn new(parts: impl IntoIterator<Item = KeyArray<K>>) -> Self {
+        let parts: Vec<_> = parts.into_iter().collect();
+        let active = parts
+            .iter()
+            .position(|p| !p.is_empty())
+            .expect("Chain::new: no keys");
+        Chain { parts, active }
+    }
+
+    pub fn parts(&self) -> &[KeyArray<K>] {
+        &self.parts
+    }
+
+    /// Panics if `part` is out of bounds.
+    pub fn part(&self, part: usize) -> &KeyArray<K> {
+        &self.parts[part]
+    }
+
+    /// Changing the selection through the part does not make it active.
+    /// Panics if `part` is out of bounds.
+    pub fn part_mut(&mut self, part: usize) -> &mut KeyArray<K> {
+        &mut self.parts[part]
+    }
+
+    pub fn into_parts(self) -> Vec<KeyArray<K>> {
+        self.parts
+    }
+
+    /// Index of the part owning the selection.
+    pub fn active_part(&self) -> usize {
+        self.active
+    }
+
+    /// Number of keys over all parts.
+    pub fn len(&self) -> usize {
+        self.parts.iter().map(KeyArray::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parts.iter().all(KeyArray::is_empty)
+    }
+
+    /// The part and index within it of global index `i`.
+    pub fn locate(&self, mut i: usize) -> Option<(usize, usize)> {
+        for (part, keys) in self.parts.iter().enumerate() {
+            if i < keys.len() {
+                return Some((part, i));
+            }
+            i -= keys.len();
+        }
+        None
+    }
+
+    /// The global index of index `i` of `part`.
+    pub fn global_index(&self, part: usize, i: usize) -> Option<usize> {
+        let keys = self.parts.get(part)?;
+        (i < keys.len()).then(|| self.parts[..part].iter().map(KeyArray::len).sum::<usize>() + i)
+    }
+
+    /// The active part's current key. Panics if that part has been
+    /// emptied.
+    pub fn current(&self) -> &K {
+        self.parts[self.active].current()
+    }
+
+    /// Global index of the current key.
+    pub fn current_index(&self) -> usize {
+        let local = self.parts[self.active].current_index();
+        self.global_index(self.active, local)
+            .expect("Chain::current_index: active part is empty")
+    }
+
+    /// Select the key at global index `i` in the part owning it. Panics if
+    /// `i` is out of bounds or the part refuses the change.
+    pub fn change(&mut self, i: usize) {
+        self.try_change(i)
+            .unwrap_or_else(|e| panic!("Chain::change: {}", e))
+    }
+
+    /// Select the key at global index `i` in the part owning it, which
+    /// becomes active; the error of that part's
+    /// [`try_change`](KeyArray::try_change) otherwise.
+    pub fn try_change(&mut self, i: usize) -> Result<(), KeyArrayError> {
+        let (part, local) = self.locate(i).ok_or(KeyArrayError::IndexOutOfBounds {
+            index: i,
+            len: self.len(),
+        })?;
+        self.parts[part].try_change(local)?;
+        self.active = part;
+        Ok(())
+    }
+
+    /// Move to the following selectable key, crossing into the next part
+    /// and wrapping from the last key to the first.
+    pub fn next(&mut self) {
+        self.step(1);
+    }
+
+    /// Move to the preceding selectable key, crossing into the previous
+    /// part and wrapping from the first key to the last.
+    pub fn prev(&mut self) {
+        self.step(-1);
+    }
+
+    fn step(&mut self, by: isize) {
+        let len = self.len();
+        if self.parts[self.active].is_empty() {
+            return;
+        }
+        let from = self.current_index();
+        for n in 1..len {
+            let i = (from as isize + by * n as isize).rem_euclid(len as isize) as usize;
+            let (part, local) = self.locate(i).expect("in bounds");
+            if self.parts[part].selectable(local) && self.try_change(i).is_ok() {
+                return;
+            }
+        }
+    }
+
+    /// The visible keys of every part in order, with a separator between
+    /// non-empty parts. Hidden keys are left out; their global indices
+    /// still count.
+    pub fn rows(&self) -> Vec<Row<'_, K>> {
+        let mut rows = Vec::new();
+        let mut index = 0;
+        for (part, keys) in self.parts.iter().enumerate() {
+            if !keys.is_empty() && !rows.is_empty() {
+                rows.push(Row::Separator);
+            }
+            for (i, key) in keys.keys().iter().enumerate() {
+                if !keys.slots[i].is_hidden() {
+                    rows.push(Row::Key {
+                        index: index + i,
+                        key,
+                        current: part == self.active && i == keys.idx,
+                    });
+                }
+            }
+            index += keys.len();
+        }
+        rows
+    }
+}
+
+#[cfg(all(test, feature = "disable"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_across_parts() {
+        let mut recent = KeyArray::new(["b"]);
+        recent.push("x");
+        recent.disable(1);
+        let mut chain = Chain::new([KeyArray::new(["a"]), recent]);
+        chain.next();
+        chain.next();
+        assert_eq!((chain.current(), chain.current_index()), (&"a", 0));
+        chain.prev();
+        assert_eq!((chain.active_part(), chain.current()), (1, &"b"));
+        // the part's own error, with its own index
+        assert_eq!(chain.try_change(2), Err(KeyArrayError::Disabled { index: 1 }));
+        assert_eq!(
+            chain.try_change(3),
+            Err(KeyArrayError::IndexOutOfBounds { index: 3, len: 3 })
+        );
+        assert_eq!((chain.locate(2), chain.global_index(1, 1)), (Some((1, 1)), Some(2)));
+        assert_eq!(chain.rows().len(), 4);
+    }
+}
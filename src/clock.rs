@@ -0,0 +1,164 @@
+//! Injectable time source for time-based features.
+//!
+//! Time-based methods take a [`Clock`] rather than calling
+//! `Instant::now()` themselves, so tests can drive time by hand and
+//! firmware can count ticks instead of reading a system timer.
+//!
+//! ```
+//! # #[cfg(feature = "expiry")] {
+//! use keyarray::clock::{Clock, MockClock};
+//! use keyarray::KeyArray;
+//! use std::time::Duration;
+//!
+//! let clock = MockClock::new();
+//! let mut ka = KeyArray::new(["wired"]);
+//! ka.push_with_ttl_on("guest", Duration::from_secs(30), &clock);
+//! clock.advance(Duration::from_secs(31));
+//! assert!(ka.purge_expired_on(&clock).is_some());
+//! # }
+//! ```
+
+#[cfg(feature = "expiry")]
+use crate::expiry::Expired;
+#[cfg(feature = "expiry")]
+use crate::id::KeyId;
+#[cfg(feature = "expiry")]
+use crate::KeyArray;
+#[cfg(feature = "expiry")]
+use std::fmt::{Debug, Display};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A source of the current time.
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// The system's monotonic clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when told to, for tests and simulations.
+#[derive(Debug)]
+pub struct MockClock {
+    start: Instant,
+    // nanoseconds since `start`
+    elapsed: AtomicU64,
+}
+
+impl MockClock {
+    /// Start at the real current time, then stand still.
+    pub fn new() -> Self {
+        MockClock {
+            start: Instant::now(),
+            elapsed: AtomicU64::new(0),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        let nanos = u64::try_from(by.as_nanos()).unwrap_or(u64::MAX);
+        self.elapsed.fetch_add(nanos, Ordering::Relaxed);
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.start + Duration::from_nanos(self.elapsed.load(Ordering::Relaxed))
+    }
+}
+
+/// A clock driven by a periodic tick, such as a timer interrupt on a board
+/// without a wall clock: each [`tick`](Self::tick) moves it on by a fixed
+/// period.
+#[derive(Debug)]
+pub struct TickClock {
+    start: Instant,
+    period: Duration,
+    ticks: AtomicU64,
+}
+
+impl TickClock {
+    pub fn new(period: Duration) -> Self {
+        TickClock {
+            start: Instant::now(),
+            period,
+            ticks: AtomicU64::new(0),
+        }
+    }
+
+    pub fn tick(&self) {
+        self.ticks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks.load(Ordering::Relaxed)
+    }
+}
+
+impl Clock for TickClock {
+    fn now(&self) -> Instant {
+        let ticks = u32::try_from(self.ticks()).unwrap_or(u32::MAX);
+        self.start + self.period * ticks
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+}
+
+#[cfg(feature = "expiry")]
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// [`push_with_ttl`](Self::push_with_ttl) from `clock`'s current time.
+    pub fn push_with_ttl_on(&mut self, key: K, ttl: Duration, clock: &impl Clock) -> KeyId {
+        self.push_with_ttl(key, ttl, clock.now())
+    }
+
+    /// [`purge_expired`](Self::purge_expired) at `clock`'s current time.
+    pub fn purge_expired_on(&mut self, clock: &impl Clock) -> Option<Expired<K>> {
+        self.purge_expired(clock.now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clocks_move_only_when_driven() {
+        let mock = Arc::new(MockClock::new());
+        let t0 = mock.now();
+        mock.advance(Duration::from_millis(1500));
+        assert_eq!(mock.now() - t0, Duration::from_millis(1500));
+
+        let ticks = TickClock::new(Duration::from_millis(10));
+        let t0 = ticks.now();
+        (0..3).for_each(|_| ticks.tick());
+        let shared: &dyn Clock = &ticks;
+        assert_eq!(shared.now() - t0, Duration::from_millis(30));
+        assert!(SystemClock.now() >= t0);
+    }
+}
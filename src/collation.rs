@@ -0,0 +1,79 @@
+//! Locale-aware ordering for string keys, backed by ICU4X collation data.
+//!
+//! Plain `str` ordering compares code points, which puts "Österreich" after
+//! "Zypern". Collating follows the rules of the user's language instead:
+//!
+//! ```
+//! use keyarray::KeyArray;
+//!
+//! let mut countries = KeyArray::new(["Zypern", "Österreich", "Deutschland"]);
+//! countries.sort_collated("de").unwrap();
+//! assert_eq!(countries.keys(), &["Deutschland", "Österreich", "Zypern"]);
+//! assert_eq!(countries.current(), &"Zypern");
+//! ```
+
+use crate::KeyArray;
+use icu_collator::options::CollatorOptions;
+use icu_collator::Collator;
+use icu_locale_core::Locale;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+/// Why a collated sort could not run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CollationError {
+    /// The locale string is not a valid BCP-47 identifier.
+    InvalidLocale(String),
+    /// No collation data is available for the locale.
+    Unsupported(String),
+}
+
+impl Display for CollationError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            CollationError::InvalidLocale(l) => write!(f, "invalid locale {:?}", l),
+            CollationError::Unsupported(l) => write!(f, "no collation data for locale {:?}", l),
+        }
+    }
+}
+
+impl std::error::Error for CollationError {}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display + AsRef<str>,
+{
+    /// Sort the keys by the collation rules of `locale` (e.g. `"de"`,
+    /// `"sv-SE"`). The current key stays current.
+    pub fn sort_collated(&mut self, locale: &str) -> Result<(), CollationError> {
+        let parsed: Locale = locale
+            .parse()
+            .map_err(|_| CollationError::InvalidLocale(locale.to_string()))?;
+        let collator = Collator::try_new((&parsed).into(), CollatorOptions::default())
+            .map_err(|_| CollationError::Unsupported(locale.to_string()))?;
+        self.sort_by(|a, b| collator.compare(a.as_ref(), b.as_ref()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swedish_puts_o_umlaut_last() {
+        let mut ka = KeyArray::new(["Öland", "Oslo", "Zürich"]);
+        ka.sort_collated("sv").unwrap();
+        assert_eq!(ka.keys(), &["Oslo", "Zürich", "Öland"]);
+        ka.sort_collated("de").unwrap();
+        assert_eq!(ka.keys(), &["Öland", "Oslo", "Zürich"]);
+    }
+
+    #[test]
+    fn rejects_bad_locale() {
+        let mut ka = KeyArray::new(["a"]);
+        assert!(matches!(
+            ka.sort_collated("not a locale"),
+            Err(CollationError::InvalidLocale(_))
+        ));
+    }
+}
@@ -0,0 +1,165 @@
+//! Migrating hand-rolled `(Vec<K>, usize)` selections to KeyArray.
+//!
+//! A selection kept as a vector and an index converts with `TryFrom`, which
+//! refuses an empty vector or an index out of bounds, and converts back
+//! with `From`. For code bases too large to switch in one go,
+//! [`VecSelection`] wraps a KeyArray and keeps the Vec-style names such
+//! code tends to use (`selected`, `as_slice`, `get`, ...) next to the whole
+//! KeyArray API, reachable through `Deref`. Each old name is deprecated
+//! with a note naming its KeyArray replacement, so the compiler lists the
+//! call sites left to migrate.
+//!
+//! ```
+//! #![allow(deprecated)]
+//! use keyarray::compat::VecSelection;
+//! use keyarray::KeyArray;
+//!
+//! let legacy = (vec!["Low", "High"], 1);
+//! let ka = KeyArray::try_from(legacy.clone())?;
+//! assert_eq!(<(Vec<_>, usize)>::from(ka), legacy);
+//!
+//! let mut quality = VecSelection::try_from(legacy)?;
+//! quality.set_selected(0);
+//! assert_eq!((quality.selected(), quality.get(1)), (0, Some(&"High")));
+//! quality.next();
+//! assert_eq!(quality.current(), &"High");
+//! # Ok::<(), keyarray::KeyArrayError>(())
+//! ```
+
+use crate::error::KeyArrayError;
+use crate::KeyArray;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+impl<K> TryFrom<(Vec<K>, usize)> for KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    type Error = KeyArrayError;
+
+    /// As [`try_new_with`](KeyArray::try_new_with).
+    fn try_from((keys, selected): (Vec<K>, usize)) -> Result<Self, Self::Error> {
+        KeyArray::try_new_with(keys, selected)
+    }
+}
+
+impl<K> From<KeyArray<K>> for (Vec<K>, usize)
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// The keys and the current index; everything else is dropped.
+    fn from(keys: KeyArray<K>) -> Self {
+        let selected = keys.idx;
+        let keys = Arc::try_unwrap(keys.keys).unwrap_or_else(|shared| (*shared).clone());
+        (keys, selected)
+    }
+}
+
+/// A KeyArray with the method names of a `(Vec<K>, usize)` selection; see
+/// the [module docs](self).
+#[derive(Clone)]
+pub struct VecSelection<K> {
+    keys: KeyArray<K>,
+}
+
+impl<K> Debug for VecSelection<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        Debug::fmt(&self.keys, f)
+    }
+}
+
+impl<K> From<KeyArray<K>> for VecSelection<K> {
+    fn from(keys: KeyArray<K>) -> Self {
+        VecSelection { keys }
+    }
+}
+
+impl<K> TryFrom<(Vec<K>, usize)> for VecSelection<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    type Error = KeyArrayError;
+
+    fn try_from(legacy: (Vec<K>, usize)) -> Result<Self, Self::Error> {
+        KeyArray::try_from(legacy).map(VecSelection::from)
+    }
+}
+
+impl<K> Deref for VecSelection<K> {
+    type Target = KeyArray<K>;
+
+    fn deref(&self) -> &KeyArray<K> {
+        &self.keys
+    }
+}
+
+impl<K> DerefMut for VecSelection<K> {
+    fn deref_mut(&mut self) -> &mut KeyArray<K> {
+        &mut self.keys
+    }
+}
+
+impl<K> VecSelection<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    pub fn into_inner(self) -> KeyArray<K> {
+        self.keys
+    }
+
+    #[deprecated(note = "use `KeyArray::current_index`")]
+    pub fn selected(&self) -> usize {
+        self.keys.current_index()
+    }
+
+    /// Panics as [`KeyArray::change`] does.
+    #[deprecated(note = "use `KeyArray::change`")]
+    pub fn set_selected(&mut self, i: usize) {
+        self.keys.change(i)
+    }
+
+    #[deprecated(note = "use `KeyArray::current`")]
+    pub fn selected_item(&self) -> &K {
+        self.keys.current()
+    }
+
+    #[deprecated(note = "use `KeyArray::keys`")]
+    pub fn as_slice(&self) -> &[K] {
+        self.keys.keys()
+    }
+
+    #[deprecated(note = "use `KeyArray::keys().to_vec()`")]
+    pub fn to_vec(&self) -> Vec<K> {
+        self.keys.keys().to_vec()
+    }
+
+    #[deprecated(note = "use `KeyArray::keys().get(i)`")]
+    pub fn get(&self, i: usize) -> Option<&K> {
+        self.keys.keys().get(i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(deprecated)]
+    fn converts_both_ways() {
+        let empty = KeyArray::<&str>::try_from((Vec::new(), 0));
+        assert_eq!(empty.unwrap_err(), KeyArrayError::Empty);
+        let oob = VecSelection::try_from((vec!["a"], 1)).unwrap_err();
+        assert_eq!(oob, KeyArrayError::IndexOutOfBounds { index: 1, len: 1 });
+
+        let mut sel = VecSelection::try_from((vec!["a", "b"], 0)).unwrap();
+        sel.push("c");
+        sel.set_selected(2);
+        assert_eq!((sel.selected_item(), sel.as_slice().len()), (&"c", 3));
+        let shared = sel.clone();
+        assert_eq!(<(Vec<_>, usize)>::from(sel.into_inner()), (shared.to_vec(), 2));
+    }
+}
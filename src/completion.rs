@@ -0,0 +1,153 @@
+//! Shell completion data for command-line options backed by a KeyArray.
+//!
+//! When a flag like `--mode` takes its values from a KeyArray built at run
+//! time, the shell can only complete it by asking the program.
+//! [`completions`](KeyArray::completions) collects the values to offer:
+//! every key that can be selected, so soft-removed, disabled and locked
+//! keys are left out, each with its tooltip as help text when the `ui`
+//! feature is on. [`render`](Completions::render) writes them in the form
+//! each shell's dynamic completion reads, one candidate per line, ready to
+//! print from a hidden `--complete-mode` flag or a `clap_complete` value
+//! hook.
+//!
+//! ```
+//! # #[cfg(feature = "tombstone")] {
+//! use keyarray::completion::Shell;
+//! use keyarray::KeyArray;
+//!
+//! let mut mode = KeyArray::new(["fast", "safe", "debug build"]);
+//! mode.soft_remove(1);
+//! let values = mode.completions_with_aliases(|_, key| match *key {
+//!     "fast" => vec!["f".to_string()],
+//!     _ => Vec::new(),
+//! });
+//! assert_eq!(values.render(Shell::Bash), "fast\nf\ndebug\\ build\n");
+//! assert_eq!(values.render(Shell::Fish), "fast\nf\talias for fast\ndebug build\n");
+//! # }
+//! ```
+
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+
+/// A shell to write completions for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Shell {
+    /// One word per line, with the characters bash splits or expands on
+    /// escaped, for `COMPREPLY=($(...))` or `compgen -W`.
+    Bash,
+    /// `value:help` per line, for `_describe`; colons in values escaped.
+    Zsh,
+    /// `value<TAB>help` per line, for `complete -a`.
+    Fish,
+}
+
+/// One value to offer.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Candidate {
+    pub value: String,
+    pub help: Option<String>,
+}
+
+/// The values a KeyArray offers for completion, in key order; from
+/// [`completions`](KeyArray::completions).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Completions {
+    candidates: Vec<Candidate>,
+}
+
+impl Completions {
+    pub fn candidates(&self) -> &[Candidate] {
+        &self.candidates
+    }
+
+    /// The candidates in `shell`'s format, each line ending in `\n`.
+    pub fn render(&self, shell: Shell) -> String {
+        let mut out = String::new();
+        for Candidate { value, help } in &self.candidates {
+            match shell {
+                Shell::Bash => out.push_str(&bash_escape(value)),
+                Shell::Zsh => {
+                    out.push_str(&value.replace('\\', "\\\\").replace(':', "\\:"));
+                    if let Some(help) = help {
+                        out.push(':');
+                        out.push_str(help);
+                    }
+                }
+                Shell::Fish => {
+                    out.push_str(value);
+                    if let Some(help) = help {
+                        out.push('\t');
+                        out.push_str(help);
+                    }
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+fn bash_escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c.is_whitespace() || "\\'\"`$&|;<>()[]{}*?!~#".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Every selectable key, written with `{}`; see the
+    /// [module docs](self).
+    pub fn completions(&self) -> Completions {
+        self.completions_with_aliases(|_, _| Vec::new())
+    }
+
+    /// [`completions`](Self::completions), each key followed by the names
+    /// `aliases(index, key)` returns for it, described as aliases.
+    pub fn completions_with_aliases<F>(&self, aliases: F) -> Completions
+    where
+        F: Fn(usize, &K) -> Vec<String>,
+    {
+        let mut candidates = Vec::new();
+        for (i, key) in self.keys.iter().enumerate().filter(|&(i, _)| self.selectable(i)) {
+            let value = key.to_string();
+            #[cfg(feature = "ui")]
+            let help = self.ui(i).and_then(|ui| ui.tooltip.clone());
+            #[cfg(not(feature = "ui"))]
+            let help = None;
+            let aliases = aliases(i, key).into_iter().map(|alias| Candidate {
+                value: alias,
+                help: Some(format!("alias for {}", value)),
+            });
+            candidates.push(Candidate { value: value.clone(), help });
+            candidates.extend(aliases);
+        }
+        Completions { candidates }
+    }
+}
+
+#[cfg(all(test, feature = "disable", feature = "permission"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaves_out_unselectable_keys() {
+        let mut ka = KeyArray::new(["a:b", "c", "d"]);
+        ka.disable(1);
+        ka.require_permission(2, "admin");
+        #[cfg(feature = "ui")]
+        ka.set_ui(0, crate::ui::KeyUi::new().tooltip("first"));
+        let out = ka.completions().render(Shell::Zsh);
+        #[cfg(feature = "ui")]
+        assert_eq!(out, "a\\:b:first\n");
+        #[cfg(not(feature = "ui"))]
+        assert_eq!(out, "a\\:b\n");
+        assert_eq!(bash_escape("it's $HOME"), "it\\'s\\ \\$HOME");
+    }
+}
@@ -0,0 +1,261 @@
+//! Keys that must be confirmed before they become current.
+//!
+//! Marking a key such as "Factory Reset" with
+//! [`require_confirmation`](KeyArray::require_confirmation) makes
+//! [`request_change`](KeyArray::request_change) hand back a
+//! [`PendingConfirmation`] instead of selecting it. The selection moves
+//! only when the token is [`confirm`](PendingConfirmation::confirm)ed
+//! before its deadline; a late, cancelled or dropped token changes nothing.
+//! Each step yields a [`ConfirmationEvent`] for logs and UIs. Plain
+//! [`change`](KeyArray::change) ignores the requirement.
+//!
+//! ```
+//! use keyarray::confirm::{ConfirmationEvent, Requested};
+//! use keyarray::KeyArray;
+//! use std::time::{Duration, Instant};
+//!
+//! let mut menu = KeyArray::new(["Back", "Factory Reset"]);
+//! menu.require_confirmation(1);
+//!
+//! let now = Instant::now();
+//! let Requested::Pending(token) = menu.request_change(1, Duration::from_secs(5), now)? else {
+//!     unreachable!();
+//! };
+//! assert_eq!(menu.current(), &"Back");
+//!
+//! let event = token.confirm(&mut menu, now + Duration::from_secs(2));
+//! assert!(matches!(event, ConfirmationEvent::Confirmed(_)));
+//! assert_eq!(menu.current(), &"Factory Reset");
+//! # Ok::<(), keyarray::KeyArrayError>(())
+//! ```
+
+use crate::check_index;
+use crate::error::KeyArrayError;
+use crate::event::ChangeEvent;
+use crate::id::KeyId;
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Reason attached to the change event of a confirmed change.
+pub const REASON: &str = "confirmed";
+
+/// The outcome of [`request_change`](KeyArray::request_change).
+#[derive(Debug)]
+pub enum Requested<K> {
+    /// The key needs no confirmation and is now current; the event is
+    /// `None` if it already was.
+    Changed(Option<ChangeEvent<K>>),
+    /// The key is waiting for [`confirm`](PendingConfirmation::confirm).
+    Pending(PendingConfirmation<K>),
+}
+
+/// A change waiting to be confirmed. Dropping it cancels the change.
+#[derive(Debug)]
+#[must_use = "the change only happens once the token is confirmed"]
+pub struct PendingConfirmation<K> {
+    id: KeyId,
+    index: usize,
+    key: K,
+    deadline: Instant,
+}
+
+/// What happened to a change that needs confirmation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfirmationEvent<K> {
+    /// Key `index` is waiting to be confirmed until `deadline`.
+    Pending {
+        index: usize,
+        key: K,
+        deadline: Instant,
+    },
+    /// The change was confirmed and made; the event carries [`REASON`].
+    Confirmed(ChangeEvent<K>),
+    /// The change was not made.
+    Cancelled { id: KeyId, key: K, cause: CancelCause },
+}
+
+/// Why a pending change was not made.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CancelCause {
+    /// [`cancel`](PendingConfirmation::cancel) was called.
+    Cancelled,
+    /// It was confirmed after its deadline.
+    TimedOut,
+    /// The key was removed in the meantime.
+    Removed,
+    /// Selecting the key failed, e.g. because it was disabled.
+    Refused(KeyArrayError),
+}
+
+impl<K: Clone> PendingConfirmation<K> {
+    /// The index of the key when the change was requested.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The key waiting to become current.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn deadline(&self) -> Instant {
+        self.deadline
+    }
+
+    /// Whether confirming at `now` would be too late.
+    pub fn is_expired(&self, now: Instant) -> bool {
+        now > self.deadline
+    }
+
+    /// The [`ConfirmationEvent::Pending`] event for this token.
+    pub fn event(&self) -> ConfirmationEvent<K> {
+        ConfirmationEvent::Pending {
+            index: self.index,
+            key: self.key.clone(),
+            deadline: self.deadline,
+        }
+    }
+
+    /// Make the key current in `keys`, unless the deadline has passed at
+    /// `now` or the key can no longer be selected.
+    pub fn confirm(self, keys: &mut KeyArray<K>, now: Instant) -> ConfirmationEvent<K>
+    where
+        K: PartialEq + Debug + Display,
+    {
+        if self.is_expired(now) {
+            return self.cancelled(CancelCause::TimedOut);
+        }
+        let Some(i) = keys.index_of_id(self.id) else {
+            return self.cancelled(CancelCause::Removed);
+        };
+        let previous = keys.current_index();
+        if let Err(e) = keys.try_change(i) {
+            return self.cancelled(CancelCause::Refused(e));
+        }
+        // a deprecated key may have been swapped for its replacement
+        ConfirmationEvent::Confirmed(ChangeEvent {
+            previous,
+            current: keys.current_index(),
+            key: keys.current().clone(),
+            reason: Some(REASON.into()),
+        })
+    }
+
+    /// Give up the change.
+    pub fn cancel(self) -> ConfirmationEvent<K> {
+        self.cancelled(CancelCause::Cancelled)
+    }
+
+    fn cancelled(self, cause: CancelCause) -> ConfirmationEvent<K> {
+        ConfirmationEvent::Cancelled {
+            id: self.id,
+            key: self.key,
+            cause,
+        }
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Require key `i` to be confirmed before
+    /// [`request_change`](Self::request_change) selects it. Panics if `i`
+    /// is out of bounds.
+    pub fn require_confirmation(&mut self, i: usize) {
+        self.set_confirm("require_confirmation", i, true);
+    }
+
+    /// Let key `i` be selected without confirmation again. Panics if `i` is
+    /// out of bounds.
+    pub fn clear_confirmation(&mut self, i: usize) {
+        self.set_confirm("clear_confirmation", i, false);
+    }
+
+    /// Whether key `i` must be confirmed; `false` if out of bounds.
+    pub fn requires_confirmation(&self, i: usize) -> bool {
+        self.slots.get(i).is_some_and(|s| s.confirm)
+    }
+
+    /// Select key `i`, or, if it requires confirmation and is not already
+    /// current, return a token that selects it when confirmed within
+    /// `timeout` of `now`. Fails as [`try_change`](Self::try_change) does.
+    pub fn request_change(
+        &mut self,
+        i: usize,
+        timeout: Duration,
+        now: Instant,
+    ) -> Result<Requested<K>, KeyArrayError> {
+        check_index(i, self.len())?;
+        self.check_selectable(i)?;
+        if self.slots[i].confirm && i != self.idx {
+            return Ok(Requested::Pending(PendingConfirmation {
+                id: self.slots[i].id,
+                index: i,
+                key: self.keys[i].clone(),
+                deadline: now + timeout,
+            }));
+        }
+        let previous = self.idx;
+        self.try_change(i)?;
+        Ok(Requested::Changed((previous != self.idx).then(|| ChangeEvent {
+            previous,
+            current: self.idx,
+            key: self.current().clone(),
+            reason: None,
+        })))
+    }
+
+    fn set_confirm(&mut self, method: &str, i: usize, confirm: bool) {
+        check_index(i, self.len()).unwrap_or_else(|e| panic!("KeyArray::{}: {}", method, e));
+        if self.slots[i].confirm != confirm {
+            Arc::make_mut(&mut self.slots)[i].confirm = confirm;
+            self.touch();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending<K>(requested: Requested<K>) -> PendingConfirmation<K> {
+        match requested {
+            Requested::Pending(token) => token,
+            Requested::Changed(_) => panic!("changed without confirmation"),
+        }
+    }
+
+    #[test]
+    fn only_timely_confirmation_changes() {
+        let now = Instant::now();
+        let secs = Duration::from_secs;
+        let mut ka = KeyArray::new(["Back", "Reset", "Wipe"]);
+        ka.require_confirmation(1);
+        ka.require_confirmation(2);
+        assert!(matches!(
+            ka.request_change(0, secs(1), now),
+            Ok(Requested::Changed(None))
+        ));
+
+        let late = pending(ka.request_change(1, secs(1), now).unwrap());
+        assert!(matches!(late.event(), ConfirmationEvent::Pending { index: 1, .. }));
+        let timed_out = late.confirm(&mut ka, now + secs(2));
+        assert!(matches!(
+            timed_out,
+            ConfirmationEvent::Cancelled { cause: CancelCause::TimedOut, .. }
+        ));
+        let cancelled = pending(ka.request_change(1, secs(1), now).unwrap()).cancel();
+        assert!(matches!(cancelled, ConfirmationEvent::Cancelled { key: "Reset", .. }));
+
+        let wipe = pending(ka.request_change(2, secs(1), now).unwrap());
+        ka.remove(1);
+        let ConfirmationEvent::Confirmed(event) = wipe.confirm(&mut ka, now) else {
+            panic!("not confirmed");
+        };
+        assert_eq!((event.previous, event.current, event.key), (0, 1, "Wipe"));
+        assert_eq!(ka.current(), &"Wipe");
+    }
+}
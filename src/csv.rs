@@ -0,0 +1,163 @@
+//! Key lists kept in spreadsheets.
+//!
+//! The CSV needs a header row with a `key` column. Optional `icon`,
+//! `tooltip` and `badge` columns fill in each key's [`KeyUi`]; empty cells
+//! and any other columns are ignored. Keys are parsed with `FromStr`.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//!
+//! let sheet = "key,icon,owner\nOn,power,ops\nOff,,ops\n";
+//! let ka: KeyArray<String> = KeyArray::from_csv_reader(sheet.as_bytes()).unwrap();
+//! assert_eq!(ka.keys(), ["On", "Off"]);
+//! assert_eq!(ka.ui(0).unwrap().icon.as_deref(), Some("power"));
+//!
+//! let mut out = Vec::new();
+//! ka.to_csv_writer(&mut out).unwrap();
+//! assert_eq!(out, b"key,icon,tooltip,badge\nOn,power,,\nOff,,,\n");
+//! ```
+
+use crate::ui::KeyUi;
+use crate::KeyArray;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::io;
+use std::str::FromStr;
+
+const KEY: &str = "key";
+const UI_COLUMNS: [&str; 3] = ["icon", "tooltip", "badge"];
+
+/// Why a CSV key list could not be read or written.
+#[derive(Debug)]
+pub enum CsvError {
+    /// Malformed CSV or an I/O failure; carries its own position.
+    Csv(::csv::Error),
+    /// The header row has no `key` column.
+    MissingKeyColumn,
+    /// The sheet has no rows.
+    NoKeys,
+    /// The key on `line` (1-based, counting the header) did not parse.
+    InvalidKey { line: u64, text: String },
+}
+
+impl Display for CsvError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            CsvError::Csv(e) => write!(f, "{}", e),
+            CsvError::MissingKeyColumn => write!(f, "no {:?} column in header", KEY),
+            CsvError::NoKeys => f.write_str("no keys"),
+            CsvError::InvalidKey { line, text } => {
+                write!(f, "line {}: invalid key {:?}", line, text)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CsvError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CsvError::Csv(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<::csv::Error> for CsvError {
+    fn from(e: ::csv::Error) -> Self {
+        CsvError::Csv(e)
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display + FromStr,
+{
+    /// Read keys and their presentation hints from CSV, selecting the first
+    /// key. Stops at the first bad row.
+    pub fn from_csv_reader<R: io::Read>(reader: R) -> Result<Self, CsvError> {
+        let mut reader = ::csv::Reader::from_reader(reader);
+        let headers = reader.headers()?.clone();
+        let column = |name| headers.iter().position(|h| h.trim() == name);
+        let key_column = column(KEY).ok_or(CsvError::MissingKeyColumn)?;
+        let ui_columns = UI_COLUMNS.map(column);
+
+        let mut keys = Vec::new();
+        let mut uis = Vec::new();
+        for record in reader.records() {
+            let record = record?;
+            let text = record.get(key_column).unwrap_or_default();
+            let key = text.parse().map_err(|_| CsvError::InvalidKey {
+                line: record.position().map_or(0, |p| p.line()),
+                text: text.to_string(),
+            })?;
+            keys.push(key);
+            let [icon, tooltip, badge] = ui_columns.map(|c| {
+                let cell = record.get(c?)?;
+                (!cell.is_empty()).then(|| cell.to_string())
+            });
+            uis.push(KeyUi {
+                icon,
+                tooltip,
+                badge,
+                ..KeyUi::default()
+            });
+        }
+        let mut ka = KeyArray::try_new(keys).map_err(|_| CsvError::NoKeys)?;
+        for (i, ui) in uis.into_iter().enumerate() {
+            if ui != KeyUi::default() {
+                ka.set_ui(i, ui);
+            }
+        }
+        Ok(ka)
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Write the keys and their icon, tooltip and badge as CSV with a
+    /// header row. Colors are not written.
+    pub fn to_csv_writer<W: io::Write>(&self, writer: W) -> Result<(), CsvError> {
+        let mut writer = ::csv::Writer::from_writer(writer);
+        writer.write_record([KEY].iter().chain(&UI_COLUMNS))?;
+        for (i, key) in self.keys.iter().enumerate() {
+            let ui = self.ui(i);
+            let cell = |f: fn(&KeyUi) -> &Option<String>| {
+                ui.and_then(|ui| f(ui).as_deref()).unwrap_or_default()
+            };
+            let key = key.to_string();
+            writer.write_record([
+                key.as_str(),
+                cell(|ui| &ui.icon),
+                cell(|ui| &ui.tooltip),
+                cell(|ui| &ui.badge),
+            ])?;
+        }
+        writer.flush().map_err(::csv::Error::from)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_and_row_errors() {
+        let sheet = "badge , key\nnew,1\n,2\n";
+        let ka: KeyArray<u8> = KeyArray::from_csv_reader(sheet.as_bytes()).unwrap();
+        assert_eq!(ka.ui(0).unwrap().badge.as_deref(), Some("new"));
+        assert_eq!(ka.ui(1), None);
+        let mut out = Vec::new();
+        ka.to_csv_writer(&mut out).unwrap();
+        let back: KeyArray<u8> = KeyArray::from_csv_reader(out.as_slice()).unwrap();
+        assert_eq!((back.keys(), back.ui(0)), (ka.keys(), ka.ui(0)));
+
+        let err = KeyArray::<u8>::from_csv_reader("key\n1\nx\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, CsvError::InvalidKey { line: 3, ref text } if text == "x"));
+        let err = KeyArray::<u8>::from_csv_reader("id\n1\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, CsvError::MissingKeyColumn));
+        let err = KeyArray::<u8>::from_csv_reader("key\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, CsvError::NoKeys));
+    }
+}
@@ -0,0 +1,274 @@
+//! Cursor navigation shared by KeyArray and any other indexable container.
+//!
+//! Implement the four required methods and get wrapping `next`/`prev`,
+//! `advance` and `peek_*` for free:
+//!
+//! ```
+//! use keyarray::SelectionCursor;
+//! use std::collections::VecDeque;
+//!
+//! struct Recent {
+//!     items: VecDeque<String>,
+//!     at: usize,
+//! }
+//!
+//! impl SelectionCursor for Recent {
+//!     type Item = String;
+//!     fn item_count(&self) -> usize { self.items.len() }
+//!     fn cursor(&self) -> usize { self.at }
+//!     fn set_cursor(&mut self, i: usize) { self.at = i }
+//!     fn item(&self, i: usize) -> Option<&String> { self.items.get(i) }
+//! }
+//!
+//! let mut r = Recent { items: ["a".into(), "b".into()].into(), at: 0 };
+//! r.prev();
+//! assert_eq!(r.selected_item().map(String::as_str), Some("b"));
+//! ```
+//!
+//! KeyArray itself can also stop at the ends instead of wrapping, e.g. for a
+//! volume control:
+//!
+//! ```
+//! # #[cfg(feature = "std")] {
+//! use keyarray::{KeyArray, WrapMode};
+//!
+//! let mut volume = KeyArray::new(["Low", "Med", "High"]);
+//! volume.set_wrap_mode(WrapMode::Saturate);
+//! volume.advance_by(5).unwrap();
+//! volume.next();
+//! assert_eq!(volume.current(), &"High");
+//! # }
+//! ```
+
+#[cfg(feature = "std")]
+use crate::error::KeyArrayError;
+#[cfg(feature = "std")]
+use crate::KeyArray;
+#[cfg(feature = "std")]
+use core::fmt::{Debug, Display};
+use core::ops::Range;
+
+/// What [`KeyArray::next`], [`prev`](KeyArray::prev) and
+/// [`advance_by`](KeyArray::advance_by) do at either end of the keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WrapMode {
+    /// Continue from the other end.
+    #[default]
+    Wrap,
+    /// Stop at the end.
+    Saturate,
+    /// Refuse the move: `advance_by` returns [`KeyArrayError::PastEnd`].
+    Error,
+}
+
+/// A position within a list of items, with wrapping navigation.
+pub trait SelectionCursor {
+    type Item;
+
+    /// Number of items.
+    fn item_count(&self) -> usize;
+
+    /// Index of the selected item. Only meaningful when `item_count() > 0`.
+    fn cursor(&self) -> usize;
+
+    /// Move the cursor. Callers guarantee `i < item_count()`.
+    fn set_cursor(&mut self, i: usize);
+
+    /// Item at `i`, if in bounds.
+    fn item(&self, i: usize) -> Option<&Self::Item>;
+
+    /// The selected item, or `None` when there are no items.
+    fn selected_item(&self) -> Option<&Self::Item> {
+        self.item(self.cursor())
+    }
+
+    /// Index `offset` steps away from the cursor, wrapping at both ends.
+    /// `None` when there are no items.
+    fn offset_index(&self, offset: isize) -> Option<usize> {
+        let len = self.item_count();
+        if len == 0 {
+            return None;
+        }
+        let step = offset.rem_euclid(len as isize) as usize;
+        Some((self.cursor() + step) % len)
+    }
+
+    /// Move `offset` steps (negative moves backwards), wrapping.
+    fn advance(&mut self, offset: isize) {
+        if let Some(i) = self.offset_index(offset) {
+            self.set_cursor(i);
+        }
+    }
+
+    /// Move to the following item, wrapping from the last to the first.
+    fn next(&mut self) {
+        self.advance(1);
+    }
+
+    /// Move to the preceding item, wrapping from the first to the last.
+    fn prev(&mut self) {
+        self.advance(-1);
+    }
+
+    /// The item `next` would select, without moving.
+    fn peek_next(&self) -> Option<&Self::Item> {
+        self.item(self.offset_index(1)?)
+    }
+
+    /// The item `prev` would select, without moving.
+    fn peek_prev(&self) -> Option<&Self::Item> {
+        self.item(self.offset_index(-1)?)
+    }
+}
+
+/// The `size`-item window that keeps `current` visible, roughly centred.
+pub fn window_around(current: usize, len: usize, size: usize) -> Range<usize> {
+    if size >= len {
+        return 0..len;
+    }
+    let start = current.saturating_sub(size / 2).min(len - size);
+    start..start + size
+}
+
+#[cfg(feature = "std")]
+impl<K> SelectionCursor for KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    type Item = K;
+
+    fn item_count(&self) -> usize {
+        self.keys.len()
+    }
+
+    fn cursor(&self) -> usize {
+        self.idx
+    }
+
+    /// Selects as [`try_change`](KeyArray::try_change) does; a disabled or
+    /// locked key is ignored.
+    fn set_cursor(&mut self, i: usize) {
+        let _ = self.try_change(i);
+    }
+
+    fn item(&self, i: usize) -> Option<&K> {
+        self.keys.get(i)
+    }
+
+    /// [`advance_by`](KeyArray::advance_by), so unselectable keys are
+    /// skipped and the [`WrapMode`] is followed; an error leaves the
+    /// selection alone.
+    fn advance(&mut self, offset: isize) {
+        let _ = self.advance_by(offset);
+    }
+
+    /// [`KeyArray::next`].
+    fn next(&mut self) {
+        KeyArray::next(self);
+    }
+
+    /// [`KeyArray::prev`].
+    fn prev(&mut self) {
+        KeyArray::prev(self);
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    pub fn wrap_mode(&self) -> WrapMode {
+        self.wrap
+    }
+
+    pub fn set_wrap_mode(&mut self, wrap: WrapMode) {
+        self.wrap = wrap;
+    }
+
+    /// Move `offset` keys (negative moves backwards), skipping soft-removed
+    /// and disabled keys, and return the new index. What happens past either end follows
+    /// [`wrap_mode`](Self::wrap_mode); on error the selection is unchanged.
+    /// Rotation weights and health are not consulted.
+    pub fn advance_by(&mut self, offset: isize) -> Result<usize, KeyArrayError> {
+        let visible: Vec<usize> = (0..self.len()).filter(|&i| self.selectable(i)).collect();
+        let Some(at) = visible.iter().position(|&i| i == self.idx) else {
+            return Ok(self.idx);
+        };
+        let last = visible.len() as isize - 1;
+        let target = at as isize + offset;
+        let target = match self.wrap {
+            WrapMode::Wrap => target.rem_euclid(last + 1),
+            WrapMode::Saturate => target.clamp(0, last),
+            WrapMode::Error if (0..=last).contains(&target) => target,
+            WrapMode::Error => {
+                return Err(KeyArrayError::PastEnd {
+                    index: self.idx,
+                    offset,
+                })
+            }
+        };
+        self.jump(visible[target as usize]);
+        Ok(self.idx)
+    }
+
+    // The index `offset` keys from `at`, wrapping, whether or not it can be
+    // selected; navigation built on this skips those itself. There must be
+    // keys.
+    pub(crate) fn wrapped(&self, at: usize, offset: isize) -> usize {
+        (at as isize + offset).rem_euclid(self.len() as isize) as usize
+    }
+
+    // Make key `i`, in bounds, current without any checks.
+    pub(crate) fn jump(&mut self, i: usize) {
+        if self.idx != i {
+            self.idx = i;
+            self.touch();
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrapping_and_peeking() {
+        let mut ka = KeyArray::new(["A", "B", "C"]);
+        assert_eq!(ka.peek_prev(), Some(&"C"));
+        assert_eq!(ka.peek_next(), Some(&"B"));
+        ka.advance(-4);
+        assert_eq!(ka.current(), &"C");
+        ka.next();
+        assert_eq!(ka.current(), &"A");
+
+        let mut empty = KeyArray::new(["X"]);
+        empty.remove(0);
+        empty.next();
+        assert_eq!(empty.selected_item(), None);
+    }
+
+    #[cfg(feature = "tombstone")]
+    #[test]
+    fn wrap_modes() {
+        let mut ka = KeyArray::new(["A", "B", "C", "D"]);
+        assert_eq!(ka.advance_by(-1), Ok(3));
+        ka.soft_remove(2);
+        ka.set_wrap_mode(WrapMode::Saturate);
+        assert_eq!(ka.advance_by(-2), Ok(0));
+        ka.prev();
+        assert_eq!(ka.current(), &"A");
+
+        ka.set_wrap_mode(WrapMode::Error);
+        assert_eq!(ka.advance_by(2), Ok(3));
+        let err = KeyArrayError::PastEnd {
+            index: 3,
+            offset: 1,
+        };
+        assert_eq!(ka.advance_by(1), Err(err));
+        ka.next();
+        assert_eq!(ka.current(), &"D");
+    }
+}
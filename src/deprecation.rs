@@ -0,0 +1,213 @@
+//! Deprecated keys, with an optional replacement.
+//!
+//! Firmware option sets evolve: a mode is superseded but must keep working
+//! for existing configurations for a while.
+//! [`deprecate`](KeyArray::deprecate) marks a key with a message and,
+//! optionally, the key replacing it. A deprecated key can still be
+//! selected, but every [`change`](KeyArray::change) to it (and every method
+//! built on it) tells the [`on_deprecated`](KeyArray::on_deprecated)
+//! observers with a [`DeprecationWarning`], and renderers badge it. Under
+//! [`set_strict_deprecation`](KeyArray::set_strict_deprecation), such a
+//! change selects the replacement instead, if it has a selectable one.
+//! The replacement follows its key's id, so it survives edits.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//! use std::sync::mpsc;
+//!
+//! let mut codec = KeyArray::new(["AAC", "MP3 (legacy)", "Opus"]);
+//! codec.deprecate_with_replacement(1, "MP3 is going away in 3.0", 2);
+//! let (tx, rx) = mpsc::channel();
+//! codec.on_deprecated(move |warning| tx.send(warning.message.clone()).unwrap());
+//!
+//! codec.change(1);
+//! assert_eq!(codec.current(), &"MP3 (legacy)");
+//! assert_eq!(rx.try_recv().unwrap(), "MP3 is going away in 3.0");
+//!
+//! codec.change(0);
+//! codec.set_strict_deprecation(true);
+//! codec.change(1);
+//! assert_eq!(codec.current(), &"Opus");
+//! ```
+
+use crate::id::KeyId;
+use crate::{check_index, KeyArray};
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+// What `deprecate` attaches to a key.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Deprecation {
+    pub(crate) message: String,
+    pub(crate) replacement: Option<KeyId>,
+}
+
+/// A deprecated key was selected; for
+/// [`on_deprecated`](KeyArray::on_deprecated) observers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeprecationWarning<K> {
+    /// Index of the deprecated key.
+    pub index: usize,
+    pub key: K,
+    pub message: String,
+    /// Index of its replacement, if it has one still present.
+    pub replacement: Option<usize>,
+    /// Whether strict mode selected the replacement instead.
+    pub redirected: bool,
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Mark key `i` as deprecated with `message`, replacing any earlier
+    /// mark. Panics if `i` is out of bounds.
+    pub fn deprecate(&mut self, i: usize, message: impl Into<String>) {
+        self.set_deprecation("deprecate", i, message.into(), None);
+    }
+
+    /// [`deprecate`](Self::deprecate) key `i` in favour of key
+    /// `replacement`. Panics if either index is out of bounds.
+    pub fn deprecate_with_replacement(
+        &mut self,
+        i: usize,
+        message: impl Into<String>,
+        replacement: usize,
+    ) {
+        check_index(replacement, self.len())
+            .unwrap_or_else(|e| panic!("KeyArray::deprecate_with_replacement: {}", e));
+        let replacement = Some(self.slots[replacement].id);
+        self.set_deprecation("deprecate_with_replacement", i, message.into(), replacement);
+    }
+
+    fn set_deprecation(&mut self, method: &str, i: usize, message: String, to: Option<KeyId>) {
+        check_index(i, self.len()).unwrap_or_else(|e| panic!("KeyArray::{}: {}", method, e));
+        let deprecation = Some(Box::new(Deprecation {
+            message,
+            replacement: to,
+        }));
+        if self.slots[i].deprecation != deprecation {
+            Arc::make_mut(&mut self.slots)[i].deprecation = deprecation;
+            self.touch();
+        }
+    }
+
+    /// Remove the mark from key `i`. Panics if `i` is out of bounds.
+    pub fn undeprecate(&mut self, i: usize) {
+        check_index(i, self.len()).unwrap_or_else(|e| panic!("KeyArray::undeprecate: {}", e));
+        if self.slots[i].deprecation.is_some() {
+            Arc::make_mut(&mut self.slots)[i].deprecation = None;
+            self.touch();
+        }
+    }
+
+    /// The deprecation message of key `i`, if it is deprecated.
+    pub fn deprecation(&self, i: usize) -> Option<&str> {
+        Some(&self.slots.get(i)?.deprecation.as_ref()?.message)
+    }
+
+    /// Index of the replacement of key `i`, if it is deprecated in favour
+    /// of a key still present.
+    pub fn replacement(&self, i: usize) -> Option<usize> {
+        let id = self.slots.get(i)?.deprecation.as_ref()?.replacement?;
+        self.index_of_id(id)
+    }
+
+    pub fn strict_deprecation(&self) -> bool {
+        self.strict_deprecation
+    }
+
+    /// Whether changing to a deprecated key selects its replacement
+    /// instead. Keys without a selectable replacement are still selected.
+    pub fn set_strict_deprecation(&mut self, strict: bool) {
+        self.strict_deprecation = strict;
+    }
+
+    // The key a change to key `i`, in bounds, should select.
+    pub(crate) fn deprecation_target(&self, i: usize) -> usize {
+        if !self.strict_deprecation {
+            return i;
+        }
+        match self.replacement(i) {
+            Some(to) if self.check_selectable(to).is_ok() => to,
+            _ => i,
+        }
+    }
+
+    // Tell observers that a change to key `requested` selected key `to`,
+    // if the former is deprecated.
+    pub(crate) fn warn_deprecated(&mut self, requested: usize, to: usize) {
+        let Some(deprecation) = &self.slots[requested].deprecation else {
+            return;
+        };
+        if !self.observers.wants_deprecation() {
+            return;
+        }
+        let warning = DeprecationWarning {
+            index: requested,
+            key: self.keys[requested].clone(),
+            message: deprecation.message.clone(),
+            replacement: self.replacement(requested),
+            redirected: requested != to,
+        };
+        self.observers.notify_deprecated(&warning);
+    }
+}
+
+#[cfg(all(test, feature = "disable", feature = "transitions"))]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn strict_mode_follows_the_replacement() {
+        let mut ka = KeyArray::new(["old", "a", "new"]);
+        ka.deprecate_with_replacement(0, "use new", 2);
+        ka.move_key(2, 1);
+        assert_eq!((ka.deprecation(0), ka.replacement(0)), (Some("use new"), Some(1)));
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&seen);
+        ka.on_deprecated(move |w| log.lock().unwrap().push((w.index, w.redirected)));
+        ka.set_strict_deprecation(true);
+        ka.change(0);
+        assert_eq!(ka.current(), &"new");
+        ka.disable(1);
+        ka.change(0);
+        assert_eq!(ka.current(), &"old");
+        assert_eq!(*seen.lock().unwrap(), [(0, true), (0, false)]);
+
+        ka.undeprecate(0);
+        ka.remove(2);
+        assert_eq!((ka.deprecation(0), ka.replacement(0)), (None, None));
+    }
+
+    #[test]
+    fn redirects_are_reported_and_checked() {
+        let mut ka = KeyArray::new(["a", "old", "new"]);
+        ka.deprecate_with_replacement(1, "use new", 2);
+        ka.set_strict_deprecation(true);
+        ka.deny_transition(0, 2);
+        let denied = crate::KeyArrayError::TransitionDenied { from: 0, to: 2 };
+        assert_eq!(ka.guarded_change(1), Err(denied));
+        assert_eq!(ka.current_index(), 0);
+
+        let ev = ka.change_with_reason(1, "test").unwrap();
+        assert_eq!((ev.previous, ev.current, ev.key), (0, 2, "new"));
+    }
+
+    #[test]
+    fn selecting_helpers_return_the_replacement() {
+        let mut ka = KeyArray::new_with(["a", "old", "new"], 1);
+        ka.bookmark("mark");
+        ka.change(0);
+        ka.deprecate_with_replacement(1, "use new", 2);
+        ka.set_strict_deprecation(true);
+        assert_eq!(ka.jump_to_bookmark("mark"), Some(2));
+        ka.change(0);
+        assert_eq!(ka.select_id(ka.id_at(1).unwrap()), Some(2));
+        ka.change(0);
+        assert_eq!(ka.handle_key('o'), Some(2));
+        assert_eq!(ka.current(), &"new");
+    }
+}
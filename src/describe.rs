@@ -0,0 +1,204 @@
+//! A self-description of a KeyArray's runtime configuration.
+//!
+//! [`describe`](KeyArray::describe) returns a [`KeyArrayDescriptor`]: every
+//! key with its id and flags, the bookmarks, the transition rules and the
+//! navigation settings, all as plain data. With the `serde` feature it is
+//! serializable, so an admin UI or a remote tool can draw and validate
+//! against what the array actually allows instead of assuming it. Closures
+//! (transition guards, the permission checker) cannot be described; a
+//! guarded rule is marked as such, and each key says whether it can be
+//! selected right now.
+//!
+//! ```
+//! # #[cfg(all(feature = "permission", feature = "transitions"))] {
+//! use keyarray::describe::RuleKind;
+//! use keyarray::KeyArray;
+//!
+//! let mut mode = KeyArray::new(["Eco", "Normal", "Boost"]);
+//! mode.require_permission(2, "service");
+//! mode.deny_transition(0, 2);
+//! mode.bookmark("default");
+//!
+//! let described = mode.describe();
+//! assert_eq!(described.keys[2].permission.as_deref(), Some("service"));
+//! assert!(!described.keys[2].selectable);
+//! assert_eq!(described.bookmarks, [("default".to_string(), Some(0))]);
+//! assert_eq!((described.transitions[0].to, described.transitions[0].rule), (2, RuleKind::Deny));
+//! # }
+//! ```
+
+use crate::cursor::WrapMode;
+use crate::id::KeyId;
+#[cfg(feature = "traversal")]
+use crate::traversal::Traversal;
+use crate::KeyArray;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+/// Everything [`describe`](KeyArray::describe) reports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyArrayDescriptor<K> {
+    pub keys: Vec<KeyDescriptor<K>>,
+    /// Index of the current key.
+    pub current: usize,
+    /// Bookmarks by name, with the index of their key, `None` if it was
+    /// removed.
+    pub bookmarks: Vec<(String, Option<usize>)>,
+    /// Rules between keys still present, by index.
+    pub transitions: Vec<TransitionDescriptor>,
+    pub wrap: WrapMode,
+    #[cfg(feature = "traversal")]
+    pub traversal: Traversal,
+}
+
+/// One key and its flags.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyDescriptor<K> {
+    pub key: K,
+    pub id: KeyId,
+    /// Soft-removed.
+    pub hidden: bool,
+    pub disabled: bool,
+    /// Selecting it needs confirmation.
+    pub confirm: bool,
+    /// It expires at some point.
+    pub expires: bool,
+    /// The permission it requires.
+    pub permission: Option<String>,
+    /// The deprecation message, if it is deprecated.
+    pub deprecated: Option<String>,
+    /// Index of the key replacing it.
+    pub replacement: Option<usize>,
+    /// It can be made current now: not hidden, disabled or locked.
+    pub selectable: bool,
+}
+
+/// A transition rule from key `from` to key `to`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransitionDescriptor {
+    pub from: usize,
+    pub to: usize,
+    pub rule: RuleKind,
+}
+
+/// The kind of a transition rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RuleKind {
+    Allow,
+    /// Allowed while a guard closure agrees.
+    Guarded,
+    Deny,
+}
+
+impl Display for RuleKind {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(match self {
+            RuleKind::Allow => "allow",
+            RuleKind::Guarded => "allow if guard",
+            RuleKind::Deny => "deny",
+        })
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// The configuration as data; see the [module docs](self).
+    pub fn describe(&self) -> KeyArrayDescriptor<K> {
+        let keys = self
+            .keys
+            .iter()
+            .zip(self.slots.iter())
+            .enumerate()
+            .map(|(i, (key, slot))| KeyDescriptor {
+                key: key.clone(),
+                id: slot.id,
+                #[cfg(feature = "tombstone")]
+                hidden: slot.hidden,
+                #[cfg(not(feature = "tombstone"))]
+                hidden: false,
+                #[cfg(feature = "disable")]
+                disabled: slot.disabled,
+                #[cfg(not(feature = "disable"))]
+                disabled: false,
+                #[cfg(feature = "confirm")]
+                confirm: slot.confirm,
+                #[cfg(not(feature = "confirm"))]
+                confirm: false,
+                #[cfg(feature = "expiry")]
+                expires: slot.expires.is_some(),
+                #[cfg(not(feature = "expiry"))]
+                expires: false,
+                #[cfg(feature = "permission")]
+                permission: slot.permission.clone(),
+                #[cfg(not(feature = "permission"))]
+                permission: None,
+                #[cfg(feature = "deprecation")]
+                deprecated: self.deprecation(i).map(str::to_string),
+                #[cfg(feature = "deprecation")]
+                replacement: self.replacement(i),
+                #[cfg(not(feature = "deprecation"))]
+                deprecated: None,
+                #[cfg(not(feature = "deprecation"))]
+                replacement: None,
+                selectable: self.selectable(i),
+            })
+            .collect();
+        let bookmarks = self
+            .bookmarks
+            .iter()
+            .map(|(name, &id)| (name.clone(), self.index_of_id(id)))
+            .collect();
+        #[cfg(feature = "transitions")]
+        let mut transitions: Vec<_> = self
+            .transitions
+            .describe()
+            .filter_map(|(from, to, rule)| {
+                Some(TransitionDescriptor {
+                    from: self.index_of_id(from)?,
+                    to: self.index_of_id(to)?,
+                    rule,
+                })
+            })
+            .collect();
+        #[cfg(feature = "transitions")]
+        transitions.sort();
+        #[cfg(not(feature = "transitions"))]
+        let transitions = Vec::new();
+        KeyArrayDescriptor {
+            keys,
+            current: self.idx,
+            bookmarks,
+            transitions,
+            wrap: self.wrap,
+            #[cfg(feature = "traversal")]
+            traversal: self.traversal,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "tombstone", feature = "transitions"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_flags_and_rules_by_index() {
+        let mut ka = KeyArray::new(["a", "b", "c"]);
+        ka.guard_transition(2, 0, |_, _| true);
+        ka.allow_transition(1, 2);
+        ka.soft_remove(1);
+        let described = ka.describe();
+        let flags: Vec<_> = described.keys.iter().map(|k| (k.hidden, k.selectable)).collect();
+        assert_eq!(flags, [(false, true), (true, false), (false, true)]);
+        let rules: Vec<_> = described.transitions.iter().map(|t| (t.from, t.rule)).collect();
+        assert_eq!(rules, [(1, RuleKind::Allow), (2, RuleKind::Guarded)]);
+
+        ka.remove(0);
+        let left = TransitionDescriptor { from: 0, to: 1, rule: RuleKind::Allow };
+        assert_eq!(ka.describe().transitions, [left]);
+    }
+}
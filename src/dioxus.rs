@@ -0,0 +1,127 @@
+//! Dioxus integration: a KeyArray held in a signal.
+//!
+//! ```no_run
+//! use dioxus::prelude::*;
+//! use keyarray::dioxus::use_keyarray;
+//! use keyarray::KeyArray;
+//!
+//! fn ModePicker() -> Element {
+//!     let mut mode = use_keyarray(|| KeyArray::new(["On", "Off", "Auto"]));
+//!     // call `mode.next()`, `mode.prev()` or `mode.select(i)` from event
+//!     // handlers; every component reading `mode` re-renders afterwards
+//!     # let _ = mode.current();
+//!     # mode.next();
+//!     VNode::empty()
+//! }
+//! ```
+
+use crate::error::KeyArrayError;
+use crate::{check_index, KeyArray};
+use dioxus::prelude::*;
+use dioxus::signals::{ReadableRef, WritableRef};
+use std::fmt::{Debug, Display};
+
+/// Handle returned by [`use_keyarray`]. `Copy`, so it can be moved into any
+/// number of event handlers.
+pub struct UseKeyArray<K: 'static> {
+    state: Signal<KeyArray<K>>,
+}
+
+impl<K: 'static> Clone for UseKeyArray<K> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K: 'static> Copy for UseKeyArray<K> {}
+
+/// Create a KeyArray signal owned by the calling component. `init` runs once,
+/// on first render.
+pub fn use_keyarray<K>(init: impl FnOnce() -> KeyArray<K>) -> UseKeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display + 'static,
+{
+    UseKeyArray {
+        state: use_signal(init),
+    }
+}
+
+impl<K> UseKeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display + 'static,
+{
+    /// Subscribe to and borrow the whole array.
+    pub fn read(&self) -> ReadableRef<'_, Signal<KeyArray<K>>> {
+        self.state.read()
+    }
+
+    /// Borrow the array mutably; subscribers re-render when the guard drops.
+    pub fn write(&mut self) -> WritableRef<'_, Signal<KeyArray<K>>> {
+        self.state.write()
+    }
+
+    /// Subscribe to and clone the current key.
+    pub fn current(&self) -> K {
+        self.state.read().current().clone()
+    }
+
+    /// Subscribe to and get the current index.
+    pub fn current_index(&self) -> usize {
+        self.state.read().current_index()
+    }
+
+    /// Select key `i`. Only notifies subscribers if the selection actually
+    /// moved; fails, notifying no one, as [`KeyArray::try_change`] does.
+    pub fn select(&mut self, i: usize) -> Result<(), KeyArrayError> {
+        {
+            let state = self.state.peek();
+            check_index(i, state.len())?;
+            state.check_selectable(i)?;
+            if state.current_index() == i {
+                return Ok(());
+            }
+        }
+        self.state.write().try_change(i)
+    }
+
+    /// Select the following key, wrapping.
+    pub fn next(&mut self) {
+        self.state.write().next();
+    }
+
+    /// Select the preceding key, wrapping.
+    pub fn prev(&mut self) {
+        self.state.write().prev();
+    }
+
+    /// The underlying signal, for APIs that take one directly.
+    pub fn signal(&self) -> Signal<KeyArray<K>> {
+        self.state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    thread_local! {
+        static SEEN: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn app() -> Element {
+        let mut mode = use_keyarray(|| KeyArray::new(["On", "Off"]));
+        SEEN.with(|s| s.borrow_mut().push(mode.current()));
+        use_hook(move || mode.next());
+        VNode::empty()
+    }
+
+    #[test]
+    fn hook_state_persists_across_renders() {
+        let mut dom = VirtualDom::new(app);
+        dom.rebuild_in_place();
+        dom.mark_all_dirty();
+        dom.render_immediate_to_vec();
+        SEEN.with(|s| assert_eq!(*s.borrow(), vec!["On", "Off"]));
+    }
+}
@@ -0,0 +1,150 @@
+//! Greyed-out keys.
+//!
+//! A disabled key stays in place and is still shown, but cannot be
+//! selected: [`try_change`](KeyArray::try_change) refuses it with
+//! [`KeyArrayError::Disabled`] and [`next`](KeyArray::next) /
+//! [`prev`](KeyArray::prev) step over it. Disabling the current key moves
+//! the selection to the nearest enabled key.
+//!
+//! ```
+//! use keyarray::{KeyArray, KeyArrayError};
+//!
+//! let mut speed = KeyArray::new(["Slow", "Normal", "Fast"]);
+//! speed.disable(1);
+//! speed.next();
+//! assert_eq!(speed.current(), &"Fast");
+//! assert_eq!(speed.try_change(1), Err(KeyArrayError::Disabled { index: 1 }));
+//!
+//! speed.disable(2);
+//! assert_eq!(speed.current(), &"Slow");
+//! assert_eq!(speed.try_disable(0), Err(KeyArrayError::NoEnabledKey));
+//! ```
+
+use crate::check_index;
+use crate::error::KeyArrayError;
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Disable key `i`. If it was current, the nearest selectable key
+    /// becomes current, preferring the following one on a tie. Panics if
+    /// `i` is out of bounds or no other key is selectable.
+    pub fn disable(&mut self, i: usize) {
+        self.try_disable(i)
+            .unwrap_or_else(|e| panic!("KeyArray::disable: {}", e))
+    }
+
+    /// [`disable`](Self::disable), returning an error instead of
+    /// panicking. Nothing changes on error.
+    pub fn try_disable(&mut self, i: usize) -> Result<(), KeyArrayError> {
+        check_index(i, self.keys.len())?;
+        if self.slots[i].disabled {
+            return Ok(());
+        }
+        let target = if i == self.idx {
+            let len = self.len();
+            let nearest = (1..len)
+                .flat_map(|d| [i.checked_add(d), i.checked_sub(d)])
+                .flatten()
+                .find(|&j| j < len && self.selectable(j));
+            Some(nearest.ok_or(KeyArrayError::NoEnabledKey)?)
+        } else {
+            None
+        };
+        Arc::make_mut(&mut self.slots)[i].disabled = true;
+        if let Some(target) = target {
+            self.idx = target;
+        }
+        self.touch();
+        Ok(())
+    }
+
+    /// Enable key `i` again. Returns whether it was disabled. Panics if `i`
+    /// is out of bounds.
+    pub fn enable(&mut self, i: usize) -> bool {
+        assert!(
+            i < self.keys.len(),
+            "KeyArray::enable: index {} out of bounds",
+            i
+        );
+        if !self.slots[i].disabled {
+            return false;
+        }
+        Arc::make_mut(&mut self.slots)[i].disabled = false;
+        self.touch();
+        true
+    }
+
+    /// Whether key `i` can be selected; `false` if out of bounds.
+    pub fn is_enabled(&self, i: usize) -> bool {
+        self.slots.get(i).is_some_and(|s| !s.disabled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_keys_are_skipped_and_refused() {
+        let mut ka = KeyArray::new_with(["A", "B", "C", "D", "E"], 2);
+        ka.disable(2);
+        assert_eq!(ka.current(), &"D");
+        ka.disable(1);
+        ka.disable(3);
+        assert_eq!(ka.current(), &"E");
+        ka.prev();
+        assert_eq!(ka.current(), &"A");
+        assert_eq!(ka.advance_by(-1), Ok(4));
+        assert!(!ka.is_enabled(3) && ka.is_enabled(4) && !ka.is_enabled(9));
+
+        ka.disable(0);
+        assert_eq!(ka.try_disable(4), Err(KeyArrayError::NoEnabledKey));
+        assert!(ka.is_enabled(4));
+        assert!(ka.enable(2) && !ka.enable(2));
+        ka.change(2);
+    }
+
+    #[cfg(feature = "history")]
+    #[test]
+    fn selecting_methods_pass_over_disabled_keys() {
+        let mut ka = KeyArray::new(["A", "B", "C", "D"]);
+        ka.enable_history(8);
+        ka.change(1);
+        ka.change(2);
+        ka.disable(1);
+        assert!(ka.back());
+        assert_eq!(ka.current(), &"A");
+        assert_eq!(ka.select_id(ka.id_at(1).unwrap()), None);
+        assert_eq!(ka.change_with_reason(1, "test"), None);
+
+        crate::SelectionCursor::set_cursor(&mut ka, 1);
+        crate::SelectionCursor::advance(&mut ka, 1);
+        assert_eq!(ka.current(), &"C");
+    }
+
+    #[test]
+    #[cfg(all(feature = "history", feature = "observers"))]
+    fn skipping_disabled_keys_is_one_change() {
+        use std::sync::{Arc, Mutex};
+
+        let mut ka = KeyArray::new(["A", "B", "C", "D"]);
+        ka.disable(1);
+        ka.disable(2);
+        ka.enable_history(8);
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&seen);
+        ka.on_change(move |from, to, key| log.lock().unwrap().push((from, to, *key)));
+        let generation = ka.generation();
+        ka.next();
+        assert_eq!(*seen.lock().unwrap(), [(0, 3, "D")]);
+        assert_eq!(ka.generation(), generation + 1);
+        assert!(ka.undo());
+        assert_eq!(ka.current(), &"A");
+        assert!(!ka.undo());
+    }
+}
@@ -0,0 +1,242 @@
+//! Fieldless enums as key lists.
+//!
+//! An enum that implements [`KeyEnum`] lists its own variants, so it stays
+//! the single source of truth for which keys exist and in what order.
+//! [`key_enum!`](crate::key_enum) declares such an enum and implements the
+//! trait, along with `Display` showing each variant's name.
+//!
+//! ```
+//! use keyarray::{key_enum, KeyArray};
+//!
+//! key_enum! {
+//!     pub enum Power { On, Off, Auto }
+//! }
+//!
+//! let mut power = KeyArray::<Power>::from_enum();
+//! assert_eq!(power.change_to_variant(Power::Auto), Some(2));
+//! assert_eq!(power.current_as::<Power>(), Some(Power::Auto));
+//!
+//! // keys loaded as strings map back by name
+//! let saved = KeyArray::new_with(["On", "Off", "Auto"], 1);
+//! assert_eq!(saved.current_as::<Power>(), Some(Power::Off));
+//! ```
+//!
+//! When the key set is closed, [`TypedKeyArray`] fixes it to the variants,
+//! so selecting takes a variant instead of an index and cannot fail:
+//!
+//! ```
+//! # use keyarray::key_enum;
+//! use keyarray::enums::TypedKeyArray;
+//! # key_enum! { pub enum Power { On, Off, Auto } }
+//!
+//! let mut power = TypedKeyArray::new(Power::Off);
+//! power.next();
+//! assert_eq!(power.current(), Power::Auto);
+//! power.select(Power::On);
+//! assert_eq!(power.current_index(), 0);
+//! ```
+
+use crate::KeyArray;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+/// A fieldless enum whose variants are the keys of a KeyArray.
+pub trait KeyEnum: Copy + PartialEq + 'static {
+    /// Every variant, in key order.
+    const VARIANTS: &'static [Self];
+
+    /// The variant's name, as keys of other types are matched against it.
+    fn name(self) -> &'static str;
+}
+
+/// Declare a fieldless enum and implement [`KeyEnum`](crate::enums::KeyEnum)
+/// and `Display` for it. The enum also derives `Debug`, `Clone`, `Copy`,
+/// `PartialEq`, `Eq` and `Hash`; other attributes are passed through.
+#[macro_export]
+macro_rules! key_enum {
+    (
+        $(#[$attr:meta])*
+        $vis:vis enum $name:ident {
+            $($(#[$variant_attr:meta])* $variant:ident),+ $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        $vis enum $name {
+            $($(#[$variant_attr])* $variant),+
+        }
+
+        impl $crate::enums::KeyEnum for $name {
+            const VARIANTS: &'static [Self] = &[$($name::$variant),+];
+
+            fn name(self) -> &'static str {
+                match self {
+                    $($name::$variant => stringify!($variant)),+
+                }
+            }
+        }
+
+        impl ::core::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                f.write_str($crate::enums::KeyEnum::name(*self))
+            }
+        }
+    };
+}
+
+impl<E> KeyArray<E>
+where
+    E: KeyEnum + Debug + Display,
+{
+    /// One key per variant of `E`, in declaration order, the first current.
+    pub fn from_enum() -> Self {
+        KeyArray::new(E::VARIANTS.iter().copied())
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// The variant of `E` named like the current key, if any.
+    pub fn current_as<E: KeyEnum>(&self) -> Option<E> {
+        let current = self.current().to_string();
+        E::VARIANTS.iter().copied().find(|v| v.name() == current)
+    }
+
+    /// Make the first key named like `variant` current and return the index
+    /// now current, which is its replacement's if it is deprecated under
+    /// strict mode. Leaves the selection untouched and returns `None` if no key
+    /// is, or it is disabled.
+    pub fn change_to_variant<E: KeyEnum>(&mut self, variant: E) -> Option<usize> {
+        let i = self.keys.iter().position(|k| k.to_string() == variant.name())?;
+        self.try_change(i).ok()?;
+        Some(self.current_index())
+    }
+}
+
+/// A KeyArray holding exactly the variants of `E`, in order. Keys cannot
+/// be added or removed, so selection is by variant and never fails.
+#[derive(Clone)]
+pub struct TypedKeyArray<E> {
+    inner: KeyArray<E>,
+}
+
+impl<E> TypedKeyArray<E>
+where
+    E: KeyEnum + Debug + Display,
+{
+    /// Every variant, with `current` selected.
+    pub fn new(current: E) -> Self {
+        let mut typed = TypedKeyArray {
+            inner: KeyArray::from_enum(),
+        };
+        typed.select(current);
+        typed
+    }
+
+    /// The current variant.
+    pub fn current(&self) -> E {
+        *self.inner.current()
+    }
+
+    /// Index of the current variant in [`KeyEnum::VARIANTS`].
+    pub fn current_index(&self) -> usize {
+        self.inner.current_index()
+    }
+
+    /// Make `variant` current.
+    pub fn select(&mut self, variant: E) {
+        let i = Self::index_of(variant);
+        if i != self.inner.current_index() {
+            self.inner.change(i);
+        }
+    }
+
+    /// Select the following variant, wrapping.
+    pub fn next(&mut self) {
+        self.inner.next();
+    }
+
+    /// Select the preceding variant, wrapping.
+    pub fn prev(&mut self) {
+        self.inner.prev();
+    }
+
+    /// The underlying KeyArray, for reading.
+    pub fn as_key_array(&self) -> &KeyArray<E> {
+        &self.inner
+    }
+
+    /// Give up the typing and return the underlying KeyArray.
+    pub fn into_inner(self) -> KeyArray<E> {
+        self.inner
+    }
+
+    fn index_of(variant: E) -> usize {
+        E::VARIANTS
+            .iter()
+            .position(|v| *v == variant)
+            .expect("KeyEnum::VARIANTS lists every variant")
+    }
+}
+
+impl<E> Debug for TypedKeyArray<E>
+where
+    E: KeyEnum + Debug + Display,
+{
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.debug_tuple("TypedKeyArray").field(&self.current()).finish()
+    }
+}
+
+/// The first variant selected.
+impl<E> Default for TypedKeyArray<E>
+where
+    E: KeyEnum + Debug + Display,
+{
+    fn default() -> Self {
+        TypedKeyArray {
+            inner: KeyArray::from_enum(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    key_enum! {
+        /// Test modes.
+        enum Mode {
+            Eco,
+            /// The default.
+            Normal,
+            Sport,
+        }
+    }
+
+    #[test]
+    fn round_trips_through_names() {
+        assert_eq!(Mode::VARIANTS, [Mode::Eco, Mode::Normal, Mode::Sport]);
+        let mut ka = KeyArray::<Mode>::from_enum();
+        ka.remove(1);
+        assert_eq!(ka.change_to_variant(Mode::Sport), Some(1));
+        assert_eq!(ka.change_to_variant(Mode::Normal), None);
+        assert_eq!(ka.current_as(), Some(Mode::Sport));
+
+        let mut names = KeyArray::new(["Eco", "Turbo"]);
+        names.change(1);
+        assert_eq!(names.current_as::<Mode>(), None);
+        assert_eq!(names.change_to_variant(Mode::Eco), Some(0));
+    }
+
+    #[test]
+    fn typed_selection() {
+        let mut typed = TypedKeyArray::<Mode>::default();
+        typed.prev();
+        assert_eq!(typed.current(), Mode::Sport);
+        typed.select(Mode::Normal);
+        assert_eq!(typed.as_key_array().current_index(), 1);
+        assert_eq!(typed.into_inner().current_as(), Some(Mode::Normal));
+    }
+}
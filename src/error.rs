@@ -0,0 +1,80 @@
+//! Errors from the fallible `try_*` methods.
+//!
+//! ```
+//! # #[cfg(feature = "std")] {
+//! use keyarray::{KeyArray, KeyArrayError};
+//!
+//! let mut ka = KeyArray::new(["On", "Off"]);
+//! let from_config = 7;
+//! assert_eq!(
+//!     ka.try_change(from_config),
+//!     Err(KeyArrayError::IndexOutOfBounds { index: 7, len: 2 })
+//! );
+//! # }
+//! ```
+
+use core::fmt::{Display, Formatter, Result as FmtResult};
+
+/// Why a `try_*` method refused its input. The panicking counterparts panic
+/// with this as their message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum KeyArrayError {
+    /// No keys were supplied.
+    Empty,
+    /// `index` does not point into (or, for inserts, just past) `len` keys.
+    IndexOutOfBounds { index: usize, len: usize },
+    /// An equal key already exists at `index`.
+    DuplicateKey { index: usize },
+    /// No key is equal to the one asked for; see
+    /// [`change_to`](crate::KeyArray::change_to).
+    KeyNotFound,
+    /// Moving `offset` keys from `index` would pass an end under
+    /// [`WrapMode::Error`](crate::WrapMode::Error).
+    PastEnd { index: usize, offset: isize },
+    /// The transition table does not permit moving from key `from` to key
+    /// `to`; see [`guarded_change`](crate::KeyArray::guarded_change).
+    TransitionDenied { from: usize, to: usize },
+    /// A [`KeyArrayFixed`](crate::KeyArrayFixed) already holds `capacity`
+    /// keys.
+    Full { capacity: usize },
+    /// Key `index` is [disabled](crate::KeyArray::disable).
+    Disabled { index: usize },
+    /// Disabling the current key left no enabled key to move to.
+    NoEnabledKey,
+    /// Key `index` needs a [permission](crate::KeyArray::require_permission)
+    /// the checker does not grant.
+    PermissionDenied { index: usize },
+}
+
+impl Display for KeyArrayError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            KeyArrayError::Empty => f.write_str("must supply at least one key"),
+            KeyArrayError::IndexOutOfBounds { index, len } => {
+                write!(f, "index {} out of bounds for {} keys", index, len)
+            }
+            KeyArrayError::DuplicateKey { index } => {
+                write!(f, "an equal key already exists at index {}", index)
+            }
+            KeyArrayError::KeyNotFound => f.write_str("no such key"),
+            KeyArrayError::PastEnd { index, offset } => {
+                write!(f, "moving {} from index {} passes the end", offset, index)
+            }
+            KeyArrayError::TransitionDenied { from, to } => {
+                write!(f, "transition from index {} to {} is not allowed", from, to)
+            }
+            KeyArrayError::Full { capacity } => {
+                write!(f, "no room for more than {} keys", capacity)
+            }
+            KeyArrayError::Disabled { index } => write!(f, "key {} is disabled", index),
+            KeyArrayError::NoEnabledKey => f.write_str("no other key is enabled"),
+            KeyArrayError::PermissionDenied { index } => {
+                write!(f, "key {} needs a permission that is not granted", index)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for KeyArrayError {}
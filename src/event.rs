@@ -0,0 +1,148 @@
+//! Events describing how the selection moved.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//!
+//! let mut ka = KeyArray::new(["Day", "Night"]);
+//! let ev = ka.change_with_reason(1, "schedule").unwrap();
+//! assert_eq!(ev.reason.unwrap().as_str(), "schedule");
+//! ```
+
+use crate::error::KeyArrayError;
+use crate::id::KeyId;
+use crate::{check_index, KeyArray};
+use std::borrow::Cow;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+/// The current key changed. Indices are as they were before and after the
+/// operation, so `previous` may refer to a key that has since moved or been
+/// removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChangeEvent<K> {
+    pub previous: usize,
+    pub current: usize,
+    /// The key that is current after the change.
+    pub key: K,
+    /// Who or what moved the selection, if the caller said.
+    pub reason: Option<ChangeReason>,
+}
+
+/// A key's value was replaced by [`rename`](KeyArray::rename). Its id,
+/// attached data and selection are unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Renamed<K> {
+    pub index: usize,
+    pub id: KeyId,
+    pub old: K,
+    pub new: K,
+}
+
+/// A change to the set or order of keys, from
+/// [`on_structure_change`](KeyArray::on_structure_change). `Removed` and
+/// `Moved::from` use indices from before the operation; the rest use
+/// indices from after it. Keys only shifted by an insert or removal
+/// elsewhere do not count as moved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StructureEvent<K> {
+    Added { id: KeyId, index: usize, key: K },
+    Removed { id: KeyId, index: usize, key: K },
+    Renamed { id: KeyId, index: usize, old: K, new: K },
+    Moved { id: KeyId, from: usize, to: usize },
+}
+
+/// Caller-supplied cause of a change, such as `"user_click"`, `"schedule"`
+/// or `"sync"`, kept for audit trails.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct ChangeReason(Cow<'static, str>);
+
+impl ChangeReason {
+    pub fn new(reason: impl Into<Cow<'static, str>>) -> Self {
+        ChangeReason(reason.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&'static str> for ChangeReason {
+    fn from(reason: &'static str) -> Self {
+        ChangeReason::new(reason)
+    }
+}
+
+impl From<String> for ChangeReason {
+    fn from(reason: String) -> Self {
+        ChangeReason::new(reason)
+    }
+}
+
+impl Display for ChangeReason {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for ChangeReason {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "{=str}", self.as_str())
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// [`change`](Self::change), recording why. Returns the event, carrying
+    /// `reason`, or `None` if `i` was already current or cannot be selected
+    /// (disabled or locked). Panics if `i` is out of bounds.
+    pub fn change_with_reason(
+        &mut self,
+        i: usize,
+        reason: impl Into<ChangeReason>,
+    ) -> Option<ChangeEvent<K>> {
+        check_index(i, self.len()).unwrap_or_else(|e| self.fail("change_with_reason", e));
+        self.try_change_with_reason(i, reason).ok().flatten()
+    }
+
+    /// [`change_with_reason`](Self::change_with_reason), failing as
+    /// [`try_change`](Self::try_change) does.
+    pub fn try_change_with_reason(
+        &mut self,
+        i: usize,
+        reason: impl Into<ChangeReason>,
+    ) -> Result<Option<ChangeEvent<K>>, KeyArrayError> {
+        let previous = self.idx;
+        self.try_change(i)?;
+        // a deprecated key may have been swapped for its replacement
+        Ok((previous != self.idx).then(|| ChangeEvent {
+            previous,
+            current: self.idx,
+            key: self.current().clone(),
+            reason: Some(reason.into()),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reason_travels_with_event() {
+        let mut ka = KeyArray::new(["A", "B"]);
+        assert!(ka.change_with_reason(0, "sync").is_none());
+        let ev = ka.change_with_reason(1, format!("user {}", 7)).unwrap();
+        assert_eq!((ev.previous, ev.current, ev.key), (0, 1, "B"));
+        assert_eq!(ev.reason, Some(ChangeReason::from("user 7".to_string())));
+    }
+}
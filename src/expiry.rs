@@ -0,0 +1,179 @@
+//! Keys that expire, such as discovered devices that must keep announcing
+//! themselves.
+//!
+//! A key added with [`push_with_ttl`](KeyArray::push_with_ttl) lives until
+//! its deadline; [`purge_expired`](KeyArray::purge_expired) then removes it.
+//! Nothing expires on its own: call `purge_expired` from a timer, sleeping
+//! until [`next_expiry`](KeyArray::next_expiry). Times are passed in; the
+//! `_on` variants in [`clock`](crate::clock) read them from a
+//! [`Clock`](crate::clock::Clock).
+//!
+//! ```
+//! use keyarray::KeyArray;
+//! use std::time::{Duration, Instant};
+//!
+//! let start = Instant::now();
+//! let mut devices = KeyArray::new(["local"]);
+//! devices.push_with_ttl("printer", Duration::from_secs(30), start);
+//! devices.push_with_ttl("tv", Duration::from_secs(60), start);
+//!
+//! let expired = devices.purge_expired(start + Duration::from_secs(45)).unwrap();
+//! assert_eq!(expired.keys().collect::<Vec<_>>(), [&"printer"]);
+//! assert_eq!(devices.keys(), ["local", "tv"]);
+//! ```
+
+use crate::event::{ChangeEvent, ChangeReason};
+use crate::id::KeyId;
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Reason attached to the change event when an expiry moves the selection.
+pub const REASON: &str = "expired";
+
+/// What one [`purge_expired`](KeyArray::purge_expired) removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expired<K> {
+    /// The expired keys with their ids, in their former order.
+    pub removed: Vec<(KeyId, K)>,
+    /// Set if the current index or key changed, as for
+    /// [`update`](KeyArray::update); carries [`REASON`].
+    pub change: Option<ChangeEvent<K>>,
+}
+
+impl<K> Expired<K> {
+    pub fn keys(&self) -> impl Iterator<Item = &K> + '_ {
+        self.removed.iter().map(|(_, k)| k)
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// [`push`](Self::push) a key that expires `ttl` after `now`.
+    pub fn push_with_ttl(&mut self, key: K, ttl: Duration, now: Instant) -> KeyId {
+        let id = self.push(key);
+        let last = self.len() - 1;
+        Arc::make_mut(&mut self.slots)[last].expires = Some(now + ttl);
+        id
+    }
+
+    /// Give key `i` a fresh lease of `ttl` from `now`, e.g. when a device
+    /// announces itself again. Panics if `i` is out of bounds.
+    pub fn set_ttl(&mut self, i: usize, ttl: Duration, now: Instant) {
+        self.set_expiry(i, Some(now + ttl), "set_ttl");
+    }
+
+    /// Make key `i` permanent. Panics if `i` is out of bounds.
+    pub fn clear_ttl(&mut self, i: usize) {
+        self.set_expiry(i, None, "clear_ttl");
+    }
+
+    fn set_expiry(&mut self, i: usize, expires: Option<Instant>, op: &str) {
+        assert!(
+            i < self.keys.len(),
+            "KeyArray::{}: index {} out of bounds",
+            op,
+            i
+        );
+        Arc::make_mut(&mut self.slots)[i].expires = expires;
+        self.touch();
+    }
+
+    /// When key `i` expires; `None` if it is permanent or out of bounds.
+    pub fn expires_at(&self, i: usize) -> Option<Instant> {
+        self.slots.get(i)?.expires
+    }
+
+    /// The earliest deadline of any key.
+    pub fn next_expiry(&self) -> Option<Instant> {
+        self.slots.iter().filter_map(|s| s.expires).min()
+    }
+
+    /// Remove every key whose deadline is at or before `now`. If the current
+    /// key expires, the first surviving key after it becomes current, or
+    /// else the last one before it; if every key expires the array is left
+    /// empty. Returns `None` if nothing expired.
+    pub fn purge_expired(&mut self, now: Instant) -> Option<Expired<K>> {
+        let keep: Vec<bool> = self
+            .slots
+            .iter()
+            .map(|s| s.expires.is_none_or(|t| t > now))
+            .collect();
+        if !keep.contains(&false) {
+            return None;
+        }
+        let removed = (0..self.len())
+            .filter(|&i| !keep[i])
+            .map(|i| (self.slots[i].id, self.keys[i].clone()))
+            .collect();
+        let previous = self.idx;
+        let before = self.current().clone();
+        let survivor = (previous..self.len())
+            .chain((0..previous).rev())
+            .find(|&i| keep[i]);
+        let Some(survivor) = survivor else {
+            Arc::make_mut(&mut self.keys).clear();
+            Arc::make_mut(&mut self.slots).clear();
+            self.idx = 0;
+            self.touch();
+            return Some(Expired {
+                removed,
+                change: None,
+            });
+        };
+        self.retain_mask(&keep, survivor);
+        let change = (self.idx != previous || *self.current() != before).then(|| ChangeEvent {
+            previous,
+            current: self.idx,
+            key: self.current().clone(),
+            reason: Some(ChangeReason::from(REASON)),
+        });
+        Some(Expired { removed, change })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expired_current_moves_on() {
+        let t0 = Instant::now();
+        let ttl = |s| Duration::from_secs(s);
+        let mut ka = KeyArray::new(["a"]);
+        ka.push_with_ttl("b", ttl(10), t0);
+        ka.push_with_ttl("c", ttl(20), t0);
+        ka.push("d");
+        ka.change(1);
+        assert_eq!(ka.next_expiry(), Some(t0 + ttl(10)));
+        assert_eq!(ka.purge_expired(t0 + ttl(5)), None);
+
+        ka.set_ttl(0, ttl(10), t0);
+        let expired = ka.purge_expired(t0 + ttl(10)).unwrap();
+        assert_eq!(expired.keys().collect::<Vec<_>>(), [&"a", &"b"]);
+        let ev = expired.change.unwrap();
+        assert_eq!((ev.previous, ev.current, ev.key), (1, 0, "c"));
+        assert_eq!(ev.reason.unwrap().as_str(), REASON);
+
+        ka.change(1);
+        ka.clear_ttl(1);
+        assert_eq!(ka.expires_at(1), None);
+        // "d" stays current; only its index shifts
+        let ev = ka.purge_expired(t0 + ttl(60)).unwrap().change.unwrap();
+        assert_eq!((ev.previous, ev.current, ev.key), (1, 0, "d"));
+        assert_eq!(ka.keys(), ["d"]);
+    }
+
+    #[test]
+    fn everything_expires() {
+        let t0 = Instant::now();
+        let mut ka = KeyArray::new(["x"]);
+        ka.set_ttl(0, Duration::ZERO, t0);
+        let expired = ka.purge_expired(t0).unwrap();
+        assert_eq!((expired.removed.len(), expired.change), (1, None));
+        assert!(ka.is_empty());
+    }
+}
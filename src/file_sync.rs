@@ -0,0 +1,141 @@
+//! One selection shared by several processes through a file.
+//!
+//! [`FileKeyArray`] stores the current key like [`UserConfig`] does, and
+//! serializes access with an advisory lock on a `<file>.lock` sidecar, so a
+//! tray applet and a daemon can both flip the same mode without clobbering
+//! each other. Other processes' changes are picked up by
+//! [`refresh`](FileKeyArray::refresh); call it on a timer or from a file
+//! watcher.
+//!
+//! ```no_run
+//! use keyarray::file_sync::FileKeyArray;
+//! use keyarray::KeyArray;
+//!
+//! let mut mode = FileKeyArray::open("/run/user/1000/power-mode", KeyArray::new(["Eco", "Balanced", "Boost"]))?;
+//! mode.change(2)?;
+//! // ... later, in another process ...
+//! if mode.refresh()? {
+//!     println!("now {}", mode.keys().current());
+//! }
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use crate::persist::UserConfig;
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::PathBuf;
+
+/// A KeyArray whose selection lives in a file shared between processes.
+pub struct FileKeyArray<K> {
+    config: UserConfig,
+    lock: File,
+    keys: KeyArray<K>,
+}
+
+// Holds the advisory lock until dropped.
+struct Held<'a>(&'a File);
+
+impl Drop for Held<'_> {
+    fn drop(&mut self) {
+        let _ = self.0.unlock();
+    }
+}
+
+impl<K> FileKeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Share the selection stored at `path`, restoring it into `defaults`
+    /// as [`UserConfig::load_or`] does.
+    pub fn open(path: impl Into<PathBuf>, defaults: KeyArray<K>) -> io::Result<Self> {
+        let config = UserConfig::at(path);
+        if let Some(dir) = config.path().parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut lock_path = config.path().to_path_buf().into_os_string();
+        lock_path.push(".lock");
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(PathBuf::from(lock_path))?;
+        lock.lock()?;
+        let held = Held(&lock);
+        let keys = config.load_or(defaults)?;
+        drop(held);
+        Ok(FileKeyArray { config, lock, keys })
+    }
+
+    /// The local copy, as of the last change or refresh.
+    pub fn keys(&self) -> &KeyArray<K> {
+        &self.keys
+    }
+
+    /// Re-read the file and adopt a selection made by another process.
+    /// Returns whether the current key changed. A saved key that is not in
+    /// this process's list, or that it cannot select, is ignored.
+    pub fn refresh(&mut self) -> io::Result<bool> {
+        self.lock.lock_shared()?;
+        let _held = Held(&self.lock);
+        reload(&self.config, &mut self.keys)
+    }
+
+    /// Select key `i` and write it out. Fails with
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput), wrapping the
+    /// [`KeyArrayError`](crate::KeyArrayError), if it cannot be selected.
+    pub fn change(&mut self, i: usize) -> io::Result<()> {
+        self.update(|keys| keys.try_change(i))?
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+    }
+
+    /// Read-modify-write under the exclusive lock: adopt the latest
+    /// selection from the file, apply `f`, and atomically write the result.
+    pub fn update<R>(&mut self, f: impl FnOnce(&mut KeyArray<K>) -> R) -> io::Result<R> {
+        self.lock.lock()?;
+        let _held = Held(&self.lock);
+        reload(&self.config, &mut self.keys)?;
+        let result = f(&mut self.keys);
+        self.config.save(&self.keys)?;
+        Ok(result)
+    }
+}
+
+// Adopt the file's selection; the caller holds the lock.
+fn reload<K>(config: &UserConfig, keys: &mut KeyArray<K>) -> io::Result<bool>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    match config.saved_index(keys)? {
+        // a key this process cannot select is ignored
+        Some(i) if i != keys.current_index() => Ok(keys.try_change(i).is_ok()),
+        _ => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_handles_share_a_selection() {
+        let dir = std::env::temp_dir().join(format!("keyarray-sync-{}", std::process::id()));
+        let path = dir.join("mode");
+        let _ = fs::remove_file(&path);
+        let defaults = || KeyArray::new(["Eco", "Balanced", "Boost"]);
+
+        let mut daemon = FileKeyArray::open(&path, defaults()).unwrap();
+        let mut applet = FileKeyArray::open(&path, defaults()).unwrap();
+        applet.change(2).unwrap();
+        assert!(daemon.refresh().unwrap());
+        assert_eq!(daemon.keys().current(), &"Boost");
+        assert!(!daemon.refresh().unwrap());
+
+        // the update starts from the applet's latest write
+        applet.change(1).unwrap();
+        daemon.update(|keys| keys.next()).unwrap();
+        assert_eq!(daemon.keys().current(), &"Boost");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "Boost\n");
+    }
+}
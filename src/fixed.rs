@@ -0,0 +1,281 @@
+//! A fixed-capacity KeyArray for `no_std` targets without a heap.
+//!
+//! [`KeyArrayFixed<K, N>`](KeyArrayFixed) keeps up to `N` keys inline in an
+//! array, so it can live in a `static` or on the stack of a
+//! microcontroller. It mirrors KeyArray's selection and editing methods,
+//! except that adding a key once `N` keys are stored panics, or fails with
+//! [`KeyArrayError::Full`] from the `try_*` forms. Both types implement
+//! [`SelectionCursor`], which generic navigation code can take instead of
+//! either.
+//!
+//! ```
+//! use keyarray::{KeyArrayFixed, KeyArrayError, SelectionCursor};
+//!
+//! let mut mode: KeyArrayFixed<&str, 4> = KeyArrayFixed::new(&["Off", "Low", "High"]);
+//! mode.next();
+//! assert_eq!(mode.current(), &"Low");
+//! mode.push("Turbo");
+//! assert_eq!(mode.try_push("Boost"), Err(KeyArrayError::Full { capacity: 4 }));
+//!
+//! fn cycle(menu: &mut impl SelectionCursor) {
+//!     menu.advance(2);
+//! }
+//! cycle(&mut mode);
+//! assert_eq!(mode.current_index(), 3);
+//! ```
+
+use crate::check_index;
+use crate::cursor::SelectionCursor;
+use crate::error::KeyArrayError;
+use core::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+/// Up to `N` keys, stored inline, one of which is current.
+#[derive(Clone)]
+pub struct KeyArrayFixed<K, const N: usize> {
+    // only `..len` are keys; the rest are stale copies kept so the array
+    // is always initialised
+    keys: [K; N],
+    len: usize,
+    idx: usize,
+}
+
+impl<K, const N: usize> KeyArrayFixed<K, N>
+where
+    K: Clone + PartialEq,
+{
+    /// Create from a slice of keys. Panics if it is empty or longer than
+    /// `N`.
+    pub fn new(keys: &[K]) -> Self {
+        Self::try_new(keys).unwrap_or_else(|e| panic!("KeyArrayFixed::new: {}", e))
+    }
+
+    /// [`new`](Self::new), returning an error instead of panicking.
+    pub fn try_new(keys: &[K]) -> Result<Self, KeyArrayError> {
+        Self::try_new_with(keys, 0)
+    }
+
+    /// Same as `new`, but start at `start_idx`. Panics if out of bounds.
+    pub fn new_with(keys: &[K], start_idx: usize) -> Self {
+        Self::try_new_with(keys, start_idx)
+            .unwrap_or_else(|e| panic!("KeyArrayFixed::new_with: {}", e))
+    }
+
+    /// [`new_with`](Self::new_with), returning an error instead of
+    /// panicking.
+    pub fn try_new_with(keys: &[K], start_idx: usize) -> Result<Self, KeyArrayError> {
+        let Some(first) = keys.first() else {
+            return Err(KeyArrayError::Empty);
+        };
+        if keys.len() > N {
+            return Err(KeyArrayError::Full { capacity: N });
+        }
+        check_index(start_idx, keys.len())?;
+        Ok(KeyArrayFixed {
+            keys: core::array::from_fn(|i| keys.get(i).unwrap_or(first).clone()),
+            len: keys.len(),
+            idx: start_idx,
+        })
+    }
+
+    /// Change the current key by zero-based index.
+    /// Panics if `i` is out of bounds.
+    pub fn change(&mut self, i: usize) {
+        self.try_change(i)
+            .unwrap_or_else(|e| panic!("KeyArrayFixed::change: {}", e))
+    }
+
+    /// [`change`](Self::change), returning an error instead of panicking.
+    pub fn try_change(&mut self, i: usize) -> Result<(), KeyArrayError> {
+        check_index(i, self.len)?;
+        self.idx = i;
+        Ok(())
+    }
+
+    /// Get a reference to the current key. Panics if every key was removed.
+    pub fn current(&self) -> &K {
+        &self.keys()[self.idx]
+    }
+
+    /// Get the index of the current key.
+    pub fn current_index(&self) -> usize {
+        self.idx
+    }
+
+    /// Get a slice of all keys.
+    pub fn keys(&self) -> &[K] {
+        &self.keys[..self.len]
+    }
+
+    /// Number of keys.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if there are no keys.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The most keys this array can hold, `N`.
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns true if no more keys fit.
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Append a key after the last. Panics if there is no room.
+    pub fn push(&mut self, key: K) {
+        self.try_push(key)
+            .unwrap_or_else(|e| panic!("KeyArrayFixed::push: {}", e))
+    }
+
+    /// Insert a key at position `i`. Panics if `i > len` or there is no
+    /// room.
+    pub fn insert(&mut self, i: usize, key: K) {
+        self.try_insert(i, key)
+            .unwrap_or_else(|e| panic!("KeyArrayFixed::insert: {}", e))
+    }
+
+    /// Append a key after the last. Fails with [`KeyArrayError::Full`] if
+    /// there is no room.
+    pub fn try_push(&mut self, key: K) -> Result<(), KeyArrayError> {
+        self.try_insert(self.len, key)
+    }
+
+    /// Insert a key at position `i`. Fails if `i > len` or there is no
+    /// room.
+    pub fn try_insert(&mut self, i: usize, key: K) -> Result<(), KeyArrayError> {
+        if i > self.len {
+            let len = self.len;
+            return Err(KeyArrayError::IndexOutOfBounds { index: i, len });
+        }
+        if self.is_full() {
+            return Err(KeyArrayError::Full { capacity: N });
+        }
+        self.keys[self.len] = key;
+        self.keys[i..=self.len].rotate_right(1);
+        self.len += 1;
+        // if you inserted before current idx, bump it forward
+        if i <= self.idx && self.len > 1 {
+            self.idx += 1;
+        }
+        Ok(())
+    }
+
+    /// Remove and return the key at `i`. Panics if out of bounds.
+    pub fn remove(&mut self, i: usize) -> K {
+        self.try_remove(i)
+            .unwrap_or_else(|e| panic!("KeyArrayFixed::remove: {}", e))
+    }
+
+    /// [`remove`](Self::remove), returning an error instead of panicking.
+    pub fn try_remove(&mut self, i: usize) -> Result<K, KeyArrayError> {
+        check_index(i, self.len)?;
+        let removed = self.keys[i].clone();
+        self.keys[i..self.len].rotate_left(1);
+        self.len -= 1;
+        if self.idx >= self.len {
+            self.idx = self.len.saturating_sub(1);
+        }
+        Ok(removed)
+    }
+}
+
+impl<K, const N: usize> SelectionCursor for KeyArrayFixed<K, N> {
+    type Item = K;
+
+    fn item_count(&self) -> usize {
+        self.len
+    }
+
+    fn cursor(&self) -> usize {
+        self.idx
+    }
+
+    fn set_cursor(&mut self, i: usize) {
+        self.idx = i;
+    }
+
+    fn item(&self, i: usize) -> Option<&K> {
+        self.keys[..self.len].get(i)
+    }
+}
+
+impl<K, const N: usize> Display for KeyArrayFixed<K, N>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "keys={:?}, current_idx={}, current={}",
+            self.keys(),
+            self.idx,
+            self.current()
+        )
+    }
+}
+
+/// `{:?}` marks the current key: `KeyArrayFixed(["On", >"Off"<])`.
+impl<K: Debug, const N: usize> Debug for KeyArrayFixed<K, N> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str("KeyArrayFixed([")?;
+        for (i, key) in self.keys[..self.len].iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            if i == self.idx {
+                write!(f, ">{:?}<", key)?;
+            } else {
+                write!(f, "{:?}", key)?;
+            }
+        }
+        f.write_str("])")
+    }
+}
+
+/// Compact RTT form: `KeyArrayFixed(["On", "Off"], current=1)`.
+#[cfg(feature = "defmt")]
+impl<K, const N: usize> defmt::Format for KeyArrayFixed<K, N>
+where
+    K: Clone + PartialEq + defmt::Format,
+{
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "KeyArrayFixed({=[?]}, current={=usize})", self.keys(), self.idx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use std::format;
+
+    #[test]
+    fn edits_within_capacity() {
+        let mut ka: KeyArrayFixed<u8, 3> = KeyArrayFixed::new_with(&[1, 2], 1);
+        ka.insert(0, 0);
+        assert_eq!((ka.keys(), ka.current()), (&[0, 1, 2][..], &2));
+        assert!(ka.is_full());
+        assert_eq!(ka.try_push(3), Err(KeyArrayError::Full { capacity: 3 }));
+        assert_eq!(ka.remove(2), 2);
+        assert_eq!((ka.keys(), ka.current()), (&[0, 1][..], &1));
+        ka.prev();
+        assert_eq!(format!("{:?}", ka), "KeyArrayFixed([>0<, 1])");
+        assert_eq!(
+            KeyArrayFixed::<u8, 1>::try_new(&[1, 2]).unwrap_err(),
+            KeyArrayError::Full { capacity: 1 }
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "KeyArrayFixed::push")]
+    fn push_past_capacity_panics() {
+        let mut ka: KeyArrayFixed<u8, 1> = KeyArrayFixed::new(&[1]);
+        ka.push(2);
+    }
+}
@@ -0,0 +1,83 @@
+//! Consuming `with_*` counterparts of the mutating methods, for builder-style
+//! pipelines and immutable-state frameworks.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//!
+//! let ka = KeyArray::new(["Low", "High"])
+//!     .with_insert(1, "Medium")
+//!     .with_push("Max")
+//!     .with_change(2);
+//! assert_eq!(ka.keys(), &["Low", "Medium", "High", "Max"]);
+//! assert_eq!(ka.current(), &"High");
+//! ```
+
+use crate::KeyArray;
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display};
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// [`change`](Self::change), returning the result.
+    pub fn with_change(mut self, i: usize) -> Self {
+        self.change(i);
+        self
+    }
+
+    /// [`next`](Self::next), returning the result.
+    pub fn with_next(mut self) -> Self {
+        self.next();
+        self
+    }
+
+    /// [`prev`](Self::prev), returning the result.
+    pub fn with_prev(mut self) -> Self {
+        self.prev();
+        self
+    }
+
+    /// [`push`](Self::push), returning the result.
+    pub fn with_push(mut self, key: K) -> Self {
+        self.push(key);
+        self
+    }
+
+    /// [`insert`](Self::insert), returning the result.
+    pub fn with_insert(mut self, i: usize, key: K) -> Self {
+        self.insert(i, key);
+        self
+    }
+
+    /// [`remove`](Self::remove), discarding the removed key.
+    pub fn with_remove(mut self, i: usize) -> Self {
+        self.remove(i);
+        self
+    }
+
+    /// [`sort_by`](Self::sort_by), returning the result.
+    pub fn with_sort_by<F>(mut self, compare: F) -> Self
+    where
+        F: FnMut(&K, &K) -> Ordering,
+    {
+        self.sort_by(compare);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pipeline() {
+        let ka = KeyArray::new(["B", "C", "A"])
+            .with_prev()
+            .with_sort_by(|a, b| a.cmp(b))
+            .with_remove(0)
+            .with_next();
+        assert_eq!(ka.keys(), &["B", "C"]);
+        assert_eq!(ka.current(), &"C");
+    }
+}
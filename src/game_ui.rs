@@ -0,0 +1,159 @@
+//! Immediate-mode menu row for macroquad games.
+//!
+//! Call [`key_row`] once per frame: it reads the keyboard, moves the
+//! selection, draws the row and reports whether the player confirmed.
+//!
+//! ```no_run
+//! use keyarray::game_ui::{key_row, MenuStyle};
+//! use keyarray::KeyArray;
+//! use macroquad::prelude::*;
+//!
+//! #[macroquad::main("Menu")]
+//! async fn main() {
+//!     let mut difficulty = KeyArray::new(["Easy", "Normal", "Hard"]);
+//!     loop {
+//!         clear_background(BLACK);
+//!         if key_row(&mut difficulty, 40.0, 80.0, &MenuStyle::default()) {
+//!             break;
+//!         }
+//!         next_frame().await;
+//!     }
+//! }
+//! ```
+//!
+//! Gamepads are not read by macroquad itself; map your gamepad library's
+//! buttons to a [`MenuInput`] and pass it to [`key_row_with_input`].
+
+use crate::KeyArray;
+use ::macroquad::color::{Color, DARKGRAY, GOLD, WHITE};
+use ::macroquad::input::{is_key_pressed, KeyCode};
+use ::macroquad::shapes::draw_rectangle;
+use ::macroquad::text::{draw_text, measure_text};
+use std::fmt::{Debug, Display};
+
+/// One navigation action for a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MenuInput {
+    Next,
+    Prev,
+    Confirm,
+}
+
+/// Look and feel of [`draw_key_row`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MenuStyle {
+    pub font_size: f32,
+    /// Horizontal gap between items.
+    pub spacing: f32,
+    /// Padding around the highlight box.
+    pub padding: f32,
+    pub text: Color,
+    pub selected_text: Color,
+    pub highlight: Color,
+}
+
+impl Default for MenuStyle {
+    fn default() -> Self {
+        MenuStyle {
+            font_size: 32.0,
+            spacing: 24.0,
+            padding: 6.0,
+            text: WHITE,
+            selected_text: DARKGRAY,
+            highlight: GOLD,
+        }
+    }
+}
+
+/// This frame's action from the arrow keys, WASD, Enter and Space.
+pub fn keyboard_input() -> Option<MenuInput> {
+    if is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::D) {
+        Some(MenuInput::Next)
+    } else if is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::A) {
+        Some(MenuInput::Prev)
+    } else if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space) {
+        Some(MenuInput::Confirm)
+    } else {
+        None
+    }
+}
+
+/// Apply `input` to `keys`; returns true on [`MenuInput::Confirm`].
+pub fn apply_input<K>(keys: &mut KeyArray<K>, input: Option<MenuInput>) -> bool
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    match input {
+        Some(MenuInput::Next) => keys.next(),
+        Some(MenuInput::Prev) => keys.prev(),
+        Some(MenuInput::Confirm) => return true,
+        None => {}
+    }
+    false
+}
+
+/// Draw the keys left to right starting at (`x`, `y`), the text baseline,
+/// with the current key on a highlight box.
+pub fn draw_key_row<K>(keys: &KeyArray<K>, x: f32, y: f32, style: &MenuStyle)
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    let mut cursor = x;
+    for (i, key) in keys.keys().iter().enumerate() {
+        let label = key.to_string();
+        let size = measure_text(&label, None, style.font_size as u16, 1.0);
+        let color = if i == keys.current_index() {
+            draw_rectangle(
+                cursor - style.padding,
+                y - size.offset_y - style.padding,
+                size.width + 2.0 * style.padding,
+                size.height + 2.0 * style.padding,
+                style.highlight,
+            );
+            style.selected_text
+        } else {
+            style.text
+        };
+        draw_text(&label, cursor, y, style.font_size, color);
+        cursor += size.width + style.spacing;
+    }
+}
+
+/// [`key_row_with_input`] driven by [`keyboard_input`].
+pub fn key_row<K>(keys: &mut KeyArray<K>, x: f32, y: f32, style: &MenuStyle) -> bool
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    key_row_with_input(keys, keyboard_input(), x, y, style)
+}
+
+/// Apply `input`, draw the row, and return true if the player confirmed.
+pub fn key_row_with_input<K>(
+    keys: &mut KeyArray<K>,
+    input: Option<MenuInput>,
+    x: f32,
+    y: f32,
+    style: &MenuStyle,
+) -> bool
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    let confirmed = apply_input(keys, input);
+    draw_key_row(keys, x, y, style);
+    confirmed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn input_drives_selection() {
+        let mut ka = KeyArray::new(["Easy", "Normal", "Hard"]);
+        assert!(!apply_input(&mut ka, Some(MenuInput::Prev)));
+        assert_eq!(ka.current(), &"Hard");
+        assert!(!apply_input(&mut ka, None));
+        assert!(apply_input(&mut ka, Some(MenuInput::Confirm)));
+        assert_eq!(ka.current(), &"Hard");
+    }
+}
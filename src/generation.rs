@@ -0,0 +1,56 @@
+//! Cheap change detection for render loops.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//!
+//! let mut ka = KeyArray::new(["On", "Off"]);
+//! let seen = ka.generation();
+//! ka.change(1);
+//! assert!(ka.generation() > seen);
+//! assert!(ka.take_dirty()); // redraw
+//! assert!(!ka.take_dirty()); // nothing new since
+//! ```
+
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Counter bumped by every mutation that changes the selection, the keys,
+    /// or data attached to them. Never decreases; clones carry it over.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Whether anything changed since the last [`take_dirty`](Self::take_dirty).
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Return whether anything changed since the last call, and reset the
+    /// flag.
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_op_changes_stay_clean() {
+        let mut ka = KeyArray::new(["A", "B"]);
+        assert!(!ka.is_dirty());
+        ka.change(0);
+        assert_eq!(ka.generation(), 0);
+        ka.next();
+        ka.push("C");
+        assert_eq!(ka.generation(), 2);
+        assert!(ka.take_dirty());
+        ka.keys_mut()[0] = "Z";
+        assert!(ka.is_dirty());
+    }
+}
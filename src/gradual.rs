@@ -0,0 +1,235 @@
+//! Gradual changes through the keys in between.
+//!
+//! Hardware such as a fan, a motor or a heater often cannot jump from its
+//! lowest to its highest mode. [`transition_plan`](KeyArray::transition_plan)
+//! lists the keys to pass through on the way, skipping hidden and disabled
+//! ones, and [`gradual_change`](KeyArray::gradual_change) turns that plan
+//! into a [`GradualChange`] that moves one key per hop, staying on each for
+//! its dwell time. Drive it by calling [`tick`](GradualChange::tick) from a
+//! control loop, or await [`drive`](GradualChange::drive) with the sleep
+//! function of any async runtime ([`drive_on`](GradualChange::drive_on)
+//! reads the time from a [`Clock`](crate::clock::Clock)).
+//!
+//! ```
+//! use keyarray::KeyArray;
+//! use std::time::{Duration, Instant};
+//!
+//! let mut fan = KeyArray::new(["Off", "Low", "Mid", "High"]);
+//! assert_eq!(fan.transition_plan(0, 3), [1, 2, 3]);
+//!
+//! let t0 = Instant::now();
+//! let mut ramp = fan.gradual_change(3, t0, |_, _| Duration::from_secs(2));
+//! ramp.tick(&mut fan, t0)?;
+//! assert_eq!(fan.current(), &"Low");
+//! ramp.tick(&mut fan, t0 + Duration::from_secs(1))?;
+//! assert_eq!(fan.current(), &"Low");
+//! ramp.tick(&mut fan, t0 + Duration::from_secs(2))?;
+//! assert_eq!(fan.current(), &"Mid");
+//! assert_eq!(ramp.remaining(), 1);
+//! # Ok::<(), keyarray::KeyArrayError>(())
+//! ```
+
+use crate::clock::{Clock, SystemClock};
+use crate::error::KeyArrayError;
+use crate::event::ChangeEvent;
+use crate::id::KeyId;
+use crate::{check_index, KeyArray};
+use std::collections::VecDeque;
+use std::fmt::{Debug, Display};
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+/// Reason attached to the change event of every hop.
+pub const REASON: &str = "gradual";
+
+/// A change in progress, moving one key per hop. Created by
+/// [`gradual_change`](KeyArray::gradual_change). The steps follow the keys'
+/// ids, so they survive edits; a step whose key was removed is skipped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[must_use = "the selection only moves when the change is ticked or driven"]
+pub struct GradualChange {
+    // each step's key and how long to stay on it before the next hop
+    steps: VecDeque<(KeyId, Duration)>,
+    due: Instant,
+}
+
+impl GradualChange {
+    /// Make the next hop if it is due at `now`. Returns its event, or
+    /// `None` if no hop was made. Fails, abandoning the remaining steps, if
+    /// the key of the hop cannot be selected.
+    pub fn tick<K>(
+        &mut self,
+        keys: &mut KeyArray<K>,
+        now: Instant,
+    ) -> Result<Option<ChangeEvent<K>>, KeyArrayError>
+    where
+        K: Clone + PartialEq + Debug + Display,
+    {
+        while let Some(&(id, dwell)) = self.steps.front() {
+            if now < self.due {
+                break;
+            }
+            self.steps.pop_front();
+            let Some(i) = keys.index_of_id(id) else {
+                continue;
+            };
+            let previous = keys.current_index();
+            if let Err(e) = keys.try_change(i) {
+                self.steps.clear();
+                return Err(e);
+            }
+            self.due = now + dwell;
+            if keys.current_index() != previous {
+                return Ok(Some(ChangeEvent {
+                    previous,
+                    current: keys.current_index(),
+                    key: keys.current().clone(),
+                    reason: Some(REASON.into()),
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Make every hop on time, sleeping in between with `sleep`, such as
+    /// `tokio::time::sleep` or `async_std::task::sleep`. Fails like
+    /// [`tick`](Self::tick).
+    pub async fn drive<K, S, F>(self, keys: &mut KeyArray<K>, sleep: S) -> Result<(), KeyArrayError>
+    where
+        K: Clone + PartialEq + Debug + Display,
+        S: FnMut(Duration) -> F,
+        F: Future<Output = ()>,
+    {
+        self.drive_on(keys, sleep, &SystemClock).await
+    }
+
+    /// [`drive`](Self::drive), reading the time from `clock`.
+    pub async fn drive_on<K, S, F>(
+        mut self,
+        keys: &mut KeyArray<K>,
+        mut sleep: S,
+        clock: &impl Clock,
+    ) -> Result<(), KeyArrayError>
+    where
+        K: Clone + PartialEq + Debug + Display,
+        S: FnMut(Duration) -> F,
+        F: Future<Output = ()>,
+    {
+        while let Some(due) = self.next_due() {
+            let wait = due.saturating_duration_since(clock.now());
+            if !wait.is_zero() {
+                sleep(wait).await;
+            }
+            self.tick(keys, clock.now().max(due))?;
+        }
+        Ok(())
+    }
+
+    /// When the next hop is due; `None` once the change is finished.
+    pub fn next_due(&self) -> Option<Instant> {
+        (!self.steps.is_empty()).then_some(self.due)
+    }
+
+    /// How many hops are left.
+    pub fn remaining(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// The keys to pass through, one index at a time, when moving from key
+    /// `from` to key `to`, ending with `to`. Hidden and disabled keys in
+    /// between are skipped. Empty if `from == to`. Panics if either index
+    /// is out of bounds.
+    pub fn transition_plan(&self, from: usize, to: usize) -> Vec<usize> {
+        for i in [from, to] {
+            check_index(i, self.len())
+                .unwrap_or_else(|e| panic!("KeyArray::transition_plan: {}", e));
+        }
+        if from == to {
+            return Vec::new();
+        }
+        let mut plan: Vec<usize> = if from < to {
+            (from + 1..to).collect()
+        } else {
+            (to + 1..from).rev().collect()
+        };
+        plan.retain(|&i| self.selectable(i));
+        plan.push(to);
+        plan
+    }
+
+    /// Start moving from the current key to key `to` along its
+    /// [`transition_plan`](Self::transition_plan). The first hop is due at
+    /// `now`; after each, the selection stays on key `i` for
+    /// `dwell(i, key)`. Nothing changes until the result is ticked. Panics
+    /// if `to` is out of bounds.
+    pub fn gradual_change<F>(&self, to: usize, now: Instant, mut dwell: F) -> GradualChange
+    where
+        F: FnMut(usize, &K) -> Duration,
+    {
+        check_index(to, self.len()).unwrap_or_else(|e| panic!("KeyArray::gradual_change: {}", e));
+        let steps = self
+            .transition_plan(self.idx, to)
+            .into_iter()
+            .map(|i| (self.slots[i].id, dwell(i, &self.keys[i])))
+            .collect();
+        GradualChange { steps, due: now }
+    }
+}
+
+#[cfg(all(test, feature = "disable"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hops_skip_unselectable_and_removed_keys() {
+        let mut ka = KeyArray::new_with(["0", "1", "2", "3", "4"], 4);
+        ka.disable(2);
+        assert_eq!(ka.transition_plan(4, 0), [3, 1, 0]);
+        assert!(ka.transition_plan(1, 1).is_empty());
+
+        let t0 = Instant::now();
+        let secs = Duration::from_secs;
+        let mut down = ka.gradual_change(0, t0, |i, _| secs(i as u64));
+        let ev = down.tick(&mut ka, t0).unwrap().unwrap();
+        assert_eq!((ev.previous, ev.current, ev.reason.unwrap().as_str()), (4, 3, REASON));
+        assert_eq!(down.next_due(), Some(t0 + secs(3)));
+        ka.remove(1);
+        let ev = down.tick(&mut ka, t0 + secs(3)).unwrap().unwrap();
+        assert_eq!((ev.current, &ev.key), (0, &"0"));
+        assert!(down.is_finished() && down.next_due().is_none());
+
+        ka.enable(1);
+        let mut up = ka.gradual_change(3, t0, |_, _| Duration::ZERO);
+        ka.disable(1);
+        assert_eq!(up.tick(&mut ka, t0), Err(KeyArrayError::Disabled { index: 1 }));
+        assert_eq!(up.remaining(), 0);
+    }
+
+    #[test]
+    fn driving_sleeps_on_the_clock() {
+        use crate::clock::MockClock;
+        use futures::executor::block_on;
+
+        let clock = MockClock::new();
+        let mut ka = KeyArray::new(["0", "1", "2", "3"]);
+        let ramp = ka.gradual_change(3, clock.now(), |_, _| Duration::from_secs(5));
+        let mut slept = Vec::new();
+        let sleep = |d| {
+            slept.push(d);
+            clock.advance(d);
+            std::future::ready(())
+        };
+        block_on(ramp.drive_on(&mut ka, sleep, &clock)).unwrap();
+        assert_eq!(ka.current(), &"3");
+        assert_eq!(slept, [Duration::from_secs(5); 2]);
+    }
+}
@@ -0,0 +1,556 @@
+//! Selections that depend on other selections.
+//!
+//! A [`KeyGraph`] owns named KeyArrays and constraints between them: a
+//! constraint says which keys of a downstream array are allowed while a
+//! given key of an upstream array is current. Changing a selection through
+//! the graph re-checks everything downstream of it, moving any selection
+//! that became invalid to the nearest allowed key and reporting each such
+//! move as a [`Cascade`]. Constraints may not form a cycle.
+//!
+//! [`snapshot_all`](KeyGraph::snapshot_all) captures every selection at
+//! once as a [`GraphSnapshot`], serializable with the `serde` feature, and
+//! [`restore_all`](KeyGraph::restore_all) brings a saved session back,
+//! repairing stale arrays leniently and reporting what it fixed.
+//!
+//! ```
+//! use keyarray::graph::KeyGraph;
+//! use keyarray::KeyArray;
+//!
+//! let mut g = KeyGraph::new();
+//! g.add("mode", KeyArray::new(["Auto", "Manual"])).unwrap();
+//! g.add("gear", KeyArray::new(["D", "1", "2", "3"])).unwrap();
+//! // in Manual, only numbered gears
+//! g.depend("gear", "mode", |mode, gear| *mode == "Auto" || *gear != "D").unwrap();
+//!
+//! let cascades = g.change("mode", 1).unwrap();
+//! assert_eq!(cascades[0].array, "gear");
+//! assert_eq!(g.get("gear").unwrap().current(), &"1");
+//! assert_eq!(g.valid_indices("gear").unwrap(), [1, 2, 3]);
+//! assert!(g.change("gear", 0).is_err());
+//! ```
+
+use crate::error::KeyArrayError;
+use crate::event::{ChangeEvent, ChangeReason};
+use crate::repair::{Repair, RepairReport};
+use crate::KeyArray;
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+/// Reason attached to the change events of cascaded moves.
+pub const REASON: &str = "cascade";
+
+type Constraint<K> = Box<dyn Fn(&K, &K) -> bool + Send + Sync>;
+
+struct Edge<K> {
+    upstream: usize,
+    downstream: usize,
+    allows: Constraint<K>,
+}
+
+/// Named KeyArrays with constraints between their selections; see the
+/// [module docs](self).
+pub struct KeyGraph<K> {
+    names: Vec<String>,
+    arrays: Vec<KeyArray<K>>,
+    edges: Vec<Edge<K>>,
+}
+
+/// A selection the graph moved because an upstream selection changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cascade<K> {
+    pub array: String,
+    /// Carries [`REASON`].
+    pub event: ChangeEvent<K>,
+}
+
+/// One array as saved by [`snapshot_all`](KeyGraph::snapshot_all): its keys
+/// as `Display` text and its current index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SavedArray {
+    pub keys: Vec<String>,
+    pub current: usize,
+}
+
+/// Every array of a graph at one moment, by name.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GraphSnapshot {
+    pub arrays: BTreeMap<String, SavedArray>,
+}
+
+/// What [`restore_all`](KeyGraph::restore_all) did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestoreReport<K> {
+    /// The corrections made to each restored array; clean reports included.
+    pub repairs: BTreeMap<String, RepairReport>,
+    /// Registered arrays the snapshot does not mention, left as they were.
+    pub missing: Vec<String>,
+    /// Saved arrays no longer registered, ignored.
+    pub unknown: Vec<String>,
+    /// Selections moved afterwards because restored upstream selections no
+    /// longer allowed them.
+    pub cascades: Vec<Cascade<K>>,
+}
+
+impl<K> RestoreReport<K> {
+    /// Whether every registered array was restored exactly as saved.
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty()
+            && self.unknown.is_empty()
+            && self.cascades.is_empty()
+            && self.repairs.values().all(RepairReport::is_clean)
+    }
+}
+
+/// Why a graph operation was refused. Nothing is changed when it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphError {
+    /// No array is registered under the name.
+    UnknownArray(String),
+    /// An array is already registered under the name.
+    DuplicateName(String),
+    /// The constraint would make `upstream` depend on itself.
+    Cycle { upstream: String, downstream: String },
+    /// The constraints do not allow key `index` of `array`.
+    NotAllowed { array: String, index: usize },
+    /// No key of `array` is allowed by its constraints.
+    NoValidKey(String),
+    Index(KeyArrayError),
+}
+
+impl Display for GraphError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            GraphError::UnknownArray(name) => write!(f, "no array named {:?}", name),
+            GraphError::DuplicateName(name) => write!(f, "an array named {:?} exists", name),
+            GraphError::Cycle {
+                upstream,
+                downstream,
+            } => write!(
+                f,
+                "{:?} depending on {:?} would form a cycle",
+                downstream, upstream
+            ),
+            GraphError::NotAllowed { array, index } => {
+                write!(f, "key {} of {:?} is not allowed", index, array)
+            }
+            GraphError::NoValidKey(name) => write!(f, "no key of {:?} is allowed", name),
+            GraphError::Index(e) => Display::fmt(e, f),
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
+
+impl From<KeyArrayError> for GraphError {
+    fn from(e: KeyArrayError) -> Self {
+        GraphError::Index(e)
+    }
+}
+
+impl<K> Default for KeyGraph<K> {
+    fn default() -> Self {
+        KeyGraph {
+            names: Vec::new(),
+            arrays: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+}
+
+impl<K> KeyGraph<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `keys` under `name`.
+    pub fn add(&mut self, name: impl Into<String>, keys: KeyArray<K>) -> Result<(), GraphError> {
+        let name = name.into();
+        if self.names.contains(&name) {
+            return Err(GraphError::DuplicateName(name));
+        }
+        self.names.push(name);
+        self.arrays.push(keys);
+        Ok(())
+    }
+
+    /// The array registered under `name`.
+    pub fn get(&self, name: &str) -> Option<&KeyArray<K>> {
+        Some(&self.arrays[self.position(name).ok()?])
+    }
+
+    /// Names of the registered arrays, in the order they were added.
+    pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.names.iter().map(String::as_str)
+    }
+
+    /// Only allow a key of `downstream` while `allows(upstream_key,
+    /// downstream_key)` holds for the current key of `upstream`. Selections
+    /// the new constraint invalidates are moved, as by
+    /// [`change`](Self::change).
+    pub fn depend<F>(
+        &mut self,
+        downstream: &str,
+        upstream: &str,
+        allows: F,
+    ) -> Result<Vec<Cascade<K>>, GraphError>
+    where
+        F: Fn(&K, &K) -> bool + Send + Sync + 'static,
+    {
+        let (down, up) = (self.position(downstream)?, self.position(upstream)?);
+        if down == up || self.reaches(down, up) {
+            return Err(GraphError::Cycle {
+                upstream: upstream.to_owned(),
+                downstream: downstream.to_owned(),
+            });
+        }
+        self.edges.push(Edge {
+            upstream: up,
+            downstream: down,
+            allows: Box::new(allows),
+        });
+        let cascades = self.staged(|g, arrays| g.revalidate(arrays));
+        if cascades.is_err() {
+            self.edges.pop();
+        }
+        cascades
+    }
+
+    /// Whether key `i` of `name` is allowed by the current upstream
+    /// selections. False if `i` is out of bounds.
+    pub fn is_allowed(&self, name: &str, i: usize) -> Result<bool, GraphError> {
+        let node = self.position(name)?;
+        Ok(i < self.arrays[node].len() && self.allowed(&self.arrays, node, i))
+    }
+
+    /// Indices of the keys of `name` its constraints currently allow.
+    pub fn valid_indices(&self, name: &str) -> Result<Vec<usize>, GraphError> {
+        let node = self.position(name)?;
+        let len = self.arrays[node].len();
+        Ok((0..len).filter(|&i| self.allowed(&self.arrays, node, i)).collect())
+    }
+
+    /// Make key `i` of `name` current and re-check everything downstream.
+    /// Fails, changing nothing, if `i` is not allowed or a downstream array
+    /// is left without any allowed key.
+    pub fn change(&mut self, name: &str, i: usize) -> Result<Vec<Cascade<K>>, GraphError> {
+        let node = self.position(name)?;
+        self.staged(|g, arrays| {
+            arrays[node].try_change(i)?;
+            if !g.allowed(arrays, node, i) {
+                return Err(GraphError::NotAllowed {
+                    array: name.to_owned(),
+                    index: i,
+                });
+            }
+            g.revalidate(arrays)
+        })
+    }
+
+    // Run `op` on copies of the arrays first, so a failure changes
+    // nothing, then on the live arrays: copies have no observers.
+    fn staged<T>(
+        &mut self,
+        op: impl Fn(&Self, &mut [KeyArray<K>]) -> Result<T, GraphError>,
+    ) -> Result<T, GraphError> {
+        op(self, &mut self.arrays.clone())?;
+        let mut arrays = std::mem::take(&mut self.arrays);
+        let result = op(self, &mut arrays);
+        self.arrays = arrays;
+        result
+    }
+
+    fn position(&self, name: &str) -> Result<usize, GraphError> {
+        self.names
+            .iter()
+            .position(|n| n == name)
+            .ok_or_else(|| GraphError::UnknownArray(name.to_owned()))
+    }
+
+    fn allowed(&self, arrays: &[KeyArray<K>], node: usize, i: usize) -> bool {
+        let key = &arrays[node].keys()[i];
+        self.edges
+            .iter()
+            .filter(|e| e.downstream == node)
+            .all(|e| {
+                let up = &arrays[e.upstream];
+                up.is_empty() || (e.allows)(up.current(), key)
+            })
+    }
+
+    // Whether `from` is upstream of `to`, directly or through others.
+    fn reaches(&self, from: usize, to: usize) -> bool {
+        let mut stack = vec![from];
+        let mut seen = vec![false; self.names.len()];
+        while let Some(node) = stack.pop() {
+            if node == to {
+                return true;
+            }
+            if !std::mem::replace(&mut seen[node], true) {
+                let next = self.edges.iter().filter(|e| e.upstream == node);
+                stack.extend(next.map(|e| e.downstream));
+            }
+        }
+        false
+    }
+
+    // Upstream arrays before their dependents.
+    fn topological_order(&self) -> Vec<usize> {
+        let mut incoming = vec![0; self.names.len()];
+        for e in &self.edges {
+            incoming[e.downstream] += 1;
+        }
+        let mut ready: Vec<usize> = (0..incoming.len()).filter(|&n| incoming[n] == 0).collect();
+        let mut order = Vec::with_capacity(incoming.len());
+        while let Some(node) = ready.pop() {
+            order.push(node);
+            for e in self.edges.iter().filter(|e| e.upstream == node) {
+                incoming[e.downstream] -= 1;
+                if incoming[e.downstream] == 0 {
+                    ready.push(e.downstream);
+                }
+            }
+        }
+        order
+    }
+
+    // Move every disallowed selection to the nearest allowed key at or
+    // after it that can be selected, upstream first so cascades see final
+    // upstream selections.
+    fn revalidate(&self, arrays: &mut [KeyArray<K>]) -> Result<Vec<Cascade<K>>, GraphError> {
+        let mut cascades = Vec::new();
+        for node in self.topological_order() {
+            let len = arrays[node].len();
+            let current = arrays[node].current_index();
+            if len == 0 || self.allowed(arrays, node, current) {
+                continue;
+            }
+            let no_key = || GraphError::NoValidKey(self.names[node].clone());
+            let target = (current..len)
+                .chain(0..current)
+                .find(|&i| {
+                    self.allowed(arrays, node, i) && arrays[node].check_selectable(i).is_ok()
+                })
+                .ok_or_else(no_key)?;
+            let event = arrays[node]
+                .try_change_with_reason(target, ChangeReason::from(REASON))
+                .map_err(|_| no_key())?;
+            if let Some(event) = event {
+                cascades.push(Cascade {
+                    array: self.names[node].clone(),
+                    event,
+                });
+            }
+        }
+        Ok(cascades)
+    }
+}
+
+impl<K> KeyGraph<K>
+where
+    K: Clone + PartialEq + Debug + Display + FromStr,
+{
+    /// Capture the keys and selection of every array.
+    pub fn snapshot_all(&self) -> GraphSnapshot {
+        let arrays = self.names.iter().zip(&self.arrays).map(|(name, keys)| {
+            let saved = SavedArray {
+                keys: keys.keys().iter().map(ToString::to_string).collect(),
+                current: keys.current_index(),
+            };
+            (name.clone(), saved)
+        });
+        GraphSnapshot {
+            arrays: arrays.collect(),
+        }
+    }
+
+    /// Bring back the arrays saved in `snapshot`. Each saved key list is
+    /// checked with [`from_texts_lenient`](KeyArray::from_texts_lenient),
+    /// taking the registered array as the known keys, so keys the
+    /// application no longer offers are dropped and reported rather than
+    /// refused. A list that differs from the registered one is applied with
+    /// [`set_keys`](KeyArray::set_keys), so per-key state follows its key;
+    /// observers, history and the rest of the array are kept either way.
+    /// The saved selection is then made with
+    /// [`try_change`](KeyArray::try_change); one that cannot be selected
+    /// stays as it was and is reported as [`Repair::IndexClamped`]. Then
+    /// constraints are re-checked as by [`change`](Self::change). Fails,
+    /// changing nothing, if an array is left without any allowed key.
+    pub fn restore_all(
+        &mut self,
+        snapshot: &GraphSnapshot,
+    ) -> Result<RestoreReport<K>, GraphError> {
+        self.staged(|g, arrays| g.restore_into(arrays, snapshot))
+    }
+
+    fn restore_into(
+        &self,
+        arrays: &mut [KeyArray<K>],
+        snapshot: &GraphSnapshot,
+    ) -> Result<RestoreReport<K>, GraphError> {
+        let mut report = RestoreReport {
+            repairs: BTreeMap::new(),
+            missing: Vec::new(),
+            unknown: Vec::new(),
+            cascades: Vec::new(),
+        };
+        for (name, known) in self.names.iter().zip(arrays.iter_mut()) {
+            let Some(saved) = snapshot.arrays.get(name) else {
+                report.missing.push(name.clone());
+                continue;
+            };
+            let (restored, mut repairs) =
+                KeyArray::from_texts_lenient(&saved.keys, saved.current, known.clone());
+            if restored.keys() != known.keys() {
+                known.set_keys(restored.keys().iter().cloned());
+            }
+            if known.try_change(restored.current_index()).is_err() {
+                repairs.repairs.push(Repair::IndexClamped {
+                    saved: saved.current,
+                    index: known.current_index(),
+                });
+            }
+            report.repairs.insert(name.clone(), repairs);
+        }
+        let unknown = snapshot.arrays.keys().filter(|name| !self.names.contains(name));
+        report.unknown = unknown.cloned().collect();
+        report.cascades = self.revalidate(arrays)?;
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cascades_follow_chains_and_refuse_cycles() {
+        let mut g = KeyGraph::new();
+        g.add("a", KeyArray::new([0, 1])).unwrap();
+        g.add("b", KeyArray::new([0, 1, 2])).unwrap();
+        g.add("c", KeyArray::new([0, 1, 2])).unwrap();
+        // b and c must be at least their upstream
+        g.depend("b", "a", |up, k| k >= up).unwrap();
+        g.depend("c", "b", |up, k| k >= up).unwrap();
+        assert!(matches!(g.depend("a", "c", |_, _| true), Err(GraphError::Cycle { .. })));
+        assert_eq!(g.add("a", KeyArray::new([9])), Err(GraphError::DuplicateName("a".into())));
+
+        let cascades = g.change("a", 1).unwrap();
+        let moved: Vec<_> = cascades.iter().map(|c| (c.array.as_str(), c.event.current)).collect();
+        assert_eq!(moved, [("b", 1), ("c", 1)]);
+        assert_eq!(cascades[0].event.reason.as_ref().unwrap().as_str(), REASON);
+
+        // nothing changes when a downstream array runs dry
+        g.depend("c", "a", |up, k| *up == 0 || *k == 0).unwrap_err();
+        assert_eq!(g.change("b", 0), Err(GraphError::NotAllowed { array: "b".into(), index: 0 }));
+        assert_eq!(g.is_allowed("c", 2), Ok(true));
+    }
+
+    #[cfg(feature = "disable")]
+    #[test]
+    fn cascades_pass_over_disabled_keys() {
+        let mut g = KeyGraph::new();
+        g.add("mode", KeyArray::new(["Auto", "Manual"])).unwrap();
+        let mut gear = KeyArray::new(["D", "1", "2"]);
+        gear.disable(1);
+        g.add("gear", gear).unwrap();
+        g.depend("gear", "mode", |mode, gear| *mode == "Auto" || *gear != "D").unwrap();
+        let cascades = g.change("mode", 1).unwrap();
+        assert_eq!(cascades[0].event.current, 2);
+
+        g.change("mode", 0).unwrap();
+        g.change("gear", 0).unwrap();
+        g.arrays[1].disable(2);
+        assert_eq!(g.change("mode", 1), Err(GraphError::NoValidKey("gear".into())));
+    }
+
+    #[cfg(feature = "observers")]
+    #[test]
+    fn observers_see_changes_and_cascades() {
+        use std::sync::{Arc, Mutex};
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut g = KeyGraph::new();
+        let mut mode = KeyArray::new(["Auto", "Manual"]);
+        let mut gear = KeyArray::new(["D", "1"]);
+        let log = seen.clone();
+        let _mode = mode.on_change(move |_, i, _| log.lock().unwrap().push(("mode", i)));
+        let log = seen.clone();
+        let _gear = gear.on_change(move |_, i, _| log.lock().unwrap().push(("gear", i)));
+        g.add("mode", mode).unwrap();
+        g.add("gear", gear).unwrap();
+        g.depend("gear", "mode", |mode, gear| *mode == "Auto" || *gear != "D").unwrap();
+        g.change("mode", 1).unwrap();
+        assert_eq!(*seen.lock().unwrap(), [("mode", 1), ("gear", 1)]);
+        assert_eq!(g.get("gear").unwrap().observer_count(), 1);
+    }
+
+    #[test]
+    fn sessions_restore_with_repairs() {
+        let mut g = KeyGraph::new();
+        g.add("mode", KeyArray::new([0u8, 1])).unwrap();
+        g.add("level", KeyArray::new([0u8, 1, 2])).unwrap();
+        g.add("extra", KeyArray::new([7u8])).unwrap();
+        g.depend("level", "mode", |m, l| *m == 0 || *l > 0).unwrap();
+        g.change("level", 0).unwrap();
+        let mut saved = g.snapshot_all();
+        let level = SavedArray {
+            keys: vec!["0".into(), "1".into(), "2".into()],
+            current: 0,
+        };
+        assert_eq!(saved.arrays["level"], level);
+
+        // a stale session: mode was changed by hand, level lists a retired key
+        saved.arrays.get_mut("mode").unwrap().current = 1;
+        saved.arrays.get_mut("level").unwrap().keys.insert(0, "9".into());
+        saved.arrays.get_mut("level").unwrap().current = 1;
+        saved.arrays.remove("extra");
+        saved.arrays.insert("gone".into(), level);
+        let report = g.restore_all(&saved).unwrap();
+        assert!(!report.is_clean());
+        assert_eq!(report.repairs["level"].repairs.len(), 1);
+        assert_eq!((report.missing, report.unknown), (vec!["extra".into()], vec!["gone".into()]));
+        assert_eq!(report.cascades[0].event.current, 1);
+        assert_eq!(g.get("level").unwrap().current(), &1);
+
+        let again = g.snapshot_all();
+        assert!(g.restore_all(&again).unwrap().is_clean());
+    }
+
+    #[cfg(feature = "disable")]
+    #[test]
+    fn restoring_keeps_per_key_state() {
+        let mut g = KeyGraph::new();
+        let mut level = KeyArray::new([0u8, 1, 2, 3]);
+        level.disable(2);
+        #[cfg(feature = "meta")]
+        level.set_meta(3, "max");
+        #[cfg(feature = "observers")]
+        let _sub = level.on_change(|_, _, _| {});
+        g.add("level", level).unwrap();
+        let mut saved = g.snapshot_all();
+        saved.arrays.get_mut("level").unwrap().current = 3;
+        assert!(g.restore_all(&saved).unwrap().is_clean());
+        assert_eq!(g.get("level").unwrap().current(), &3);
+        assert!(!g.get("level").unwrap().is_enabled(2));
+
+        // a session without key 1 that had the now disabled key 2 selected
+        let level = saved.arrays.get_mut("level").unwrap();
+        level.keys.remove(1);
+        level.current = 1;
+        let report = g.restore_all(&saved).unwrap();
+        let index = Repair::IndexClamped { saved: 1, index: 2 };
+        assert_eq!(report.repairs["level"].repairs, [index]);
+        let level = g.get("level").unwrap();
+        assert_eq!((level.keys(), level.current()), (&[0, 2, 3][..], &3));
+        assert!(!level.is_enabled(1));
+        #[cfg(feature = "meta")]
+        assert_eq!(level.meta::<&str>(2), Some(&"max"));
+        #[cfg(feature = "observers")]
+        assert_eq!(level.observer_count(), 1);
+    }
+}
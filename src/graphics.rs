@@ -0,0 +1,335 @@
+//! Key rows and scrolling lists on any `embedded-graphics` display.
+//!
+//! Both helpers draw with a monospace font and show the current key in
+//! inverse video, which reads well on 1-bit OLEDs. Other keys are drawn in
+//! their [`KeyStyle`] colors, through the style's palette, and underlined
+//! if their emphasis says so. They take any [`StyledKeys`], so a
+//! heap-free [`KeyArrayFixed`] works without `std`; only a [`KeyArray`]
+//! with the `ui` feature has per-key styles.
+//!
+//! ```
+//! use embedded_graphics::mock_display::MockDisplay;
+//! use embedded_graphics::mono_font::ascii::FONT_6X10;
+//! use embedded_graphics::pixelcolor::BinaryColor;
+//! use embedded_graphics::prelude::*;
+//! use keyarray::graphics::{draw_key_list, KeyListStyle};
+//! use keyarray::KeyArrayFixed;
+//!
+//! let mut display = MockDisplay::new();
+//! display.set_allow_overdraw(true);
+//! let style = KeyListStyle::new(&FONT_6X10, BinaryColor::On, BinaryColor::Off);
+//! let menu: KeyArrayFixed<&str, 4> =
+//!     KeyArrayFixed::new(&["Volume", "Brightness", "Sleep", "Reset"]);
+//! let shown = draw_key_list(&menu, &mut display, Point::zero(), 3, &style).unwrap();
+//! assert_eq!(shown, 0..3);
+//! ```
+//!
+//! Labels are formatted on the stack and cut after 64 bytes.
+
+use crate::borrowed::KeyArrayRef;
+use crate::cursor::{window_around, SelectionCursor};
+use crate::fixed::KeyArrayFixed;
+use crate::style::{ColorHint, KeyStyle};
+#[cfg(feature = "std")]
+use crate::KeyArray;
+use core::fmt::{self, Display, Write};
+use core::ops::Range;
+use embedded_graphics::mono_font::{MonoFont, MonoTextStyle};
+use embedded_graphics::pixelcolor::Rgb888;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{PrimitiveStyle, Rectangle};
+use embedded_graphics::text::{Baseline, DecorationColor, Text};
+
+/// Keys [`draw_key_row`] and [`draw_key_list`] can draw: a
+/// [`SelectionCursor`] with a [`KeyStyle`] for each key.
+pub trait StyledKeys: SelectionCursor {
+    /// The style of key `i`; the default style unless overridden.
+    fn key_style(&self, _i: usize) -> KeyStyle {
+        KeyStyle::default()
+    }
+}
+
+impl<K, const N: usize> StyledKeys for KeyArrayFixed<K, N> {}
+
+impl<K> StyledKeys for KeyArrayRef<'_, K> {}
+
+#[cfg(feature = "std")]
+impl<K> StyledKeys for KeyArray<K>
+where
+    K: Clone + PartialEq + core::fmt::Debug + Display,
+{
+    /// [`KeyArray::style`], with the `ui` feature.
+    #[cfg(feature = "ui")]
+    fn key_style(&self, i: usize) -> KeyStyle {
+        self.style(i)
+    }
+}
+
+// A key's label, formatted into a stack buffer so drawing needs no heap;
+// the text past `LABEL_BYTES` is dropped, at a char boundary.
+const LABEL_BYTES: usize = 64;
+
+struct Label {
+    buf: [u8; LABEL_BYTES],
+    len: usize,
+}
+
+impl Label {
+    fn of(key: &impl Display) -> Self {
+        let mut label = Label {
+            buf: [0; LABEL_BYTES],
+            len: 0,
+        };
+        // an error only means the label was cut
+        let _ = write!(label, "{}", key);
+        label
+    }
+
+    fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or_default()
+    }
+}
+
+impl Write for Label {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut end = s.len().min(LABEL_BYTES - self.len);
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.buf[self.len..self.len + end].copy_from_slice(&s.as_bytes()[..end]);
+        self.len += end;
+        if end < s.len() {
+            Err(fmt::Error)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Font and colors for [`draw_key_row`] and [`draw_key_list`].
+#[derive(Clone, Copy)]
+pub struct KeyListStyle<'a, C> {
+    pub font: &'a MonoFont<'a>,
+    pub text: C,
+    pub selected_text: C,
+    /// Fill behind the current key.
+    pub highlight: C,
+    /// Pixels between the text and the edge of the highlight.
+    pub padding: u32,
+    /// Pixels between items in a row.
+    pub spacing: u32,
+    /// The display color for a key's [`ColorHint`]; `None` keeps `text`, or
+    /// no background. The default maps nothing, for 1-bit displays.
+    pub palette: fn(ColorHint) -> Option<C>,
+}
+
+/// A palette for color displays: every hint in its
+/// [shared color](ColorHint::to_rgb).
+pub fn rgb_palette<C: From<Rgb888>>(hint: ColorHint) -> Option<C> {
+    let (r, g, b) = hint.to_rgb()?;
+    Some(Rgb888::new(r, g, b).into())
+}
+
+impl<'a, C: PixelColor> KeyListStyle<'a, C> {
+    /// Inverse video: `foreground` text, and `background` text on a
+    /// `foreground` bar for the current key.
+    pub fn new(font: &'a MonoFont<'a>, foreground: C, background: C) -> Self {
+        KeyListStyle {
+            font,
+            text: foreground,
+            selected_text: background,
+            highlight: foreground,
+            padding: 1,
+            spacing: font.character_size.width,
+            palette: |_| None,
+        }
+    }
+
+    /// Draw key styles with `palette`, such as [`rgb_palette`].
+    pub fn with_palette(mut self, palette: fn(ColorHint) -> Option<C>) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    /// Height of one list row, padding included.
+    pub fn row_height(&self) -> u32 {
+        self.font.character_size.height + 2 * self.padding
+    }
+
+    // pixel width of `label`, padding excluded
+    fn text_width(&self, label: &str) -> u32 {
+        let advance = self.font.character_size.width + self.font.character_spacing;
+        (label.chars().count() as u32 * advance).saturating_sub(self.font.character_spacing)
+    }
+
+    fn draw_item<D>(
+        &self,
+        target: &mut D,
+        label: &str,
+        at: Point,
+        box_width: Option<u32>,
+        current: bool,
+        style: KeyStyle,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = C>,
+    {
+        let fill = if current {
+            Some(self.highlight)
+        } else {
+            (self.palette)(style.background)
+        };
+        if let Some(fill) = fill {
+            let width = box_width.unwrap_or(self.text_width(label) + 2 * self.padding);
+            Rectangle::new(at, Size::new(width, self.row_height()))
+                .into_styled(PrimitiveStyle::with_fill(fill))
+                .draw(target)?;
+        }
+        let color = if current {
+            self.selected_text
+        } else {
+            (self.palette)(style.foreground).unwrap_or(self.text)
+        };
+        let mut text = MonoTextStyle::new(self.font, color);
+        if style.emphasis.underline {
+            text.underline_color = DecorationColor::TextColor;
+        }
+        let pad = self.padding as i32;
+        Text::with_baseline(label, at + Point::new(pad, pad), text, Baseline::Top).draw(target)?;
+        Ok(())
+    }
+}
+
+/// Draw the keys left to right from `top_left`. Keys past the right edge of
+/// the display are clipped.
+pub fn draw_key_row<C, D>(
+    keys: &C,
+    target: &mut D,
+    top_left: Point,
+    style: &KeyListStyle<'_, D::Color>,
+) -> Result<(), D::Error>
+where
+    C: StyledKeys + ?Sized,
+    C::Item: Display,
+    D: DrawTarget,
+{
+    let mut at = top_left;
+    for i in 0..keys.item_count() {
+        let Some(key) = keys.item(i) else { break };
+        let label = Label::of(key);
+        let label = label.as_str();
+        let current = i == keys.cursor();
+        style.draw_item(target, label, at, None, current, keys.key_style(i))?;
+        let width = style.text_width(label) + 2 * style.padding + style.spacing;
+        at.x += width as i32;
+    }
+    Ok(())
+}
+
+/// Draw up to `rows` keys top to bottom from `top_left`, scrolled so the
+/// current key is visible. The highlight bar runs to the display's right
+/// edge. Returns the indices that were drawn, e.g. for a scroll indicator.
+pub fn draw_key_list<C, D>(
+    keys: &C,
+    target: &mut D,
+    top_left: Point,
+    rows: usize,
+    style: &KeyListStyle<'_, D::Color>,
+) -> Result<Range<usize>, D::Error>
+where
+    C: StyledKeys + ?Sized,
+    C::Item: Display,
+    D: DrawTarget,
+{
+    let shown = window_around(keys.cursor(), keys.item_count(), rows);
+    let bar = target
+        .bounding_box()
+        .bottom_right()
+        .map(|corner| (corner.x - top_left.x + 1).max(0) as u32);
+    let mut at = top_left;
+    for i in shown.clone() {
+        let Some(key) = keys.item(i) else { break };
+        let label = Label::of(key);
+        let current = i == keys.cursor();
+        style.draw_item(target, label.as_str(), at, bar, current, keys.key_style(i))?;
+        at.y += style.row_height() as i32;
+    }
+    Ok(shown)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use embedded_graphics::mock_display::MockDisplay;
+    use embedded_graphics::mono_font::ascii::FONT_6X10;
+    use embedded_graphics::pixelcolor::BinaryColor;
+
+    fn display() -> MockDisplay<BinaryColor> {
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+        display
+    }
+
+    #[test]
+    fn list_scrolls_to_current() {
+        let style = KeyListStyle::new(&FONT_6X10, BinaryColor::On, BinaryColor::Off);
+        let ka: KeyArrayFixed<&str, 4> = KeyArrayFixed::new_with(&["A", "B", "C", "D"], 2);
+        let mut display = display();
+        let shown = draw_key_list(&ka, &mut display, Point::zero(), 2, &style).unwrap();
+        assert_eq!(shown, 1..3);
+        // the bar behind "C" is the second row and spans the display
+        let row = style.row_height() as i32;
+        assert_eq!(
+            display.get_pixel(Point::new(63, row)),
+            Some(BinaryColor::On)
+        );
+        assert_eq!(display.get_pixel(Point::new(63, 0)), None);
+    }
+
+    #[test]
+    fn row_highlights_only_current() {
+        let style = KeyListStyle::new(&FONT_6X10, BinaryColor::On, BinaryColor::Off);
+        let ka: KeyArrayFixed<&str, 2> = KeyArrayFixed::new_with(&["On", "Off"], 1);
+        let mut display = display();
+        draw_key_row(&ka, &mut display, Point::zero(), &style).unwrap();
+        // "On" is 12px + 2px padding + 6px gap, so "Off" starts at x = 20
+        assert_eq!(display.get_pixel(Point::new(0, 0)), None);
+        assert_eq!(display.get_pixel(Point::new(20, 0)), Some(BinaryColor::On));
+        assert_eq!(
+            display.get_pixel(Point::new(20 + 19, 0)),
+            Some(BinaryColor::On)
+        );
+        assert_eq!(display.get_pixel(Point::new(20 + 20, 0)), None);
+    }
+
+    #[cfg(feature = "ui")]
+    #[test]
+    fn key_styles_go_through_the_palette() {
+        let style = KeyListStyle::new(&FONT_6X10, BinaryColor::On, BinaryColor::Off)
+            .with_palette(|hint| (hint == ColorHint::Danger).then_some(BinaryColor::On));
+        let mut ka = KeyArray::new(["On", "Off"]);
+        ka.set_ui(1, crate::KeyUi::new().background(ColorHint::Danger));
+        let mut display = display();
+        draw_key_row(&ka, &mut display, Point::zero(), &style).unwrap();
+        // "Off" is not current, but its danger background is filled
+        assert_eq!(display.get_pixel(Point::new(20, 0)), Some(BinaryColor::On));
+
+        let rgb = KeyListStyle::new(&FONT_6X10, Rgb888::WHITE, Rgb888::BLACK)
+            .with_palette(rgb_palette);
+        assert_eq!((rgb.palette)(ColorHint::Danger), Some(Rgb888::new(205, 49, 49)));
+        assert_eq!((rgb.palette)(ColorHint::Default), None);
+    }
+
+    #[test]
+    fn long_labels_are_cut_at_a_char_boundary() {
+        let label = Label::of(&"é".repeat(40));
+        assert_eq!(label.as_str(), "é".repeat(32));
+        let fixed: KeyArrayFixed<&str, 2> = KeyArrayFixed::new_with(&["On", "Off"], 1);
+        let style = KeyListStyle::new(&FONT_6X10, BinaryColor::On, BinaryColor::Off);
+        let mut display = display();
+        draw_key_row(&fixed, &mut display, Point::zero(), &style).unwrap();
+        assert_eq!(display.get_pixel(Point::new(20, 0)), Some(BinaryColor::On));
+    }
+}
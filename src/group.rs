@@ -0,0 +1,151 @@
+//! Changing several KeyArrays together, all or none.
+//!
+//! A [`ChangeGroup`] collects one change per array and applies them in two
+//! phases: first every change is checked as by
+//! [`check_change`](KeyArray::check_change), then, only if all of them
+//! pass, every change is made. The group holds each array mutably until it
+//! is committed, so nothing can invalidate a checked change in between.
+//! Arrays of different key types may share a group.
+//!
+//! ```
+//! # #[cfg(feature = "disable")] {
+//! use keyarray::group::ChangeGroup;
+//! use keyarray::KeyArray;
+//!
+//! let mut input = KeyArray::new(["Mic", "Line"]);
+//! let mut rate = KeyArray::new([44_100, 48_000]);
+//! rate.disable(1);
+//!
+//! let mut group = ChangeGroup::new();
+//! group.add(&mut input, 1).add(&mut rate, 1);
+//! let err = group.commit().unwrap_err();
+//! assert_eq!(err.member, 1);
+//! assert_eq!(input.current(), &"Mic");
+//! # }
+//! ```
+
+use crate::error::KeyArrayError;
+use crate::KeyArray;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+// One array and the index it should move to.
+trait Member {
+    fn prepare(&self) -> Result<(), KeyArrayError>;
+    fn commit(&mut self);
+}
+
+struct Change<'a, K> {
+    keys: &'a mut KeyArray<K>,
+    target: usize,
+}
+
+impl<K> Member for Change<'_, K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    fn prepare(&self) -> Result<(), KeyArrayError> {
+        #[cfg(feature = "transitions")]
+        return self.keys.check_change(self.target);
+        #[cfg(not(feature = "transitions"))]
+        {
+            crate::check_index(self.target, self.keys.len())?;
+            self.keys.check_selectable(self.target)
+        }
+    }
+
+    fn commit(&mut self) {
+        // checked in `prepare`, and the array cannot have changed since
+        self.keys
+            .try_change(self.target)
+            .expect("prepared change cannot fail");
+    }
+}
+
+/// Changes to several arrays that are made together or not at all.
+#[derive(Default)]
+pub struct ChangeGroup<'a> {
+    members: Vec<Box<dyn Member + 'a>>,
+}
+
+/// Why a [`ChangeGroup`] was not committed. No array was changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GroupError {
+    /// Position of the refused change, in the order it was added.
+    pub member: usize,
+    pub error: KeyArrayError,
+}
+
+impl Display for GroupError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "change {} of the group: {}", self.member, self.error)
+    }
+}
+
+impl std::error::Error for GroupError {}
+
+impl<'a> ChangeGroup<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Move `keys` to key `i` when the group is committed.
+    pub fn add<K>(&mut self, keys: &'a mut KeyArray<K>, i: usize) -> &mut Self
+    where
+        K: Clone + PartialEq + Debug + Display + 'a,
+    {
+        self.members.push(Box::new(Change { keys, target: i }));
+        self
+    }
+
+    /// Number of changes in the group.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Phase one: check every change without making any.
+    pub fn prepare(&self) -> Result<(), GroupError> {
+        for (member, m) in self.members.iter().enumerate() {
+            m.prepare().map_err(|error| GroupError { member, error })?;
+        }
+        Ok(())
+    }
+
+    /// Check every change and, if all pass, make them in the order they
+    /// were added. Otherwise fail with the first refusal, changing nothing.
+    pub fn commit(mut self) -> Result<(), GroupError> {
+        self.prepare()?;
+        for m in &mut self.members {
+            m.commit();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "transitions"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn all_or_nothing() {
+        let mut a = KeyArray::new(["x", "y"]);
+        let mut b = KeyArray::new([1, 2, 3]);
+        b.allow_transition(0, 1);
+        {
+            let mut group = ChangeGroup::new();
+            group.add(&mut a, 1).add(&mut b, 2);
+            let denied = KeyArrayError::TransitionDenied { from: 0, to: 2 };
+            assert_eq!(group.prepare(), Err(GroupError { member: 1, error: denied }));
+        }
+        assert_eq!((a.current_index(), b.current_index()), (0, 0));
+
+        let mut group = ChangeGroup::new();
+        group.add(&mut a, 1).add(&mut b, 1);
+        assert_eq!(group.len(), 2);
+        group.commit().unwrap();
+        assert_eq!((a.current(), b.current()), (&"y", &2));
+    }
+}
@@ -0,0 +1,298 @@
+//! Selection history with back/forward navigation and undo/redo.
+//!
+//! Once [`enable_history`](KeyArray::enable_history) is called, every
+//! mutation is recorded in two bounded buffers:
+//!
+//! - the *trail* of keys that were current, walked with
+//!   [`back`](KeyArray::back) and [`forward`](KeyArray::forward) like a
+//!   browser's history. The trail follows key ids, so after inserts and
+//!   removals [`history`](KeyArray::history) still reports where each
+//!   visited key is now; visited keys that were removed drop out.
+//! - the *states* of the array (keys, their attached data, and the
+//!   selection), stepped through with [`undo`](KeyArray::undo) and
+//!   [`redo`](KeyArray::redo). Storage is copy-on-write, so a state costs a
+//!   couple of reference counts until the next edit.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//!
+//! let mut wizard = KeyArray::new(["Welcome", "License", "Install"]);
+//! wizard.enable_history(16);
+//! wizard.next();
+//! wizard.next();
+//! assert_eq!(wizard.history(), [0, 1, 2]);
+//! assert!(wizard.back());
+//! assert_eq!(wizard.current(), &"License");
+//!
+//! wizard.insert(0, "Language");
+//! assert_eq!(wizard.history(), [1, 2, 3]);
+//! assert!(wizard.undo());
+//! assert_eq!(wizard.keys(), ["Welcome", "License", "Install"]);
+//! assert!(wizard.redo());
+//! assert_eq!(wizard.keys()[0], "Language");
+//! ```
+
+use crate::id::KeyId;
+use crate::memory::{vec_bytes, Sizer};
+use crate::{KeyArray, Slot};
+use std::collections::HashMap;
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+#[derive(Clone)]
+struct State<K> {
+    keys: Arc<Vec<K>>,
+    slots: Arc<Vec<Slot>>,
+    idx: usize,
+}
+
+#[derive(Clone)]
+pub(crate) struct History<K> {
+    capacity: usize,
+    // keys that were current, oldest first, and where they are now
+    trail: Vec<KeyId>,
+    indices: Vec<usize>,
+    // entry of `trail` that is current
+    at: usize,
+    states: Vec<State<K>>,
+    // entry of `states` the array is in
+    state: usize,
+}
+
+impl<K> History<K> {
+    pub(crate) fn heap_bytes(&self, sizer: &mut Sizer<K>) -> usize {
+        let states = self.states.iter().map(|s| sizer.keys(&s.keys) + sizer.slots(&s.slots));
+        let states = states.sum::<usize>() + vec_bytes(&self.states);
+        vec_bytes(&self.trail) + vec_bytes(&self.indices) + states
+    }
+
+    // Drop the oldest visits beyond capacity, shifting the cursor with them.
+    fn trim_trail(&mut self) {
+        let excess = self.trail.len().saturating_sub(self.capacity);
+        self.trail.drain(..excess);
+        self.at = self.at.saturating_sub(excess);
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Start recording up to `capacity` visited keys and as many undo
+    /// states, dropping the oldest beyond that. Replaces any earlier
+    /// history. Panics if `capacity` is zero.
+    pub fn enable_history(&mut self, capacity: usize) {
+        assert!(capacity > 0, "KeyArray::enable_history: capacity must be positive");
+        let current = self.slots.get(self.idx).map(|s| s.id);
+        self.history = Some(Box::new(History {
+            capacity,
+            trail: current.into_iter().collect(),
+            indices: current.map(|_| self.idx).into_iter().collect(),
+            at: 0,
+            states: vec![self.state()],
+            state: 0,
+        }));
+    }
+
+    /// Stop recording and forget the history.
+    pub fn disable_history(&mut self) {
+        self.history = None;
+    }
+
+    /// Current indices of the visited keys, oldest first; empty if history
+    /// is off. The entry at [`history_position`](Self::history_position) is
+    /// the current key.
+    pub fn history(&self) -> &[usize] {
+        match &self.history {
+            Some(h) => &h.indices,
+            None => &[],
+        }
+    }
+
+    /// Position of the current key in [`history`](Self::history).
+    pub fn history_position(&self) -> Option<usize> {
+        self.history.as_ref().filter(|h| !h.trail.is_empty()).map(|h| h.at)
+    }
+
+    /// Go back to the previously visited key, passing over keys that can
+    /// no longer be selected. Returns false, changing nothing, if there is
+    /// none or history is off.
+    pub fn back(&mut self) -> bool {
+        self.walk_trail(-1)
+    }
+
+    /// Return to the key [`back`](Self::back) left. Returns false if there
+    /// is none or history is off.
+    pub fn forward(&mut self) -> bool {
+        self.walk_trail(1)
+    }
+
+    /// Restore the keys, their data and the selection as they were before
+    /// the last recorded mutation. Returns false if there is nothing to undo
+    /// or history is off.
+    pub fn undo(&mut self) -> bool {
+        self.step_state(-1)
+    }
+
+    /// Reapply the last undone mutation. Returns false if there is nothing
+    /// to redo or history is off.
+    pub fn redo(&mut self) -> bool {
+        self.step_state(1)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.as_ref().is_some_and(|h| h.state > 0)
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history
+            .as_ref()
+            .is_some_and(|h| h.state + 1 < h.states.len())
+    }
+
+    // Called from `touch`: store the new state and follow the selection.
+    pub(crate) fn record_history(&mut self) {
+        let state = self.state();
+        let Some(h) = self.history.as_deref_mut() else {
+            return;
+        };
+        let last = &h.states[h.state];
+        let same = Arc::ptr_eq(&last.keys, &state.keys)
+            && Arc::ptr_eq(&last.slots, &state.slots)
+            && last.idx == state.idx;
+        if !same {
+            h.states.truncate(h.state + 1);
+            h.states.push(state);
+            if h.states.len() > h.capacity {
+                h.states.remove(0);
+            }
+            h.state = h.states.len() - 1;
+        }
+        self.sync_trail();
+    }
+
+    fn state(&self) -> State<K> {
+        State {
+            keys: Arc::clone(&self.keys),
+            slots: Arc::clone(&self.slots),
+            idx: self.idx,
+        }
+    }
+
+    // Append the current key to the trail if it moved, then drop visited
+    // keys that no longer exist and refresh the indices of the rest.
+    fn sync_trail(&mut self) {
+        let positions: HashMap<KeyId, usize> =
+            self.slots.iter().enumerate().map(|(i, s)| (s.id, i)).collect();
+        let current = self.slots.get(self.idx).map(|s| s.id);
+        let Some(h) = self.history.as_deref_mut() else {
+            return;
+        };
+        if let Some(id) = current {
+            if h.trail.get(h.at) != Some(&id) {
+                h.trail.truncate(h.at + 1);
+                h.trail.push(id);
+                h.at = h.trail.len() - 1;
+                h.trim_trail();
+            }
+        }
+        let removed_before = h.trail[..h.at.min(h.trail.len())]
+            .iter()
+            .filter(|id| !positions.contains_key(id))
+            .count();
+        h.trail.retain(|id| positions.contains_key(id));
+        h.at = h.at.saturating_sub(removed_before).min(h.trail.len().saturating_sub(1));
+        h.indices = h.trail.iter().map(|id| positions[id]).collect();
+    }
+
+    fn walk_trail(&mut self, by: isize) -> bool {
+        let Some(h) = self.history.as_deref() else {
+            return false;
+        };
+        // visited keys that can no longer be selected are passed over
+        let mut at = h.at;
+        let i = loop {
+            let Some(next) = at.checked_add_signed(by).filter(|&at| at < h.trail.len()) else {
+                return false;
+            };
+            at = next;
+            if self.check_selectable(h.indices[at]).is_ok() {
+                break h.indices[at];
+            }
+        };
+        // move the cursor first, so the change below is not recorded as a
+        // new visit
+        if let Some(h) = self.history.as_deref_mut() {
+            h.at = at;
+        }
+        self.try_change(i).is_ok()
+    }
+
+    fn step_state(&mut self, by: isize) -> bool {
+        let Some(mut h) = self.history.take() else {
+            return false;
+        };
+        let Some(to) = h.state.checked_add_signed(by).filter(|&s| s < h.states.len()) else {
+            self.history = Some(h);
+            return false;
+        };
+        h.state = to;
+        let state = h.states[to].clone();
+        self.keys = state.keys;
+        self.slots = state.slots;
+        self.idx = state.idx;
+        // touch without recording, then only follow the selection
+        self.touch();
+        self.history = Some(h);
+        self.sync_trail();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_covers_edits_and_redo_is_cut_by_new_ones() {
+        let mut ka = KeyArray::new(["A", "B"]);
+        assert!(!ka.undo());
+        ka.enable_history(3);
+        ka.push("C");
+        ka.change(2);
+        ka.remove(0);
+        assert_eq!(ka.keys(), ["B", "C"]);
+        assert!(ka.undo() && ka.undo());
+        assert_eq!((ka.keys(), ka.current()), (&["A", "B", "C"][..], &"A"));
+        // only three states are kept
+        assert!(!ka.undo());
+        assert!(ka.redo() && ka.can_redo());
+        ka.change(1);
+        assert!(!ka.can_redo());
+        assert_eq!(ka.current(), &"B");
+    }
+
+    #[test]
+    fn trail_follows_ids() {
+        let mut ka = KeyArray::new(["A", "B", "C", "D"]);
+        ka.enable_history(8);
+        for i in [1, 2, 3] {
+            ka.change(i);
+        }
+        assert!(ka.back() && ka.back());
+        assert_eq!((ka.current(), ka.history_position()), (&"B", Some(1)));
+        ka.insert(0, "Z");
+        assert_eq!(ka.history(), [1, 2, 3, 4]);
+        assert!(ka.forward());
+        assert_eq!(ka.current(), &"C");
+
+        // visiting a new key drops the forward entries
+        ka.back();
+        ka.change(4);
+        assert_eq!(ka.history(), [1, 2, 4]);
+        assert!(!ka.forward());
+        // removed keys drop out
+        ka.remove(1);
+        assert_eq!((ka.history(), ka.history_position()), (&[1, 3][..], Some(1)));
+    }
+}
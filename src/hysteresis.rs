@@ -0,0 +1,141 @@
+//! Anti-flapping for selections driven by a noisy signal.
+//!
+//! When a sensor picks the key (temperature to fan speed, signal strength
+//! to network), readings near a boundary make the selection oscillate. A
+//! [`Hysteresis`] only lets a proposed key through once it has been
+//! proposed on enough consecutive calls and for long enough; a proposal of
+//! any other key, including the current one, starts over.
+//!
+//! ```
+//! use keyarray::hysteresis::Hysteresis;
+//! use keyarray::KeyArray;
+//! use std::time::{Duration, Instant};
+//!
+//! let mut fan = KeyArray::new(["Low", "High"]);
+//! let mut settle = Hysteresis::calls(3);
+//! let now = Instant::now();
+//! for reading in [1, 0, 1, 1] {
+//!     settle.propose(&mut fan, reading, now).unwrap();
+//! }
+//! assert_eq!(fan.current(), &"Low");
+//! let ev = settle.propose(&mut fan, 1, now).unwrap().unwrap();
+//! assert_eq!(ev.key, "High");
+//! ```
+
+use crate::error::KeyArrayError;
+use crate::event::ChangeEvent;
+use crate::id::KeyId;
+use crate::{check_index, KeyArray};
+use std::fmt::{Debug, Display};
+use std::time::{Duration, Instant};
+
+/// Reason attached to the change event when a proposal is let through.
+pub const REASON: &str = "settled";
+
+/// How long a proposed key must persist before it becomes current.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hysteresis {
+    calls: u32,
+    hold: Duration,
+    // the key being proposed, how often in a row, and since when
+    candidate: Option<(KeyId, u32, Instant)>,
+}
+
+impl Hysteresis {
+    /// Let a key through once it was proposed on `calls` consecutive
+    /// calls, spanning at least `hold` from the first to the last.
+    pub fn new(calls: u32, hold: Duration) -> Self {
+        Hysteresis {
+            calls: calls.max(1),
+            hold,
+            candidate: None,
+        }
+    }
+
+    /// Let a key through on its `n`th consecutive proposal.
+    pub fn calls(n: u32) -> Self {
+        Self::new(n, Duration::ZERO)
+    }
+
+    /// Let a key through once it has been proposed throughout `hold`.
+    pub fn duration(hold: Duration) -> Self {
+        Self::new(1, hold)
+    }
+
+    /// Propose key `i` of `keys` at `now`. Returns the change event if the
+    /// proposal has persisted long enough and was applied, `None` while it
+    /// is still pending or `i` is already current. Fails, forgetting the
+    /// pending proposal, if `i` cannot be selected.
+    pub fn propose<K>(
+        &mut self,
+        keys: &mut KeyArray<K>,
+        i: usize,
+        now: Instant,
+    ) -> Result<Option<ChangeEvent<K>>, KeyArrayError>
+    where
+        K: Clone + PartialEq + Debug + Display,
+    {
+        let checked = check_index(i, keys.len()).and_then(|()| keys.check_selectable(i));
+        if let Err(e) = checked {
+            self.candidate = None;
+            return Err(e);
+        }
+        if i == keys.current_index() {
+            self.candidate = None;
+            return Ok(None);
+        }
+        let id = keys.id_at(i).expect("index was checked");
+        let (count, since) = match self.candidate {
+            Some((pending, count, since)) if pending == id => (count.saturating_add(1), since),
+            _ => (1, now),
+        };
+        if count < self.calls || now.saturating_duration_since(since) < self.hold {
+            self.candidate = Some((id, count, since));
+            return Ok(None);
+        }
+        self.candidate = None;
+        let previous = keys.current_index();
+        keys.try_change(i)?;
+        Ok(Some(ChangeEvent {
+            previous,
+            current: keys.current_index(),
+            key: keys.current().clone(),
+            reason: Some(REASON.into()),
+        }))
+    }
+
+    /// The key currently being proposed, if any.
+    pub fn pending(&self) -> Option<KeyId> {
+        self.candidate.map(|(id, _, _)| id)
+    }
+
+    /// Forget the pending proposal.
+    pub fn reset(&mut self) {
+        self.candidate = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn needs_both_calls_and_time() {
+        let t0 = Instant::now();
+        let secs = Duration::from_secs;
+        let mut ka = KeyArray::new(["a", "b", "c"]);
+        let mut h = Hysteresis::new(2, secs(5));
+        assert_eq!(h.propose(&mut ka, 1, t0), Ok(None));
+        assert_eq!(h.pending(), ka.id_at(1));
+        assert_eq!(h.propose(&mut ka, 1, t0 + secs(4)), Ok(None));
+        let ev = h.propose(&mut ka, 1, t0 + secs(5)).unwrap().unwrap();
+        assert_eq!((ev.current, ev.reason.unwrap().as_str()), (1, REASON));
+
+        // back to the current key in between starts over
+        h.propose(&mut ka, 2, t0).unwrap();
+        h.propose(&mut ka, 1, t0).unwrap();
+        h.propose(&mut ka, 2, t0 + secs(9)).unwrap();
+        assert_eq!(ka.current(), &"b");
+        assert!(h.propose(&mut ka, 7, t0).is_err() && h.pending().is_none());
+    }
+}
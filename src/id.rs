@@ -0,0 +1,100 @@
+//! Stable key identities.
+//!
+//! Every key gets a [`KeyId`] when it enters the array. The id follows the
+//! key through inserts, removals and sorts and is never reused, so it stays a
+//! valid handle where an index would silently point at a different key.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//!
+//! let mut ka = KeyArray::new(["On", "Off", "Auto"]);
+//! let auto = ka.id_at(2).unwrap();
+//! ka.remove(0);
+//! ka.insert(0, "Eco");
+//! ka.select_id(auto);
+//! assert_eq!(ka.current(), &"Auto");
+//!
+//! // mutations that add keys hand back the new id
+//! let turbo = ka.push("Turbo");
+//! assert_eq!(ka.remove_id(turbo), Some("Turbo"));
+//! ```
+
+use crate::KeyArray;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+/// Opaque handle to one key of one KeyArray (and its clones).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyId(pub(crate) u64);
+
+impl Display for KeyId {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(f, "#{}", self.0)
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Id of the key at index `i`.
+    pub fn id_at(&self, i: usize) -> Option<KeyId> {
+        self.slots.get(i).map(|s| s.id)
+    }
+
+    /// Id of the current key.
+    pub fn current_id(&self) -> KeyId {
+        self.slots[self.idx].id
+    }
+
+    /// Current index of the key with `id`, or `None` if it was removed.
+    pub fn index_of_id(&self, id: KeyId) -> Option<usize> {
+        self.slots.iter().position(|s| s.id == id)
+    }
+
+    /// The key with `id`, or `None` if it was removed.
+    pub fn key_by_id(&self, id: KeyId) -> Option<&K> {
+        self.index_of_id(id).map(|i| &self.keys[i])
+    }
+
+    /// Remove and return the key with `id`, or `None` if it was already
+    /// removed. The selection is adjusted exactly as by [`remove`](Self::remove).
+    pub fn remove_id(&mut self, id: KeyId) -> Option<K> {
+        let i = self.index_of_id(id)?;
+        Some(self.remove(i))
+    }
+
+    /// Make the key with `id` current and return the index now current,
+    /// which is its replacement's if it is deprecated under strict mode.
+    /// Leaves the selection untouched and returns `None` if it was removed
+    /// or cannot be selected (disabled or locked).
+    pub fn select_id(&mut self, id: KeyId) -> Option<usize> {
+        let i = self.index_of_id(id)?;
+        self.try_change(i).ok()?;
+        Some(self.current_index())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_survive_edits_and_are_not_reused() {
+        let mut ka = KeyArray::new(["A", "B"]);
+        let a = ka.id_at(0).unwrap();
+        let b = ka.id_at(1).unwrap();
+        assert_eq!(ka.remove_id(b), Some("B"));
+        assert_eq!(ka.remove_id(b), None);
+        let c = ka.push("C");
+        assert_eq!(ka.id_at(1), Some(c));
+        assert_ne!(c, b);
+        assert_eq!(ka.select_id(b), None);
+        ka.sort_by(|x, y| y.cmp(x));
+        assert_eq!(ka.index_of_id(a), Some(1));
+        assert_eq!(ka.key_by_id(a), Some(&"A"));
+        ka.select_id(a);
+        assert_eq!(ka.current_id(), a);
+    }
+}
@@ -0,0 +1,188 @@
+//! In-place editing of key values.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//!
+//! let mut ka = KeyArray::new(["Wifi", "Ethernet"]);
+//! let ev = ka.rename(0, "Wi-Fi").unwrap();
+//! assert_eq!((ev.old, ev.new), ("Wifi", "Wi-Fi"));
+//! assert!(ka.rename(1, "Wi-Fi").is_err());
+//! ```
+
+use crate::error::KeyArrayError;
+use crate::event::Renamed;
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+/// Mutable access to the keys of a [`KeyArray`], returned by
+/// [`KeyArray::keys_mut`]. When the guard is dropped the edit is checked:
+/// if it made a key equal to another one, or gave a key a value another
+/// one had before (as swapping, sorting or reversing would), the whole
+/// edit is undone;
+/// otherwise the array's derived state is brought back in line with the
+/// edited keys. [`finish`](Self::finish) does the same and reports which.
+pub struct KeysMut<'a, K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    array: &'a mut KeyArray<K>,
+    // the keys before the edit, put back if it is rejected; `None` once
+    // settled
+    before: Option<Arc<Vec<K>>>,
+}
+
+impl<K> KeysMut<'_, K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// End the edit, as dropping the guard does. Fails with
+    /// [`KeyArrayError::DuplicateKey`], pointing at the key an edited one
+    /// now equals or the index its value was at before, if the edit was
+    /// undone.
+    pub fn finish(mut self) -> Result<(), KeyArrayError> {
+        self.settle()
+    }
+
+    fn settle(&mut self) -> Result<(), KeyArrayError> {
+        let Some(before) = self.before.take() else {
+            return Ok(());
+        };
+        let keys = &self.array.keys;
+        if Arc::ptr_eq(&before, keys) {
+            return Ok(());
+        }
+        // keys that were equal to others before the edit stay allowed; an
+        // edited key may not take a value another slot held before either,
+        // as that would move a key away from its id and attached data
+        let edited = || (0..keys.len()).filter(|&i| keys[i] != before[i]);
+        let duplicate = edited()
+            .find_map(|i| (0..keys.len()).find(|&j| j != i && keys[j] == keys[i]))
+            .or_else(|| {
+                edited().find_map(|i| (0..before.len()).find(|&j| j != i && before[j] == keys[i]))
+            });
+        if let Some(index) = duplicate {
+            self.array.keys = before;
+            return Err(KeyArrayError::DuplicateKey { index });
+        }
+        self.array.keys_edited();
+        Ok(())
+    }
+}
+
+impl<K> Deref for KeysMut<'_, K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    type Target = [K];
+
+    fn deref(&self) -> &[K] {
+        &self.array.keys
+    }
+}
+
+impl<K> DerefMut for KeysMut<'_, K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    fn deref_mut(&mut self) -> &mut [K] {
+        Arc::make_mut(&mut self.array.keys).as_mut_slice()
+    }
+}
+
+impl<K> Drop for KeysMut<'_, K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    fn drop(&mut self) {
+        let _ = self.settle();
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Edit key values in place. The number of keys cannot change through
+    /// the guard, and an edit that reorders them is undone, so the current
+    /// index and per-key data stay put. The first write copies the key
+    /// list, so a rejected edit can be undone; see [`KeysMut`].
+    pub fn keys_mut(&mut self) -> KeysMut<'_, K> {
+        KeysMut {
+            before: Some(Arc::clone(&self.keys)),
+            array: self,
+        }
+    }
+
+    /// Give key `i` a new value, keeping its id, attached data, bookmarks
+    /// and selection. Fails without changing anything if `i` is out of
+    /// bounds or another key already equals `new_key`.
+    pub fn rename(&mut self, i: usize, new_key: K) -> Result<Renamed<K>, KeyArrayError> {
+        let len = self.keys.len();
+        if i >= len {
+            return Err(KeyArrayError::IndexOutOfBounds { index: i, len });
+        }
+        if let Some(index) = (0..len).find(|&j| j != i && self.keys[j] == new_key) {
+            return Err(KeyArrayError::DuplicateKey { index });
+        }
+        let old = std::mem::replace(&mut Arc::make_mut(&mut self.keys)[i], new_key.clone());
+        self.keys_edited();
+        Ok(Renamed {
+            index: i,
+            id: self.slots[i].id,
+            old,
+            new: new_key,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_labels() {
+        let mut ka = KeyArray::new_with(vec!["on".to_string(), "off".to_string()], 1);
+        for k in ka.keys_mut().iter_mut() {
+            *k = k.to_uppercase();
+        }
+        assert_eq!(ka.keys(), &["ON", "OFF"]);
+        assert_eq!(ka.current(), "OFF");
+    }
+
+    #[test]
+    fn duplicating_edit_is_undone() {
+        let mut ka = KeyArray::new(["a", "b", "c"]);
+        let generation = ka.generation();
+        let mut keys = ka.keys_mut();
+        keys[0] = "z";
+        keys[2] = "b";
+        assert_eq!(keys.finish(), Err(KeyArrayError::DuplicateKey { index: 1 }));
+        assert_eq!((ka.keys(), ka.generation()), (&["a", "b", "c"][..], generation));
+
+        ka.keys_mut()[0] = "c";
+        assert_eq!(ka.keys(), &["a", "b", "c"]);
+        let mut keys = ka.keys_mut();
+        keys.swap(0, 2);
+        assert_eq!(keys.finish(), Err(KeyArrayError::DuplicateKey { index: 2 }));
+        ka.keys_mut().reverse();
+        assert_eq!((ka.keys(), ka.generation()), (&["a", "b", "c"][..], generation));
+    }
+
+    #[test]
+    fn rename_keeps_identity() {
+        let mut ka = KeyArray::new_with(["a", "b", "c"], 1);
+        let id = ka.current_id();
+        ka.bookmark("mark");
+        let ev = ka.rename(1, "B").unwrap();
+        assert_eq!((ev.index, ev.id, ev.old, ev.new), (1, id, "b", "B"));
+        assert_eq!((ka.current(), ka.bookmark_index("mark")), (&"B", Some(1)));
+        assert_eq!(
+            ka.rename(0, "c"),
+            Err(KeyArrayError::DuplicateKey { index: 2 })
+        );
+        assert!(ka.rename(0, "a").is_ok());
+        assert!(ka.rename(3, "d").is_err());
+    }
+}
@@ -0,0 +1,159 @@
+//! Defaults with user overrides layered on top.
+//!
+//! A [`Layered`] keeps the application's defaults and the user's changes
+//! as two KeyArrays and resolves them on demand: the user's keys come first,
+//! in the user's order and with the user's selection, followed by any
+//! default keys the user layer does not have. Each layer is stored (and,
+//! with the `serde` feature, serialized) on its own, so new defaults shipped
+//! in an update still show up for users with saved overrides.
+//!
+//! ```
+//! use keyarray::layers::Layered;
+//! use keyarray::KeyArray;
+//!
+//! let mut theme = Layered::new(KeyArray::new(["Light", "Dark"]));
+//! theme.change(1);
+//! theme.user_mut().unwrap().push("Solarized");
+//!
+//! // a later release adds a default
+//! theme.defaults_mut().push("High contrast");
+//! let effective = theme.resolve();
+//! assert_eq!(effective.keys(), ["Light", "Dark", "Solarized", "High contrast"]);
+//! assert_eq!(theme.current(), &"Dark");
+//!
+//! theme.reset_user();
+//! assert_eq!(theme.current(), &"Light");
+//! ```
+
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+
+/// A defaults layer and an optional user layer; see the
+/// [module docs](self).
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "K: Clone + PartialEq + Debug + Display + serde::Serialize",
+        deserialize = "K: Clone + PartialEq + Debug + Display + serde::Deserialize<'de>"
+    ))
+)]
+pub struct Layered<K> {
+    defaults: KeyArray<K>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    user: Option<KeyArray<K>>,
+}
+
+impl<K> Layered<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Defaults only; the user layer is created by the first change.
+    pub fn new(defaults: KeyArray<K>) -> Self {
+        Layered {
+            defaults,
+            user: None,
+        }
+    }
+
+    pub fn with_user(defaults: KeyArray<K>, user: KeyArray<K>) -> Self {
+        Layered {
+            defaults,
+            user: Some(user),
+        }
+    }
+
+    pub fn defaults(&self) -> &KeyArray<K> {
+        &self.defaults
+    }
+
+    pub fn defaults_mut(&mut self) -> &mut KeyArray<K> {
+        &mut self.defaults
+    }
+
+    pub fn user(&self) -> Option<&KeyArray<K>> {
+        self.user.as_ref()
+    }
+
+    /// The user layer, created as a copy of the effective view if there is
+    /// none yet. `None` only if the defaults are empty too.
+    pub fn user_mut(&mut self) -> Option<&mut KeyArray<K>> {
+        if self.user.is_none() && !self.defaults.is_empty() {
+            self.user = Some(self.resolve());
+        }
+        self.user.as_mut()
+    }
+
+    /// Drop the user layer and return it, going back to the defaults.
+    pub fn reset_user(&mut self) -> Option<KeyArray<K>> {
+        self.user.take()
+    }
+
+    /// The effective KeyArray: the user's keys and selection, then the
+    /// default keys missing from the user layer. Ids and attached data are
+    /// those of the layer each key comes from.
+    pub fn resolve(&self) -> KeyArray<K> {
+        let Some(user) = self.user.as_ref().filter(|u| !u.is_empty()) else {
+            return self.defaults.clone();
+        };
+        let mut effective = user.clone();
+        for key in self.missing_defaults() {
+            effective.push(key.clone());
+        }
+        effective
+    }
+
+    /// The effective current key. Panics if both layers are empty.
+    pub fn current(&self) -> &K {
+        match &self.user {
+            Some(user) if !user.is_empty() => user.current(),
+            _ => self.defaults.current(),
+        }
+    }
+
+    /// Select the key at index `i` of the [`resolve`](Self::resolve)d view
+    /// and record the choice in the user layer. Panics if `i` is out of
+    /// bounds, or as [`KeyArray::change`] does on the user layer.
+    pub fn change(&mut self, i: usize) {
+        let len = self.resolve().len();
+        assert!(i < len, "Layered::change: index {} out of bounds", i);
+        let missing: Vec<K> = self.missing_defaults().cloned().collect();
+        let user = self.user_mut().expect("checked non-empty above");
+        if i >= user.len() {
+            // take the defaults below the user's keys over, keeping the order
+            for key in missing {
+                user.push(key);
+            }
+        }
+        user.change(i);
+    }
+
+    fn missing_defaults(&self) -> impl Iterator<Item = &K> + '_ {
+        let user = self.user.as_ref().map_or(&[][..], |u| u.keys());
+        self.defaults.keys().iter().filter(move |k| !user.contains(k))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_order_wins_and_defaults_fill_in() {
+        let mut layers = Layered::with_user(
+            KeyArray::new(["a", "b", "c"]),
+            KeyArray::new_with(["c", "x"], 1),
+        );
+        assert_eq!(layers.resolve().keys(), ["c", "x", "a", "b"]);
+        assert_eq!(layers.current(), &"x");
+
+        layers.change(3);
+        assert_eq!(layers.user().unwrap().keys(), ["c", "x", "a", "b"]);
+        assert_eq!((layers.current(), layers.defaults().current()), (&"b", &"a"));
+
+        layers.user_mut().unwrap().remove(0);
+        layers.defaults_mut().push("d");
+        assert_eq!(layers.resolve().keys(), ["x", "a", "b", "c", "d"]);
+    }
+}
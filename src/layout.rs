@@ -0,0 +1,234 @@
+//! Keys wrapped across several rows, as in a toolbar or an icon grid.
+//!
+//! A [`GridLayout`] places `len` items on a grid, either filling each row
+//! before the next ([`row_major`](GridLayout::row_major), with a wrap
+//! width) or each column before the next
+//! ([`column_major`](GridLayout::column_major), with a row count). It only
+//! does the arithmetic: where each index goes and which index an arrow key
+//! leads to, so the same mapping serves any grid widget. A KeyArray can
+//! follow it directly with [`move_in`](KeyArray::move_in).
+//!
+//! ```
+//! use keyarray::layout::{Direction, GridLayout};
+//! use keyarray::KeyArray;
+//!
+//! // A D G
+//! // B E
+//! // C F
+//! let mut tools = KeyArray::new(["A", "B", "C", "D", "E", "F", "G"]);
+//! let grid = GridLayout::column_major(tools.len(), 3);
+//! assert_eq!((grid.rows(), grid.columns()), (3, 3));
+//! assert_eq!(grid.position(4), Some((1, 1)));
+//!
+//! tools.move_in(&grid, Direction::Right);
+//! assert_eq!(tools.current(), &"D");
+//! tools.move_in(&grid, Direction::Down);
+//! tools.move_in(&grid, Direction::Right);
+//! // no key right of E, so the last key of the next column
+//! assert_eq!(tools.current(), &"G");
+//! ```
+
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+
+/// Which way items fill the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Order {
+    /// Left to right, wrapping to the next row.
+    RowMajor,
+    /// Top to bottom, wrapping to the next column.
+    ColumnMajor,
+}
+
+/// A move on the grid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Placement of `len` items on a grid; see the [module docs](self).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GridLayout {
+    len: usize,
+    // items per row (row-major) or per column (column-major)
+    wrap: usize,
+    order: Order,
+}
+
+impl GridLayout {
+    /// Rows of `columns` items each, the last possibly shorter. Panics if
+    /// `columns` is zero.
+    pub fn row_major(len: usize, columns: usize) -> Self {
+        assert!(columns > 0, "GridLayout::row_major: columns must be positive");
+        GridLayout {
+            len,
+            wrap: columns,
+            order: Order::RowMajor,
+        }
+    }
+
+    /// Columns of `rows` items each, the last possibly shorter. Panics if
+    /// `rows` is zero.
+    pub fn column_major(len: usize, rows: usize) -> Self {
+        assert!(rows > 0, "GridLayout::column_major: rows must be positive");
+        GridLayout {
+            len,
+            wrap: rows,
+            order: Order::ColumnMajor,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn order(&self) -> Order {
+        self.order
+    }
+
+    // lines along the wrap direction: rows (row-major) or columns
+    fn lines(&self) -> usize {
+        self.len.div_ceil(self.wrap)
+    }
+
+    pub fn rows(&self) -> usize {
+        match self.order {
+            Order::RowMajor => self.lines(),
+            Order::ColumnMajor => self.wrap.min(self.len),
+        }
+    }
+
+    pub fn columns(&self) -> usize {
+        match self.order {
+            Order::RowMajor => self.wrap.min(self.len),
+            Order::ColumnMajor => self.lines(),
+        }
+    }
+
+    /// `(row, column)` of item `i`, or `None` if out of bounds.
+    pub fn position(&self, i: usize) -> Option<(usize, usize)> {
+        if i >= self.len {
+            return None;
+        }
+        let (line, offset) = (i / self.wrap, i % self.wrap);
+        Some(match self.order {
+            Order::RowMajor => (line, offset),
+            Order::ColumnMajor => (offset, line),
+        })
+    }
+
+    /// The item at `row` and `column`, or `None` if the cell is empty.
+    pub fn index_at(&self, row: usize, column: usize) -> Option<usize> {
+        let (line, offset) = match self.order {
+            Order::RowMajor => (row, column),
+            Order::ColumnMajor => (column, row),
+        };
+        if offset >= self.wrap {
+            return None;
+        }
+        Some(line * self.wrap + offset).filter(|&i| i < self.len)
+    }
+
+    /// The item one step from item `i` in `direction`. Moving into an
+    /// empty cell of the shorter last row or column lands on its last
+    /// item instead. `None` at the edge of the grid or if `i` is out of
+    /// bounds.
+    pub fn step(&self, i: usize, direction: Direction) -> Option<usize> {
+        let (row, column) = self.position(i)?;
+        let (row, column) = match direction {
+            Direction::Up => (row.checked_sub(1)?, column),
+            Direction::Down => (row + 1, column),
+            Direction::Left => (row, column.checked_sub(1)?),
+            Direction::Right => (row, column + 1),
+        };
+        if row >= self.rows() || column >= self.columns() {
+            return None;
+        }
+        // only a move across lines can reach past the short last line
+        let across = matches!(
+            (self.order, direction),
+            (Order::RowMajor, Direction::Up | Direction::Down)
+                | (Order::ColumnMajor, Direction::Left | Direction::Right)
+        );
+        self.index_at(row, column).or_else(|| across.then(|| self.len - 1))
+    }
+
+    /// The item below `i`; see [`step`](Self::step).
+    pub fn next_row(&self, i: usize) -> Option<usize> {
+        self.step(i, Direction::Down)
+    }
+
+    /// The item above `i`; see [`step`](Self::step).
+    pub fn prev_row(&self, i: usize) -> Option<usize> {
+        self.step(i, Direction::Up)
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Move the selection one step in `direction` on `layout`, passing over
+    /// hidden and disabled keys. Returns whether it moved; it stays put at
+    /// the edge of the grid. Panics if `layout` does not have one item per
+    /// key.
+    pub fn move_in(&mut self, layout: &GridLayout, direction: Direction) -> bool {
+        assert_eq!(
+            layout.len(),
+            self.len(),
+            "KeyArray::move_in: layout is for {} keys, not {}",
+            layout.len(),
+            self.len()
+        );
+        let mut at = self.idx;
+        while let Some(next) = layout.step(at, direction) {
+            if next == at {
+                break;
+            }
+            if self.selectable(next) {
+                self.change(next);
+                return true;
+            }
+            at = next;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn row_major_wraps_and_clamps() {
+        // 0 1 2
+        // 3 4
+        let grid = GridLayout::row_major(5, 3);
+        assert_eq!((grid.rows(), grid.columns()), (2, 3));
+        assert_eq!(grid.index_at(1, 2), None);
+        assert_eq!(grid.next_row(2), Some(4));
+        assert_eq!(grid.prev_row(4), Some(1));
+        assert_eq!(grid.step(4, Direction::Right), None);
+        assert_eq!(grid.prev_row(0), None);
+        assert_eq!(GridLayout::row_major(2, 4).columns(), 2);
+
+        #[cfg(feature = "disable")]
+        {
+            let mut ka = KeyArray::new(["0", "1", "2", "3", "4", "5"]);
+            let grid = GridLayout::column_major(6, 2);
+            ka.disable(2);
+            assert!(ka.move_in(&grid, Direction::Right));
+            assert_eq!(ka.current_index(), 4);
+            assert!(!ka.move_in(&grid, Direction::Right));
+            assert!(ka.move_in(&grid, Direction::Down));
+            assert_eq!(ka.current_index(), 5);
+        }
+    }
+}
@@ -1,74 +1,598 @@
-/// KeyArray is like a row of buttons; exactly one button (the current key)
-/// is “pressed” at any time.
-///
-/// To create (defaults to first key):
-///     let mut mykeys = KeyArray::new(["On", "Off", "Auto"]);
-///
-/// To create with an explicit start:
-///     let mut mykeys = KeyArray::new_with(["On", "Off"], 1);
-///
-/// To change the status (current key by index):
-///     mykeys.change(2);
-///
-/// To inspect:
-///     let idx = mykeys.current_index();
-///     let key = mykeys.current();
-///     let all = mykeys.keys();
-///
-/// To edit the key list:
-///     mykeys.push("New");
-///     mykeys.insert(1, "Inserted");
-///     let removed = mykeys.remove(0);
-///
+//! KeyArray is like a row of buttons; exactly one button (the current key)
+//! is “pressed” at any time.
+//!
+//! ```
+//! # #[cfg(feature = "std")] {
+//! use keyarray::KeyArray;
+//!
+//! // To create (defaults to first key):
+//! let mut mykeys = KeyArray::new(["On", "Off", "Auto"]);
+//!
+//! // To create with an explicit start:
+//! let other = KeyArray::new_with(["On", "Off"], 1);
+//!
+//! // To change the status (current key by index):
+//! mykeys.change(2);
+//!
+//! // To inspect:
+//! let idx = mykeys.current_index();
+//! let key = mykeys.current();
+//! let all = mykeys.keys();
+//!
+//! // To edit the key list:
+//! mykeys.push("New");
+//! mykeys.insert(1, "Inserted");
+//! let removed = mykeys.remove(0);
+//! # }
+//! ```
 
+//! # Cargo features
+//!
+//! The selection core (`KeyArray`, ids, cursor navigation, queries) only
+//! needs `std`. Everything else is a feature, including the subsystems
+//! built into KeyArray itself; `default` enables `std` and `full`.
+//!
+//! | Feature     | Adds                                                  |
+//! |-------------|-------------------------------------------------------|
+//! | `std`       | KeyArray itself and everything built on it            |
+//! | `full`      | the features from `activity` to `traversal` below     |
+//! | `activity`  | `track_activity` usage statistics                     |
+//! | `batch`     | [`Batch`] amortised bulk edits                        |
+//! | `confirm`   | `require_confirmation` and `request_change`           |
+//! | `deprecation` | `deprecate`, `on_deprecated`, strict redirects      |
+//! | `disable`   | `disable` / `enable` greyed-out keys                  |
+//! | `expiry`    | `push_with_ttl` keys and `purge_expired`              |
+//! | `history`   | `enable_history`, undo/redo and back/forward          |
+//! | `meta`      | `set_meta` typed payloads on keys                     |
+//! | `msg`       | [`Msg`], `update`, [`reduce`], [`SelectionViewModel`], `simulate` |
+//! | `multi`     | [`MultiKeyArray`] and its [`rules`]                   |
+//! | `observers` | `on_change` and the other callbacks, `realtime`       |
+//! | `overrides` | `change_manual` / `change_auto`                       |
+//! | `permission` | `require_permission` locked keys                     |
+//! | `predict`   | `predict_next` and pluggable predictors               |
+//! | `provenance` | `push_from` key sources, `reset_to_defaults`         |
+//! | `ui`        | [`KeyUi`] per-key presentation hints                  |
+//! | `render`    | [`KeyArrayRenderer`] and the built-in renderers       |
+//! | `rotation`  | weighted [`Rotation`], failover, `select_for`, splits |
+//! | `tombstone` | `soft_remove` / `restore` hidden keys                 |
+//! | `trace`     | `enable_tracing` recent operations in panics          |
+//! | `transitions` | `allow_transition` rules and `guarded_change`       |
+//! | `traversal` | `set_traversal` safe-order changes                    |
+//! | `bevy`      | `KeyArray` as a Bevy component, `KeyArrayPlugin`      |
+//! | `collation` | locale-aware `sort_collated` (ICU4X)                  |
+//! | `critical-section` | interrupt-safe `shared::SharedKeyArray`  |
+//! | `csv`       | `from_csv_reader` / `to_csv_writer` spreadsheets      |
+//! | `defmt`     | `defmt::Format` for KeyArray, events and errors       |
+//! | `dioxus`    | `dioxus::use_keyarray` signal hook                    |
+//! | `embedded-graphics` | `graphics` key rows and lists on displays |
+//! | `embedded-hal` | `buttons::ButtonDriver` for physical buttons       |
+//! | `file-sync` | `file_sync::FileKeyArray` shared between processes    |
+//! | `futures`   | `stream::AsyncKeyArray` with a `changes()` stream     |
+//! | `macroquad` | `game_ui` immediate-mode menu row                     |
+//! | `notify`    | `watch::KeyFileWatcher` reloads keys from a file      |
+//! | `persist`   | `persist::persist_user_config`                        |
+//! | `prost`     | protobuf `proto::KeyArrayState` / `proto::Op`         |
+//! | `rand`      | seedable `shuffle_with`, `change_random_with`, Markov steps |
+//! | `rayon`     | `par_iter` / `par_positions`                          |
+//! | `remote`    | line-based `remote::serve` debug console protocol     |
+//! | `serde`     | checked `Serialize`/`Deserialize` for KeyArray, events |
+//! | `tauri`     | command helpers and `tauri_commands!`                 |
+//! | `tokio`     | `sync::SyncKeyArray::watch_async` watch channel       |
+//! | `unicode`   | NFC/NFKC [`Normalization`] for name lookup            |
+//!
+//! Without the `std` feature (on by default, and implied by every other
+//! feature but `critical-section`, `defmt`, `embedded-graphics` and
+//! `embedded-hal`) the crate is `no_std` and offers [`KeyArrayFixed`],
+//! [`KeyArrayRef`], [`SelectionCursor`] and [`KeyStyle`], and those four
+//! features work on them.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "activity")]
+pub mod activity;
+#[cfg(feature = "batch")]
+pub mod batch;
+#[cfg(feature = "bench")]
+pub mod bench;
+#[cfg(feature = "bevy")]
+pub mod bevy;
+#[cfg(feature = "std")]
+pub mod bookmarks;
+#[cfg(feature = "std")]
+pub mod builder;
+pub mod borrowed;
+#[cfg(feature = "embedded-hal")]
+pub mod buttons;
+#[cfg(feature = "std")]
+pub mod canonical;
+#[cfg(feature = "std")]
+pub mod chain;
+#[cfg(feature = "std")]
+pub mod clock;
+#[cfg(feature = "collation")]
+pub mod collation;
+#[cfg(feature = "std")]
+pub mod compat;
+#[cfg(feature = "std")]
+pub mod completion;
+#[cfg(feature = "confirm")]
+pub mod confirm;
+#[cfg(feature = "csv")]
+pub mod csv;
+pub mod cursor;
+#[cfg(feature = "dioxus")]
+pub mod dioxus;
+#[cfg(feature = "deprecation")]
+pub mod deprecation;
+#[cfg(feature = "std")]
+pub mod describe;
+#[cfg(feature = "disable")]
+pub mod disable;
+#[cfg(feature = "std")]
+pub mod enums;
+pub mod error;
+#[cfg(feature = "std")]
+pub mod event;
+#[cfg(feature = "expiry")]
+pub mod expiry;
+#[cfg(feature = "file-sync")]
+pub mod file_sync;
+pub mod fixed;
+#[cfg(feature = "std")]
+pub mod functional;
+#[cfg(feature = "macroquad")]
+pub mod game_ui;
+#[cfg(feature = "std")]
+pub mod generation;
+#[cfg(feature = "std")]
+pub mod gradual;
+#[cfg(feature = "std")]
+pub mod graph;
+#[cfg(feature = "embedded-graphics")]
+pub mod graphics;
+#[cfg(feature = "std")]
+pub mod group;
+#[cfg(feature = "history")]
+pub mod history;
+#[cfg(feature = "std")]
+pub mod hysteresis;
+#[cfg(feature = "std")]
+pub mod id;
+#[cfg(feature = "std")]
+pub mod keys_mut;
+#[cfg(feature = "std")]
+pub mod layers;
+#[cfg(feature = "std")]
+pub mod layout;
+#[cfg(feature = "std")]
+pub mod lookup;
+#[cfg(feature = "overrides")]
+pub mod manual;
+#[cfg(feature = "std")]
+pub mod memory;
+#[cfg(feature = "meta")]
+pub mod meta;
+#[cfg(feature = "std")]
+pub mod mnemonic;
+#[cfg(feature = "msg")]
+pub mod msg;
+#[cfg(feature = "multi")]
+pub mod multi;
+#[cfg(feature = "std")]
+pub mod names;
+#[cfg(feature = "observers")]
+pub mod observe;
+#[cfg(feature = "std")]
+pub mod optional;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+#[cfg(feature = "persist")]
+pub mod persist;
+#[cfg(feature = "permission")]
+pub mod permission;
+#[cfg(feature = "std")]
+pub mod placeholder;
+#[cfg(feature = "std")]
+pub mod query;
+#[cfg(feature = "predict")]
+pub mod predict;
+#[cfg(feature = "std")]
+pub mod projection;
+#[cfg(feature = "provenance")]
+pub mod provenance;
+#[cfg(feature = "rand")]
+pub mod random;
+#[cfg(feature = "observers")]
+pub mod realtime;
+#[cfg(feature = "prost")]
+pub mod proto;
+#[cfg(feature = "msg")]
+pub mod reduce;
+#[cfg(feature = "remote")]
+pub mod remote;
+#[cfg(feature = "render")]
+pub mod render;
+#[cfg(feature = "std")]
+pub mod repair;
+#[cfg(feature = "rotation")]
+pub mod rotation;
+#[cfg(feature = "multi")]
+pub mod rules;
+#[cfg(feature = "std")]
+pub mod script;
+#[cfg(feature = "std")]
+pub mod search;
+#[cfg(feature = "serde")]
+mod serde_impls;
+#[cfg(feature = "critical-section")]
+pub mod shared;
+#[cfg(feature = "msg")]
+pub mod simulate;
+#[cfg(feature = "std")]
+mod std_traits;
+#[cfg(feature = "rotation")]
+pub mod split;
+#[cfg(feature = "rotation")]
+pub mod sticky;
+#[cfg(feature = "futures")]
+pub mod stream;
+pub mod style;
+#[cfg(feature = "std")]
+pub mod sync;
+#[cfg(feature = "tauri")]
+pub mod tauri;
+#[cfg(feature = "msg")]
+pub mod testing;
+#[cfg(feature = "std")]
+pub mod text;
+#[cfg(feature = "tombstone")]
+pub mod tombstone;
+#[cfg(feature = "trace")]
+pub mod trace;
+#[cfg(feature = "transitions")]
+pub mod transition;
+#[cfg(feature = "traversal")]
+pub mod traversal;
+#[cfg(feature = "ui")]
+pub mod ui;
+#[cfg(feature = "msg")]
+pub mod view_model;
+#[cfg(feature = "notify")]
+pub mod watch;
+
+#[cfg(feature = "batch")]
+pub use batch::Batch;
+pub use borrowed::{AsKeyArray, KeyArrayRef};
+pub use cursor::{SelectionCursor, WrapMode};
+pub use error::KeyArrayError;
+#[cfg(feature = "std")]
+pub use event::{ChangeEvent, ChangeReason, Renamed, StructureEvent};
+pub use fixed::KeyArrayFixed;
+#[cfg(feature = "std")]
+pub use id::KeyId;
+#[cfg(feature = "std")]
+pub use keys_mut::KeysMut;
+#[cfg(feature = "msg")]
+pub use msg::Msg;
+#[cfg(feature = "multi")]
+pub use multi::MultiKeyArray;
+#[cfg(feature = "std")]
+pub use names::Normalization;
+#[cfg(feature = "msg")]
+pub use reduce::reduce;
+#[cfg(feature = "render")]
+pub use render::KeyArrayRenderer;
+#[cfg(feature = "rotation")]
+pub use rotation::Rotation;
+pub use style::{ColorHint, Emphasis, KeyStyle};
+#[cfg(feature = "ui")]
+pub use ui::KeyUi;
+#[cfg(feature = "msg")]
+pub use view_model::SelectionViewModel;
+
+#[cfg(feature = "std")]
+use std::cmp::Ordering;
+#[cfg(feature = "std")]
+use std::collections::BTreeMap;
+#[cfg(feature = "std")]
 use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+#[cfg(feature = "std")]
+use std::sync::Arc;
+#[cfg(feature = "expiry")]
+use std::time::Instant;
 
+#[cfg(feature = "std")]
+// Storage is copy-on-write: clones share the key list until one of them
+// edits it, so selection-only updates of a cloned state are O(1).
+#[derive(Clone)]
+#[cfg_attr(feature = "bevy", derive(bevy_ecs::component::Component))]
 pub struct KeyArray<K> {
-    keys: Vec<K>,
+    keys: Arc<Vec<K>>,
     idx: usize,
+    // per-key side data, always the same length as `keys`
+    slots: Arc<Vec<Slot>>,
+    // id handed to the next key added
+    next_id: u64,
+    // named selections, see `bookmark`
+    bookmarks: Arc<BTreeMap<String, KeyId>>,
+    #[cfg(feature = "rotation")]
+    rotation: Rotation,
+    wrap: WrapMode,
+    // see `set_traversal`
+    #[cfg(feature = "traversal")]
+    traversal: traversal::Traversal,
+    // see `on_change`
+    #[cfg(feature = "observers")]
+    observers: observe::Observers<K>,
+    // see `allow_transition`
+    #[cfg(feature = "transitions")]
+    transitions: transition::Transitions<K>,
+    // see `enable_history`
+    #[cfg(feature = "history")]
+    history: Option<Box<history::History<K>>>,
+    // see `track_activity`
+    #[cfg(feature = "activity")]
+    activity: Option<Box<activity::Activity>>,
+    // see `set_predictor`
+    #[cfg(feature = "predict")]
+    predictor: Option<Arc<dyn predict::Predictor<K>>>,
+    // see `set_permission_checker`
+    #[cfg(feature = "permission")]
+    permission_checker: Option<permission::Checker>,
+    // see `set_strict_deprecation`
+    #[cfg(feature = "deprecation")]
+    strict_deprecation: bool,
+    // see `change_manual`
+    #[cfg(feature = "overrides")]
+    overrides: manual::Overrides,
+    // see `enable_tracing`
+    #[cfg(feature = "trace")]
+    trace: Option<Box<trace::Trace>>,
+    // bumped by `touch` on every mutation
+    generation: u64,
+    dirty: bool,
+}
+
+#[cfg(feature = "std")]
+/// Everything attached to a single key, moved together with it by every
+/// structural edit.
+#[derive(Debug, Clone, PartialEq)]
+struct Slot {
+    id: KeyId,
+    #[cfg(feature = "ui")]
+    ui: Option<KeyUi>,
+    #[cfg(feature = "rotation")]
+    rotation: rotation::KeyRotation,
+    // see `push_with_ttl`
+    #[cfg(feature = "expiry")]
+    expires: Option<Instant>,
+    // see `soft_remove`
+    #[cfg(feature = "tombstone")]
+    hidden: bool,
+    // see `disable`
+    #[cfg(feature = "disable")]
+    disabled: bool,
+    // see `require_confirmation`
+    #[cfg(feature = "confirm")]
+    confirm: bool,
+    // see `push_from`
+    #[cfg(feature = "provenance")]
+    source: provenance::Source,
+    // see `set_meta`
+    #[cfg(feature = "meta")]
+    meta: Option<meta::Meta>,
+    // see `require_permission`
+    #[cfg(feature = "permission")]
+    permission: Option<String>,
+    // see `deprecate`
+    #[cfg(feature = "deprecation")]
+    deprecation: Option<Box<deprecation::Deprecation>>,
+}
+
+#[cfg(feature = "std")]
+impl Slot {
+    fn new(id: KeyId) -> Self {
+        Slot {
+            id,
+            #[cfg(feature = "ui")]
+            ui: None,
+            #[cfg(feature = "rotation")]
+            rotation: rotation::KeyRotation::default(),
+            #[cfg(feature = "expiry")]
+            expires: None,
+            #[cfg(feature = "tombstone")]
+            hidden: false,
+            #[cfg(feature = "disable")]
+            disabled: false,
+            #[cfg(feature = "confirm")]
+            confirm: false,
+            #[cfg(feature = "provenance")]
+            source: provenance::Source::Defaults,
+            #[cfg(feature = "meta")]
+            meta: None,
+            #[cfg(feature = "permission")]
+            permission: None,
+            #[cfg(feature = "deprecation")]
+            deprecation: None,
+        }
+    }
+
+    // Whether the key is soft-removed; never without the `tombstone`
+    // feature.
+    fn is_hidden(&self) -> bool {
+        #[cfg(feature = "tombstone")]
+        return self.hidden;
+        #[cfg(not(feature = "tombstone"))]
+        false
+    }
 }
 
+// What the `traversal` module provides when it is compiled out: changes
+// go straight to their target.
+#[cfg(all(feature = "std", not(feature = "traversal")))]
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    pub(crate) fn move_to(&mut self, i: usize) {
+        self.idx = i;
+        self.touch();
+    }
+}
+
+// What the `trace` module provides when it is compiled out: nothing is
+// recorded, and panics carry only the error.
+#[cfg(all(feature = "std", not(feature = "trace")))]
+impl<K> KeyArray<K> {
+    fn trace_op(&mut self, _name: &'static str, _index: Option<usize>) {}
+
+    fn fail(&self, method: &str, error: KeyArrayError) -> ! {
+        panic!("KeyArray::{}: {}", method, error)
+    }
+}
+
+fn check_index(index: usize, len: usize) -> Result<(), KeyArrayError> {
+    if index < len {
+        Ok(())
+    } else {
+        Err(KeyArrayError::IndexOutOfBounds { index, len })
+    }
+}
+
+#[cfg(feature = "std")]
+fn fresh_slots(n: usize) -> Arc<Vec<Slot>> {
+    Arc::new((0..n as u64).map(|i| Slot::new(KeyId(i))).collect())
+}
+
+#[cfg(feature = "std")]
 impl<K> KeyArray<K>
 where
     K: Clone + PartialEq + Debug + Display,
 {
     /// Create from any iterable of keys. Panics if empty.
     pub fn new(keys: impl IntoIterator<Item = K>) -> Self {
-        let keys: Vec<K> = keys.into_iter().collect();
-        assert!(
-            !keys.is_empty(),
-            "KeyArray::new: must supply at least one key"
-        );
-        KeyArray { keys, idx: 0 }
+        Self::try_new(keys).unwrap_or_else(|e| panic!("KeyArray::new: {}", e))
+    }
+
+    /// [`new`](Self::new), failing with [`KeyArrayError::Empty`] instead of
+    /// panicking.
+    pub fn try_new(keys: impl IntoIterator<Item = K>) -> Result<Self, KeyArrayError> {
+        Self::try_new_with(keys, 0)
     }
 
     /// Same as `new`, but start at `start_idx`. Panics if out of bounds.
     pub fn new_with(keys: impl IntoIterator<Item = K>, start_idx: usize) -> Self {
+        Self::try_new_with(keys, start_idx)
+            .unwrap_or_else(|e| panic!("KeyArray::new_with: {}", e))
+    }
+
+    /// [`new_with`](Self::new_with), returning an error instead of panicking.
+    pub fn try_new_with(
+        keys: impl IntoIterator<Item = K>,
+        start_idx: usize,
+    ) -> Result<Self, KeyArrayError> {
         let keys: Vec<K> = keys.into_iter().collect();
-        assert!(
-            !keys.is_empty(),
-            "KeyArray::new_with: must supply keys"
-        );
-        assert!(
-            start_idx < keys.len(),
-            "KeyArray::new_with: start_idx {} out of bounds",
-            start_idx
-        );
+        if keys.is_empty() {
+            return Err(KeyArrayError::Empty);
+        }
+        check_index(start_idx, keys.len())?;
+        Ok(Self::from_vec(keys, start_idx))
+    }
+
+    // No checks: `keys` may be empty, as for `OptionalKeyArray`.
+    fn from_vec(keys: Vec<K>, start_idx: usize) -> Self {
+        let slots = fresh_slots(keys.len());
         KeyArray {
-            keys,
+            next_id: keys.len() as u64,
+            keys: Arc::new(keys),
             idx: start_idx,
+            slots,
+            bookmarks: Arc::default(),
+            #[cfg(feature = "rotation")]
+            rotation: Rotation::default(),
+            wrap: WrapMode::default(),
+            #[cfg(feature = "traversal")]
+            traversal: traversal::Traversal::default(),
+            #[cfg(feature = "observers")]
+            observers: Default::default(),
+            #[cfg(feature = "transitions")]
+            transitions: Default::default(),
+            #[cfg(feature = "history")]
+            history: None,
+            #[cfg(feature = "activity")]
+            activity: None,
+            #[cfg(feature = "predict")]
+            predictor: None,
+            #[cfg(feature = "permission")]
+            permission_checker: None,
+            #[cfg(feature = "deprecation")]
+            strict_deprecation: false,
+            #[cfg(feature = "overrides")]
+            overrides: Default::default(),
+            #[cfg(feature = "trace")]
+            trace: None,
+            generation: 0,
+            dirty: false,
         }
     }
 
     /// Change the current key by zero‐based index.
     /// Panics if `i` is out of bounds.
     pub fn change(&mut self, i: usize) {
-        assert!(
-            i < self.keys.len(),
-            "KeyArray::change: index {} out of bounds",
-            i
-        );
-        self.idx = i;
+        self.try_change(i).unwrap_or_else(|e| self.fail("change", e))
+    }
+
+    /// [`change`](Self::change), returning an error instead of panicking.
+    /// Under [`Traversal::Adjacent`](traversal::Traversal::Adjacent) the
+    /// keys in between become current first, one at a time. A
+    /// [deprecated](Self::deprecate) key may be swapped for its
+    /// replacement.
+    pub fn try_change(&mut self, i: usize) -> Result<(), KeyArrayError> {
+        self.trace_op("change", Some(i));
+        check_index(i, self.keys.len())?;
+        self.check_selectable(i)?;
+        #[cfg(feature = "deprecation")]
+        let target = self.deprecation_target(i);
+        #[cfg(not(feature = "deprecation"))]
+        let target = i;
+        if self.idx != target {
+            self.move_to(target);
+            #[cfg(feature = "deprecation")]
+            self.warn_deprecated(i, target);
+        }
+        Ok(())
+    }
+
+    /// Move to the following key, wrapping from the last to the first.
+    /// Does nothing if there are no keys. Soft-removed and disabled keys
+    /// are skipped.
+    /// With the `rotation` feature, keys out of rotation are skipped too,
+    /// and under [`Rotation::Weighted`] the following key is picked by
+    /// weight instead. Under a [`WrapMode`] other than `Wrap` this is
+    /// [`advance_by(1)`](Self::advance_by) and stops at the last key.
+    pub fn next(&mut self) {
+        self.trace_op("next", None);
+        if self.wrap != WrapMode::Wrap {
+            let _ = self.advance_by(1);
+            return;
+        }
+        #[cfg(feature = "rotation")]
+        self.step_visible(Self::rotate);
+        #[cfg(not(feature = "rotation"))]
+        self.step_visible(|keys, at| keys.wrapped(at, 1));
+    }
+
+    /// Move to the preceding key, wrapping from the first to the last.
+    /// Does nothing if there are no keys. Soft-removed and disabled keys
+    /// are skipped.
+    /// Under a [`WrapMode`] other than `Wrap` this stops at the first key.
+    pub fn prev(&mut self) {
+        self.trace_op("prev", None);
+        if self.wrap != WrapMode::Wrap {
+            let _ = self.advance_by(-1);
+            return;
+        }
+        self.step_visible(|keys, at| keys.wrapped(at, -1));
     }
 
     /// Get a reference to the current key.
@@ -96,42 +620,288 @@ where
         self.len() == 0
     }
 
-    /// Append a new key after the last.
-    pub fn push(&mut self, key: K) {
-        self.keys.push(key);
+    /// Append a new key after the last, returning its id.
+    pub fn push(&mut self, key: K) -> KeyId {
+        self.trace_op("push", None);
+        Arc::make_mut(&mut self.keys).push(key);
+        let slot = self.new_slot();
+        let id = slot.id;
+        Arc::make_mut(&mut self.slots).push(slot);
+        self.touch();
+        id
     }
 
-    /// Insert a key at position `i`. Panics if `i > len`.
-    pub fn insert(&mut self, i: usize, key: K) {
-        assert!(
-            i <= self.keys.len(),
-            "KeyArray::insert: index {} out of bounds",
-            i
-        );
-        self.keys.insert(i, key);
+    /// Insert a key at position `i`, returning its id. Panics if `i > len`.
+    pub fn insert(&mut self, i: usize, key: K) -> KeyId {
+        self.try_insert(i, key).unwrap_or_else(|e| self.fail("insert", e))
+    }
+
+    /// [`insert`](Self::insert), returning an error instead of panicking.
+    pub fn try_insert(&mut self, i: usize, key: K) -> Result<KeyId, KeyArrayError> {
+        self.trace_op("insert", Some(i));
+        if i > self.keys.len() {
+            let len = self.keys.len();
+            return Err(KeyArrayError::IndexOutOfBounds { index: i, len });
+        }
+        Arc::make_mut(&mut self.keys).insert(i, key);
+        let slot = self.new_slot();
+        let id = slot.id;
+        Arc::make_mut(&mut self.slots).insert(i, slot);
         // if you inserted before current idx, bump it forward
         if i <= self.idx {
             self.idx += 1;
         }
+        self.touch();
+        Ok(id)
     }
 
     /// Remove and return the key at `i`. Panics if out of bounds.
     pub fn remove(&mut self, i: usize) -> K {
-        assert!(
-            i < self.keys.len(),
-            "KeyArray::remove: index {} out of bounds",
-            i
-        );
-        let removed = self.keys.remove(i);
+        self.try_remove(i).unwrap_or_else(|e| self.fail("remove", e))
+    }
+
+    /// [`remove`](Self::remove), returning an error instead of panicking.
+    pub fn try_remove(&mut self, i: usize) -> Result<K, KeyArrayError> {
+        self.trace_op("remove", Some(i));
+        check_index(i, self.keys.len())?;
+        let removed = Arc::make_mut(&mut self.keys).remove(i);
+        Arc::make_mut(&mut self.slots).remove(i);
         // adjust current index
         if self.idx >= self.keys.len() {
             // if we removed the last element, clamp idx
             self.idx = self.keys.len().saturating_sub(1);
         }
+        self.touch();
+        Ok(removed)
+    }
+
+    /// Replace every key. Keys equal to an old key (matched in order, each
+    /// old key used once) keep their id and attached data; if the current
+    /// key is among them it stays current, otherwise the index is clamped as
+    /// by [`remove`](Self::remove). Panics if `keys` is empty.
+    pub fn set_keys(&mut self, keys: impl IntoIterator<Item = K>) {
+        self.trace_op("set_keys", None);
+        let keys: Vec<K> = keys.into_iter().collect();
+        assert!(!keys.is_empty(), "KeyArray::set_keys: must supply keys");
+        let mut old: Vec<Option<Slot>> = self.slots.iter().cloned().map(Some).collect();
+        let mut new_idx = None;
+        let mut slots = Vec::with_capacity(keys.len());
+        for key in &keys {
+            let matched = (0..old.len()).find(|&j| old[j].is_some() && self.keys[j] == *key);
+            let slot = match matched {
+                Some(j) => {
+                    if j == self.idx {
+                        new_idx = Some(slots.len());
+                    }
+                    old[j].take().unwrap()
+                }
+                None => self.new_slot(),
+            };
+            slots.push(slot);
+        }
+        self.idx = new_idx.unwrap_or(self.idx.min(keys.len() - 1));
+        self.keys = Arc::new(keys);
+        self.slots = Arc::new(slots);
+        self.touch();
+    }
+
+    // Repeat `step` from the current key until it lands on one that can be
+    // selected, and make only that one current; after a full lap without
+    // one, nothing changes.
+    fn step_visible(&mut self, mut step: impl FnMut(&mut Self, usize) -> usize) {
+        let mut at = self.idx;
+        for _ in 0..self.len() {
+            at = step(self, at);
+            if self.selectable(at) {
+                self.jump(at);
+                return;
+            }
+        }
+    }
+
+    // Whether navigation may land on key `i`.
+    fn selectable(&self, i: usize) -> bool {
+        !self.slots[i].is_hidden() && self.check_selectable(i).is_ok()
+    }
+
+    // Why key `i`, in bounds, cannot be selected, if it cannot.
+    #[cfg_attr(
+        not(any(feature = "disable", feature = "permission")),
+        allow(unused_variables)
+    )]
+    pub(crate) fn check_selectable(&self, i: usize) -> Result<(), KeyArrayError> {
+        #[cfg(feature = "disable")]
+        if self.slots[i].disabled {
+            return Err(KeyArrayError::Disabled { index: i });
+        }
+        #[cfg(feature = "permission")]
+        if !self.is_permitted(i) {
+            return Err(KeyArrayError::PermissionDenied { index: i });
+        }
+        Ok(())
+    }
+
+    fn new_slot(&mut self) -> Slot {
+        let id = KeyId(self.next_id);
+        self.next_id += 1;
+        Slot::new(id)
+    }
+
+    // Record that something observable changed (see `generation`).
+    fn touch(&mut self) {
+        self.generation += 1;
+        self.dirty = true;
+        #[cfg(any(feature = "activity", feature = "observers"))]
+        let current = self.slots.get(self.idx).map(|s| s.id);
+        #[cfg(feature = "activity")]
+        if let Some(activity) = &mut self.activity {
+            activity.record(current);
+        }
+        #[cfg(feature = "observers")]
+        {
+            self.observers.notify_structure(&self.keys, &self.slots);
+            let current = current.zip(self.keys.get(self.idx));
+            self.observers.notify(self.idx, current);
+        }
+        #[cfg(feature = "history")]
+        if self.history.is_some() {
+            self.record_history();
+        }
+    }
+
+    // Drop every key whose `keep` flag is false, in one pass. The key that
+    // was at `current` (which must be kept) becomes current.
+    fn retain_mask(&mut self, keep: &[bool], current: usize) {
+        debug_assert!(keep[current]);
+        let mut flags = keep.iter();
+        Arc::make_mut(&mut self.keys).retain(|_| *flags.next().unwrap());
+        let mut flags = keep.iter();
+        Arc::make_mut(&mut self.slots).retain(|_| *flags.next().unwrap());
+        self.idx = keep[..current].iter().filter(|k| **k).count();
+        self.touch();
+    }
+
+    // Called after keys were edited in place (see `keys_mut`); restores
+    // anything derived from key values.
+    fn keys_edited(&mut self) {
+        self.touch();
+    }
+
+    /// Stable-sort the keys with `compare`; the current key stays current.
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&K, &K) -> Ordering,
+    {
+        self.reorder(|tagged| tagged.sort_by(|a, b| compare(&a.1, &b.1)));
+    }
+
+    /// Stable-sort by `f(key)`, calling `f` once per key; worth it when the
+    /// sort key is expensive (parsing, allocation). The current key stays
+    /// current.
+    pub fn sort_by_cached_key<T, F>(&mut self, mut f: F)
+    where
+        T: Ord,
+        F: FnMut(&K) -> T,
+    {
+        self.reorder(|tagged| tagged.sort_by_cached_key(|t| f(&t.1)));
+    }
+
+    /// Swap keys `i` and `j`; the current key stays current. Panics if
+    /// either is out of bounds.
+    pub fn swap(&mut self, i: usize, j: usize) {
+        self.trace_op("swap", Some(i));
+        for k in [i, j] {
+            check_index(k, self.len()).unwrap_or_else(|e| self.fail("swap", e));
+        }
+        self.reorder(|tagged| tagged.swap(i, j));
+    }
+
+    /// Move the key at `from` so that it ends up at `to`, shifting the keys
+    /// in between; the current key stays current. Panics if either is out
+    /// of bounds.
+    pub fn move_key(&mut self, from: usize, to: usize) {
+        self.trace_op("move_key", Some(from));
+        for k in [from, to] {
+            check_index(k, self.len()).unwrap_or_else(|e| self.fail("move_key", e));
+        }
+        self.reorder(|tagged| {
+            let moved = tagged.remove(from);
+            tagged.insert(to, moved);
+        });
+    }
+
+    /// Keep only the keys for which `keep` returns true and return how many
+    /// were removed. If the current key is removed, the first kept key
+    /// after it becomes current, or else the last one before it; if no key
+    /// is kept the array is left empty.
+    pub fn retain<F>(&mut self, mut keep: F) -> usize
+    where
+        F: FnMut(&K) -> bool,
+    {
+        let keep: Vec<bool> = self.keys.iter().map(&mut keep).collect();
+        self.retain_flags(&keep)
+    }
+
+    /// Remove every key equal to an earlier one and return how many were
+    /// removed. If the current key was a later duplicate, the kept one
+    /// becomes current.
+    pub fn dedup(&mut self) -> usize {
+        let keep: Vec<bool> = (0..self.len())
+            .map(|i| !self.keys[..i].contains(&self.keys[i]))
+            .collect();
+        if !keep.contains(&false) {
+            return 0;
+        }
+        let first = self.keys.iter().position(|k| k == self.current());
+        let first = first.expect("the current key equals itself");
+        self.retain_mask(&keep, first);
+        keep.iter().filter(|k| !**k).count()
+    }
+
+    // `retain_mask`, choosing the survivor described at `retain`.
+    fn retain_flags(&mut self, keep: &[bool]) -> usize {
+        let removed = keep.iter().filter(|k| !**k).count();
+        if removed == 0 {
+            return 0;
+        }
+        let survivor = (self.idx..self.len())
+            .chain((0..self.idx).rev())
+            .find(|&i| keep[i]);
+        match survivor {
+            Some(survivor) => self.retain_mask(keep, survivor),
+            None => {
+                Arc::make_mut(&mut self.keys).clear();
+                Arc::make_mut(&mut self.slots).clear();
+                self.idx = 0;
+                self.touch();
+            }
+        }
         removed
     }
+
+    // Hand every key, tagged with its old index and slot, to `sort`, then
+    // store them back in the new order and follow the current key.
+    fn reorder(&mut self, sort: impl FnOnce(&mut Vec<(usize, K, Slot)>)) {
+        let keys = Arc::make_mut(&mut self.keys);
+        let slots = Arc::make_mut(&mut self.slots);
+        let mut tagged: Vec<(usize, K, Slot)> = keys
+            .drain(..)
+            .zip(slots.drain(..))
+            .enumerate()
+            .map(|(i, (k, s))| (i, k, s))
+            .collect();
+        sort(&mut tagged);
+        let current = self.idx;
+        self.idx = tagged.iter().position(|t| t.0 == current).unwrap_or(0);
+        for (_, k, s) in tagged {
+            keys.push(k);
+            slots.push(s);
+        }
+        self.touch();
+    }
 }
 
+#[cfg(feature = "std")]
 impl<K> Display for KeyArray<K>
 where
     K: Clone + PartialEq + Debug + Display,
@@ -147,7 +917,64 @@ where
     }
 }
 
-#[cfg(test)]
+#[cfg(feature = "std")]
+/// `{:?}` marks the current key: `KeyArray(["On", >"Off"<, "Auto"])`.
+/// `{:#?}` lists one key per line with its id and flags.
+impl<K> Debug for KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        if !f.alternate() {
+            f.write_str("KeyArray([")?;
+            for (i, key) in self.keys.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                if i == self.idx {
+                    write!(f, ">{:?}<", key)?;
+                } else {
+                    write!(f, "{:?}", key)?;
+                }
+            }
+            return f.write_str("])");
+        }
+        f.write_str("KeyArray [\n")?;
+        for (i, (key, slot)) in self.keys.iter().zip(self.slots.iter()).enumerate() {
+            write!(f, "    {}: {:?} [{}", i, key, slot.id)?;
+            if i == self.idx {
+                f.write_str(", current")?;
+            }
+            #[cfg(feature = "disable")]
+            if slot.disabled {
+                f.write_str(", disabled")?;
+            }
+            #[cfg(feature = "permission")]
+            if let Some(permission) = &slot.permission {
+                write!(f, ", needs {:?}", permission)?;
+            }
+            #[cfg(feature = "ui")]
+            if slot.ui.is_some() {
+                f.write_str(", ui")?;
+            }
+            f.write_str("],\n")?;
+        }
+        f.write_str("]")
+    }
+}
+
+/// Compact RTT form: `KeyArray(["On", "Off"], current=1)`.
+#[cfg(all(feature = "std", feature = "defmt"))]
+impl<K> defmt::Format for KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display + defmt::Format,
+{
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "KeyArray({=[?]}, current={=usize})", self.keys(), self.idx)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
 
@@ -184,6 +1011,34 @@ mod tests {
         ka.change(5);
     }
 
+    #[test]
+    fn try_api_reports_errors() {
+        assert_eq!(KeyArray::<u8>::try_new([]).unwrap_err(), KeyArrayError::Empty);
+        let oob = |index, len| KeyArrayError::IndexOutOfBounds { index, len };
+        assert_eq!(KeyArray::try_new_with([1, 2], 2).unwrap_err(), oob(2, 2));
+        let mut ka = KeyArray::try_new_with([1, 2], 1).unwrap();
+        assert_eq!(ka.try_change(2), Err(oob(2, 2)));
+        assert_eq!(ka.try_insert(3, 9), Err(oob(3, 2)));
+        assert!(ka.try_insert(2, 3).is_ok());
+        assert_eq!(ka.try_remove(0), Ok(1));
+        assert_eq!(ka.try_remove(2), Err(oob(2, 2)));
+        assert_eq!((ka.keys(), ka.current()), (&[2, 3][..], &3));
+    }
+
+    #[test]
+    fn bulk_edits_follow_current() {
+        let mut ka = KeyArray::new_with(["c", "a", "b", "a", "d"], 3);
+        assert_eq!(ka.dedup(), 1);
+        assert_eq!((ka.keys(), ka.current_index()), (&["c", "a", "b", "d"][..], 1));
+        ka.swap(0, 1);
+        ka.move_key(3, 0);
+        assert_eq!((ka.keys(), ka.current()), (&["d", "a", "c", "b"][..], &"a"));
+        assert_eq!(ka.retain(|k| *k != "a" && *k != "c"), 2);
+        assert_eq!((ka.keys(), ka.current()), (&["d", "b"][..], &"b"));
+        assert_eq!(ka.retain(|_| false), 2);
+        assert!(ka.is_empty());
+    }
+
     #[test]
     fn display_format() {
         let ka = KeyArray::new(["Up", "Down"]);
@@ -202,4 +1057,48 @@ mod tests {
         assert!(empty.is_empty());
         assert_eq!(empty.len(), 0);
     }
+
+    #[cfg(feature = "ui")]
+    #[test]
+    fn debug_marks_current() {
+        let mut ka = KeyArray::new_with(["On", "Off", "Auto"], 1);
+        assert_eq!(format!("{:?}", ka), r#"KeyArray(["On", >"Off"<, "Auto"])"#);
+        ka.set_ui(2, KeyUi::new());
+        assert_eq!(
+            format!("{:#?}", ka),
+            "KeyArray [\n    0: \"On\" [#0],\n    1: \"Off\" [#1, current],\n    2: \"Auto\" [#2, ui],\n]"
+        );
+    }
+
+    #[test]
+    fn sort_keeps_current() {
+        let mut ka = KeyArray::new_with(["C", "A", "B"], 0);
+        ka.sort_by(|a, b| a.cmp(b));
+        assert_eq!(ka.keys(), &["A", "B", "C"]);
+        assert_eq!(ka.current(), &"C");
+    }
+
+    #[test]
+    fn set_keys_preserves_by_value() {
+        let mut ka = KeyArray::new_with(["A", "B", "C"], 1);
+        let b = ka.current_id();
+        ka.set_keys(["Z", "C", "B"]);
+        assert_eq!((ka.current(), ka.current_id()), (&"B", b));
+        ka.set_keys(["X", "Y"]);
+        assert_eq!(ka.current_index(), 1);
+        assert_ne!(ka.current_id(), b);
+    }
+
+    #[test]
+    fn cached_key_sort() {
+        let mut calls = 0;
+        let mut ka = KeyArray::new_with(["v10", "v9", "v100", "v9"], 2);
+        ka.sort_by_cached_key(|k| {
+            calls += 1;
+            k[1..].parse::<u32>().unwrap()
+        });
+        assert_eq!(calls, 4);
+        assert_eq!(ka.keys(), &["v9", "v9", "v10", "v100"]);
+        assert_eq!(ka.current_index(), 3);
+    }
 }
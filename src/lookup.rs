@@ -0,0 +1,147 @@
+//! Finding and selecting keys by value.
+//!
+//! Code usually knows the key it wants ("Auto"), not its index.
+//! [`position_of`](KeyArray::position_of), [`contains`](KeyArray::contains)
+//! and [`change_to`](KeyArray::change_to) look it up with a linear scan,
+//! taking the first equal key.
+//!
+//! ```
+//! use keyarray::{KeyArray, KeyArrayError};
+//!
+//! let mut mode = KeyArray::new(["Off", "Auto", "On"]);
+//! assert_eq!(mode.change_to(&"Auto"), Ok(1));
+//! assert_eq!(mode.position_of(&"On"), Some(2));
+//! assert!(!mode.contains(&"Eco"));
+//! assert_eq!(mode.change_to(&"Eco"), Err(KeyArrayError::KeyNotFound));
+//! ```
+//!
+//! With the `hash-index` feature, a [`HashIndex`] makes these lookups
+//! O(1) on large arrays of `Hash + Eq` keys. It is rebuilt on the first
+//! lookup after the keys change; selection changes and other edits that
+//! leave the keys alone keep it.
+//!
+//! ```
+//! # #[cfg(feature = "hash-index")]
+//! # {
+//! use keyarray::lookup::HashIndex;
+//! use keyarray::KeyArray;
+//!
+//! let mut ports = KeyArray::new((8000..9000).map(|p| p.to_string()));
+//! let mut index = HashIndex::new();
+//! assert_eq!(index.change_to(&mut ports, &"8443".to_string()), Ok(443));
+//! assert_eq!(index.position_of(&ports, &"8999".to_string()), Some(999));
+//! # }
+//! ```
+
+use crate::error::KeyArrayError;
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+#[cfg(feature = "hash-index")]
+use std::{collections::HashMap, hash::Hash, sync::Arc};
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Index of the first key equal to `key`.
+    pub fn position_of(&self, key: &K) -> Option<usize> {
+        self.keys.iter().position(|k| k == key)
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.keys.contains(key)
+    }
+
+    /// Make the first key equal to `key` current and return its index.
+    /// Fails with [`KeyArrayError::KeyNotFound`] if there is none, or as
+    /// [`try_change`](Self::try_change) does.
+    pub fn change_to(&mut self, key: &K) -> Result<usize, KeyArrayError> {
+        let i = self.position_of(key).ok_or(KeyArrayError::KeyNotFound)?;
+        self.try_change(i)?;
+        Ok(i)
+    }
+}
+
+/// A hash map from keys to their first index, kept for one KeyArray; see
+/// the [module docs](self). While it is up to date, the first edit of the
+/// keys copies the key list, as the index shares it to notice the edit.
+#[cfg(feature = "hash-index")]
+#[derive(Debug, Clone)]
+pub struct HashIndex<K> {
+    // the keys the map was built from
+    built: Option<Arc<Vec<K>>>,
+    map: HashMap<K, usize>,
+}
+
+#[cfg(feature = "hash-index")]
+impl<K> Default for HashIndex<K> {
+    fn default() -> Self {
+        HashIndex {
+            built: None,
+            map: HashMap::new(),
+        }
+    }
+}
+
+#[cfg(feature = "hash-index")]
+impl<K> HashIndex<K>
+where
+    K: Clone + PartialEq + Debug + Display + Hash + Eq,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn refresh(&mut self, keys: &KeyArray<K>) {
+        if self.built.as_ref().is_some_and(|b| Arc::ptr_eq(b, &keys.keys)) {
+            return;
+        }
+        self.map.clear();
+        for (i, key) in keys.keys.iter().enumerate() {
+            self.map.entry(key.clone()).or_insert(i);
+        }
+        self.built = Some(Arc::clone(&keys.keys));
+    }
+
+    /// [`KeyArray::position_of`], by hash.
+    pub fn position_of(&mut self, keys: &KeyArray<K>, key: &K) -> Option<usize> {
+        self.refresh(keys);
+        self.map.get(key).copied()
+    }
+
+    /// [`KeyArray::contains`], by hash.
+    pub fn contains(&mut self, keys: &KeyArray<K>, key: &K) -> bool {
+        self.position_of(keys, key).is_some()
+    }
+
+    /// [`KeyArray::change_to`], by hash.
+    pub fn change_to(&mut self, keys: &mut KeyArray<K>, key: &K) -> Result<usize, KeyArrayError> {
+        let i = self.position_of(keys, key).ok_or(KeyArrayError::KeyNotFound)?;
+        keys.try_change(i)?;
+        Ok(i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_equal_key_wins() {
+        let mut ka = KeyArray::new(["a", "b", "a"]);
+        ka.change(1);
+        assert_eq!(ka.change_to(&"a"), Ok(0));
+        assert_eq!(ka.change_to(&"c"), Err(KeyArrayError::KeyNotFound));
+        assert_eq!((ka.position_of(&"a"), ka.contains(&"c")), (Some(0), false));
+
+        #[cfg(feature = "hash-index")]
+        {
+            let mut index = HashIndex::new();
+            assert_eq!(index.position_of(&ka, &"a"), Some(0));
+            ka.change(2);
+            ka.remove(0);
+            assert_eq!(index.change_to(&mut ka, &"a"), Ok(1));
+            assert!(!index.contains(&ka, &"z"));
+        }
+    }
+}
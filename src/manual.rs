@@ -0,0 +1,179 @@
+//! Manual changes that automatic ones must not undo.
+//!
+//! A controller may flip modes on its own, but when the user picks one by
+//! hand, that choice should stick for a while.
+//! [`change_manual`](KeyArray::change_manual) selects a key and starts an
+//! override; while it lasts, [`change_auto`](KeyArray::change_auto)
+//! requests are [`Suppressed`](Automatic::Suppressed) instead of applied.
+//! The override ends after the [`ManualHold`] set with
+//! [`set_manual_hold`](KeyArray::set_manual_hold), or when
+//! [`release_override`](KeyArray::release_override) is called; automatic
+//! requests made meanwhile are not replayed, so the controller's next one
+//! takes over. Plain [`change`](KeyArray::change) neither starts nor
+//! respects an override.
+//!
+//! ```
+//! use keyarray::manual::{Automatic, ManualHold};
+//! use keyarray::KeyArray;
+//! use std::time::{Duration, Instant};
+//!
+//! let mut hvac = KeyArray::new(["Heat", "Cool", "Fan"]);
+//! hvac.set_manual_hold(ManualHold::For(Duration::from_secs(3600)));
+//!
+//! let t0 = Instant::now();
+//! hvac.change_manual(2, t0)?;
+//! let later = t0 + Duration::from_secs(600);
+//! assert!(matches!(hvac.change_auto(1, later)?, Automatic::Suppressed));
+//! assert_eq!(hvac.override_remaining(later), Some(Duration::from_secs(3000)));
+//!
+//! let expired = t0 + Duration::from_secs(3600);
+//! assert!(matches!(hvac.change_auto(1, expired)?, Automatic::Changed(Some(_))));
+//! assert_eq!(hvac.current(), &"Cool");
+//! # Ok::<(), keyarray::KeyArrayError>(())
+//! ```
+
+use crate::error::KeyArrayError;
+use crate::event::ChangeEvent;
+use crate::id::KeyId;
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+use std::time::{Duration, Instant};
+
+/// Reason attached to the change event of a manual change.
+pub const MANUAL: &str = "manual";
+
+/// Reason attached to the change event of an applied automatic change.
+pub const AUTOMATIC: &str = "automatic";
+
+/// How long a manual change holds off automatic ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ManualHold {
+    /// Until [`release_override`](KeyArray::release_override).
+    #[default]
+    UntilReleased,
+    /// For this long, or until released.
+    For(Duration),
+}
+
+/// An override in force; from [`manual_override`](KeyArray::manual_override).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ManualOverride {
+    /// The key selected by hand.
+    pub id: KeyId,
+    pub since: Instant,
+    /// When it ends, `None` if it lasts until released.
+    pub until: Option<Instant>,
+}
+
+/// The outcome of [`change_auto`](KeyArray::change_auto).
+#[derive(Debug)]
+pub enum Automatic<K> {
+    /// No override was in force and the change was made; the event is
+    /// `None` if the key already was current.
+    Changed(Option<ChangeEvent<K>>),
+    /// A manual override is in force; nothing changed.
+    Suppressed,
+}
+
+// The hold setting and the override in force, if any.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Overrides {
+    hold: ManualHold,
+    active: Option<ManualOverride>,
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    pub fn manual_hold(&self) -> ManualHold {
+        self.overrides.hold
+    }
+
+    /// How long later manual changes hold off automatic ones. An override
+    /// already in force keeps its end.
+    pub fn set_manual_hold(&mut self, hold: ManualHold) {
+        self.overrides.hold = hold;
+    }
+
+    /// Select key `i` on the user's behalf and hold off automatic changes
+    /// from `now` on, restarting any override in force. Fails, changing
+    /// nothing, as [`try_change`](Self::try_change) does.
+    pub fn change_manual(
+        &mut self,
+        i: usize,
+        now: Instant,
+    ) -> Result<Option<ChangeEvent<K>>, KeyArrayError> {
+        let event = self.try_change_with_reason(i, MANUAL)?;
+        let until = match self.overrides.hold {
+            ManualHold::UntilReleased => None,
+            ManualHold::For(hold) => Some(now + hold),
+        };
+        self.overrides.active = Some(ManualOverride {
+            id: self.slots[self.idx].id,
+            since: now,
+            until,
+        });
+        Ok(event)
+    }
+
+    /// Select key `i` unless a manual override is in force at `now`. Fails
+    /// as [`try_change`](Self::try_change) does, unless suppressed.
+    pub fn change_auto(&mut self, i: usize, now: Instant) -> Result<Automatic<K>, KeyArrayError> {
+        if self.manual_override(now).is_some() {
+            return Ok(Automatic::Suppressed);
+        }
+        self.overrides.active = None;
+        self.try_change_with_reason(i, AUTOMATIC).map(Automatic::Changed)
+    }
+
+    /// The override in force at `now`, if any.
+    pub fn manual_override(&self, now: Instant) -> Option<ManualOverride> {
+        self.overrides
+            .active
+            .filter(|o| o.until.is_none_or(|until| now < until))
+    }
+
+    pub fn is_overridden(&self, now: Instant) -> bool {
+        self.manual_override(now).is_some()
+    }
+
+    /// How much longer the override in force at `now` lasts; `None` if
+    /// there is none or it lasts until released.
+    pub fn override_remaining(&self, now: Instant) -> Option<Duration> {
+        Some(self.manual_override(now)?.until? - now)
+    }
+
+    /// End the override, letting automatic changes through again. Returns
+    /// whether one was set.
+    pub fn release_override(&mut self) -> bool {
+        self.overrides.active.take().is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn override_holds_until_released() {
+        let mut ka = KeyArray::new(["auto", "a", "b"]);
+        let now = Instant::now();
+        assert!(matches!(ka.change_auto(1, now), Ok(Automatic::Changed(Some(_)))));
+        let ev = ka.change_manual(2, now).unwrap().unwrap();
+        assert_eq!((ev.previous, ev.reason.unwrap().as_str()), (1, MANUAL));
+        let far = now + Duration::from_secs(86_400);
+        assert!(matches!(ka.change_auto(0, far), Ok(Automatic::Suppressed)));
+        let held = ka.manual_override(far).unwrap();
+        assert_eq!((held.id, held.until), (ka.id_at(2).unwrap(), None));
+        assert_eq!(ka.override_remaining(far), None);
+
+        assert!(ka.release_override());
+        assert!(!ka.is_overridden(far));
+        assert_eq!(
+            ka.change_auto(7, far).unwrap_err(),
+            KeyArrayError::IndexOutOfBounds { index: 7, len: 3 }
+        );
+        assert!(ka.change_manual(7, far).is_err() && !ka.is_overridden(far));
+    }
+}
@@ -0,0 +1,200 @@
+//! How much memory a KeyArray holds on to.
+//!
+//! [`memory_footprint`](KeyArray::memory_footprint) estimates the heap bytes
+//! behind each part of an array: the key list, the data attached to keys,
+//! bookmarks, the transition table, undo history and the activity log. The
+//! keys' own heap allocations, such as a `String`'s text, are only known to
+//! the caller, so [`memory_footprint_with`](KeyArray::memory_footprint_with)
+//! takes a function measuring them. Storage shared copy-on-write, such as
+//! an undo state that has not diverged from the live keys, is counted once.
+//!
+//! ```
+//! # #[cfg(feature = "history")] {
+//! use keyarray::KeyArray;
+//!
+//! let mut ka = KeyArray::new(vec!["one".to_string(), "two".to_string()]);
+//! let before = ka.memory_footprint_with(|k| k.capacity());
+//! assert!(before.keys >= 2 * std::mem::size_of::<String>() + 6);
+//!
+//! ka.enable_history(64);
+//! ka.next();
+//! let after = ka.memory_footprint_with(|k| k.capacity());
+//! assert!(after.history > 0 && after.total() > before.total());
+//! # }
+//! ```
+
+use crate::{KeyArray, Slot};
+use std::collections::BTreeSet;
+use std::fmt::{Debug, Display};
+use std::mem::size_of;
+use std::sync::Arc;
+
+/// Estimated heap bytes held by a KeyArray, by part; from
+/// [`memory_footprint`](KeyArray::memory_footprint). Allocator overhead and
+/// the spare room in map nodes are not included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct MemoryReport {
+    /// The key list, plus whatever the keys allocate themselves if measured.
+    pub keys: usize,
+    /// Data attached to keys: ids, flags, UI hints, payloads.
+    pub metadata: usize,
+    pub bookmarks: usize,
+    pub transitions: usize,
+    /// Undo states and the trail of visited keys.
+    pub history: usize,
+    /// Changes recorded by [`track_activity`](KeyArray::track_activity).
+    pub activity: usize,
+}
+
+impl MemoryReport {
+    pub fn total(&self) -> usize {
+        self.keys + self.metadata + self.bookmarks + self.transitions + self.history + self.activity
+    }
+}
+
+// Measures key lists and slot tables, counting each shared one only once.
+pub(crate) struct Sizer<'a, K> {
+    key_heap: &'a mut dyn FnMut(&K) -> usize,
+    seen: BTreeSet<*const ()>,
+}
+
+impl<K> Sizer<'_, K> {
+    fn first_sight<T>(&mut self, shared: &Arc<T>) -> bool {
+        self.seen.insert(Arc::as_ptr(shared).cast())
+    }
+
+    pub(crate) fn keys(&mut self, keys: &Arc<Vec<K>>) -> usize {
+        if !self.first_sight(keys) {
+            return 0;
+        }
+        vec_bytes(keys) + keys.iter().map(&mut *self.key_heap).sum::<usize>()
+    }
+
+    pub(crate) fn slots(&mut self, slots: &Arc<Vec<Slot>>) -> usize {
+        if !self.first_sight(slots) {
+            return 0;
+        }
+        vec_bytes(slots) + slots.iter().map(slot_heap).sum::<usize>()
+    }
+}
+
+pub(crate) fn vec_bytes<T>(v: &Vec<T>) -> usize {
+    v.capacity() * size_of::<T>()
+}
+
+// What a slot allocates beyond itself.
+#[cfg_attr(
+    not(any(
+        feature = "ui",
+        feature = "provenance",
+        feature = "meta",
+        feature = "permission",
+        feature = "deprecation"
+    )),
+    allow(unused_variables)
+)]
+fn slot_heap(slot: &Slot) -> usize {
+    #[cfg(feature = "ui")]
+    let ui = slot.ui.as_ref().map_or(0, |ui| {
+        let text = |s: &Option<String>| s.as_ref().map_or(0, String::capacity);
+        text(&ui.icon) + text(&ui.tooltip) + text(&ui.badge)
+    });
+    #[cfg(not(feature = "ui"))]
+    let ui = 0;
+    #[cfg(feature = "provenance")]
+    let source = match &slot.source {
+        crate::provenance::Source::Plugin(name) => name.capacity(),
+        _ => 0,
+    };
+    #[cfg(not(feature = "provenance"))]
+    let source = 0;
+    #[cfg(feature = "meta")]
+    let meta = slot.meta.as_ref().map_or(0, |m| m.size());
+    #[cfg(not(feature = "meta"))]
+    let meta = 0;
+    #[cfg(feature = "permission")]
+    let permission = slot.permission.as_ref().map_or(0, String::capacity);
+    #[cfg(not(feature = "permission"))]
+    let permission = 0;
+    #[cfg(feature = "deprecation")]
+    let deprecation = slot.deprecation.as_ref().map_or(0, |d| {
+        std::mem::size_of_val(&**d) + d.message.capacity()
+    });
+    #[cfg(not(feature = "deprecation"))]
+    let deprecation = 0;
+    ui + source + meta + permission + deprecation
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Estimated heap bytes held by the array, not counting what the keys
+    /// allocate themselves.
+    pub fn memory_footprint(&self) -> MemoryReport {
+        self.memory_footprint_with(|_| 0)
+    }
+
+    /// Like [`memory_footprint`](Self::memory_footprint), adding
+    /// `key_heap(key)` bytes for every key kept, in the live list and in
+    /// undo states alike.
+    pub fn memory_footprint_with(&self, mut key_heap: impl FnMut(&K) -> usize) -> MemoryReport {
+        let mut sizer = Sizer {
+            key_heap: &mut key_heap,
+            seen: BTreeSet::new(),
+        };
+        let keys = sizer.keys(&self.keys);
+        let metadata = sizer.slots(&self.slots);
+        let bookmarks = self
+            .bookmarks
+            .keys()
+            .map(|name| name.capacity() + size_of::<(String, crate::id::KeyId)>())
+            .sum();
+        #[cfg(feature = "history")]
+        let history = self.history.as_ref().map_or(0, |h| h.heap_bytes(&mut sizer));
+        #[cfg(not(feature = "history"))]
+        let history = 0;
+        #[cfg(feature = "transitions")]
+        let transitions = self.transitions.heap_bytes();
+        #[cfg(not(feature = "transitions"))]
+        let transitions = 0;
+        #[cfg(feature = "activity")]
+        let activity = self.activity.as_ref().map_or(0, |a| a.heap_bytes());
+        #[cfg(not(feature = "activity"))]
+        let activity = 0;
+        MemoryReport {
+            keys,
+            metadata,
+            bookmarks,
+            transitions,
+            history,
+            activity,
+        }
+    }
+}
+
+#[cfg(all(test, feature = "history", feature = "meta"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_storage_is_counted_once() {
+        let mut ka = KeyArray::new(["a", "b", "c"]);
+        let plain = ka.memory_footprint();
+        assert_eq!(plain.keys, 3 * size_of::<&str>());
+        assert_eq!(plain.bookmarks + plain.history + plain.activity, 0);
+
+        // the only undo state shares the live keys
+        ka.enable_history(8);
+        let recorded = ka.memory_footprint();
+        assert_eq!((recorded.keys, recorded.metadata), (plain.keys, plain.metadata));
+        ka.push("d");
+        assert!(ka.memory_footprint().history >= plain.keys + plain.metadata);
+
+        ka.set_meta(0, [0u8; 64]);
+        ka.bookmark("start");
+        let report = ka.memory_footprint_with(|k| k.len());
+        assert!(report.metadata >= recorded.metadata + 64);
+        assert!(report.bookmarks >= "start".len());
+    }
+}
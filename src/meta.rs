@@ -0,0 +1,116 @@
+//! Arbitrary payloads attached to keys.
+//!
+//! A payload set with [`set_meta`](KeyArray::set_meta) lives in the key's
+//! slot, so inserts, removals and sorts move it along with the key instead
+//! of a parallel `Vec` drifting out of step. Any `'static + Send + Sync`
+//! type will do; reading it back names the type, and a key whose payload
+//! has another type reads as having none.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//!
+//! let mut fan = KeyArray::new(["Low", "High"]);
+//! fan.set_meta(0, 20u8);
+//! fan.set_meta(1, 90u8);
+//! fan.insert(0, "Off");
+//! fan.change(2);
+//! assert_eq!(fan.current_meta::<u8>(), Some(&90));
+//! assert_eq!(fan.meta::<u8>(0), None);
+//! ```
+//!
+//! Payloads are shared, not copied, between clones of a KeyArray.
+
+use crate::id::KeyId;
+use crate::KeyArray;
+use std::any::Any;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::sync::Arc;
+
+// Compared by identity, since payloads need not implement `PartialEq`.
+#[derive(Clone)]
+pub(crate) struct Meta(Arc<dyn Any + Send + Sync>);
+
+impl PartialEq for Meta {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Meta {
+    // bytes of the payload itself
+    pub(crate) fn size(&self) -> usize {
+        std::mem::size_of_val(&*self.0)
+    }
+}
+
+impl Debug for Meta {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str("Meta(..)")
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Attach `meta` to key `i`, replacing any payload it had. Panics if
+    /// `i` is out of bounds.
+    pub fn set_meta<V: Any + Send + Sync>(&mut self, i: usize, meta: V) {
+        assert!(
+            i < self.keys.len(),
+            "KeyArray::set_meta: index {} out of bounds",
+            i
+        );
+        Arc::make_mut(&mut self.slots)[i].meta = Some(Meta(Arc::new(meta)));
+        self.touch();
+    }
+
+    /// [`push`](Self::push) a key with `meta` attached.
+    pub fn push_with_meta<V: Any + Send + Sync>(&mut self, key: K, meta: V) -> KeyId {
+        let id = self.push(key);
+        let last = self.len() - 1;
+        Arc::make_mut(&mut self.slots)[last].meta = Some(Meta(Arc::new(meta)));
+        id
+    }
+
+    /// The payload of key `i`, if it has one of type `V`.
+    pub fn meta<V: Any>(&self, i: usize) -> Option<&V> {
+        self.slots.get(i)?.meta.as_ref()?.0.downcast_ref()
+    }
+
+    /// The payload of the current key, if it has one of type `V`.
+    pub fn current_meta<V: Any>(&self) -> Option<&V> {
+        self.meta(self.idx)
+    }
+
+    /// Detach the payload of key `i`, whatever its type. Returns whether
+    /// there was one.
+    pub fn clear_meta(&mut self, i: usize) -> bool {
+        if self.slots.get(i).is_none_or(|s| s.meta.is_none()) {
+            return false;
+        }
+        Arc::make_mut(&mut self.slots)[i].meta = None;
+        self.touch();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meta_follows_its_key() {
+        let mut ka = KeyArray::new(["b", "a"]);
+        ka.set_meta(0, String::from("bee"));
+        ka.push_with_meta("c", 3i32);
+        ka.sort_by(|x, y| x.cmp(y));
+        assert_eq!(ka.meta::<String>(1).map(String::as_str), Some("bee"));
+        assert_eq!(ka.meta::<i32>(1), None);
+        assert_eq!(ka.meta::<i32>(2), Some(&3));
+
+        let copy = ka.clone();
+        assert!(ka.clear_meta(1) && !ka.clear_meta(1));
+        assert!(ka.meta::<String>(1).is_none() && copy.meta::<String>(1).is_some());
+    }
+}
@@ -0,0 +1,140 @@
+//! Keyboard mnemonics: one letter per key, as in `&File`.
+//!
+//! [`assign_mnemonics`](KeyArray::assign_mnemonics) gives each key the
+//! first letter or digit of its label that no other key has taken, and
+//! [`handle_key`](KeyArray::handle_key) selects the key with a pressed
+//! letter. Older keys choose first, by [`KeyId`](crate::id::KeyId), so
+//! adding, removing or reordering keys never takes a letter away from a
+//! key that keeps its label. Hidden keys get none.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//!
+//! let mut menu = KeyArray::new(["Save", "Save As", "Settings"]);
+//! let mnemonics = menu.assign_mnemonics();
+//! let letters: Vec<_> = (0..3).map(|i| mnemonics.get(i).map(|m| m.letter)).collect();
+//! assert_eq!(letters, [Some('s'), Some('a'), Some('e')]);
+//! assert_eq!(mnemonics.get(1).unwrap().split("Save As"), ("S", "a", "ve As"));
+//!
+//! assert_eq!(menu.handle_key('E'), Some(2));
+//! assert_eq!(menu.current(), &"Settings");
+//! ```
+
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+
+/// A key's mnemonic letter and where it is in the label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Mnemonic {
+    /// Lowercase.
+    pub letter: char,
+    /// Byte offset of the letter in the label, for underlining it.
+    pub offset: usize,
+}
+
+impl Mnemonic {
+    /// `label` around the letter: before, the letter as written, after.
+    pub fn split<'a>(&self, label: &'a str) -> (&'a str, &'a str, &'a str) {
+        let (before, rest) = label.split_at(self.offset);
+        let len = rest.chars().next().map_or(0, char::len_utf8);
+        let (letter, after) = rest.split_at(len);
+        (before, letter, after)
+    }
+}
+
+/// The mnemonic of every key, by index; from
+/// [`assign_mnemonics`](KeyArray::assign_mnemonics).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Mnemonics {
+    entries: Vec<Option<Mnemonic>>,
+}
+
+impl Mnemonics {
+    /// The mnemonic of key `i`; `None` if every letter of its label was
+    /// taken, it is hidden, or `i` is out of bounds.
+    pub fn get(&self, i: usize) -> Option<Mnemonic> {
+        self.entries.get(i).copied().flatten()
+    }
+
+    /// The key with mnemonic `letter`, in either case.
+    pub fn index_of(&self, letter: char) -> Option<usize> {
+        let letter = lowercase(letter);
+        self.entries
+            .iter()
+            .position(|m| m.is_some_and(|m| m.letter == letter))
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Give each visible key the first letter or digit of its `Display`
+    /// label not taken by an older key.
+    pub fn assign_mnemonics(&self) -> Mnemonics {
+        let mut entries = vec![None; self.len()];
+        let mut order: Vec<usize> =
+            (0..self.len()).filter(|&i| !self.slots[i].is_hidden()).collect();
+        order.sort_by_key(|&i| self.slots[i].id);
+        let mut taken = Vec::new();
+        for i in order {
+            let label = self.keys[i].to_string();
+            let free = label
+                .char_indices()
+                .filter(|(_, c)| c.is_alphanumeric())
+                .map(|(offset, c)| Mnemonic {
+                    letter: lowercase(c),
+                    offset,
+                })
+                .find(|m| !taken.contains(&m.letter));
+            if let Some(m) = free {
+                taken.push(m.letter);
+                entries[i] = Some(m);
+            }
+        }
+        Mnemonics { entries }
+    }
+
+    /// Select the key whose mnemonic is `letter`, in either case. Returns
+    /// the index now current, which is its replacement's if it is deprecated
+    /// under strict mode, or `None` if no key has the letter or that key is
+    /// disabled.
+    pub fn handle_key(&mut self, letter: char) -> Option<usize> {
+        let i = self.assign_mnemonics().index_of(letter)?;
+        self.try_change(i).ok()?;
+        Some(self.current_index())
+    }
+}
+
+// A single lowercase char; letters whose lowercase form is longer keep
+// their first char.
+fn lowercase(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn older_keys_keep_their_letters() {
+        let mut ka = KeyArray::new(["Open", "Close"]);
+        ka.insert(0, "Copy");
+        ka.push("...");
+        let m = ka.assign_mnemonics();
+        let letters: Vec<_> = (0..4).map(|i| m.get(i).map(|m| m.letter)).collect();
+        assert_eq!(letters, [Some('p'), Some('o'), Some('c'), None]);
+        assert_eq!(m.index_of('C'), Some(2));
+
+        #[cfg(all(feature = "disable", feature = "tombstone"))]
+        {
+            ka.disable(2);
+            assert_eq!(ka.handle_key('c'), None);
+            // hiding "Open" frees its letter for "Copy"
+            ka.soft_remove(1);
+            assert_eq!(ka.assign_mnemonics().get(1), None);
+            assert_eq!(ka.handle_key('p'), None);
+            assert_eq!(ka.handle_key('O'), Some(0));
+        }
+    }
+}
@@ -0,0 +1,121 @@
+//! Elm-style messages: every mutation as a value routed through
+//! [`KeyArray::update`].
+//!
+//! ```
+//! use keyarray::{KeyArray, Msg};
+//!
+//! let mut ka = KeyArray::new(["On", "Off", "Auto"]);
+//! let ev = ka.update(Msg::Next).unwrap();
+//! assert_eq!((ev.previous, ev.current, ev.key), (0, 1, "Off"));
+//! assert!(ka.update(Msg::Select(1)).is_none());
+//! ```
+
+use crate::error::KeyArrayError;
+use crate::event::{ChangeEvent, ChangeReason};
+use crate::{check_index, KeyArray};
+use std::fmt::{Debug, Display};
+
+/// A single mutation of a KeyArray.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Msg<K> {
+    Next,
+    Prev,
+    Select(usize),
+    Insert(usize, K),
+    Remove(usize),
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Apply `msg`, returning a [`ChangeEvent`] if the current index or the
+    /// current key changed. Panics under the same conditions as the method
+    /// each message maps to.
+    pub fn update(&mut self, msg: Msg<K>) -> Option<ChangeEvent<K>> {
+        let previous = self.idx;
+        let before = self.keys.get(previous).cloned();
+        match msg {
+            Msg::Next => self.next(),
+            Msg::Prev => self.prev(),
+            Msg::Select(i) => self.change(i),
+            Msg::Insert(i, key) => {
+                self.insert(i, key);
+            }
+            Msg::Remove(i) => {
+                self.remove(i);
+            }
+        }
+        let after = self.keys.get(self.idx)?;
+        if self.idx == previous && before.as_ref() == Some(after) {
+            return None;
+        }
+        Some(ChangeEvent {
+            previous,
+            current: self.idx,
+            key: after.clone(),
+            reason: None,
+        })
+    }
+
+    /// [`update`](Self::update), failing with [`KeyArrayError`] instead of
+    /// panicking.
+    pub fn try_update(&mut self, msg: Msg<K>) -> Result<Option<ChangeEvent<K>>, KeyArrayError> {
+        let len = self.len();
+        match msg {
+            Msg::Select(i) => {
+                check_index(i, len)?;
+                self.check_selectable(i)?;
+            }
+            Msg::Remove(i) => check_index(i, len)?,
+            Msg::Insert(i, _) if i > len => {
+                return Err(KeyArrayError::IndexOutOfBounds { index: i, len });
+            }
+            _ => {}
+        }
+        Ok(self.update(msg))
+    }
+
+    /// [`update`](Self::update), attaching `reason` to the event.
+    pub fn update_with_reason(
+        &mut self,
+        msg: Msg<K>,
+        reason: impl Into<ChangeReason>,
+    ) -> Option<ChangeEvent<K>> {
+        let mut ev = self.update(msg)?;
+        ev.reason = Some(reason.into());
+        Some(ev)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn structural_messages() {
+        let mut ka = KeyArray::new_with(["A", "B", "C"], 1);
+        // inserting in front shifts the index but keeps the key
+        let ev = ka.update(Msg::Insert(0, "Z")).unwrap();
+        assert_eq!((ev.previous, ev.current, ev.key), (1, 2, "B"));
+        assert_eq!(ka.update(Msg::Remove(3)), None);
+        let ev = ka.update(Msg::Prev).unwrap();
+        assert_eq!(ev.key, "A");
+        ka.update(Msg::Remove(0));
+        ka.update(Msg::Remove(0));
+        ka.update(Msg::Remove(0));
+        assert!(ka.is_empty());
+        let oob = KeyArrayError::IndexOutOfBounds { index: 1, len: 0 };
+        assert_eq!(ka.try_update(Msg::Insert(1, "A")), Err(oob));
+
+        #[cfg(feature = "disable")]
+        {
+            ka.push("A");
+            ka.push("B");
+            ka.disable(1);
+            let disabled = KeyArrayError::Disabled { index: 1 };
+            assert_eq!(ka.try_update(Msg::Select(1)), Err(disabled));
+        }
+    }
+}
@@ -0,0 +1,536 @@
+//! Checkbox-style sibling of KeyArray: any number of keys may be pressed at
+//! once, subject to a [`PressPolicy`].
+//!
+//! Every mutation goes through one validation step and reports a single
+//! coalesced [`PressDelta`], so bulk verbs like [`MultiKeyArray::select_all`]
+//! cost one check and one event instead of one per key.
+//!
+//! ```
+//! use keyarray::multi::{MultiKeyArray, PressPolicy};
+//!
+//! let mut toppings = MultiKeyArray::new(["Cheese", "Ham", "Olives", "Basil"]);
+//! toppings.select_range(1..3).unwrap();
+//! let delta = toppings.invert().unwrap().unwrap();
+//! assert_eq!(delta.pressed, vec![0, 3]);
+//! assert_eq!(delta.released, vec![1, 2]);
+//!
+//! toppings.set_policy(PressPolicy::AtLeastOne).unwrap();
+//! assert!(toppings.deselect_all().is_err());
+//! ```
+
+use crate::error::KeyArrayError;
+use crate::rules::{Rule, RuleViolation};
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::ops::{Bound, RangeBounds};
+
+/// How many keys may be pressed at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PressPolicy {
+    #[default]
+    AnyNumber,
+    AtLeastOne,
+    ExactlyN(usize),
+}
+
+impl PressPolicy {
+    /// Whether `count` pressed keys satisfy the policy.
+    pub fn allows(self, count: usize) -> bool {
+        match self {
+            PressPolicy::AnyNumber => true,
+            PressPolicy::AtLeastOne => count >= 1,
+            PressPolicy::ExactlyN(n) => count == n,
+        }
+    }
+}
+
+/// A mutation was rejected because it would break the press policy.
+/// The array is left unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PolicyViolation {
+    pub policy: PressPolicy,
+    /// How many keys the rejected mutation would have left pressed.
+    pub pressed: usize,
+}
+
+impl Display for PolicyViolation {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        write!(
+            f,
+            "{:?} does not allow {} pressed key(s)",
+            self.policy, self.pressed
+        )
+    }
+}
+
+impl std::error::Error for PolicyViolation {}
+
+/// Why a mutation was rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Violation<K> {
+    Policy(PolicyViolation),
+    Rule(RuleViolation<K>),
+    /// An index was out of bounds; only from the `try_*` methods, the
+    /// others panic.
+    Index(KeyArrayError),
+}
+
+impl<K: Debug> Display for Violation<K> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Violation::Policy(v) => Display::fmt(v, f),
+            Violation::Rule(v) => Display::fmt(v, f),
+            Violation::Index(e) => Display::fmt(e, f),
+        }
+    }
+}
+
+impl<K: Debug> std::error::Error for Violation<K> {}
+
+impl<K> From<PolicyViolation> for Violation<K> {
+    fn from(v: PolicyViolation) -> Self {
+        Violation::Policy(v)
+    }
+}
+
+impl<K> From<KeyArrayError> for Violation<K> {
+    fn from(e: KeyArrayError) -> Self {
+        Violation::Index(e)
+    }
+}
+
+/// Net effect of one mutation, in ascending index order.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PressDelta {
+    pub pressed: Vec<usize>,
+    pub released: Vec<usize>,
+}
+
+pub struct MultiKeyArray<K> {
+    keys: Vec<K>,
+    pressed: Vec<bool>,
+    policy: PressPolicy,
+    rules: Vec<Rule<K>>,
+}
+
+impl<K> MultiKeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Create with nothing pressed and [`PressPolicy::AnyNumber`].
+    /// Panics if empty.
+    pub fn new(keys: impl IntoIterator<Item = K>) -> Self {
+        let keys: Vec<K> = keys.into_iter().collect();
+        assert!(
+            !keys.is_empty(),
+            "MultiKeyArray::new: must supply at least one key"
+        );
+        let pressed = vec![false; keys.len()];
+        MultiKeyArray {
+            keys,
+            pressed,
+            policy: PressPolicy::AnyNumber,
+            rules: Vec::new(),
+        }
+    }
+
+    /// Get a slice of all keys.
+    pub fn keys(&self) -> &[K] {
+        &self.keys
+    }
+
+    /// Number of keys.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns true if there are no keys.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Append a released key.
+    pub fn push(&mut self, key: K) {
+        self.keys.push(key);
+        self.pressed.push(false);
+    }
+
+    /// Insert a released key at position `i`. Panics if `i > len`.
+    pub fn insert(&mut self, i: usize, key: K) {
+        self.try_insert(i, key)
+            .unwrap_or_else(|e| panic!("MultiKeyArray::insert: {}", e))
+    }
+
+    /// [`insert`](Self::insert), returning an error instead of panicking.
+    pub fn try_insert(&mut self, i: usize, key: K) -> Result<(), KeyArrayError> {
+        let len = self.keys.len();
+        if i > len {
+            return Err(KeyArrayError::IndexOutOfBounds { index: i, len });
+        }
+        self.keys.insert(i, key);
+        self.pressed.insert(i, false);
+        Ok(())
+    }
+
+    /// Remove and return key `i`. Fails, removing nothing, if the presses
+    /// left behind would break the policy or a rule. Panics if out of
+    /// bounds.
+    pub fn remove(&mut self, i: usize) -> Result<K, Violation<K>> {
+        self.check_index(i, "remove");
+        self.try_remove(i)
+    }
+
+    /// [`remove`](Self::remove), failing with [`Violation::Index`] instead
+    /// of panicking.
+    pub fn try_remove(&mut self, i: usize) -> Result<K, Violation<K>> {
+        let len = self.keys.len();
+        if i >= len {
+            return Err(KeyArrayError::IndexOutOfBounds { index: i, len }.into());
+        }
+        let mut keys = self.keys.clone();
+        let mut pressed = self.pressed.clone();
+        keys.remove(i);
+        pressed.remove(i);
+        self.check_presses(&keys, &pressed)?;
+        self.pressed = pressed;
+        Ok(self.keys.remove(i))
+    }
+
+    /// Keep only the keys for which `keep` returns true, with their
+    /// presses, and return how many were removed. Fails, removing nothing,
+    /// as [`remove`](Self::remove) does.
+    pub fn retain<F>(&mut self, mut keep: F) -> Result<usize, Violation<K>>
+    where
+        F: FnMut(&K) -> bool,
+    {
+        let keep: Vec<bool> = self.keys.iter().map(&mut keep).collect();
+        self.retain_mask(&keep)
+    }
+
+    /// Remove every key equal to an earlier one and return how many were
+    /// removed. A removed duplicate's press is dropped, so this fails,
+    /// removing nothing, as [`remove`](Self::remove) does.
+    pub fn dedup(&mut self) -> Result<usize, Violation<K>> {
+        let keep: Vec<bool> = (0..self.len())
+            .map(|i| !self.keys[..i].contains(&self.keys[i]))
+            .collect();
+        self.retain_mask(&keep)
+    }
+
+    /// Stable-sort the keys with `compare`; presses stay with their keys.
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&K, &K) -> Ordering,
+    {
+        self.reorder(|tagged| tagged.sort_by(|a, b| compare(&a.0, &b.0)));
+    }
+
+    /// Stable-sort by `f(key)`, calling `f` once per key; presses stay with
+    /// their keys.
+    pub fn sort_by_cached_key<T, F>(&mut self, mut f: F)
+    where
+        T: Ord,
+        F: FnMut(&K) -> T,
+    {
+        self.reorder(|tagged| tagged.sort_by_cached_key(|t| f(&t.0)));
+    }
+
+    /// Swap keys `i` and `j` with their presses. Panics if either is out
+    /// of bounds.
+    pub fn swap(&mut self, i: usize, j: usize) {
+        self.check_index(i, "swap");
+        self.check_index(j, "swap");
+        self.keys.swap(i, j);
+        self.pressed.swap(i, j);
+    }
+
+    /// Move the key at `from`, with its press, so that it ends up at `to`,
+    /// shifting the keys in between. Panics if either is out of bounds.
+    pub fn move_key(&mut self, from: usize, to: usize) {
+        self.check_index(from, "move_key");
+        self.check_index(to, "move_key");
+        let key = self.keys.remove(from);
+        self.keys.insert(to, key);
+        let pressed = self.pressed.remove(from);
+        self.pressed.insert(to, pressed);
+    }
+
+    /// The active press policy.
+    pub fn policy(&self) -> PressPolicy {
+        self.policy
+    }
+
+    /// Switch policy. Fails, keeping the old policy, if the current presses
+    /// do not satisfy the new one.
+    pub fn set_policy(&mut self, policy: PressPolicy) -> Result<(), PolicyViolation> {
+        let count = self.pressed_count();
+        if !policy.allows(count) {
+            return Err(PolicyViolation {
+                policy,
+                pressed: count,
+            });
+        }
+        self.policy = policy;
+        Ok(())
+    }
+
+    /// Whether key `i` is pressed. Panics if out of bounds.
+    pub fn is_pressed(&self, i: usize) -> bool {
+        self.pressed[i]
+    }
+
+    /// Number of pressed keys.
+    pub fn pressed_count(&self) -> usize {
+        self.pressed.iter().filter(|&&p| p).count()
+    }
+
+    /// Indices of the pressed keys, ascending.
+    pub fn pressed_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.pressed
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &p)| p.then_some(i))
+    }
+
+    /// The pressed keys, in key order.
+    pub fn pressed(&self) -> impl Iterator<Item = &K> + '_ {
+        self.pressed_indices().map(move |i| &self.keys[i])
+    }
+
+    /// Press key `i`. Panics if out of bounds.
+    pub fn press(&mut self, i: usize) -> Result<Option<PressDelta>, Violation<K>> {
+        self.set(i, true, "press")
+    }
+
+    /// Release key `i`. Panics if out of bounds.
+    pub fn release(&mut self, i: usize) -> Result<Option<PressDelta>, Violation<K>> {
+        self.set(i, false, "release")
+    }
+
+    /// Flip key `i`. Panics if out of bounds.
+    pub fn toggle(&mut self, i: usize) -> Result<Option<PressDelta>, Violation<K>> {
+        self.check_index(i, "toggle");
+        let value = !self.pressed[i];
+        self.set(i, value, "toggle")
+    }
+
+    /// Press every key.
+    pub fn select_all(&mut self) -> Result<Option<PressDelta>, Violation<K>> {
+        self.commit(vec![true; self.len()])
+    }
+
+    /// Release every key.
+    pub fn deselect_all(&mut self) -> Result<Option<PressDelta>, Violation<K>> {
+        self.commit(vec![false; self.len()])
+    }
+
+    /// Flip every key.
+    pub fn invert(&mut self) -> Result<Option<PressDelta>, Violation<K>> {
+        let next = self.pressed.iter().map(|&p| !p).collect();
+        self.commit(next)
+    }
+
+    /// Press every key in `range`, leaving the others as they are.
+    /// Panics if the range reaches past the last key.
+    pub fn select_range<R>(&mut self, range: R) -> Result<Option<PressDelta>, Violation<K>>
+    where
+        R: RangeBounds<usize>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(&s) => s,
+            Bound::Excluded(&s) => s + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&e) => e + 1,
+            Bound::Excluded(&e) => e,
+            Bound::Unbounded => self.len(),
+        };
+        assert!(
+            start <= end && end <= self.len(),
+            "MultiKeyArray::select_range: range {}..{} out of bounds",
+            start,
+            end
+        );
+        let mut next = self.pressed.clone();
+        next[start..end].fill(true);
+        self.commit(next)
+    }
+
+    /// Add a constraint between keys. Fails, without adding it, if the
+    /// current presses already break it.
+    pub fn add_rule(&mut self, rule: Rule<K>) -> Result<(), RuleViolation<K>> {
+        if !rule.is_satisfied(&self.keys, &self.pressed) {
+            return Err(RuleViolation { rule });
+        }
+        self.rules.push(rule);
+        Ok(())
+    }
+
+    /// The registered constraints, in the order they were added.
+    pub fn rules(&self) -> &[Rule<K>] {
+        &self.rules
+    }
+
+    /// Drop every constraint.
+    pub fn clear_rules(&mut self) {
+        self.rules.clear();
+    }
+
+    // Whether `keys` with `pressed` satisfy the policy and every rule.
+    fn check_presses(&self, keys: &[K], pressed: &[bool]) -> Result<(), Violation<K>> {
+        let count = pressed.iter().filter(|&&p| p).count();
+        if !self.policy.allows(count) {
+            return Err(Violation::Policy(PolicyViolation {
+                policy: self.policy,
+                pressed: count,
+            }));
+        }
+        self.check_rules(keys, pressed).map_err(Violation::Rule)
+    }
+
+    // Drop every key whose `keep` flag is false, if what is left passes
+    // `check_presses`.
+    fn retain_mask(&mut self, keep: &[bool]) -> Result<usize, Violation<K>> {
+        let removed = keep.iter().filter(|k| !**k).count();
+        if removed == 0 {
+            return Ok(0);
+        }
+        let kept = |i: &usize| keep[*i];
+        let keys: Vec<K> = (0..self.len()).filter(kept).map(|i| self.keys[i].clone()).collect();
+        let pressed: Vec<bool> = (0..self.len()).filter(kept).map(|i| self.pressed[i]).collect();
+        self.check_presses(&keys, &pressed)?;
+        self.keys = keys;
+        self.pressed = pressed;
+        Ok(removed)
+    }
+
+    // Hand every key with its press to `sort`, then store them back in the
+    // new order.
+    fn reorder(&mut self, sort: impl FnOnce(&mut Vec<(K, bool)>)) {
+        let mut tagged: Vec<(K, bool)> = self.keys.drain(..).zip(self.pressed.drain(..)).collect();
+        sort(&mut tagged);
+        (self.keys, self.pressed) = tagged.into_iter().unzip();
+    }
+
+    fn check_rules(&self, keys: &[K], pressed: &[bool]) -> Result<(), RuleViolation<K>> {
+        match self.rules.iter().find(|r| !r.is_satisfied(keys, pressed)) {
+            Some(rule) => Err(RuleViolation { rule: rule.clone() }),
+            None => Ok(()),
+        }
+    }
+
+    fn check_index(&self, i: usize, op: &str) {
+        assert!(
+            i < self.keys.len(),
+            "MultiKeyArray::{}: index {} out of bounds",
+            op,
+            i
+        );
+    }
+
+    fn set(
+        &mut self,
+        i: usize,
+        value: bool,
+        op: &str,
+    ) -> Result<Option<PressDelta>, Violation<K>> {
+        self.check_index(i, op);
+        let mut next = self.pressed.clone();
+        next[i] = value;
+        self.commit(next)
+    }
+
+    // The single point every press-state mutation goes through.
+    fn commit(&mut self, next: Vec<bool>) -> Result<Option<PressDelta>, Violation<K>> {
+        self.check_presses(&self.keys, &next)?;
+        let mut delta = PressDelta::default();
+        for (i, (&old, &new)) in self.pressed.iter().zip(&next).enumerate() {
+            match (old, new) {
+                (false, true) => delta.pressed.push(i),
+                (true, false) => delta.released.push(i),
+                _ => {}
+            }
+        }
+        self.pressed = next;
+        if delta.pressed.is_empty() && delta.released.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(delta))
+        }
+    }
+}
+
+impl<K> Display for MultiKeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let pressed: Vec<usize> = self.pressed_indices().collect();
+        write!(f, "keys={:?}, pressed={:?}", self.keys, pressed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bulk_verbs_coalesce() {
+        let mut m = MultiKeyArray::new(["A", "B", "C"]);
+        let delta = m.select_all().unwrap().unwrap();
+        assert_eq!(delta.pressed, vec![0, 1, 2]);
+        assert_eq!(m.select_range(..).unwrap(), None);
+        m.toggle(1).unwrap();
+        assert_eq!(m.pressed().collect::<Vec<_>>(), vec![&"A", &"C"]);
+        assert_eq!(format!("{}", m), r#"keys=["A", "B", "C"], pressed=[0, 2]"#);
+    }
+
+    #[test]
+    fn policy_rejects_without_side_effects() {
+        let mut m = MultiKeyArray::new(["A", "B", "C"]);
+        m.press(0).unwrap();
+        m.set_policy(PressPolicy::ExactlyN(1)).unwrap();
+        let err = m.select_range(1..=2).unwrap_err();
+        assert!(matches!(err, Violation::Policy(PolicyViolation { pressed: 3, .. })));
+        assert_eq!(m.pressed_indices().collect::<Vec<_>>(), vec![0]);
+        assert!(m.set_policy(PressPolicy::ExactlyN(2)).is_err());
+    }
+
+    #[test]
+    fn edits_keep_presses_aligned() {
+        let mut m = MultiKeyArray::new(["B", "D"]);
+        m.press(1).unwrap();
+        m.insert(0, "A");
+        m.push("E");
+        assert_eq!(m.pressed().collect::<Vec<_>>(), vec![&"D"]);
+        m.set_policy(PressPolicy::AtLeastOne).unwrap();
+        assert!(matches!(m.remove(2), Err(Violation::Policy(_))));
+        assert_eq!(m.remove(0).unwrap(), "A");
+        assert_eq!((m.keys(), m.pressed_count()), (&["B", "D", "E"][..], 1));
+        assert!(matches!(m.try_remove(3), Err(Violation::Index(_))));
+        assert!(m.try_insert(4, "F").is_err());
+    }
+
+    #[test]
+    fn reorders_and_retain_carry_presses() {
+        let mut m = MultiKeyArray::new(["C", "A", "B", "A"]);
+        m.press(0).unwrap();
+        m.press(3).unwrap();
+        m.sort_by(|a, b| a.cmp(b));
+        assert_eq!(m.keys(), &["A", "A", "B", "C"]);
+        assert_eq!(m.pressed_indices().collect::<Vec<_>>(), vec![1, 3]);
+        m.swap(0, 3);
+        m.move_key(0, 2);
+        assert_eq!(m.pressed().collect::<Vec<_>>(), vec![&"A", &"C"]);
+
+        m.set_policy(PressPolicy::ExactlyN(2)).unwrap();
+        assert_eq!(m.dedup(), Ok(1));
+        assert_eq!(m.retain(|k| *k != "B"), Ok(1));
+        assert!(matches!(m.retain(|k| *k != "C"), Err(Violation::Policy(_))));
+        assert_eq!(m.keys(), &["A", "C"]);
+    }
+}
@@ -0,0 +1,82 @@
+//! Selecting string keys by name.
+//!
+//! With the `unicode` feature, both sides can be normalized before comparing,
+//! so "café" typed with a combining accent still matches a precomposed key.
+
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+#[cfg(feature = "unicode")]
+use unicode_normalization::UnicodeNormalization;
+
+/// How names are compared against keys. Non-exhaustive, as the normalizing
+/// variants only exist with the `unicode` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum Normalization {
+    /// Compare code points as-is.
+    #[default]
+    Exact,
+    /// Canonical composition (NFC): combining sequences match precomposed
+    /// characters.
+    #[cfg(feature = "unicode")]
+    Nfc,
+    /// Compatibility composition (NFKC): additionally folds ligatures,
+    /// full-width forms, superscripts, etc.
+    #[cfg(feature = "unicode")]
+    Nfkc,
+}
+
+impl Normalization {
+    /// Whether `a` and `b` are equal under this normalization.
+    pub fn matches(self, a: &str, b: &str) -> bool {
+        match self {
+            Normalization::Exact => a == b,
+            #[cfg(feature = "unicode")]
+            Normalization::Nfc => a.nfc().eq(b.nfc()),
+            #[cfg(feature = "unicode")]
+            Normalization::Nfkc => a.nfkc().eq(b.nfkc()),
+        }
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display + AsRef<str>,
+{
+    /// Index of the first key equal to `name` under `norm`.
+    pub fn find_name(&self, name: &str, norm: Normalization) -> Option<usize> {
+        self.keys().iter().position(|k| norm.matches(k.as_ref(), name))
+    }
+
+    /// Make the key matching `name` current and return the index now
+    /// current, which is its replacement's if it is deprecated under strict
+    /// mode. Leaves the selection untouched and returns `None` if nothing matches
+    /// or the match is disabled.
+    pub fn change_to_name(&mut self, name: &str, norm: Normalization) -> Option<usize> {
+        let i = self.find_name(name, norm)?;
+        self.try_change(i).ok()?;
+        Some(self.current_index())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_match() {
+        let mut ka = KeyArray::new(["tea", "café"]);
+        assert_eq!(ka.change_to_name("café", Normalization::Exact), Some(1));
+        assert_eq!(ka.find_name("cafe\u{301}", Normalization::Exact), None);
+        assert_eq!(ka.current(), &"café");
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn normalized_match() {
+        let ka = KeyArray::new(["tea", "caf\u{e9}", "ﬁle"]);
+        assert_eq!(ka.find_name("cafe\u{301}", Normalization::Nfc), Some(1));
+        assert_eq!(ka.find_name("file", Normalization::Nfc), None);
+        assert_eq!(ka.find_name("file", Normalization::Nfkc), Some(2));
+    }
+}
@@ -0,0 +1,608 @@
+//! Callbacks run whenever the selection changes.
+//!
+//! Observers are told about every change of the current index or of the
+//! key at it, whichever method caused it (`change`, `next`, `remove`,
+//! sorts, batches, ...), so nothing has to poll `current_index()`.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//! use std::sync::mpsc;
+//!
+//! let (tx, rx) = mpsc::channel();
+//! let mut toggle = KeyArray::new(["Off", "On"]);
+//! let sub = toggle.on_change(move |old, new, key| tx.send((old, new, *key)).unwrap());
+//! toggle.next();
+//! toggle.unsubscribe(sub);
+//! toggle.next();
+//! assert_eq!(rx.try_iter().collect::<Vec<_>>(), [(0, 1, "On")]);
+//! ```
+//!
+//! Slow observers, such as one saving to disk, can be protected from change
+//! storms with [`set_notification_batching`](KeyArray::set_notification_batching):
+//! changes within the window are then coalesced into one notification, sent
+//! by the first change once the window has passed or by
+//! [`poll_notifications`](KeyArray::poll_notifications).
+//! [`on_change_batch`](KeyArray::on_change_batch) observers also see every
+//! key passed through on the way.
+//!
+//! ```
+//! use keyarray::clock::MockClock;
+//! use keyarray::KeyArray;
+//! use std::sync::{mpsc, Arc};
+//! use std::time::Duration;
+//!
+//! let clock = Arc::new(MockClock::new());
+//! let (tx, rx) = mpsc::channel();
+//! let mut preset = KeyArray::new(["A", "B", "C", "D"]);
+//! preset.on_change_batch(move |batch| tx.send(batch.clone()).unwrap());
+//! preset.set_notification_batching_on(Duration::from_millis(500), Arc::clone(&clock));
+//! preset.next();
+//! preset.next();
+//! preset.next();
+//! assert!(rx.try_recv().is_err());
+//!
+//! clock.advance(Duration::from_millis(500));
+//! assert!(preset.poll_notifications());
+//! let batch = rx.try_recv().unwrap();
+//! assert_eq!((batch.from, batch.to, batch.hops), (0, 3, vec!["B", "C", "D"]));
+//! ```
+//!
+//! An observer registered with [`on_change_weak`](KeyArray::on_change_weak)
+//! only holds a weak reference to its owner and is dropped automatically
+//! once the owner is, so a forgotten `unsubscribe` does not leak.
+//!
+//! [`on_structure_change`](KeyArray::on_structure_change) observers are
+//! told about the keys themselves instead: each one added, removed, renamed
+//! or moved, with its id and indices.
+//!
+//! ```
+//! use keyarray::{KeyArray, StructureEvent};
+//! use std::sync::mpsc;
+//!
+//! let (tx, rx) = mpsc::channel();
+//! let mut tabs = KeyArray::new(["Home", "Mail", "News"]);
+//! tabs.on_structure_change(move |ev| tx.send(ev.clone()).unwrap());
+//! tabs.insert(0, "Chat");
+//! tabs.move_key(3, 1);
+//! let events: Vec<_> = rx.try_iter().collect();
+//! assert_eq!(events.len(), 2);
+//! assert!(matches!(events[0], StructureEvent::Added { index: 0, key: "Chat", .. }));
+//! assert!(matches!(events[1], StructureEvent::Moved { from: 3, to: 1, .. }));
+//! ```
+//!
+//! Observers belong to one instance: a clone starts without any.
+
+use crate::clock::{Clock, SystemClock};
+#[cfg(feature = "deprecation")]
+use crate::deprecation::DeprecationWarning;
+use crate::event::StructureEvent;
+use crate::id::KeyId;
+use crate::{KeyArray, Slot};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Debug, Display};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+/// Handle returned by [`on_change`](KeyArray::on_change), used to
+/// [`unsubscribe`](KeyArray::unsubscribe).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Subscription(u64);
+
+/// One notification to [`on_change_batch`](KeyArray::on_change_batch)
+/// observers: a single change, or every change of a batching window.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangeBatch<K> {
+    /// The current index before the first change.
+    pub from: usize,
+    /// The current index after the last change.
+    pub to: usize,
+    /// Each key that became current, in order; the last is current now.
+    pub hops: Vec<K>,
+}
+
+type Callback<K> = Box<dyn FnMut(usize, usize, &K) + Send + Sync>;
+type BatchCallback<K> = Box<dyn FnMut(&ChangeBatch<K>) + Send + Sync>;
+type StructureCallback<K> = Box<dyn FnMut(&StructureEvent<K>) + Send + Sync>;
+#[cfg(feature = "deprecation")]
+type DeprecationCallback<K> = Box<dyn FnMut(&DeprecationWarning<K>) + Send + Sync>;
+
+type Alive = Box<dyn Fn() -> bool + Send + Sync>;
+
+type Layout<K> = (Arc<Vec<K>>, Arc<Vec<Slot>>);
+
+enum Observer<K> {
+    Change(Callback<K>),
+    Batch(BatchCallback<K>),
+    Structure(StructureCallback<K>),
+    #[cfg(feature = "deprecation")]
+    Deprecated(DeprecationCallback<K>),
+}
+
+struct Entry<K> {
+    sub: Subscription,
+    observer: Observer<K>,
+    // for weak observers, whether the owner still exists
+    alive: Option<Alive>,
+}
+
+impl<K> Entry<K> {
+    fn is_alive(&self) -> bool {
+        self.alive.as_ref().is_none_or(|alive| alive())
+    }
+}
+
+struct Batching {
+    window: Duration,
+    clock: Arc<dyn Clock + Send + Sync>,
+}
+
+struct Pending<K> {
+    started: Instant,
+    // the selection as reported before the batch
+    origin: (usize, KeyId),
+    hops: Vec<K>,
+}
+
+pub(crate) struct Observers<K> {
+    next: u64,
+    callbacks: Vec<Entry<K>>,
+    // index and id of the current key as last reported
+    seen: Option<(usize, KeyId)>,
+    batching: Option<Batching>,
+    pending: Option<Pending<K>>,
+    // the keys as last reported to structure observers, while there are any
+    layout: Option<Layout<K>>,
+}
+
+impl<K> Default for Observers<K> {
+    fn default() -> Self {
+        Observers {
+            next: 0,
+            callbacks: Vec::new(),
+            seen: None,
+            batching: None,
+            pending: None,
+            layout: None,
+        }
+    }
+}
+
+// Callbacks cannot be cloned, and would fire twice if shared.
+impl<K> Clone for Observers<K> {
+    fn clone(&self) -> Self {
+        Observers::default()
+    }
+}
+
+impl<K: Clone> Observers<K> {
+    // Report the selection if it differs from the one last reported, or
+    // add it to the pending batch.
+    pub(crate) fn notify(&mut self, idx: usize, current: Option<(KeyId, &K)>) {
+        self.callbacks.retain(Entry::is_alive);
+        if self.callbacks.is_empty() {
+            return;
+        }
+        let Some((id, key)) = current else {
+            self.seen = None;
+            return;
+        };
+        let Some(seen) = self.seen.replace((idx, id)) else {
+            return;
+        };
+        if seen == (idx, id) {
+            return;
+        }
+        let Some(batching) = &self.batching else {
+            self.deliver(seen, key, || vec![key.clone()]);
+            return;
+        };
+        let now = batching.clock.now();
+        let window = batching.window;
+        let pending = self.pending.get_or_insert_with(|| Pending {
+            started: now,
+            origin: seen,
+            hops: Vec::new(),
+        });
+        pending.hops.push(key.clone());
+        if now.saturating_duration_since(pending.started) >= window {
+            self.flush();
+        }
+    }
+
+    fn has_structure_observers(&self) -> bool {
+        self.callbacks.iter().any(|e| matches!(e.observer, Observer::Structure(_)))
+    }
+
+    // Tell structure observers how the keys differ from those last
+    // reported.
+    pub(crate) fn notify_structure(&mut self, keys: &Arc<Vec<K>>, slots: &Arc<Vec<Slot>>)
+    where
+        K: PartialEq,
+    {
+        self.callbacks.retain(Entry::is_alive);
+        if !self.has_structure_observers() {
+            self.layout = None;
+            return;
+        }
+        let now = (Arc::clone(keys), Arc::clone(slots));
+        let Some((old_keys, old_slots)) = self.layout.replace(now) else {
+            return;
+        };
+        if Arc::ptr_eq(&old_keys, keys) && Arc::ptr_eq(&old_slots, slots) {
+            return;
+        }
+        let events = structure_diff((&old_keys, &old_slots), (keys, slots));
+        for entry in &mut self.callbacks {
+            if let Observer::Structure(f) = &mut entry.observer {
+                events.iter().for_each(&mut *f);
+            }
+        }
+    }
+
+    #[cfg(feature = "deprecation")]
+    pub(crate) fn wants_deprecation(&self) -> bool {
+        self.callbacks.iter().any(|e| matches!(e.observer, Observer::Deprecated(_)))
+    }
+
+    #[cfg(feature = "deprecation")]
+    pub(crate) fn notify_deprecated(&mut self, warning: &DeprecationWarning<K>) {
+        for entry in &mut self.callbacks {
+            if let Observer::Deprecated(f) = &mut entry.observer {
+                f(warning);
+            }
+        }
+    }
+
+    // Send the pending batch, if any.
+    fn flush(&mut self) -> bool {
+        let Some(pending) = self.pending.take() else {
+            return false;
+        };
+        let Some(key) = pending.hops.last().cloned() else {
+            return false;
+        };
+        if self.seen.is_none() {
+            return false;
+        }
+        self.deliver(pending.origin, &key, || pending.hops);
+        true
+    }
+
+    // Tell every observer about the move from `origin` to the selection
+    // last seen. Plain observers are skipped if it ended where it began.
+    fn deliver(&mut self, origin: (usize, KeyId), key: &K, hops: impl FnOnce() -> Vec<K>) {
+        let Some(seen) = self.seen else {
+            return;
+        };
+        let wanted = self.callbacks.iter().any(|e| matches!(e.observer, Observer::Batch(_)));
+        let batch = wanted.then(|| ChangeBatch {
+            from: origin.0,
+            to: seen.0,
+            hops: hops(),
+        });
+        for entry in &mut self.callbacks {
+            match (&mut entry.observer, &batch) {
+                (Observer::Change(f), _) if seen != origin => f(origin.0, seen.0, key),
+                (Observer::Batch(f), Some(batch)) => f(batch),
+                _ => {}
+            }
+        }
+    }
+}
+
+// The events turning `old` into `new`: removals, additions, moves and
+// renames, each in index order. The keys kept in their relative order are
+// the longest such run; every other kept key moved.
+fn structure_diff<K: Clone + PartialEq>(
+    old: (&[K], &[Slot]),
+    new: (&[K], &[Slot]),
+) -> Vec<StructureEvent<K>> {
+    let old_index: HashMap<KeyId, usize> =
+        old.1.iter().enumerate().map(|(i, s)| (s.id, i)).collect();
+    let new_ids: HashSet<KeyId> = new.1.iter().map(|s| s.id).collect();
+    let mut events: Vec<StructureEvent<K>> = old
+        .1
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| !new_ids.contains(&s.id))
+        .map(|(index, s)| StructureEvent::Removed {
+            id: s.id,
+            index,
+            key: old.0[index].clone(),
+        })
+        .collect();
+    // (old index, new index) of every kept key, in new order
+    let mut kept = Vec::new();
+    for (index, s) in new.1.iter().enumerate() {
+        match old_index.get(&s.id) {
+            Some(&from) => kept.push((from, index)),
+            None => events.push(StructureEvent::Added {
+                id: s.id,
+                index,
+                key: new.0[index].clone(),
+            }),
+        }
+    }
+    let in_order = longest_increasing(&kept.iter().map(|&(from, _)| from).collect::<Vec<_>>());
+    for (n, &(from, to)) in kept.iter().enumerate() {
+        if !in_order[n] {
+            let id = new.1[to].id;
+            events.push(StructureEvent::Moved { id, from, to });
+        }
+    }
+    for &(from, index) in &kept {
+        if old.0[from] != new.0[index] {
+            events.push(StructureEvent::Renamed {
+                id: new.1[index].id,
+                index,
+                old: old.0[from].clone(),
+                new: new.0[index].clone(),
+            });
+        }
+    }
+    events
+}
+
+// Marks the members of one longest strictly increasing subsequence.
+fn longest_increasing(values: &[usize]) -> Vec<bool> {
+    // tails[k]: position of the smallest last value of a run of length k + 1
+    let mut tails: Vec<usize> = Vec::new();
+    let mut before = vec![None; values.len()];
+    for (n, &v) in values.iter().enumerate() {
+        let k = tails.partition_point(|&t| values[t] < v);
+        before[n] = k.checked_sub(1).map(|k| tails[k]);
+        if k == tails.len() {
+            tails.push(n);
+        } else {
+            tails[k] = n;
+        }
+    }
+    let mut member = vec![false; values.len()];
+    let mut at = tails.last().copied();
+    while let Some(n) = at {
+        member[n] = true;
+        at = before[n];
+    }
+    member
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Call `f(old_index, new_index, new_key)` after every change of the
+    /// current index or key, until [`unsubscribe`](Self::unsubscribe)d.
+    pub fn on_change<F>(&mut self, f: F) -> Subscription
+    where
+        F: FnMut(usize, usize, &K) + Send + Sync + 'static,
+    {
+        self.subscribe(Observer::Change(Box::new(f)), None)
+    }
+
+    /// [`on_change`](Self::on_change), holding only a weak reference to
+    /// `owner`: `f(owner, old_index, new_index, new_key)` is called while
+    /// `owner` exists, and the observer is dropped once it no longer does.
+    pub fn on_change_weak<T, F>(&mut self, owner: &Arc<T>, mut f: F) -> Subscription
+    where
+        T: Send + Sync + 'static,
+        F: FnMut(&T, usize, usize, &K) + Send + Sync + 'static,
+    {
+        let weak = Arc::downgrade(owner);
+        let alive = Weak::clone(&weak);
+        let callback = move |old, new, key: &K| {
+            if let Some(owner) = weak.upgrade() {
+                f(&owner, old, new, key);
+            }
+        };
+        let alive: Alive = Box::new(move || alive.strong_count() > 0);
+        self.subscribe(Observer::Change(Box::new(callback)), Some(alive))
+    }
+
+    /// Call `f` with a [`ChangeBatch`] after every change, or once per
+    /// batching window while
+    /// [`set_notification_batching`](Self::set_notification_batching) is
+    /// on. Unlike [`on_change`](Self::on_change) observers, these are also
+    /// called for a batch that ends on the key it started from.
+    pub fn on_change_batch<F>(&mut self, f: F) -> Subscription
+    where
+        F: FnMut(&ChangeBatch<K>) + Send + Sync + 'static,
+    {
+        self.subscribe(Observer::Batch(Box::new(f)), None)
+    }
+
+    /// Call `f` with a [`StructureEvent`] for every key added, removed,
+    /// renamed or moved, by whatever method, until
+    /// [`unsubscribe`](Self::unsubscribe)d. Selection changes are not
+    /// reported. While such an observer is registered, the first edit
+    /// after each notification copies the key list.
+    pub fn on_structure_change<F>(&mut self, f: F) -> Subscription
+    where
+        F: FnMut(&StructureEvent<K>) + Send + Sync + 'static,
+    {
+        if self.observers.layout.is_none() {
+            self.observers.layout = Some((Arc::clone(&self.keys), Arc::clone(&self.slots)));
+        }
+        self.subscribe(Observer::Structure(Box::new(f)), None)
+    }
+
+    /// Call `f` with a [`DeprecationWarning`] whenever a change selects,
+    /// or is redirected away from, a [deprecated](Self::deprecate) key,
+    /// until [`unsubscribe`](Self::unsubscribe)d.
+    #[cfg(feature = "deprecation")]
+    pub fn on_deprecated<F>(&mut self, f: F) -> Subscription
+    where
+        F: FnMut(&DeprecationWarning<K>) + Send + Sync + 'static,
+    {
+        self.subscribe(Observer::Deprecated(Box::new(f)), None)
+    }
+
+    fn subscribe(&mut self, observer: Observer<K>, alive: Option<Alive>) -> Subscription {
+        let observers = &mut self.observers;
+        let sub = Subscription(observers.next);
+        observers.next += 1;
+        if observers.callbacks.is_empty() {
+            observers.seen = self.slots.get(self.idx).map(|s| (self.idx, s.id));
+        }
+        observers.callbacks.push(Entry { sub, observer, alive });
+        sub
+    }
+
+    /// Coalesce the changes made within `window` of the first into one
+    /// notification, timed by the system clock. See
+    /// [`set_notification_batching_on`](Self::set_notification_batching_on).
+    pub fn set_notification_batching(&mut self, window: Duration) {
+        self.set_notification_batching_on(window, SystemClock);
+    }
+
+    /// Coalesce the changes made within `window` of the first, as told by
+    /// `clock`, into one notification. It is sent by the first change once
+    /// the window has passed, or by
+    /// [`poll_notifications`](Self::poll_notifications), which should be
+    /// called regularly so the last change is not held back indefinitely.
+    pub fn set_notification_batching_on(
+        &mut self,
+        window: Duration,
+        clock: impl Clock + Send + Sync + 'static,
+    ) {
+        self.observers.batching = Some(Batching {
+            window,
+            clock: Arc::new(clock),
+        });
+    }
+
+    /// Notify every change as it happens again, first sending any pending
+    /// batch.
+    pub fn clear_notification_batching(&mut self) {
+        self.observers.batching = None;
+        self.observers.flush();
+    }
+
+    /// Send the pending batch if its window has passed. Returns whether a
+    /// batch was sent.
+    pub fn poll_notifications(&mut self) -> bool {
+        let observers = &mut self.observers;
+        let (Some(batching), Some(pending)) = (&observers.batching, &observers.pending) else {
+            return false;
+        };
+        let elapsed = batching.clock.now().saturating_duration_since(pending.started);
+        elapsed >= batching.window && observers.flush()
+    }
+
+    /// Send the pending batch now, whether or not its window has passed.
+    /// Returns whether a batch was sent.
+    pub fn flush_notifications(&mut self) -> bool {
+        self.observers.flush()
+    }
+
+    /// Stop calling the observer `sub`. Returns whether it was registered.
+    pub fn unsubscribe(&mut self, sub: Subscription) -> bool {
+        let callbacks = &mut self.observers.callbacks;
+        let before = callbacks.len();
+        callbacks.retain(|e| e.sub != sub);
+        let removed = callbacks.len() < before;
+        if !self.observers.has_structure_observers() {
+            self.observers.layout = None;
+        }
+        removed
+    }
+
+    /// How many observers are registered, not counting weak ones whose
+    /// owner is gone.
+    pub fn observer_count(&self) -> usize {
+        self.observers.callbacks.iter().filter(|e| e.is_alive()).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+    use std::sync::Mutex;
+
+    #[test]
+    fn every_path_reports() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut ka = KeyArray::new(["A", "B", "C"]);
+        let sink = Arc::clone(&log);
+        ka.on_change(move |old, new, key| sink.lock().unwrap().push((old, new, *key)));
+        ka.change(1);
+        ka.change(1);
+        ka.insert(0, "Z");
+        ka.remove(2);
+        ka.sort_by(|a, b| b.cmp(a));
+        ka.clone().next();
+        ka.push("D");
+        assert_eq!(
+            *log.lock().unwrap(),
+            [(0, 1, "B"), (1, 2, "B"), (2, 2, "C"), (2, 1, "C")]
+        );
+    }
+
+    #[test]
+    fn batches_coalesce_within_the_window() {
+        let clock = Arc::new(MockClock::new());
+        let plain = Arc::new(Mutex::new(Vec::new()));
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let mut ka = KeyArray::new(["A", "B", "C"]);
+        let sink = Arc::clone(&plain);
+        ka.on_change(move |old, new, key| sink.lock().unwrap().push((old, new, *key)));
+        let sink = Arc::clone(&batches);
+        ka.on_change_batch(move |b| sink.lock().unwrap().push(b.clone()));
+        ka.set_notification_batching_on(Duration::from_secs(1), Arc::clone(&clock));
+
+        ka.next();
+        ka.prev();
+        assert!(!ka.poll_notifications());
+        clock.advance(Duration::from_secs(1));
+        // the change after the window closes the batch
+        ka.change(2);
+        ka.next();
+        assert!(ka.flush_notifications() && !ka.flush_notifications());
+        ka.clear_notification_batching();
+        ka.next();
+
+        assert_eq!(*plain.lock().unwrap(), [(0, 2, "C"), (2, 0, "A"), (0, 1, "B")]);
+        let hops: Vec<_> = batches.lock().unwrap().iter().map(|b| b.hops.clone()).collect();
+        assert_eq!(hops, [vec!["B", "A", "C"], vec!["A"], vec!["B"]]);
+    }
+
+    #[test]
+    fn weak_observers_go_with_their_owner() {
+        let panel = Arc::new(Mutex::new(Vec::new()));
+        let mut ka = KeyArray::new(["A", "B"]);
+        ka.on_change_weak(&panel, |log, _, _, key| log.lock().unwrap().push(*key));
+        ka.on_change(|_, _, _| {});
+        ka.next();
+        assert_eq!((ka.observer_count(), panel.lock().unwrap().len()), (2, 1));
+        drop(panel);
+        assert_eq!(ka.observer_count(), 1);
+        ka.next();
+        assert_eq!(ka.observers.callbacks.len(), 1);
+    }
+
+    #[test]
+    fn structure_events_name_what_changed() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut ka = KeyArray::new(["a", "b", "c", "d"]);
+        let ids: Vec<_> = (0..4).map(|i| ka.id_at(i).unwrap()).collect();
+        let sink = Arc::clone(&log);
+        let sub = ka.on_structure_change(move |ev| sink.lock().unwrap().push(ev.clone()));
+        ka.next();
+        ka.swap(0, 3);
+        ka.remove(1);
+        ka.rename(0, "D").unwrap();
+        assert!(ka.unsubscribe(sub) && ka.observers.layout.is_none());
+        ka.push("e");
+
+        use StructureEvent::*;
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                Moved { id: ids[3], from: 3, to: 0 },
+                Moved { id: ids[0], from: 0, to: 3 },
+                Removed { id: ids[1], index: 1, key: "b" },
+                Renamed { id: ids[3], index: 0, old: "d", new: "D" },
+            ]
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! A KeyArray that may be empty, for lists filled in later.
+//!
+//! A [`KeyArray`] needs at least one key to have a current one. An
+//! [`OptionalKeyArray`] starts empty and may become empty again:
+//! [`current`](OptionalKeyArray::current) is an `Option`, stepping an empty
+//! list does nothing, and the first key added becomes current. Once keys
+//! are there, [`get`](OptionalKeyArray::get) and
+//! [`get_mut`](OptionalKeyArray::get_mut) give the whole KeyArray API.
+//!
+//! ```
+//! # #[cfg(feature = "disable")] {
+//! use keyarray::optional::OptionalKeyArray;
+//!
+//! let mut results = OptionalKeyArray::new();
+//! assert_eq!(results.current(), None);
+//! results.next();
+//!
+//! results.set_keys(["Alpha", "Beta"]);
+//! results.next();
+//! assert_eq!(results.current(), Some(&"Beta"));
+//! if let Some(keys) = results.get_mut() {
+//!     keys.disable(0);
+//! }
+//!
+//! results.clear();
+//! assert_eq!((results.current(), results.current_index()), (None, None));
+//! # }
+//! ```
+
+use crate::error::KeyArrayError;
+use crate::id::KeyId;
+use crate::KeyArray;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+/// A KeyArray allowed to have no keys; see the [module docs](self).
+/// Observers, history and other settings of the inner array are kept while
+/// it is empty.
+#[derive(Clone)]
+pub struct OptionalKeyArray<K> {
+    keys: KeyArray<K>,
+}
+
+impl<K> Debug for OptionalKeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        Debug::fmt(&self.keys, f)
+    }
+}
+
+impl<K> Default for OptionalKeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K> From<KeyArray<K>> for OptionalKeyArray<K> {
+    fn from(keys: KeyArray<K>) -> Self {
+        OptionalKeyArray { keys }
+    }
+}
+
+impl<K> OptionalKeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// An empty list.
+    pub fn new() -> Self {
+        OptionalKeyArray {
+            keys: KeyArray::from_vec(Vec::new(), 0),
+        }
+    }
+
+    /// `keys`, possibly none, with the first current.
+    pub fn from_keys(keys: impl IntoIterator<Item = K>) -> Self {
+        OptionalKeyArray {
+            keys: KeyArray::from_vec(keys.into_iter().collect(), 0),
+        }
+    }
+
+    /// The current key, or `None` while empty.
+    pub fn current(&self) -> Option<&K> {
+        self.keys.keys().get(self.keys.idx)
+    }
+
+    pub fn current_index(&self) -> Option<usize> {
+        (!self.is_empty()).then_some(self.keys.idx)
+    }
+
+    pub fn keys(&self) -> &[K] {
+        self.keys.keys()
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The array, or `None` while empty.
+    pub fn get(&self) -> Option<&KeyArray<K>> {
+        (!self.is_empty()).then_some(&self.keys)
+    }
+
+    /// The array for any other operation, or `None` while empty. Removing
+    /// its last key through it is fine.
+    pub fn get_mut(&mut self) -> Option<&mut KeyArray<K>> {
+        (!self.is_empty()).then_some(&mut self.keys)
+    }
+
+    /// The array, or `None` if empty.
+    pub fn into_inner(self) -> Option<KeyArray<K>> {
+        (!self.is_empty()).then_some(self.keys)
+    }
+
+    /// See [`KeyArray::change`]. Panics if `i` is out of bounds, as it is
+    /// for any `i` while empty.
+    pub fn change(&mut self, i: usize) {
+        self.keys.change(i)
+    }
+
+    pub fn try_change(&mut self, i: usize) -> Result<(), KeyArrayError> {
+        self.keys.try_change(i)
+    }
+
+    /// See [`KeyArray::next`]; does nothing while empty.
+    pub fn next(&mut self) {
+        if !self.is_empty() {
+            self.keys.next()
+        }
+    }
+
+    /// See [`KeyArray::prev`]; does nothing while empty.
+    pub fn prev(&mut self) {
+        if !self.is_empty() {
+            self.keys.prev()
+        }
+    }
+
+    /// Append `key`; into an empty list, it becomes current.
+    pub fn push(&mut self, key: K) -> KeyId {
+        self.keys.push(key)
+    }
+
+    /// Insert `key` at `i`; into an empty list, it becomes current. Panics
+    /// if `i > len`.
+    pub fn insert(&mut self, i: usize, key: K) -> KeyId {
+        self.try_insert(i, key)
+            .unwrap_or_else(|e| panic!("OptionalKeyArray::insert: {}", e))
+    }
+
+    pub fn try_insert(&mut self, i: usize, key: K) -> Result<KeyId, KeyArrayError> {
+        if self.is_empty() && i == 0 {
+            // `try_insert` would move the selection past the new key
+            return Ok(self.keys.push(key));
+        }
+        self.keys.try_insert(i, key)
+    }
+
+    /// Remove and return key `i`; removing the last key leaves the list
+    /// empty. Panics if out of bounds.
+    pub fn remove(&mut self, i: usize) -> K {
+        self.keys.remove(i)
+    }
+
+    pub fn try_remove(&mut self, i: usize) -> Result<K, KeyArrayError> {
+        self.keys.try_remove(i)
+    }
+
+    /// See [`KeyArray::set_keys`]; no keys clears the list.
+    pub fn set_keys(&mut self, keys: impl IntoIterator<Item = K>) {
+        let mut keys = keys.into_iter().peekable();
+        if keys.peek().is_none() {
+            self.clear();
+        } else {
+            self.keys.set_keys(keys);
+        }
+    }
+
+    /// Remove every key.
+    pub fn clear(&mut self) {
+        if !self.is_empty() {
+            self.keys.retain(|_| false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_state_is_defined() {
+        let mut ka = OptionalKeyArray::<&str>::default();
+        ka.prev();
+        assert!(ka.get().is_none() && ka.get_mut().is_none());
+        let oob = KeyArrayError::IndexOutOfBounds { index: 0, len: 0 };
+        assert_eq!(ka.try_change(0), Err(oob));
+
+        ka.insert(0, "b");
+        ka.insert(0, "a");
+        assert_eq!((ka.current(), ka.current_index()), (Some(&"b"), Some(1)));
+        ka.remove(1);
+        ka.remove(0);
+        assert_eq!(ka.current(), None);
+        ka.push("c");
+        assert_eq!(ka.current(), Some(&"c"));
+        ka.set_keys([]);
+        assert!(ka.is_empty() && ka.clone().into_inner().is_none());
+        assert_eq!(OptionalKeyArray::from_keys(["x"]).into_inner().unwrap().len(), 1);
+    }
+}
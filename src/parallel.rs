@@ -0,0 +1,51 @@
+//! Rayon-powered scans for very large arrays with expensive predicates.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//! use rayon::prelude::*;
+//!
+//! let ka = KeyArray::new(0..10_000u32);
+//! let n = ka.par_iter().filter(|&&k| k % 7 == 0).count();
+//! assert_eq!(n, 1429);
+//! let hits: Vec<usize> = ka.par_positions(|&k| k > 9_997).collect();
+//! assert_eq!(hits, vec![9_998, 9_999]);
+//! ```
+
+use crate::KeyArray;
+use rayon::prelude::*;
+use std::fmt::{Debug, Display};
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display + Sync,
+{
+    /// Parallel iterator over the keys.
+    pub fn par_iter(&self) -> rayon::slice::Iter<'_, K> {
+        self.keys.par_iter()
+    }
+
+    /// Indices of all keys matching `pred`, evaluated in parallel.
+    /// Collecting keeps them in ascending order.
+    pub fn par_positions<'a, P>(&'a self, pred: P) -> impl ParallelIterator<Item = usize> + 'a
+    where
+        P: Fn(&K) -> bool + Sync + Send + 'a,
+    {
+        self.keys
+            .par_iter()
+            .enumerate()
+            .filter_map(move |(i, k)| pred(k).then_some(i))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_sequential() {
+        let ka = KeyArray::new((0..1000).map(|i| i * 3));
+        let seq: Vec<usize> = ka.positions(|k| k % 4 == 0).collect();
+        let par: Vec<usize> = ka.par_positions(|k| k % 4 == 0).collect();
+        assert_eq!(seq, par);
+    }
+}
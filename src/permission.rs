@@ -0,0 +1,130 @@
+//! Keys that need a permission to be selected.
+//!
+//! [`require_permission`](KeyArray::require_permission) tags a key with the
+//! name of a capability, such as `"admin"`, and
+//! [`set_permission_checker`](KeyArray::set_permission_checker) installs the
+//! function deciding which capabilities the current user holds. A key whose
+//! tag the checker does not grant is locked: it stays in place and is drawn
+//! as locked, [`try_change`](KeyArray::try_change) refuses it with
+//! [`KeyArrayError::PermissionDenied`] and [`next`](KeyArray::next) /
+//! [`prev`](KeyArray::prev) step over it. Without a checker every tagged key
+//! is locked. Changing the checker does not move the selection.
+//!
+//! ```
+//! use keyarray::{KeyArray, KeyArrayError};
+//!
+//! let mut menu = KeyArray::new(["Status", "Network", "Factory Reset"]);
+//! menu.require_permission(2, "admin");
+//! menu.set_permission_checker(|permission| permission == "operator");
+//! assert_eq!(menu.try_change(2), Err(KeyArrayError::PermissionDenied { index: 2 }));
+//! menu.change(1);
+//! menu.next();
+//! assert_eq!(menu.current(), &"Status");
+//!
+//! menu.set_permission_checker(|permission| permission == "admin");
+//! menu.change(2);
+//! ```
+
+use crate::check_index;
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+// Shared, not copied, between clones.
+pub(crate) type Checker = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Require `permission` to select key `i`, replacing any earlier
+    /// requirement. Panics if `i` is out of bounds.
+    pub fn require_permission(&mut self, i: usize, permission: impl Into<String>) {
+        check_index(i, self.len())
+            .unwrap_or_else(|e| panic!("KeyArray::require_permission: {}", e));
+        let permission = Some(permission.into());
+        if self.slots[i].permission != permission {
+            Arc::make_mut(&mut self.slots)[i].permission = permission;
+            self.touch();
+        }
+    }
+
+    /// Let anyone select key `i` again. Panics if `i` is out of bounds.
+    pub fn clear_permission(&mut self, i: usize) {
+        check_index(i, self.len())
+            .unwrap_or_else(|e| panic!("KeyArray::clear_permission: {}", e));
+        if self.slots[i].permission.is_some() {
+            Arc::make_mut(&mut self.slots)[i].permission = None;
+            self.touch();
+        }
+    }
+
+    /// The permission key `i` requires, if any.
+    pub fn permission(&self, i: usize) -> Option<&str> {
+        self.slots.get(i)?.permission.as_deref()
+    }
+
+    /// Decide with `granted(permission)` which tagged keys are unlocked,
+    /// replacing any earlier checker.
+    pub fn set_permission_checker<F>(&mut self, granted: F)
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        self.permission_checker = Some(Arc::new(granted));
+        self.touch();
+    }
+
+    /// Remove the checker, locking every tagged key.
+    pub fn clear_permission_checker(&mut self) {
+        if self.permission_checker.take().is_some() {
+            self.touch();
+        }
+    }
+
+    /// Whether key `i` needs no permission or the checker grants it; false
+    /// if out of bounds.
+    pub fn is_permitted(&self, i: usize) -> bool {
+        let Some(slot) = self.slots.get(i) else {
+            return false;
+        };
+        match (&slot.permission, &self.permission_checker) {
+            (None, _) => true,
+            (Some(permission), Some(granted)) => granted(permission),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::KeyArrayError;
+
+    #[test]
+    fn locked_keys_are_refused_and_skipped() {
+        let mut ka = KeyArray::new(["a", "b", "c"]);
+        ka.require_permission(1, "root");
+        assert!(!ka.is_permitted(1) && ka.is_permitted(2) && !ka.is_permitted(9));
+        ka.next();
+        assert_eq!(ka.current(), &"c");
+        let denied = Err(KeyArrayError::PermissionDenied { index: 1 });
+        assert_eq!(ka.clone().try_change(1), denied);
+
+        ka.set_permission_checker(|p| p == "root");
+        assert_eq!(ka.clone().try_change(1), Ok(()));
+        ka.clear_permission_checker();
+        #[cfg(feature = "disable")]
+        {
+            ka.disable(1);
+            assert_eq!(ka.try_change(1), Err(KeyArrayError::Disabled { index: 1 }));
+        }
+        #[cfg(feature = "render")]
+        {
+            use crate::render::{PlainRenderer, RenderOptions};
+            let shown = ka.render_with(&mut PlainRenderer, RenderOptions::all());
+            assert_eq!(shown, "a b (locked) [c]");
+        }
+        ka.clear_permission(1);
+        assert_eq!(ka.permission(1), None);
+    }
+}
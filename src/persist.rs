@@ -0,0 +1,142 @@
+//! Durable per-user settings stored under the platform config directory.
+//!
+//! The key list itself is owned by the application; only the selected key is
+//! written to disk (as its `Display` text), so shipping a new set of keys in
+//! an update never leaves a stale list behind.
+//!
+//! ```no_run
+//! use keyarray::{persist::persist_user_config, KeyArray};
+//!
+//! let config = persist_user_config("myapp", "theme")?;
+//! let mut theme = config.load_or(KeyArray::new(["Light", "Dark", "System"]))?;
+//! theme.change(1);
+//! config.save(&theme)?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use crate::KeyArray;
+use directories::ProjectDirs;
+use std::fmt::{Debug, Display};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Location of one persisted setting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserConfig {
+    path: PathBuf,
+}
+
+/// Resolve the config file for `setting_name` of `app`, e.g.
+/// `~/.config/<app>/<setting_name>` on Linux. Fails if the platform has no
+/// home directory.
+pub fn persist_user_config(app: &str, setting_name: &str) -> io::Result<UserConfig> {
+    let dirs = ProjectDirs::from("", "", app).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "persist_user_config: no home directory for this user",
+        )
+    })?;
+    Ok(UserConfig::at(dirs.config_dir().join(setting_name)))
+}
+
+impl UserConfig {
+    /// Use an explicit file instead of the platform config directory.
+    pub fn at(path: impl Into<PathBuf>) -> Self {
+        UserConfig { path: path.into() }
+    }
+
+    /// Path of the backing file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Restore the saved selection into `defaults`.
+    /// On first run (no file yet) the defaults are written out as-is. A saved
+    /// key that no longer exists or cannot be selected is ignored and
+    /// overwritten.
+    pub fn load_or<K>(&self, mut defaults: KeyArray<K>) -> io::Result<KeyArray<K>>
+    where
+        K: Clone + PartialEq + Debug + Display,
+    {
+        if !self.path.exists() {
+            self.save(&defaults)?;
+            return Ok(defaults);
+        }
+        match self.saved_index(&defaults)? {
+            Some(i) if defaults.try_change(i).is_ok() => {}
+            _ => self.save(&defaults)?,
+        }
+        Ok(defaults)
+    }
+
+    /// Index in `keys` of the saved key, without changing anything. `None`
+    /// if nothing was saved yet or the saved key is not among `keys`.
+    pub fn saved_index<K>(&self, keys: &KeyArray<K>) -> io::Result<Option<usize>>
+    where
+        K: Clone + PartialEq + Debug + Display,
+    {
+        let saved = match fs::read_to_string(&self.path) {
+            Ok(s) => s,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let saved = saved.trim_end_matches(['\r', '\n']);
+        Ok(keys.keys().iter().position(|k| k.to_string() == saved))
+    }
+
+    /// Write the current key, atomically replacing any previous value.
+    pub fn save<K>(&self, keys: &KeyArray<K>) -> io::Result<()>
+    where
+        K: Clone + PartialEq + Debug + Display,
+    {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // write beside the target then rename, so readers never see a torn file
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        {
+            let mut f = File::create(&tmp)?;
+            writeln!(f, "{}", keys.current())?;
+            f.sync_all()?;
+        }
+        fs::rename(&tmp, &self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> UserConfig {
+        let dir = std::env::temp_dir().join(format!("keyarray-persist-{}", std::process::id()));
+        let config = UserConfig::at(dir.join(name));
+        let _ = fs::remove_file(config.path());
+        config
+    }
+
+    #[test]
+    fn first_run_writes_defaults() {
+        let config = scratch("first_run");
+        let ka = config.load_or(KeyArray::new_with(["A", "B"], 1)).unwrap();
+        assert_eq!(ka.current(), &"B");
+        assert_eq!(fs::read_to_string(config.path()).unwrap(), "B\n");
+    }
+
+    #[test]
+    fn round_trip_and_stale_key() {
+        let config = scratch("round_trip");
+        let mut ka = KeyArray::new(["Light", "Dark"]);
+        ka.change(1);
+        config.save(&ka).unwrap();
+        let restored = config.load_or(KeyArray::new(["Light", "Dark"])).unwrap();
+        assert_eq!(restored.current(), &"Dark");
+
+        // "Dark" was dropped in a later release: fall back to the default
+        let restored = config.load_or(KeyArray::new(["Light", "Sepia"])).unwrap();
+        assert_eq!(restored.current(), &"Light");
+        assert_eq!(fs::read_to_string(config.path()).unwrap(), "Light\n");
+    }
+}
@@ -0,0 +1,148 @@
+//! Placeholder keys for lists that load asynchronously.
+//!
+//! Using [`Loadable<K>`] as the key type, a list can show rows that are
+//! still loading or failed to load next to real keys. Placeholders display
+//! as "Loading…" or "Error: …", so every renderer shows them distinctly;
+//! [`select_ready`](KeyArray::select_ready) never picks one; and
+//! [`resolve`](KeyArray::resolve) replaces one in place with the real key,
+//! keeping its index, id and attached data, so the UI does not jump.
+//!
+//! ```
+//! use keyarray::placeholder::Loadable;
+//! use keyarray::KeyArray;
+//!
+//! let mut devices = KeyArray::new([Loadable::Ready("Speakers")]);
+//! let pending = devices.push_loading();
+//! devices.push_loading();
+//! assert_eq!(devices.keys()[1].to_string(), "Loading…");
+//!
+//! let i = devices.index_of_id(pending).unwrap();
+//! devices.resolve(i, "Headphones");
+//! devices.set_error(2, "timed out");
+//! assert_eq!(devices.select_ready(&"Headphones"), Some(1));
+//! assert_eq!(devices.keys()[2].to_string(), "Error: timed out");
+//! ```
+
+use crate::id::KeyId;
+use crate::{check_index, KeyArray};
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::sync::Arc;
+
+/// A key, or a placeholder for one.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Loadable<K> {
+    Ready(K),
+    /// Still being loaded.
+    Loading,
+    /// Loading failed, with a message for the user.
+    Error(String),
+}
+
+impl<K> Loadable<K> {
+    pub fn is_ready(&self) -> bool {
+        matches!(self, Loadable::Ready(_))
+    }
+
+    pub fn as_ready(&self) -> Option<&K> {
+        match self {
+            Loadable::Ready(key) => Some(key),
+            _ => None,
+        }
+    }
+
+    pub fn into_ready(self) -> Option<K> {
+        match self {
+            Loadable::Ready(key) => Some(key),
+            _ => None,
+        }
+    }
+}
+
+impl<K> From<K> for Loadable<K> {
+    fn from(key: K) -> Self {
+        Loadable::Ready(key)
+    }
+}
+
+impl<K: Display> Display for Loadable<K> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Loadable::Ready(key) => Display::fmt(key, f),
+            Loadable::Loading => f.write_str("Loading…"),
+            Loadable::Error(msg) => write!(f, "Error: {}", msg),
+        }
+    }
+}
+
+impl<K> KeyArray<Loadable<K>>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Append a [`Loading`](Loadable::Loading) placeholder, returning its
+    /// id to find it by once the key arrives.
+    pub fn push_loading(&mut self) -> KeyId {
+        self.push(Loadable::Loading)
+    }
+
+    /// Replace entry `i`, usually a placeholder, with `key`, keeping its
+    /// index, id and attached data. Panics if `i` is out of bounds.
+    pub fn resolve(&mut self, i: usize, key: K) {
+        self.replace_entry("resolve", i, Loadable::Ready(key));
+    }
+
+    /// Mark entry `i` as failed to load with `msg`. Panics if `i` is out of
+    /// bounds.
+    pub fn set_error(&mut self, i: usize, msg: impl Into<String>) {
+        self.replace_entry("set_error", i, Loadable::Error(msg.into()));
+    }
+
+    fn replace_entry(&mut self, method: &str, i: usize, entry: Loadable<K>) {
+        check_index(i, self.len()).unwrap_or_else(|e| panic!("KeyArray::{}: {}", method, e));
+        if self.keys[i] != entry {
+            Arc::make_mut(&mut self.keys)[i] = entry;
+            self.touch();
+        }
+    }
+
+    /// Index of the first ready key equal to `key`.
+    pub fn position_ready(&self, key: &K) -> Option<usize> {
+        self.keys().iter().position(|k| k.as_ready() == Some(key))
+    }
+
+    /// Make the first ready key equal to `key` current and return the index
+    /// now current, which is its replacement's if it is deprecated under
+    /// strict mode; `None`, leaving the selection alone, if there is none or it
+    /// is disabled. Placeholders are never selected.
+    pub fn select_ready(&mut self, key: &K) -> Option<usize> {
+        let i = self.position_ready(key)?;
+        self.try_change(i).ok()?;
+        Some(self.current_index())
+    }
+
+    /// Whether entry `i` is a placeholder; false if out of bounds.
+    pub fn is_placeholder(&self, i: usize) -> bool {
+        self.keys().get(i).is_some_and(|k| !k.is_ready())
+    }
+}
+
+#[cfg(all(test, feature = "meta"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_resolve_in_place() {
+        let mut ka = KeyArray::new([Loadable::Loading, Loadable::Ready("b")]);
+        ka.set_meta(0, 7u8);
+        assert_eq!(ka.select_ready(&"a"), None);
+        assert!(ka.is_placeholder(0) && !ka.is_placeholder(1) && !ka.is_placeholder(2));
+
+        let generation = ka.generation();
+        ka.resolve(0, "a");
+        assert!(ka.generation() > generation);
+        assert_eq!((ka.keys()[0].as_ready(), ka.meta::<u8>(0)), (Some(&"a"), Some(&7)));
+        assert_eq!(ka.select_ready(&"b"), Some(1));
+        ka.set_error(1, "gone");
+        assert_eq!(ka.current().clone().into_ready(), None);
+        assert_eq!(ka.position_ready(&"b"), None);
+    }
+}
@@ -0,0 +1,137 @@
+//! Guessing the next selection, e.g. to pre-highlight it.
+//!
+//! [`predict_next`](KeyArray::predict_next) asks the array's [`Predictor`].
+//! The default, [`FrequencyPredictor`], learns from the changes kept by
+//! [`track_activity`](KeyArray::track_activity): the key most often chosen
+//! right after the current one, else the most often chosen overall.
+//! [`set_predictor`](KeyArray::set_predictor) plugs in another, such as a
+//! closure or a model trained elsewhere.
+//!
+//! ```
+//! use keyarray::clock::SystemClock;
+//! use keyarray::KeyArray;
+//!
+//! let mut view = KeyArray::new(["Inbox", "Drafts", "Sent"]);
+//! view.track_activity(SystemClock, 32);
+//! for i in [2, 0, 2, 0, 2, 0, 1, 0] {
+//!     view.change(i);
+//! }
+//! // after Inbox, Sent was chosen twice and Drafts once
+//! assert_eq!(view.predict_next(), Some(2));
+//!
+//! view.set_predictor(|keys: &KeyArray<&str>| keys.keys().iter().position(|k| *k == "Drafts"));
+//! assert_eq!(view.predict_next(), Some(1));
+//! ```
+
+use crate::id::KeyId;
+use crate::KeyArray;
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+/// Suggests the key most likely to be selected next.
+pub trait Predictor<K>: Send + Sync {
+    /// Index of the predicted key in `keys`, or `None` for no guess.
+    fn predict(&self, keys: &KeyArray<K>) -> Option<usize>;
+}
+
+impl<K, F> Predictor<K> for F
+where
+    F: Fn(&KeyArray<K>) -> Option<usize> + Send + Sync,
+{
+    fn predict(&self, keys: &KeyArray<K>) -> Option<usize> {
+        self(keys)
+    }
+}
+
+/// The default predictor: the selectable key most often changed to right
+/// after the current key, else most often changed to at all, among the
+/// changes recorded by [`track_activity`](KeyArray::track_activity). Ties
+/// go to the lowest index. No guess while activity is not tracked, or
+/// without the `activity` feature.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrequencyPredictor;
+
+impl<K> Predictor<K> for FrequencyPredictor
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    fn predict(&self, keys: &KeyArray<K>) -> Option<usize> {
+        let current = keys.id_at(keys.current_index())?;
+        #[cfg(feature = "activity")]
+        let changes: Vec<KeyId> = keys.recorded_changes().collect();
+        #[cfg(not(feature = "activity"))]
+        let changes: Vec<KeyId> = Vec::new();
+        let (mut after, mut overall) = (BTreeMap::new(), BTreeMap::new());
+        for (i, &id) in changes.iter().enumerate() {
+            *overall.entry(id).or_insert(0) += 1;
+            if i > 0 && changes[i - 1] == current {
+                *after.entry(id).or_insert(0) += 1;
+            }
+        }
+        let best = |counts: BTreeMap<KeyId, usize>| {
+            counts
+                .into_iter()
+                .filter(|&(id, _)| id != current)
+                .filter_map(|(id, n)| Some((keys.index_of_id(id)?, n)))
+                .filter(|&(i, _)| keys.selectable(i))
+                .max_by_key(|&(i, n)| (n, std::cmp::Reverse(i)))
+                .map(|(i, _)| i)
+        };
+        best(after).or_else(|| best(overall))
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// The key the predictor expects to be selected next; `None` if it has
+    /// no guess, or guesses the current key or one that cannot be
+    /// selected.
+    pub fn predict_next(&self) -> Option<usize> {
+        let guess = match &self.predictor {
+            Some(predictor) => predictor.predict(self),
+            None => FrequencyPredictor.predict(self),
+        }?;
+        (guess < self.len() && guess != self.idx && self.selectable(guess)).then_some(guess)
+    }
+
+    /// Use `predictor` for [`predict_next`](Self::predict_next). Clones
+    /// share it.
+    pub fn set_predictor(&mut self, predictor: impl Predictor<K> + 'static) {
+        self.predictor = Some(Arc::new(predictor));
+    }
+
+    /// Go back to the [`FrequencyPredictor`].
+    pub fn clear_predictor(&mut self) {
+        self.predictor = None;
+    }
+}
+
+#[cfg(all(test, feature = "activity", feature = "disable"))]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+
+    #[test]
+    fn frequency_follows_the_current_key() {
+        let mut ka = KeyArray::new(["a", "b", "c", "d"]);
+        assert_eq!(ka.predict_next(), None);
+        ka.track_activity(MockClock::new(), 16);
+        for i in [1, 2, 1, 3] {
+            ka.change(i);
+        }
+        // nothing followed "d" yet: the overall favourite
+        assert_eq!(ka.predict_next(), Some(1));
+        ka.change(1);
+        assert_eq!(ka.predict_next(), Some(2));
+        ka.disable(2);
+        assert_eq!(ka.predict_next(), Some(3));
+
+        ka.set_predictor(|keys: &KeyArray<&str>| Some(keys.current_index()));
+        assert_eq!(ka.clone().predict_next(), None);
+        ka.clear_predictor();
+        assert_eq!(ka.predict_next(), Some(3));
+    }
+}
@@ -0,0 +1,160 @@
+//! Selecting and de-duplicating struct keys by one of their fields.
+//!
+//! When keys carry an id next to a label and a payload, whole-struct
+//! equality is the wrong notion of "the same key". [`KeyArray::by_key`]
+//! fixes the field once and answers every question in terms of it:
+//!
+//! ```
+//! use keyarray::KeyArray;
+//! use std::fmt;
+//!
+//! #[derive(Debug, Clone, PartialEq)]
+//! struct Output { id: u32, label: &'static str }
+//! impl fmt::Display for Output {
+//!     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { f.write_str(self.label) }
+//! }
+//!
+//! let mut outputs = KeyArray::new([
+//!     Output { id: 1, label: "HDMI" },
+//!     Output { id: 2, label: "Speakers" },
+//!     Output { id: 1, label: "HDMI (TV)" },
+//! ]);
+//! let mut by_id = outputs.by_key(|o| &o.id);
+//! assert_eq!(by_id.select(&2), Some(1));
+//! assert!(!by_id.is_unique());
+//! assert_eq!(by_id.dedup(), 1);
+//! assert_eq!(outputs.len(), 2);
+//! ```
+
+use crate::KeyArray;
+use std::collections::HashSet;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+
+/// A KeyArray seen through a projection; see [`KeyArray::by_key`].
+pub struct ByKey<'a, K, F> {
+    keys: &'a mut KeyArray<K>,
+    project: F,
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Compare keys by `project(key)` instead of the whole key.
+    pub fn by_key<P, F>(&mut self, project: F) -> ByKey<'_, K, F>
+    where
+        P: PartialEq + ?Sized,
+        F: Fn(&K) -> &P,
+    {
+        ByKey {
+            keys: self,
+            project,
+        }
+    }
+}
+
+impl<K, P, F> ByKey<'_, K, F>
+where
+    K: Clone + PartialEq + Debug + Display,
+    P: PartialEq + ?Sized,
+    F: Fn(&K) -> &P,
+{
+    /// Index of the first key projecting to `value`.
+    pub fn position(&self, value: &P) -> Option<usize> {
+        self.keys
+            .keys()
+            .iter()
+            .position(|k| (self.project)(k) == value)
+    }
+
+    pub fn contains(&self, value: &P) -> bool {
+        self.position(value).is_some()
+    }
+
+    /// Projection of the current key.
+    pub fn current(&self) -> &P {
+        (self.project)(self.keys.current())
+    }
+
+    /// Make the first key projecting to `value` current and return the index
+    /// now current, which is its replacement's if it is deprecated under
+    /// strict mode; `None`, leaving the selection alone, if there is none or it
+    /// is disabled.
+    pub fn select(&mut self, value: &P) -> Option<usize> {
+        let i = self.position(value)?;
+        self.keys.try_change(i).ok()?;
+        Some(self.keys.current_index())
+    }
+}
+
+impl<K, P, F> ByKey<'_, K, F>
+where
+    K: Clone + PartialEq + Debug + Display,
+    P: Eq + Hash + ?Sized,
+    F: Fn(&K) -> &P,
+{
+    /// Whether no two keys share a projection.
+    pub fn is_unique(&self) -> bool {
+        let mut seen = HashSet::new();
+        self.keys
+            .keys()
+            .iter()
+            .all(|k| seen.insert((self.project)(k)))
+    }
+
+    /// Keep only the first key for each projection and return how many were
+    /// removed. If the current key was a later duplicate, the kept one
+    /// becomes current.
+    pub fn dedup(&mut self) -> usize {
+        let keys = self.keys.keys();
+        let mut seen = HashSet::new();
+        let keep: Vec<bool> = keys
+            .iter()
+            .map(|k| seen.insert((self.project)(k)))
+            .collect();
+        let removed = keep.iter().filter(|k| !**k).count();
+        if removed == 0 {
+            return 0;
+        }
+        let current = (self.project)(self.keys.current());
+        let first = keys.iter().position(|k| (self.project)(k) == current);
+        let first = first.expect("the current key projects to itself");
+        drop(seen);
+        self.keys.retain_mask(&keep, first);
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fmt::{Formatter, Result as FmtResult};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Item(&'static str, u8);
+
+    impl Display for Item {
+        fn fmt(&self, f: &mut Formatter) -> FmtResult {
+            write!(f, "{}{}", self.0, self.1)
+        }
+    }
+
+    #[test]
+    fn dedup_keeps_first_and_moves_current() {
+        let mut ka =
+            KeyArray::new_with([Item("a", 1), Item("b", 2), Item("a", 3), Item("c", 4)], 2);
+        let mut by_name = ka.by_key(|k| k.0);
+        assert_eq!(by_name.current(), "a");
+        assert_eq!(by_name.select("zzz"), None);
+        assert_eq!(by_name.dedup(), 1);
+        assert!(by_name.is_unique());
+        assert_eq!(ka.keys(), &[Item("a", 1), Item("b", 2), Item("c", 4)]);
+        assert_eq!(ka.current_index(), 0);
+
+        ka.change(2);
+        ka.push(Item("b", 5));
+        assert_eq!(ka.by_key(|k| k.0).dedup(), 1);
+        assert_eq!(ka.current(), &Item("c", 4));
+    }
+}
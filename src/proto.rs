@@ -0,0 +1,239 @@
+//! Protobuf messages for mirroring state between services.
+//!
+//! The schema lives in `proto/keyarray.proto`; these are the matching
+//! `prost` types plus conversions. Keys travel as their `Display` text and
+//! are parsed back with `FromStr`.
+//!
+//! ```
+//! use keyarray::proto::KeyArrayState;
+//! use keyarray::KeyArray;
+//! use prost::Message;
+//!
+//! let ka = KeyArray::new_with(["On", "Off"].map(String::from), 1);
+//! let bytes = KeyArrayState::from(&ka).encode_to_vec();
+//!
+//! let state = KeyArrayState::decode(bytes.as_slice()).unwrap();
+//! let mirror: KeyArray<String> = state.try_into().unwrap();
+//! assert_eq!(mirror.current(), "Off");
+//! ```
+
+use crate::repair::RepairReport;
+use crate::{KeyArray, Msg};
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+/// Full state snapshot.
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct KeyArrayState {
+    #[prost(string, repeated, tag = "1")]
+    pub keys: Vec<String>,
+    #[prost(uint64, tag = "2")]
+    pub selected_index: u64,
+    /// Text of the selected key; informational, decoding uses the index.
+    #[prost(string, tag = "3")]
+    pub selected: String,
+    #[prost(uint64, tag = "4")]
+    pub generation: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Insert {
+    #[prost(uint64, tag = "1")]
+    pub index: u64,
+    #[prost(string, tag = "2")]
+    pub key: String,
+}
+
+/// One mutation, mirroring [`Msg`].
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Op {
+    #[prost(oneof = "op::Kind", tags = "1, 2, 3, 4, 5")]
+    pub kind: Option<op::Kind>,
+}
+
+pub mod op {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Kind {
+        /// The value is ignored.
+        #[prost(bool, tag = "1")]
+        Next(bool),
+        /// The value is ignored.
+        #[prost(bool, tag = "2")]
+        Prev(bool),
+        #[prost(uint64, tag = "3")]
+        Select(u64),
+        #[prost(message, tag = "4")]
+        Insert(super::Insert),
+        #[prost(uint64, tag = "5")]
+        Remove(u64),
+    }
+}
+
+/// Why a message could not be turned back into a KeyArray or a [`Msg`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProtoError {
+    /// The state has no keys.
+    NoKeys,
+    /// `selected_index` does not point into `keys`.
+    IndexOutOfBounds { index: u64, len: usize },
+    /// A key's text did not parse.
+    InvalidKey(String),
+    /// An [`Op`] with no operation set.
+    MissingOp,
+}
+
+impl Display for ProtoError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            ProtoError::NoKeys => f.write_str("state has no keys"),
+            ProtoError::IndexOutOfBounds { index, len } => {
+                write!(f, "selected index {} out of bounds for {} keys", index, len)
+            }
+            ProtoError::InvalidKey(text) => write!(f, "invalid key {:?}", text),
+            ProtoError::MissingOp => f.write_str("op has no operation set"),
+        }
+    }
+}
+
+impl std::error::Error for ProtoError {}
+
+fn parse_key<K: FromStr>(text: String) -> Result<K, ProtoError> {
+    text.parse().map_err(|_| ProtoError::InvalidKey(text))
+}
+
+impl<K> From<&KeyArray<K>> for KeyArrayState
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    fn from(ka: &KeyArray<K>) -> Self {
+        KeyArrayState {
+            keys: ka.keys().iter().map(ToString::to_string).collect(),
+            selected_index: ka.current_index() as u64,
+            selected: ka.current().to_string(),
+            generation: ka.generation(),
+        }
+    }
+}
+
+/// Rebuilds keys and selection; ids, generation and attached data start
+/// fresh.
+impl<K> TryFrom<KeyArrayState> for KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display + FromStr,
+{
+    type Error = ProtoError;
+
+    fn try_from(state: KeyArrayState) -> Result<Self, ProtoError> {
+        let len = state.keys.len();
+        if len == 0 {
+            return Err(ProtoError::NoKeys);
+        }
+        let index = state.selected_index;
+        if index >= len as u64 {
+            return Err(ProtoError::IndexOutOfBounds { index, len });
+        }
+        let keys = state
+            .keys
+            .into_iter()
+            .map(parse_key)
+            .collect::<Result<Vec<K>, _>>()?;
+        Ok(KeyArray::new_with(keys, index as usize))
+    }
+}
+
+impl KeyArrayState {
+    /// Rebuild a KeyArray however damaged the state is, keeping only keys
+    /// in `known`; see [`KeyArray::from_texts_lenient`].
+    pub fn repair<K>(self, known: KeyArray<K>) -> (KeyArray<K>, RepairReport)
+    where
+        K: Clone + PartialEq + Debug + Display + FromStr,
+    {
+        let selected = usize::try_from(self.selected_index).unwrap_or(usize::MAX);
+        KeyArray::from_texts_lenient(self.keys, selected, known)
+    }
+}
+
+impl<K: Display> From<&Msg<K>> for Op {
+    fn from(msg: &Msg<K>) -> Self {
+        let kind = match msg {
+            Msg::Next => op::Kind::Next(true),
+            Msg::Prev => op::Kind::Prev(true),
+            Msg::Select(i) => op::Kind::Select(*i as u64),
+            Msg::Insert(i, key) => op::Kind::Insert(Insert {
+                index: *i as u64,
+                key: key.to_string(),
+            }),
+            Msg::Remove(i) => op::Kind::Remove(*i as u64),
+        };
+        Op { kind: Some(kind) }
+    }
+}
+
+/// Indices are not range-checked here; [`KeyArray::update`] panics on bad
+/// ones as usual.
+impl<K: FromStr> TryFrom<Op> for Msg<K> {
+    type Error = ProtoError;
+
+    fn try_from(op: Op) -> Result<Self, ProtoError> {
+        Ok(match op.kind.ok_or(ProtoError::MissingOp)? {
+            op::Kind::Next(_) => Msg::Next,
+            op::Kind::Prev(_) => Msg::Prev,
+            op::Kind::Select(i) => Msg::Select(i as usize),
+            op::Kind::Insert(Insert { index, key }) => Msg::Insert(index as usize, parse_key(key)?),
+            op::Kind::Remove(i) => Msg::Remove(i as usize),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+
+    #[test]
+    fn state_round_trip() {
+        let mut ka = KeyArray::new([10u32, 20, 30]);
+        ka.change(2);
+        let state =
+            KeyArrayState::decode(KeyArrayState::from(&ka).encode_to_vec().as_slice()).unwrap();
+        assert_eq!((state.selected.as_str(), state.generation), ("30", 1));
+        let back = KeyArray::<u32>::try_from(state.clone()).unwrap();
+        assert_eq!((back.keys(), back.current_index()), (ka.keys(), 2));
+
+        let bad = KeyArrayState {
+            selected_index: 3,
+            ..state.clone()
+        };
+        assert_eq!(
+            KeyArray::<u32>::try_from(bad).unwrap_err(),
+            ProtoError::IndexOutOfBounds { index: 3, len: 3 }
+        );
+        let bad = KeyArrayState {
+            keys: vec!["x".into()],
+            selected_index: 0,
+            ..state
+        };
+        assert_eq!(
+            KeyArray::<u32>::try_from(bad).unwrap_err(),
+            ProtoError::InvalidKey("x".into())
+        );
+    }
+
+    #[test]
+    fn ops_round_trip() {
+        for msg in [
+            Msg::Next,
+            Msg::Prev,
+            Msg::Select(2),
+            Msg::Insert(1, 7u8),
+            Msg::Remove(0),
+        ] {
+            let op = Op::decode(Op::from(&msg).encode_to_vec().as_slice()).unwrap();
+            assert_eq!(Msg::try_from(op), Ok(msg));
+        }
+        assert_eq!(
+            Msg::<u8>::try_from(Op::default()),
+            Err(ProtoError::MissingOp)
+        );
+    }
+}
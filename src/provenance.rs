@@ -0,0 +1,150 @@
+//! Where each key came from.
+//!
+//! Every key carries a [`Source`], [`Defaults`](Source::Defaults) unless
+//! given another, so keys can later be filtered or dropped by origin:
+//! "reset to defaults" removes what the user and plugins added while the
+//! built-in keys keep their ids and the selection.
+//!
+//! ```
+//! use keyarray::provenance::Source;
+//! use keyarray::KeyArray;
+//!
+//! let mut themes = KeyArray::new(["Light", "Dark"]);
+//! themes.push_from("Solarized", Source::UserConfig);
+//! themes.push_from("Nord", Source::Plugin("nord".into()));
+//! assert_eq!(themes.indices_from(&Source::UserConfig).collect::<Vec<_>>(), [2]);
+//!
+//! themes.change(1);
+//! assert_eq!(themes.reset_to_defaults(), ["Solarized", "Nord"]);
+//! assert_eq!((themes.keys(), themes.current()), (&["Light", "Dark"][..], &"Dark"));
+//! ```
+
+use crate::id::KeyId;
+use crate::KeyArray;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::sync::Arc;
+
+/// The origin of a key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Source {
+    /// Built into the application.
+    #[default]
+    Defaults,
+    /// Added by the user, e.g. loaded from their configuration.
+    UserConfig,
+    /// Found at run time, such as a device on the network.
+    Discovered,
+    /// Contributed by the named plugin.
+    Plugin(String),
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Source::Defaults => f.write_str("defaults"),
+            Source::UserConfig => f.write_str("user config"),
+            Source::Discovered => f.write_str("discovered"),
+            Source::Plugin(name) => write!(f, "plugin {}", name),
+        }
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// [`push`](Self::push) a key recorded as coming from `source`.
+    pub fn push_from(&mut self, key: K, source: Source) -> KeyId {
+        let id = self.push(key);
+        let last = self.len() - 1;
+        Arc::make_mut(&mut self.slots)[last].source = source;
+        id
+    }
+
+    /// Record that key `i` came from `source`. Panics if `i` is out of
+    /// bounds.
+    pub fn set_source(&mut self, i: usize, source: Source) {
+        assert!(
+            i < self.keys.len(),
+            "KeyArray::set_source: index {} out of bounds",
+            i
+        );
+        Arc::make_mut(&mut self.slots)[i].source = source;
+        self.touch();
+    }
+
+    /// Where key `i` came from; `None` if `i` is out of bounds.
+    pub fn source(&self, i: usize) -> Option<&Source> {
+        self.slots.get(i).map(|s| &s.source)
+    }
+
+    /// Indices of the keys that came from `source`, in order.
+    pub fn indices_from<'a>(&'a self, source: &'a Source) -> impl Iterator<Item = usize> + 'a {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(move |(i, s)| (s.source == *source).then_some(i))
+    }
+
+    /// Remove every key that came from `source` and return them in their
+    /// former order. If the current key is removed, the first surviving key
+    /// after it becomes current, or else the last one before it; if no key
+    /// survives the array is left empty.
+    pub fn remove_from_source(&mut self, source: &Source) -> Vec<K> {
+        self.remove_sources(|s| s == source)
+    }
+
+    /// Remove every key that did not come from [`Source::Defaults`], as by
+    /// [`remove_from_source`](Self::remove_from_source).
+    pub fn reset_to_defaults(&mut self) -> Vec<K> {
+        self.remove_sources(|s| *s != Source::Defaults)
+    }
+
+    fn remove_sources(&mut self, mut drop: impl FnMut(&Source) -> bool) -> Vec<K> {
+        let keep: Vec<bool> = self.slots.iter().map(|s| !drop(&s.source)).collect();
+        if !keep.contains(&false) {
+            return Vec::new();
+        }
+        let removed = (0..self.len())
+            .filter(|&i| !keep[i])
+            .map(|i| self.keys[i].clone())
+            .collect();
+        let survivor = (self.idx..self.len())
+            .chain((0..self.idx).rev())
+            .find(|&i| keep[i]);
+        match survivor {
+            Some(survivor) => self.retain_mask(&keep, survivor),
+            None => {
+                Arc::make_mut(&mut self.keys).clear();
+                Arc::make_mut(&mut self.slots).clear();
+                self.idx = 0;
+                self.touch();
+            }
+        }
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removal_by_source_moves_selection() {
+        let plugin = Source::Plugin("extra".into());
+        let mut ka = KeyArray::new(["a", "b"]);
+        ka.push_from("c", Source::Discovered);
+        ka.push_from("d", plugin.clone());
+        ka.set_source(0, Source::Discovered);
+        assert_eq!(ka.source(3), Some(&plugin));
+        assert_eq!(ka.source(4), None);
+
+        ka.change(2);
+        assert_eq!(ka.remove_from_source(&Source::Discovered), ["a", "c"]);
+        assert_eq!((ka.keys(), ka.current()), (&["b", "d"][..], &"d"));
+        assert!(ka.remove_from_source(&Source::UserConfig).is_empty());
+        assert_eq!(ka.reset_to_defaults(), ["d"]);
+        assert_eq!(ka.reset_to_defaults(), Vec::<&str>::new());
+        assert_eq!(ka.current(), &"b");
+    }
+}
@@ -0,0 +1,151 @@
+//! Predicate-based scans over the keys.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//!
+//! let ka = KeyArray::new(["dev-a", "prod", "dev-b"]);
+//! let dev: Vec<usize> = ka.positions(|k| k.starts_with("dev")).collect();
+//! assert_eq!(dev, vec![0, 2]);
+//! assert_eq!(ka.find_map_key(|k| k.strip_prefix("dev-")), Some((0, "a")));
+//!
+//! // no 1000p option: take the closest
+//! let mut res = KeyArray::new([480, 720, 1080, 2160]);
+//! assert_eq!(res.select_nearest_by(|&h| (h as i32 - 1000).abs()), Some(2));
+//! ```
+
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Indices of all keys matching `pred`, ascending.
+    pub fn positions<'a, P>(&'a self, mut pred: P) -> impl Iterator<Item = usize> + 'a
+    where
+        P: FnMut(&K) -> bool + 'a,
+    {
+        self.keys
+            .iter()
+            .enumerate()
+            .filter_map(move |(i, k)| pred(k).then_some(i))
+    }
+
+    /// First key for which `f` returns `Some`, with its index.
+    pub fn find_map_key<'a, T, F>(&'a self, mut f: F) -> Option<(usize, T)>
+    where
+        F: FnMut(&'a K) -> Option<T>,
+    {
+        self.keys
+            .iter()
+            .enumerate()
+            .find_map(|(i, k)| f(k).map(|t| (i, t)))
+    }
+
+    /// Index of the key minimizing `distance`; ties go to the lowest index.
+    /// Keys whose distance is incomparable (NaN) are skipped.
+    pub fn nearest_by<T, F>(&self, distance: F) -> Option<usize>
+    where
+        T: PartialOrd,
+        F: FnMut(&K) -> T,
+    {
+        self.nearest_where(distance, |_| true)
+    }
+
+    // `nearest_by` among the keys `keep` accepts.
+    fn nearest_where<T, F, P>(&self, mut distance: F, keep: P) -> Option<usize>
+    where
+        T: PartialOrd,
+        F: FnMut(&K) -> T,
+        P: Fn(usize) -> bool,
+    {
+        let mut best: Option<(usize, T)> = None;
+        for (i, k) in self.keys.iter().enumerate() {
+            if !keep(i) {
+                continue;
+            }
+            let d = distance(k);
+            if d.partial_cmp(&d).is_none() {
+                continue;
+            }
+            if best.as_ref().is_none_or(|(_, b)| d < *b) {
+                best = Some((i, d));
+            }
+        }
+        best.map(|(i, _)| i)
+    }
+
+    /// Make the [`nearest_by`](Self::nearest_by) key current and return the
+    /// index now current (its replacement's if it is deprecated under strict
+    /// mode), passing over keys that cannot be selected (disabled or
+    /// locked); `None` if no other key has a comparable distance.
+    pub fn select_nearest_by<T, F>(&mut self, distance: F) -> Option<usize>
+    where
+        T: PartialOrd,
+        F: FnMut(&K) -> T,
+    {
+        let i = self.nearest_where(distance, |i| self.check_selectable(i).is_ok())?;
+        self.try_change(i).ok()?;
+        Some(self.current_index())
+    }
+
+    /// Binary-search keys sorted ascending by `f` for `value`, as
+    /// [`slice::binary_search_by_key`]: `Ok` with a matching index, or `Err`
+    /// with the index where such a key would be inserted. O(log n); the
+    /// result is unspecified if the keys are not sorted by `f`.
+    pub fn search_by_ordered_value<T, F>(&self, value: &T, f: F) -> Result<usize, usize>
+    where
+        T: Ord,
+        F: FnMut(&K) -> T,
+    {
+        self.keys.binary_search_by_key(value, f)
+    }
+
+    /// Make the key found by
+    /// [`search_by_ordered_value`](Self::search_by_ordered_value) current.
+    /// On `Err` the selection is left alone; a key found that cannot be
+    /// selected (disabled or locked) is `Err` with its own index, where an
+    /// equal key could be inserted.
+    pub fn select_by_ordered_value<T, F>(&mut self, value: &T, f: F) -> Result<usize, usize>
+    where
+        T: Ord,
+        F: FnMut(&K) -> T,
+    {
+        let i = self.search_by_ordered_value(value, f)?;
+        self.try_change(i).map_err(|_| i)?;
+        Ok(i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_and_find_map() {
+        let ka = KeyArray::new([3, 8, 5, 10]);
+        assert_eq!(ka.positions(|&k| k > 4).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(ka.positions(|&k| k > 99).next(), None);
+        assert_eq!(ka.find_map_key(|&k| (k % 5 == 0).then_some(k / 5)), Some((2, 1)));
+    }
+
+    #[test]
+    fn nearest_ties_and_nan() {
+        let mut ka = KeyArray::new([1.0, 3.0, 5.0, f64::NAN]);
+        // 3.0 and 5.0 are both 1.0 away from 4.0: the lower index wins
+        assert_eq!(ka.select_nearest_by(|k| (k - 4.0f64).abs()), Some(1));
+        assert_eq!(ka.current(), &3.0);
+        assert_eq!(ka.nearest_by(|k| -k), Some(2));
+        assert_eq!(ka.nearest_by(|_| f64::NAN), None);
+    }
+
+    #[test]
+    fn ordered_value_search() {
+        let mut ka = KeyArray::new((0..1000u32).map(|i| i * 10));
+        assert_eq!(ka.select_by_ordered_value(&4560, |&k| k), Ok(456));
+        assert_eq!(ka.current(), &4560);
+        assert_eq!(ka.select_by_ordered_value(&4565, |&k| k), Err(457));
+        assert_eq!(ka.current_index(), 456);
+        assert_eq!(ka.search_by_ordered_value(&999, |&k| k / 10), Ok(999));
+    }
+}
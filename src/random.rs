@@ -0,0 +1,144 @@
+//! Randomised selection and ordering.
+//!
+//! Every method takes the random number generator explicitly, so a seeded
+//! generator replays the same choices in simulations and tests. The
+//! versions without `_with` draw from `rand::rng()` for convenience.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//! use rand::rngs::StdRng;
+//! use rand::SeedableRng;
+//!
+//! let mut a = KeyArray::new(1..=8);
+//! let mut b = a.clone();
+//! a.shuffle_with(&mut StdRng::seed_from_u64(7));
+//! b.shuffle_with(&mut StdRng::seed_from_u64(7));
+//! assert_eq!(a.keys(), b.keys());
+//!
+//! // a Markov step: weights from the current key to each key
+//! let mut weather = KeyArray::new(["Sun", "Rain"]);
+//! let mut rng = StdRng::seed_from_u64(1);
+//! weather.step_markov_with(&mut rng, |from, to| if from == to { 0.8 } else { 0.2 });
+//! ```
+
+use crate::KeyArray;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::fmt::{Debug, Display};
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Put the keys in a random order drawn from `rng`; the current key
+    /// stays current.
+    pub fn shuffle_with(&mut self, rng: &mut impl Rng) {
+        self.reorder(|tagged| tagged.shuffle(rng));
+    }
+
+    /// [`shuffle_with`](Self::shuffle_with) the thread-local generator.
+    pub fn shuffle(&mut self) {
+        self.shuffle_with(&mut rand::rng());
+    }
+
+    /// Make a uniformly random key current and return the index now
+    /// current, which is its replacement's if it is deprecated under strict
+    /// mode. Soft-removed keys are never picked; `None` if every key is.
+    pub fn change_random_with(&mut self, rng: &mut impl Rng) -> Option<usize> {
+        self.pick_weighted(rng, |_| 1.0)
+    }
+
+    /// [`change_random_with`](Self::change_random_with) the thread-local
+    /// generator.
+    pub fn change_random(&mut self) -> Option<usize> {
+        self.change_random_with(&mut rand::rng())
+    }
+
+    /// Take one Markov chain step: move to key `to` with probability
+    /// proportional to `weight(current_index, to)`, and return the new
+    /// index (`to`'s replacement's if it is deprecated under strict mode).
+    /// Soft-removed keys and non-positive weights are never picked;
+    /// `None`, with the selection unchanged, if nothing can be.
+    pub fn step_markov_with<F>(&mut self, rng: &mut impl Rng, mut weight: F) -> Option<usize>
+    where
+        F: FnMut(usize, usize) -> f64,
+    {
+        let from = self.idx;
+        self.pick_weighted(rng, |to| weight(from, to))
+    }
+
+    /// [`step_markov_with`](Self::step_markov_with) the thread-local
+    /// generator.
+    pub fn step_markov<F>(&mut self, weight: F) -> Option<usize>
+    where
+        F: FnMut(usize, usize) -> f64,
+    {
+        self.step_markov_with(&mut rand::rng(), weight)
+    }
+
+    // Change to a selectable key chosen with probability proportional to
+    // `weight(index)`, ignoring weights that are not positive and finite.
+    fn pick_weighted(
+        &mut self,
+        rng: &mut impl Rng,
+        mut weight: impl FnMut(usize) -> f64,
+    ) -> Option<usize> {
+        let weights: Vec<f64> = (0..self.len())
+            .map(|i| match weight(i) {
+                w if self.selectable(i) && w.is_finite() && w > 0.0 => w,
+                _ => 0.0,
+            })
+            .collect();
+        let total: f64 = weights.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut roll = rng.random_range(0.0..total);
+        let last = weights.iter().rposition(|&w| w > 0.0)?;
+        let i = weights
+            .iter()
+            .position(|&w| {
+                roll -= w;
+                w > 0.0 && roll < 0.0
+            })
+            .unwrap_or(last);
+        self.try_change(i).ok()?;
+        Some(self.current_index())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn seeded_runs_repeat() {
+        let run = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut ka = KeyArray::new_with(0..10, 3);
+            ka.shuffle_with(&mut rng);
+            assert_eq!(ka.current(), &3);
+            let picks: Vec<_> = (0..20).map(|_| ka.change_random_with(&mut rng)).collect();
+            (ka.keys().to_vec(), picks)
+        };
+        assert_eq!(run(42), run(42));
+    }
+
+    #[test]
+    fn weights_and_hidden_keys_are_respected() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut ka = KeyArray::new(["A", "B", "C"]);
+        ka.soft_remove(1);
+        for _ in 0..50 {
+            assert_ne!(ka.change_random_with(&mut rng), Some(1));
+        }
+        // a deterministic chain: always move to the following key
+        let step = |from: usize, to: usize| if to == (from + 1) % 3 { 1.0 } else { 0.0 };
+        ka.change(2);
+        assert_eq!(ka.step_markov_with(&mut rng, step), Some(0));
+        assert_eq!(ka.step_markov_with(&mut rng, step), None);
+        assert_eq!(ka.current(), &"A");
+    }
+}
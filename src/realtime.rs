@@ -0,0 +1,209 @@
+//! Publishing the selection to real-time threads.
+//!
+//! An audio callback must not take locks or allocate, so it cannot share a
+//! KeyArray through [`SyncKeyArray`](crate::sync::SyncKeyArray).
+//! [`publish_realtime`](KeyArray::publish_realtime) instead hands out an
+//! [`RtReader`] that sees the current index and a per-key `u64` (a gain's
+//! bits, a table offset, ...) through a sequence lock made of atomics. The
+//! array publishes on every change its observers are told about, without
+//! waiting; a read never waits either: if it overlaps a publish, it returns
+//! the snapshot read before. Neither side allocates after setup.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//! use std::thread;
+//!
+//! let mut preset = KeyArray::new(["Soft", "Loud"]);
+//! let gain = |name: &&str| if *name == "Soft" { 0.25f32 } else { 1.0 };
+//! let mut reader = preset.publish_realtime(move |name| gain(name).to_bits().into());
+//! preset.next();
+//!
+//! let audio = thread::spawn(move || {
+//!     let now = reader.read();
+//!     (now.index, f32::from_bits(now.value as u32))
+//! });
+//! assert_eq!(audio.join().unwrap(), (1, 1.0));
+//! ```
+//!
+//! A loop that only needs the index can use
+//! [`share_index`](KeyArray::share_index): reading a [`SharedIndex`] is a
+//! single relaxed atomic load, with no retry and no bounds check, because
+//! the array only stores indices it has already validated. `cargo bench
+//! --bench realtime` compares it with the other ways to read the index.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//!
+//! let mut bands = KeyArray::new(["Low", "Mid", "High"]);
+//! let band = bands.share_index();
+//! let coefficients = [0.1f32, 0.5, 0.9];
+//! bands.change(2);
+//! // in the DSP loop
+//! assert_eq!(coefficients[band.current_index()], 0.9);
+//! ```
+
+use crate::KeyArray;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::sync::atomic::{fence, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// The selection as seen by an [`RtReader`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Snapshot {
+    /// Index of the current key.
+    pub index: usize,
+    /// The publisher's value for the current key.
+    pub value: u64,
+    /// How many snapshots were published before this one.
+    pub version: u64,
+}
+
+// A sequence lock: odd while a publish is in progress.
+struct Seqlock {
+    seq: AtomicU64,
+    index: AtomicUsize,
+    value: AtomicU64,
+}
+
+impl Seqlock {
+    // Only ever called by the array's single observer.
+    fn publish(&self, index: usize, value: u64) {
+        let seq = self.seq.load(Ordering::Relaxed);
+        self.seq.store(seq + 1, Ordering::Relaxed);
+        fence(Ordering::Release);
+        self.index.store(index, Ordering::Relaxed);
+        self.value.store(value, Ordering::Relaxed);
+        self.seq.store(seq + 2, Ordering::Release);
+    }
+
+    fn try_read(&self) -> Option<Snapshot> {
+        let before = self.seq.load(Ordering::Acquire);
+        if before % 2 == 1 {
+            return None;
+        }
+        let index = self.index.load(Ordering::Relaxed);
+        let value = self.value.load(Ordering::Relaxed);
+        fence(Ordering::Acquire);
+        (self.seq.load(Ordering::Relaxed) == before).then_some(Snapshot {
+            index,
+            value,
+            version: before / 2 - 1,
+        })
+    }
+}
+
+/// Wait-free reader of the selection; from
+/// [`publish_realtime`](KeyArray::publish_realtime). Clones read the same
+/// publications. Publishing stops once every reader is dropped.
+#[derive(Clone)]
+pub struct RtReader {
+    shared: Arc<Seqlock>,
+    last: Snapshot,
+}
+
+impl RtReader {
+    /// The latest snapshot, or the one read before if a publish is under
+    /// way. Never waits or allocates.
+    pub fn read(&mut self) -> Snapshot {
+        if let Some(snapshot) = self.shared.try_read() {
+            self.last = snapshot;
+        }
+        self.last
+    }
+
+    /// Index of the current key, as by [`read`](Self::read).
+    pub fn index(&mut self) -> usize {
+        self.read().index
+    }
+}
+
+impl Debug for RtReader {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.debug_struct("RtReader").field("last", &self.last).finish()
+    }
+}
+
+/// The current index as a single atomic; from
+/// [`share_index`](KeyArray::share_index). Clones share it. Updates stop
+/// once every clone is dropped.
+#[derive(Debug, Clone)]
+pub struct SharedIndex {
+    index: Arc<AtomicUsize>,
+}
+
+impl SharedIndex {
+    /// Index of the current key: one relaxed load. It was in bounds when
+    /// stored, and stays so until the array is shortened past it, which
+    /// then stores the new index. It is not ordered with other memory.
+    #[inline]
+    pub fn current_index(&self) -> usize {
+        self.index.load(Ordering::Relaxed)
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Keep a [`SharedIndex`] up to date with every change observers are
+    /// told about. Panics if the array is empty.
+    pub fn share_index(&mut self) -> SharedIndex {
+        assert!(!self.is_empty(), "KeyArray::share_index: no keys");
+        let index = Arc::new(AtomicUsize::new(self.idx));
+        self.on_change_weak(&index, |index, _, new, _| index.store(new, Ordering::Relaxed));
+        SharedIndex { index }
+    }
+
+    /// Publish the current index and `value(current key)` to real-time
+    /// readers; see the [module docs](self). Panics if the array is empty.
+    pub fn publish_realtime<F>(&mut self, value: F) -> RtReader
+    where
+        F: Fn(&K) -> u64 + Send + Sync + 'static,
+    {
+        assert!(!self.is_empty(), "KeyArray::publish_realtime: no keys");
+        let shared = Arc::new(Seqlock {
+            seq: AtomicU64::new(0),
+            index: AtomicUsize::new(0),
+            value: AtomicU64::new(0),
+        });
+        shared.publish(self.idx, value(self.current()));
+        self.on_change_weak(&shared, move |lock, _, new, key| lock.publish(new, value(key)));
+        let last = shared.try_read().expect("no publish in progress");
+        RtReader { shared, last }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn reads_are_never_torn() {
+        let mut ka = KeyArray::new((0..8).collect::<Vec<u64>>());
+        let mut reader = ka.publish_realtime(|k| k * 10);
+        assert_eq!(reader.read(), Snapshot { index: 0, value: 0, version: 0 });
+        let mut rt = reader.clone();
+        let audio = thread::spawn(move || {
+            for _ in 0..100_000 {
+                let s = rt.read();
+                assert_eq!(s.value, s.index as u64 * 10);
+            }
+        });
+        for _ in 0..100_000 {
+            ka.next();
+        }
+        audio.join().unwrap();
+        assert_eq!(reader.read().version, 100_000);
+
+        drop(reader);
+        ka.next();
+        assert_eq!(ka.observer_count(), 0);
+
+        let shared = ka.share_index();
+        ka.change(7);
+        ka.remove(7);
+        ka.remove(6);
+        assert_eq!(shared.clone().current_index(), 5);
+    }
+}
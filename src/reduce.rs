@@ -0,0 +1,56 @@
+//! Pure, redux-style state transitions.
+//!
+//! [`reduce`] never mutates its input. Because KeyArray storage is
+//! copy-on-write, a new state produced by a selection-only message shares its
+//! key list with the old one; only structural messages copy the keys.
+//!
+//! ```
+//! use keyarray::{reduce, KeyArray, Msg};
+//!
+//! let s0 = KeyArray::new(["On", "Off", "Auto"]);
+//! let s1 = reduce(&s0, Msg::Next);
+//! assert_eq!((s0.current(), s1.current()), (&"On", &"Off"));
+//! assert!(s1.shares_keys_with(&s0));
+//! ```
+
+use crate::{KeyArray, Msg};
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+/// The state that results from applying `msg` to `state`.
+/// Panics under the same conditions as [`KeyArray::update`].
+pub fn reduce<K>(state: &KeyArray<K>, msg: Msg<K>) -> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    let mut next = state.clone();
+    next.update(msg);
+    next
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Whether `self` and `other` still share one copy of the key list.
+    pub fn shares_keys_with(&self, other: &KeyArray<K>) -> bool {
+        Arc::ptr_eq(&self.keys, &other.keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn structural_messages_copy_on_write() {
+        let s0 = KeyArray::new(["A", "B"]);
+        let s1 = reduce(&s0, Msg::Select(1));
+        let s2 = reduce(&s1, Msg::Insert(0, "Z"));
+        assert!(s1.shares_keys_with(&s0));
+        assert!(!s2.shares_keys_with(&s1));
+        assert_eq!(s1.keys(), &["A", "B"]);
+        assert_eq!(s2.keys(), &["Z", "A", "B"]);
+        assert_eq!(s2.current(), &"B");
+    }
+}
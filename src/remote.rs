@@ -0,0 +1,193 @@
+//! A line-based control protocol for driving a KeyArray from a debug
+//! console, over any `futures` `AsyncRead`/`AsyncWrite` pair (a TCP socket, a
+//! serial port, stdin/stdout).
+//!
+//! ```text
+//! > list
+//! < 0 Off
+//! < * 1 Low
+//! < 2 High
+//! < ok
+//! > select 2
+//! < ok 2 High
+//! > next
+//! < ok 0 Off
+//! > jump
+//! < err unknown command "jump"; try "help"
+//! ```
+//!
+//! Each request is one line; each reply ends with a line starting with `ok`
+//! or `err`. Changes go through an [`AsyncKeyArray`], so subscribers of
+//! [`changes`](AsyncKeyArray::changes) see them like any other update, with
+//! [`REASON`] as their reason.
+
+use crate::stream::AsyncKeyArray;
+use crate::Msg;
+use futures::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+use std::fmt::{Debug, Display, Write as _};
+use std::io;
+use std::str::FromStr;
+
+/// One request line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Command {
+    /// Every key with its index, the current one marked `*`.
+    List,
+    /// The current index and key.
+    Current,
+    Select(usize),
+    Next,
+    Prev,
+    Help,
+    /// End the session.
+    Quit,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    /// Parse a line such as `select 2`; surrounding whitespace is ignored.
+    fn from_str(line: &str) -> Result<Self, String> {
+        let mut words = line.split_whitespace();
+        let command = match (words.next(), words.next()) {
+            (Some("list"), None) => Command::List,
+            (Some("current"), None) => Command::Current,
+            (Some("select"), Some(i)) => {
+                Command::Select(i.parse().map_err(|_| format!("invalid index {:?}", i))?)
+            }
+            (Some("select"), None) => return Err("usage: select <index>".into()),
+            (Some("next"), None) => Command::Next,
+            (Some("prev"), None) => Command::Prev,
+            (Some("help"), None) => Command::Help,
+            (Some("quit"), None) => Command::Quit,
+            (Some(word), _) => {
+                return Err(format!("unknown command {:?}; try \"help\"", word));
+            }
+            (None, _) => return Err("empty command".into()),
+        };
+        match words.next() {
+            Some(extra) => Err(format!("unexpected argument {:?}", extra)),
+            None => Ok(command),
+        }
+    }
+}
+
+/// Reason attached to every change made over the protocol.
+pub const REASON: &str = "remote";
+
+const HELP: &str = "list | current | select <index> | next | prev | help | quit\n";
+
+/// Run `command` and return the full reply, newline-terminated.
+pub fn respond<K>(keys: &AsyncKeyArray<K>, command: Command) -> String
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    let current = |keys: &AsyncKeyArray<K>| {
+        let snapshot = keys.snapshot();
+        format!("ok {} {}\n", snapshot.current_index(), snapshot.current())
+    };
+    match command {
+        Command::List => {
+            let snapshot = keys.snapshot();
+            let mut out = String::new();
+            for (i, key) in snapshot.keys().iter().enumerate() {
+                let mark = if i == snapshot.current_index() {
+                    "* "
+                } else {
+                    ""
+                };
+                let _ = writeln!(out, "{}{} {}", mark, i, key);
+            }
+            out + "ok\n"
+        }
+        Command::Current => current(keys),
+        Command::Select(i) => match keys.try_update_with_reason(Msg::Select(i), REASON) {
+            Ok(Some(ev)) => format!("ok {} {}\n", ev.current, ev.key),
+            Ok(None) => current(keys),
+            Err(e) => format!("err {}\n", e),
+        },
+        Command::Next => {
+            keys.update_with_reason(Msg::Next, REASON);
+            current(keys)
+        }
+        Command::Prev => {
+            keys.update_with_reason(Msg::Prev, REASON);
+            current(keys)
+        }
+        Command::Help => format!("{}ok\n", HELP),
+        Command::Quit => "ok bye\n".into(),
+    }
+}
+
+/// Serve requests from `reader` until it closes or the client sends `quit`.
+/// Blank lines are ignored; malformed ones get an `err` reply.
+pub async fn serve<K, R, W>(keys: &AsyncKeyArray<K>, reader: R, mut writer: W) -> io::Result<()>
+where
+    K: Clone + PartialEq + Debug + Display,
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut lines = reader.lines();
+    while let Some(line) = futures::StreamExt::next(&mut lines).await {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (reply, quit) = match line.parse() {
+            Ok(command) => (respond(keys, command), command == Command::Quit),
+            Err(message) => (format!("err {}\n", message), false),
+        };
+        writer.write_all(reply.as_bytes()).await?;
+        writer.flush().await?;
+        if quit {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KeyArray;
+    use futures::executor::block_on;
+    use futures::io::Cursor;
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(" select 2 ".parse(), Ok(Command::Select(2)));
+        assert_eq!("next".parse(), Ok(Command::Next));
+        assert!("select two".parse::<Command>().is_err());
+        assert!("next 2".parse::<Command>().is_err());
+        assert!("jump".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn session() {
+        let keys = AsyncKeyArray::new(KeyArray::new_with(["Off", "Low", "High"], 1));
+        let input = Cursor::new("list\n\nselect 2\nnext\nselect 9\njump\nquit\nprev\n");
+        let mut output = Vec::new();
+        block_on(serve(&keys, input, &mut output)).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "0 Off\n* 1 Low\n2 High\nok\n\
+             ok 2 High\n\
+             ok 0 Off\n\
+             err index 9 out of bounds for 3 keys\n\
+             err unknown command \"jump\"; try \"help\"\n\
+             ok bye\n"
+        );
+        // nothing after quit was applied
+        assert_eq!(keys.current(), "Off");
+    }
+
+    #[test]
+    fn disabled_keys_are_refused() {
+        let mut ka = KeyArray::new(["Off", "Low", "High"]);
+        ka.disable(1);
+        let keys = AsyncKeyArray::new(ka);
+        assert_eq!(respond(&keys, Command::Select(1)), "err key 1 is disabled\n");
+        assert_eq!(respond(&keys, Command::Select(0)), "ok 0 Off\n");
+        assert_eq!(keys.current(), "Off");
+    }
+}
@@ -0,0 +1,313 @@
+//! Pluggable rendering of a key row.
+//!
+//! A [`KeyArrayRenderer`] decides how one item, the gap between items, and a
+//! (possibly scrolled) window of items are written. Every method has a
+//! default, so a custom renderer only overrides what it needs:
+//!
+//! ```
+//! use keyarray::render::{ItemContext, KeyArrayRenderer, RenderOptions};
+//! use keyarray::KeyArray;
+//! use std::fmt::{self, Write};
+//!
+//! struct Arrows;
+//! impl KeyArrayRenderer<&str> for Arrows {
+//!     fn render_item(&mut self, out: &mut dyn Write, key: &&str, item: ItemContext<'_>) -> fmt::Result {
+//!         write!(out, "{}{}", if item.is_current { "> " } else { "  " }, key)
+//!     }
+//!     fn render_separator(&mut self, out: &mut dyn Write) -> fmt::Result {
+//!         out.write_char('\n')
+//!     }
+//! }
+//!
+//! let ka = KeyArray::new_with(["On", "Off"], 1);
+//! assert_eq!(ka.render_with(&mut Arrows, RenderOptions::all()), "  On\n> Off");
+//! ```
+
+use crate::ui::{ColorHint, Emphasis, KeyStyle, KeyUi};
+use crate::KeyArray;
+use std::fmt::{self, Debug, Display, Write};
+use std::ops::Range;
+
+pub use crate::cursor::window_around;
+
+/// What a renderer knows about the item it is drawing.
+#[derive(Debug, Clone, Copy)]
+pub struct ItemContext<'a> {
+    pub index: usize,
+    pub is_current: bool,
+    /// The key needs a [permission](KeyArray::require_permission) that is
+    /// not granted.
+    pub is_locked: bool,
+    /// The key is [deprecated](KeyArray::deprecate).
+    pub is_deprecated: bool,
+    pub ui: Option<&'a KeyUi>,
+}
+
+/// Hooks for turning a KeyArray into text.
+pub trait KeyArrayRenderer<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Write one key. Default: `[key]` for the current key, the bare key
+    /// otherwise, followed by ` (badge)` if one is attached, ` (deprecated)`
+    /// if it is deprecated and ` (locked)` if it is locked.
+    fn render_item(&mut self, out: &mut dyn Write, key: &K, item: ItemContext<'_>) -> fmt::Result {
+        if item.is_current {
+            write!(out, "[{}]", key)?;
+        } else {
+            write!(out, "{}", key)?;
+        }
+        if let Some(badge) = item.ui.and_then(|ui| ui.badge.as_deref()) {
+            write!(out, " ({})", badge)?;
+        }
+        if item.is_deprecated {
+            out.write_str(" (deprecated)")?;
+        }
+        if item.is_locked {
+            out.write_str(" (locked)")?;
+        }
+        Ok(())
+    }
+
+    /// Write the gap between two items. Default: a single space.
+    fn render_separator(&mut self, out: &mut dyn Write) -> fmt::Result {
+        out.write_char(' ')
+    }
+
+    /// Write the keys in `window`, marking clipped ends with `…`.
+    /// Soft-removed keys are left out.
+    fn render_window(
+        &mut self,
+        out: &mut dyn Write,
+        keys: &KeyArray<K>,
+        window: Range<usize>,
+    ) -> fmt::Result {
+        if window.start > 0 {
+            out.write_char('…')?;
+            self.render_separator(out)?;
+        }
+        let mut first = true;
+        for i in window.clone().filter(|&i| !keys.slots[i].is_hidden()) {
+            if !first {
+                self.render_separator(out)?;
+            }
+            first = false;
+            let item = ItemContext {
+                index: i,
+                is_current: i == keys.current_index(),
+                #[cfg(feature = "permission")]
+                is_locked: !keys.is_permitted(i),
+                #[cfg(not(feature = "permission"))]
+                is_locked: false,
+                #[cfg(feature = "deprecation")]
+                is_deprecated: keys.deprecation(i).is_some(),
+                #[cfg(not(feature = "deprecation"))]
+                is_deprecated: false,
+                ui: keys.ui(i),
+            };
+            self.render_item(out, &keys.keys()[i], item)?;
+        }
+        if window.end < keys.len() {
+            self.render_separator(out)?;
+            out.write_char('…')?;
+        }
+        Ok(())
+    }
+}
+
+/// Uses every default: `Low [Med] High`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlainRenderer;
+
+impl<K> KeyArrayRenderer<K> for PlainRenderer where K: Clone + PartialEq + Debug + Display {}
+
+/// Terminal renderer: the current key in reverse video, locked keys dimmed,
+/// deprecated keys struck through, every key styled by its [`KeyStyle`].
+/// Semantic colors map onto the terminal's own palette.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AnsiRenderer;
+
+impl AnsiRenderer {
+    // SGR parameters for `color`, written straight to `out`; `base` is 30
+    // for the foreground and 40 for the background
+    fn write_color(out: &mut dyn Write, color: ColorHint, base: u8) -> fmt::Result {
+        match color {
+            ColorHint::Default => Ok(()),
+            ColorHint::Accent => write!(out, "{}", base + 6),
+            ColorHint::Success => write!(out, "{}", base + 2),
+            ColorHint::Warning => write!(out, "{}", base + 3),
+            ColorHint::Danger => write!(out, "{}", base + 1),
+            ColorHint::Rgb(r, g, b) => write!(out, "{};2;{};{};{}", base + 8, r, g, b),
+        }
+    }
+}
+
+impl<K> KeyArrayRenderer<K> for AnsiRenderer
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    fn render_item(&mut self, out: &mut dyn Write, key: &K, item: ItemContext<'_>) -> fmt::Result {
+        let style = item.ui.map(KeyUi::style).unwrap_or_default();
+        let marked = item.is_current || item.is_locked || item.is_deprecated;
+        if !marked && style == KeyStyle::default() {
+            return write!(out, " {} ", key);
+        }
+        let Emphasis {
+            bold,
+            italic,
+            underline,
+            dim,
+        } = style.emphasis;
+        let params = [
+            (item.is_current, "7"),
+            (bold, "1"),
+            (dim || item.is_locked, "2"),
+            (italic, "3"),
+            (underline, "4"),
+            (item.is_deprecated, "9"),
+        ];
+        let mut first = true;
+        let mut separate = |out: &mut dyn Write| {
+            if std::mem::take(&mut first) {
+                Ok(())
+            } else {
+                out.write_char(';')
+            }
+        };
+        out.write_str("\x1b[")?;
+        for (_, param) in params.iter().filter(|(on, _)| *on) {
+            separate(out)?;
+            out.write_str(param)?;
+        }
+        for (color, base) in [(style.foreground, 30), (style.background, 40)] {
+            if color != ColorHint::Default {
+                separate(out)?;
+                Self::write_color(out, color, base)?;
+            }
+        }
+        write!(out, "m {} \x1b[0m", key)
+    }
+}
+
+/// Layout choices shared by every renderer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RenderOptions {
+    /// Show at most this many keys around the current one; all if `None`.
+    pub window: Option<usize>,
+}
+
+impl RenderOptions {
+    /// Show every key.
+    pub fn all() -> Self {
+        RenderOptions { window: None }
+    }
+
+    /// Show at most `size` keys around the current one.
+    pub fn window(size: usize) -> Self {
+        RenderOptions { window: Some(size) }
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Stream the rendering into `out` without building intermediate
+    /// strings, e.g. straight into a terminal buffer or a fixed-size array.
+    pub fn render_to<W, R>(
+        &self,
+        out: &mut W,
+        renderer: &mut R,
+        options: RenderOptions,
+    ) -> fmt::Result
+    where
+        W: Write,
+        R: KeyArrayRenderer<K> + ?Sized,
+    {
+        let range = match options.window {
+            Some(size) => window_around(self.idx, self.len(), size),
+            None => 0..self.len(),
+        };
+        renderer.render_window(out, self, range)
+    }
+
+    /// Render into a new `String`; see [`render_to`](Self::render_to).
+    pub fn render_with<R>(&self, renderer: &mut R, options: RenderOptions) -> String
+    where
+        R: KeyArrayRenderer<K> + ?Sized,
+    {
+        let mut out = String::new();
+        self.render_to(&mut out, renderer, options)
+            .expect("writing to a String cannot fail");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_and_windowed() {
+        let mut ka = KeyArray::new(["A", "B", "C", "D", "E"]);
+        ka.change(2);
+        ka.set_ui(3, KeyUi::new().badge("new"));
+        assert_eq!(
+            ka.render_with(&mut PlainRenderer, RenderOptions::all()),
+            "A B [C] D (new) E"
+        );
+        assert_eq!(
+            ka.render_with(&mut PlainRenderer, RenderOptions::window(3)),
+            "… B [C] D (new) …"
+        );
+        ka.change(0);
+        assert_eq!(
+            ka.render_with(&mut PlainRenderer, RenderOptions::window(2)),
+            "[A] B …"
+        );
+    }
+
+    #[test]
+    fn ansi_marks_current() {
+        let mut ka = KeyArray::new(["A", "B"]);
+        ka.set_ui(1, KeyUi::new().color(ColorHint::Danger));
+        ka.set_ui(0, KeyUi::new().color(ColorHint::Rgb(1, 2, 3)));
+        assert_eq!(
+            ka.render_with(&mut AnsiRenderer, RenderOptions::all()),
+            "\x1b[7;38;2;1;2;3m A \x1b[0m \x1b[31m B \x1b[0m"
+        );
+        ka.set_ui(1, KeyUi::new().background(ColorHint::Warning).emphasis(Emphasis::BOLD));
+        ka.set_ui(0, KeyUi::new().emphasis(Emphasis::UNDERLINE));
+        assert_eq!(
+            ka.render_with(&mut AnsiRenderer, RenderOptions::all()),
+            "\x1b[7;4m A \x1b[0m \x1b[1;43m B \x1b[0m"
+        );
+    }
+
+    // a fixed-capacity sink: rendering must not need a heap buffer
+    struct Buf {
+        bytes: [u8; 64],
+        len: usize,
+    }
+
+    impl Write for Buf {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let end = self.len + s.len();
+            let dst = self.bytes.get_mut(self.len..end).ok_or(fmt::Error)?;
+            dst.copy_from_slice(s.as_bytes());
+            self.len = end;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn render_to_fixed_buffer() {
+        let ka = KeyArray::new_with(["Lo", "Hi"], 1);
+        let mut buf = Buf { bytes: [0; 64], len: 0 };
+        ka.render_to(&mut buf, &mut PlainRenderer, RenderOptions::all()).unwrap();
+        assert_eq!(&buf.bytes[..buf.len], b"Lo [Hi]");
+
+        let long = KeyArray::new(["x"; 40]);
+        assert!(long.render_to(&mut buf, &mut PlainRenderer, RenderOptions::all()).is_err());
+    }
+}
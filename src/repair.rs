@@ -0,0 +1,184 @@
+//! Lenient loading of persisted state.
+//!
+//! Saved state can be stale or hand-edited. Instead of rejecting it,
+//! [`from_texts_lenient`](KeyArray::from_texts_lenient) keeps what it can
+//! and lists every correction in a [`RepairReport`] for logging or for
+//! telling the user.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//!
+//! let known = KeyArray::new(["Light", "Dark", "Sepia"].map(String::from));
+//! let saved = ["Dark", "Neon", "Dark", "Light"];
+//! let (ka, report) = KeyArray::from_texts_lenient(saved, 9, known);
+//! assert_eq!(ka.keys(), ["Dark", "Light"]);
+//! assert_eq!(ka.current(), "Light");
+//! assert_eq!(report.repairs.len(), 3);
+//! ```
+
+use crate::KeyArray;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+/// One correction made while loading.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Repair {
+    /// The saved key at `position` did not parse and was dropped.
+    UnparsableKey { position: usize, text: String },
+    /// The saved key at `position` is not a known key and was dropped.
+    UnknownKey { position: usize, text: String },
+    /// The saved key at `position` repeats the one at `first` and was
+    /// dropped.
+    DuplicateKey { position: usize, first: usize },
+    /// The saved selection `saved` was out of range or dropped; `index` is
+    /// selected instead.
+    IndexClamped { saved: usize, index: usize },
+    /// No saved key survived; the defaults were used as they are.
+    UsedDefaults,
+}
+
+impl Display for Repair {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Repair::UnparsableKey { position, text } => {
+                write!(f, "dropped unparsable key {:?} at {}", text, position)
+            }
+            Repair::UnknownKey { position, text } => {
+                write!(f, "dropped unknown key {:?} at {}", text, position)
+            }
+            Repair::DuplicateKey { position, first } => {
+                write!(f, "dropped key at {} duplicating {}", position, first)
+            }
+            Repair::IndexClamped { saved, index } => {
+                write!(f, "selection {} replaced by {}", saved, index)
+            }
+            Repair::UsedDefaults => f.write_str("nothing usable saved, used defaults"),
+        }
+    }
+}
+
+/// Every correction made by a lenient load, in the order they were made.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RepairReport {
+    pub repairs: Vec<Repair>,
+}
+
+impl RepairReport {
+    /// Whether the saved state was loaded unchanged.
+    pub fn is_clean(&self) -> bool {
+        self.repairs.is_empty()
+    }
+}
+
+/// One repair per line.
+impl Display for RepairReport {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        for (i, repair) in self.repairs.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+            write!(f, "{}", repair)?;
+        }
+        Ok(())
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display + FromStr,
+{
+    /// Rebuild a saved key list (as `Display` text) with `selected` current,
+    /// accepting only keys found in `known`. Unparsable, unknown and
+    /// repeated keys are dropped; if the selected key is dropped or out of
+    /// range, the closest surviving key before it is selected. If nothing
+    /// survives, `known` is returned. Ids and attached data start fresh.
+    pub fn from_texts_lenient<S: AsRef<str>>(
+        texts: impl IntoIterator<Item = S>,
+        selected: usize,
+        known: KeyArray<K>,
+    ) -> (Self, RepairReport) {
+        let mut report = RepairReport::default();
+        let mut keys: Vec<K> = Vec::new();
+        // saved position of each kept key
+        let mut kept_from = Vec::new();
+        let mut index = None;
+        for (position, text) in texts.into_iter().enumerate() {
+            let text = text.as_ref();
+            let Ok(key) = text.parse::<K>() else {
+                let text = text.to_string();
+                report
+                    .repairs
+                    .push(Repair::UnparsableKey { position, text });
+                continue;
+            };
+            if !known.keys.contains(&key) {
+                let text = text.to_string();
+                report.repairs.push(Repair::UnknownKey { position, text });
+                continue;
+            }
+            if let Some(first) = keys.iter().position(|k| *k == key) {
+                let first = kept_from[first];
+                report
+                    .repairs
+                    .push(Repair::DuplicateKey { position, first });
+                continue;
+            }
+            if position == selected {
+                index = Some(keys.len());
+            }
+            keys.push(key);
+            kept_from.push(position);
+        }
+        if keys.is_empty() {
+            report.repairs.push(Repair::UsedDefaults);
+            return (known, report);
+        }
+        let index = index.unwrap_or_else(|| {
+            let index = kept_from.partition_point(|&p| p < selected).max(1) - 1;
+            report.repairs.push(Repair::IndexClamped {
+                saved: selected,
+                index,
+            });
+            index
+        });
+        (KeyArray::new_with(keys, index), report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_fix_is_reported() {
+        let known = KeyArray::new([1u8, 2, 3]);
+        let (ka, report) = KeyArray::from_texts_lenient(["2", "x", "9", "3", "2"], 4, known);
+        assert_eq!((ka.keys(), ka.current()), (&[2, 3][..], &3));
+        assert_eq!(
+            report.repairs,
+            [
+                Repair::UnparsableKey {
+                    position: 1,
+                    text: "x".into()
+                },
+                Repair::UnknownKey {
+                    position: 2,
+                    text: "9".into()
+                },
+                Repair::DuplicateKey {
+                    position: 4,
+                    first: 0
+                },
+                Repair::IndexClamped { saved: 4, index: 1 },
+            ]
+        );
+
+        let (ka, report) = KeyArray::from_texts_lenient(["3", "1"], 1, KeyArray::new([1u8, 3]));
+        assert!(report.is_clean());
+        assert_eq!(ka.current(), &1);
+
+        let (ka, report) = KeyArray::from_texts_lenient(["?"], 0, KeyArray::new_with([1u8, 3], 1));
+        assert_eq!(report.repairs.last(), Some(&Repair::UsedDefaults));
+        assert_eq!(ka.current(), &3);
+    }
+}
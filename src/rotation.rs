@@ -0,0 +1,257 @@
+//! Load-balancer style rotation: `next()` visits keys in proportion to
+//! their weights.
+//!
+//! Under [`Rotation::Weighted`], [`KeyArray::next`] runs smooth weighted
+//! round-robin (as in nginx): picks are spread out, so weights 5:1:1 give
+//! `A A B A C A A` rather than `A A A A A B C`.
+//!
+//! ```
+//! use keyarray::{KeyArray, Rotation};
+//!
+//! let mut backends = KeyArray::new(["a", "b", "c"]);
+//! backends.set_rotation(Rotation::Weighted);
+//! backends.set_weight(0, 5);
+//! let picks: String = (0..7)
+//!     .map(|_| {
+//!         backends.next();
+//!         *backends.current()
+//!     })
+//!     .collect();
+//! assert_eq!(picks, "aabacaa");
+//! ```
+//!
+//! In either mode, a key reported with [`mark_failed`](KeyArray::mark_failed)
+//! sits out of rotation for a while, and longer after each consecutive
+//! failure, so `next()` alone gives simple failover. If every key is sitting
+//! out, rotation ignores health rather than getting stuck.
+
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+/// Longest time out of rotation, in rounds.
+pub const MAX_BACKOFF_ROUNDS: u32 = 64;
+
+/// How [`KeyArray::next`] chooses the following key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Rotation {
+    /// The key after the current one.
+    #[default]
+    Sequential,
+    /// Smooth weighted round-robin over [`weight`](KeyArray::weight)s.
+    /// Keys with weight 0 are never picked.
+    Weighted,
+}
+
+// Per-key scheduling state, kept in the key's slot.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct KeyRotation {
+    weight: u32,
+    // smooth WRR "current weight"
+    credit: i64,
+    // consecutive failures
+    failures: u32,
+    // calls to `next` left before the key re-enters rotation
+    benched: u64,
+}
+
+impl Default for KeyRotation {
+    fn default() -> Self {
+        KeyRotation {
+            weight: 1,
+            credit: 0,
+            failures: 0,
+            benched: 0,
+        }
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    pub fn rotation(&self) -> Rotation {
+        self.rotation
+    }
+
+    /// Choose how [`next`](Self::next) moves. Switching modes restarts the
+    /// weighted schedule.
+    pub fn set_rotation(&mut self, rotation: Rotation) {
+        if self.rotation != rotation {
+            self.rotation = rotation;
+            for slot in Arc::make_mut(&mut self.slots) {
+                slot.rotation.credit = 0;
+            }
+            self.touch();
+        }
+    }
+
+    /// Weight of key `i`; every key starts at 1.
+    pub fn weight(&self, i: usize) -> Option<u32> {
+        Some(self.slots.get(i)?.rotation.weight)
+    }
+
+    /// Set the weight of key `i`; 0 takes it out of weighted rotation.
+    /// Takes effect from the next pick. Panics if `i` is out of bounds.
+    pub fn set_weight(&mut self, i: usize, weight: u32) {
+        assert!(
+            i < self.keys.len(),
+            "KeyArray::set_weight: index {} out of bounds",
+            i
+        );
+        Arc::make_mut(&mut self.slots)[i].rotation.weight = weight;
+        self.touch();
+    }
+
+    /// Report key `i` as failing. It is skipped by [`next`](Self::next) for
+    /// 1 round (`len()` calls), then 2, 4, ... up to
+    /// [`MAX_BACKOFF_ROUNDS`] on consecutive failures. Panics if `i` is out
+    /// of bounds.
+    pub fn mark_failed(&mut self, i: usize) {
+        assert!(
+            i < self.keys.len(),
+            "KeyArray::mark_failed: index {} out of bounds",
+            i
+        );
+        let len = self.keys.len() as u64;
+        let r = &mut Arc::make_mut(&mut self.slots)[i].rotation;
+        r.failures = r.failures.saturating_add(1);
+        let rounds = 1u64 << (r.failures - 1).min(MAX_BACKOFF_ROUNDS.ilog2());
+        r.benched = rounds * len;
+        self.touch();
+    }
+
+    /// Report key `i` as working again: clear its failures and return it to
+    /// rotation at once. Panics if `i` is out of bounds.
+    pub fn mark_healthy(&mut self, i: usize) {
+        assert!(
+            i < self.keys.len(),
+            "KeyArray::mark_healthy: index {} out of bounds",
+            i
+        );
+        if self.slots[i].rotation.failures > 0 || self.slots[i].rotation.benched > 0 {
+            let r = &mut Arc::make_mut(&mut self.slots)[i].rotation;
+            r.failures = 0;
+            r.benched = 0;
+            self.touch();
+        }
+    }
+
+    /// Consecutive failures of key `i` since it was last marked healthy.
+    pub fn failures(&self, i: usize) -> Option<u32> {
+        Some(self.slots.get(i)?.rotation.failures)
+    }
+
+    /// Whether key `i` is currently in rotation.
+    pub fn is_in_rotation(&self, i: usize) -> bool {
+        self.slots.get(i).is_some_and(|s| s.rotation.benched == 0)
+    }
+
+    // `next` with rotation enabled from key `from`: pick the key to go to,
+    // then count down backoffs. Only the weights' credits change here.
+    pub(crate) fn rotate(&mut self, from: usize) -> usize {
+        let benched = self.slots.iter().any(|s| s.rotation.benched > 0);
+        // fail open when nothing is left in rotation
+        let health = benched && (0..self.len()).any(|i| self.is_in_rotation(i));
+        let to = match self.rotation {
+            Rotation::Weighted => self.next_weighted(health).unwrap_or(from),
+            Rotation::Sequential if !health => self.wrapped(from, 1),
+            Rotation::Sequential => {
+                let len = self.len();
+                (1..=len)
+                    .map(|step| (from + step) % len)
+                    .find(|&i| self.is_in_rotation(i))
+                    .expect("some key is in rotation")
+            }
+        };
+        if benched {
+            for slot in Arc::make_mut(&mut self.slots) {
+                slot.rotation.benched = slot.rotation.benched.saturating_sub(1);
+            }
+        }
+        to
+    }
+
+    // One smooth weighted round-robin step, skipping benched keys if
+    // `health`: the key it picks, `None` if no key has weight.
+    fn next_weighted(&mut self, health: bool) -> Option<usize> {
+        let weight = |r: &KeyRotation| {
+            if health && r.benched > 0 {
+                0
+            } else {
+                r.weight as i64
+            }
+        };
+        let total: i64 = self.slots.iter().map(|s| weight(&s.rotation)).sum();
+        if total == 0 {
+            return None;
+        }
+        let slots = Arc::make_mut(&mut self.slots);
+        // highest credit wins; ties go to the lowest index
+        let mut best: Option<(usize, i64)> = None;
+        for (i, slot) in slots.iter_mut().enumerate() {
+            let w = weight(&slot.rotation);
+            let r = &mut slot.rotation;
+            r.credit += w;
+            if w > 0 && best.is_none_or(|(_, credit)| r.credit > credit) {
+                best = Some((i, r.credit));
+            }
+        }
+        let (best, _) = best.expect("total weight is positive");
+        slots[best].rotation.credit -= total;
+        Some(best)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weights_are_honoured_and_adjustable() {
+        let mut ka = KeyArray::new(["a", "b", "c"]);
+        ka.set_rotation(Rotation::Weighted);
+        ka.set_weight(1, 3);
+        ka.set_weight(2, 0);
+        let mut counts = [0; 3];
+        for _ in 0..40 {
+            ka.next();
+            counts[ka.current_index()] += 1;
+        }
+        assert_eq!(counts, [10, 30, 0]);
+
+        ka.set_weight(1, 0);
+        ka.set_weight(0, 0);
+        ka.next();
+        assert_eq!(ka.current_index(), 1);
+        ka.set_rotation(Rotation::Sequential);
+        ka.next();
+        assert_eq!(ka.current_index(), 2);
+    }
+
+    #[test]
+    fn failed_keys_back_off() {
+        let mut ka = KeyArray::new(["a", "b", "c"]);
+        ka.mark_failed(1);
+        let mut picks = String::new();
+        for _ in 0..6 {
+            ka.next();
+            picks.push_str(ka.current());
+        }
+        // out for one round (3 calls), then back
+        assert_eq!(picks, "cacabc");
+
+        ka.mark_failed(1);
+        ka.mark_failed(1);
+        assert_eq!(ka.failures(1), Some(3));
+        assert!(!ka.is_in_rotation(1));
+        ka.mark_healthy(1);
+        assert!(ka.is_in_rotation(1));
+
+        // everyone failing: rotation carries on regardless
+        (0..3).for_each(|i| ka.mark_failed(i));
+        ka.change(0);
+        ka.next();
+        assert_eq!(ka.current(), &"b");
+    }
+}
@@ -0,0 +1,101 @@
+//! Declarative constraints between keys of a [`MultiKeyArray`].
+//!
+//! Rules name keys by value and are checked on every mutation; a mutation
+//! that would break one is rejected with a [`RuleViolation`] describing it.
+//!
+//! ```
+//! use keyarray::multi::{MultiKeyArray, Violation};
+//! use keyarray::rules::Rule;
+//!
+//! let mut opts = MultiKeyArray::new(["Advanced", "Custom", "Eco", "Turbo"]);
+//! opts.add_rule(Rule::requires("Custom", "Advanced")).unwrap();
+//! opts.add_rule(Rule::excludes("Eco", "Turbo")).unwrap();
+//!
+//! let err = opts.press(1).unwrap_err();
+//! assert_eq!(err.to_string(), "\"Custom\" requires \"Advanced\" to be pressed");
+//! opts.press(0).unwrap();
+//! opts.press(1).unwrap();
+//! opts.press(2).unwrap();
+//! assert!(matches!(opts.press(3), Err(Violation::Rule(_))));
+//! ```
+//!
+//! [`MultiKeyArray`]: crate::multi::MultiKeyArray
+
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+/// A constraint between two keys.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Rule<K> {
+    /// `key` may only be pressed while `needs` is pressed.
+    Requires { key: K, needs: K },
+    /// The two keys may not be pressed together.
+    Excludes(K, K),
+}
+
+impl<K: PartialEq> Rule<K> {
+    pub fn requires(key: K, needs: K) -> Self {
+        Rule::Requires { key, needs }
+    }
+
+    pub fn excludes(a: K, b: K) -> Self {
+        Rule::Excludes(a, b)
+    }
+
+    /// Whether the press state `pressed` (parallel to `keys`) satisfies the
+    /// rule. Keys missing from `keys` count as released.
+    pub fn is_satisfied(&self, keys: &[K], pressed: &[bool]) -> bool {
+        let on = |k: &K| {
+            keys.iter()
+                .zip(pressed)
+                .any(|(candidate, &p)| p && candidate == k)
+        };
+        match self {
+            Rule::Requires { key, needs } => !on(key) || on(needs),
+            Rule::Excludes(a, b) => !(on(a) && on(b)),
+        }
+    }
+}
+
+impl<K: Debug> Display for Rule<K> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Rule::Requires { key, needs } => {
+                write!(f, "{:?} requires {:?} to be pressed", key, needs)
+            }
+            Rule::Excludes(a, b) => write!(f, "{:?} and {:?} cannot both be pressed", a, b),
+        }
+    }
+}
+
+/// A mutation was rejected because it would break `rule`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RuleViolation<K> {
+    pub rule: Rule<K>,
+}
+
+impl<K: Debug> Display for RuleViolation<K> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        Display::fmt(&self.rule, f)
+    }
+}
+
+impl<K: Debug> std::error::Error for RuleViolation<K> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MultiKeyArray;
+
+    #[test]
+    fn bulk_ops_are_checked_and_add_rule_validates() {
+        let mut m = MultiKeyArray::new(["Eco", "Turbo", "Quiet"]);
+        m.add_rule(Rule::excludes("Eco", "Turbo")).unwrap();
+        assert!(m.select_all().is_err());
+        assert_eq!(m.pressed_count(), 0);
+        m.press(2).unwrap();
+        assert!(m.add_rule(Rule::requires("Quiet", "Eco")).is_err());
+        assert_eq!(m.rules().len(), 1);
+    }
+}
@@ -0,0 +1,280 @@
+//! A tiny expression language for scripted selection.
+//!
+//! Configuration files and consoles can drive a KeyArray with strings such
+//! as `"next"`, `"prev*2"`, `"select 'Off'"` or `"first matching /^dev/"`
+//! instead of each inventing a parser. Steps are separated by `;` and run
+//! in order; [`execute`](KeyArray::execute) applies all of them or, if one
+//! fails, none.
+//!
+//! | Step                        | Selects                                      |
+//! |-----------------------------|----------------------------------------------|
+//! | `next`, `prev`              | as [`next`](KeyArray::next) / [`prev`](KeyArray::prev) |
+//! | `next*3`, `prev*2`          | the same, repeated                           |
+//! | `first`, `last`             | the first or last key                        |
+//! | `select 2`                  | key 2                                        |
+//! | `select 'Off'`              | the first key displayed as `Off`             |
+//! | `first matching /pat/`      | the first key whose display matches `pat`    |
+//! | `last matching /pat/`       | the last such key                            |
+//! | `next matching /pat/`       | the next such key after the current one, wrapping |
+//!
+//! Patterns are literal text, optionally anchored with `^` and `$`; there
+//! are no other metacharacters.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//!
+//! let mut env = KeyArray::new(["prod", "dev-eu", "staging", "dev-us"]);
+//! assert_eq!(env.execute("first matching /^dev/"), Ok(1));
+//! assert_eq!(env.execute("next matching /^dev/; prev*2"), Ok(1));
+//! assert_eq!(env.execute("select 'staging'"), Ok(2));
+//! assert!(env.execute("next; select 'qa'").is_err());
+//! assert_eq!(env.current(), &"staging");
+//! ```
+
+use crate::error::KeyArrayError;
+use crate::KeyArray;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+/// One parsed step of an expression.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Step {
+    Next(usize),
+    Prev(usize),
+    First,
+    Last,
+    Select(usize),
+    SelectName(String),
+    Matching(Which, Pattern),
+}
+
+/// Which of the keys matching a pattern [`Step::Matching`] selects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Which {
+    First,
+    Last,
+    /// The first after the current key, wrapping.
+    Next,
+}
+
+/// A literal pattern, optionally anchored at either end.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Pattern {
+    text: String,
+    start: bool,
+    end: bool,
+}
+
+impl Pattern {
+    /// Whether `s` contains the pattern, honouring its anchors.
+    pub fn matches(&self, s: &str) -> bool {
+        match (self.start, self.end) {
+            (true, true) => s == self.text,
+            (true, false) => s.starts_with(&self.text),
+            (false, true) => s.ends_with(&self.text),
+            (false, false) => s.contains(&self.text),
+        }
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = ScriptError;
+
+    /// Parse the text between the slashes of `/^dev/`.
+    fn from_str(s: &str) -> Result<Self, ScriptError> {
+        let (start, s) = match s.strip_prefix('^') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        let (end, s) = match s.strip_suffix('$') {
+            Some(rest) => (true, rest),
+            None => (false, s),
+        };
+        Ok(Pattern {
+            text: s.to_owned(),
+            start,
+            end,
+        })
+    }
+}
+
+/// Why an expression was not executed. Nothing is changed when it is not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptError {
+    /// The text is not a valid expression.
+    Parse(String),
+    /// No key has the name or matches the pattern of step `step`.
+    NoMatch { step: usize },
+    /// Step `step` failed, e.g. selected an index out of bounds.
+    Step { step: usize, error: KeyArrayError },
+}
+
+impl Display for ScriptError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            ScriptError::Parse(message) => f.write_str(message),
+            ScriptError::NoMatch { step } => write!(f, "step {}: no key matches", step + 1),
+            ScriptError::Step { step, error } => write!(f, "step {}: {}", step + 1, error),
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+impl FromStr for Step {
+    type Err = ScriptError;
+
+    fn from_str(step: &str) -> Result<Self, ScriptError> {
+        let step = step.trim();
+        let parse_err = |message: String| Err(ScriptError::Parse(message));
+        let (word, rest) = step.split_once(char::is_whitespace).unwrap_or((step, ""));
+        let rest = rest.trim();
+        let (word, times) = match word.split_once('*') {
+            Some((word, n)) => match n.parse::<usize>() {
+                Ok(n) => (word, Some(n)),
+                Err(_) => return parse_err(format!("invalid count {:?}", n)),
+            },
+            None => (word, None),
+        };
+        if let Some(pattern) = rest.strip_prefix("matching") {
+            let which = match word {
+                "first" => Which::First,
+                "last" => Which::Last,
+                "next" => Which::Next,
+                _ => return parse_err(format!("{:?} cannot take \"matching\"", word)),
+            };
+            let pattern = pattern.trim();
+            let Some(inner) = pattern
+                .strip_prefix('/')
+                .and_then(|p| p.strip_suffix('/'))
+                .filter(|_| pattern.len() >= 2 && times.is_none())
+            else {
+                return parse_err(format!("usage: {} matching /pattern/", word));
+            };
+            return Ok(Step::Matching(which, inner.parse()?));
+        }
+        let step = match (word, rest) {
+            ("next", "") => Step::Next(times.unwrap_or(1)),
+            ("prev", "") => Step::Prev(times.unwrap_or(1)),
+            ("first", "") if times.is_none() => Step::First,
+            ("last", "") if times.is_none() => Step::Last,
+            ("select", arg) if times.is_none() && !arg.is_empty() => {
+                match arg.strip_prefix('\'').and_then(|a| a.strip_suffix('\'')) {
+                    Some(name) if arg.len() >= 2 => Step::SelectName(name.to_owned()),
+                    _ => match arg.parse() {
+                        Ok(i) => Step::Select(i),
+                        Err(_) => return parse_err(format!("invalid index {:?}", arg)),
+                    },
+                }
+            }
+            ("select", _) => return parse_err("usage: select <index> | select '<name>'".into()),
+            ("", _) => return parse_err("empty step".into()),
+            _ => return parse_err(format!("unknown step {:?}", step)),
+        };
+        Ok(step)
+    }
+}
+
+/// Parse `expr` into its `;`-separated steps.
+pub fn parse(expr: &str) -> Result<Vec<Step>, ScriptError> {
+    expr.split(';').map(str::parse).collect()
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Parse and run `expr` (see the [module docs](crate::script)) and
+    /// return the resulting index. If parsing or any step fails, the
+    /// selection is left as it was.
+    pub fn execute(&mut self, expr: &str) -> Result<usize, ScriptError> {
+        let steps = parse(expr)?;
+        self.execute_steps(&steps)
+    }
+
+    /// Run already parsed steps, all or none, and return the resulting
+    /// index.
+    pub fn execute_steps(&mut self, steps: &[Step]) -> Result<usize, ScriptError> {
+        // find the target on a copy, so observers only see the outcome
+        let mut scratch = self.clone();
+        for (n, step) in steps.iter().enumerate() {
+            scratch.run_step(n, step)?;
+        }
+        self.try_change(scratch.idx)
+            .map_err(|error| ScriptError::Step { step: 0, error })?;
+        Ok(self.idx)
+    }
+
+    fn run_step(&mut self, n: usize, step: &Step) -> Result<(), ScriptError> {
+        let no_match = ScriptError::NoMatch { step: n };
+        let fail = |error| ScriptError::Step { step: n, error };
+        let names = || self.keys.iter().map(|k| k.to_string());
+        let target = match step {
+            Step::Next(times) => {
+                (0..*times).for_each(|_| self.next());
+                return Ok(());
+            }
+            Step::Prev(times) => {
+                (0..*times).for_each(|_| self.prev());
+                return Ok(());
+            }
+            Step::First => 0,
+            Step::Last => self.len().saturating_sub(1),
+            Step::Select(i) => *i,
+            Step::SelectName(name) => names().position(|k| k == *name).ok_or(no_match)?,
+            Step::Matching(Which::First, p) => names().position(|k| p.matches(&k)).ok_or(no_match)?,
+            Step::Matching(Which::Last, p) => {
+                let names: Vec<String> = names().collect();
+                names.iter().rposition(|k| p.matches(k)).ok_or(no_match)?
+            }
+            Step::Matching(Which::Next, p) => {
+                let names: Vec<String> = names().collect();
+                let len = self.len();
+                (1..=len)
+                    .map(|d| (self.idx + d) % len)
+                    .find(|&i| p.matches(&names[i]))
+                    .ok_or(no_match)?
+            }
+        };
+        self.try_change(target).map_err(fail)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_form() {
+        assert_eq!(
+            parse(" next ; prev*2;first;select 3; select 'a b'").unwrap(),
+            [
+                Step::Next(1),
+                Step::Prev(2),
+                Step::First,
+                Step::Select(3),
+                Step::SelectName("a b".into())
+            ]
+        );
+        let step: Step = "last matching /x$/".parse().unwrap();
+        let Step::Matching(Which::Last, p) = step else {
+            panic!("parsed as {:?}", step);
+        };
+        assert!(p.matches("box") && !p.matches("xo"));
+        let bad = ["", "jump", "select", "first*2", "select two", "prev matching /a/"];
+        for bad in bad.into_iter().chain(["next matching a"]) {
+            assert!(parse(bad).is_err(), "{:?} parsed", bad);
+        }
+    }
+
+    #[test]
+    fn failing_step_changes_nothing() {
+        let mut ka = KeyArray::new(["a1", "b", "a2"]);
+        assert_eq!(ka.execute("last matching /^a/"), Ok(2));
+        assert_eq!(ka.execute("next matching /a/"), Ok(0));
+        let error = KeyArrayError::IndexOutOfBounds { index: 7, len: 3 };
+        assert_eq!(ka.execute("last; select 7"), Err(ScriptError::Step { step: 1, error }));
+        assert_eq!(ka.execute("next; select 'z'"), Err(ScriptError::NoMatch { step: 1 }));
+        assert_eq!(ka.current_index(), 0);
+    }
+}
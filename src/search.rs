@@ -0,0 +1,144 @@
+//! Ranked search over the keys, with pluggable scoring.
+//!
+//! [`search`](KeyArray::search) scores every visible key against a query
+//! with a [`Matcher`] and returns the matches best first. The built-in
+//! [`FuzzyMatcher`] accepts the query's characters in order with gaps,
+//! preferring word starts and runs; any `Fn(&str, usize, &K) ->
+//! Option<i64>` is a matcher too, so domain-specific ranking, such as
+//! boosting recently used keys, can wrap it.
+//!
+//! ```
+//! use keyarray::search::{FuzzyMatcher, Matcher};
+//! use keyarray::KeyArray;
+//!
+//! let mut cmds = KeyArray::new(["Open File", "Profile", "Find"]);
+//! let hits = cmds.search("fi", &FuzzyMatcher);
+//! let found: Vec<_> = hits.iter().map(|h| h.index).collect();
+//! assert_eq!(found, [2, 0, 1]);
+//!
+//! // boost a favourite over the built-in ranking
+//! let favourite = |q: &str, i: usize, k: &&str| {
+//!     FuzzyMatcher.score(q, i, k).map(|s| if i == 1 { s + 100 } else { s })
+//! };
+//! assert_eq!(cmds.search_select("fi", &favourite), Some(1));
+//! assert_eq!(cmds.current(), &"Profile");
+//! ```
+
+use crate::KeyArray;
+use std::cmp::Reverse;
+use std::fmt::{Debug, Display};
+
+/// Scores keys against a search query.
+pub trait Matcher<K> {
+    /// How well key `index`, `key`, matches `query`; higher is better.
+    /// `None` leaves the key out of the results.
+    fn score(&self, query: &str, index: usize, key: &K) -> Option<i64>;
+}
+
+impl<K, F> Matcher<K> for F
+where
+    F: Fn(&str, usize, &K) -> Option<i64>,
+{
+    fn score(&self, query: &str, index: usize, key: &K) -> Option<i64> {
+        self(query, index, key)
+    }
+}
+
+/// Case-insensitive subsequence matching on the key's `Display` text. Each
+/// matched character scores, more at the start of a word or right after the
+/// previous match, and skipped characters cost a little. An empty query
+/// matches everything equally.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FuzzyMatcher;
+
+impl<K: Display> Matcher<K> for FuzzyMatcher {
+    fn score(&self, query: &str, _index: usize, key: &K) -> Option<i64> {
+        let text = key.to_string();
+        let mut wanted = query.chars().flat_map(char::to_lowercase).peekable();
+        let mut score = 0;
+        let mut previous: Option<char> = None;
+        let mut matched_last = false;
+        for c in text.chars() {
+            let Some(&w) = wanted.peek() else {
+                break;
+            };
+            let word_start = previous.is_none_or(|p| !p.is_alphanumeric());
+            previous = Some(c);
+            if c.to_lowercase().eq(std::iter::once(w)) {
+                wanted.next();
+                score += 1 + 8 * i64::from(word_start) + 4 * i64::from(matched_last);
+                matched_last = true;
+            } else {
+                score -= 1;
+                matched_last = false;
+            }
+        }
+        wanted.peek().is_none().then_some(score)
+    }
+}
+
+/// One search result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SearchHit {
+    pub index: usize,
+    pub score: i64,
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// The keys `matcher` accepts for `query`, best first; equal scores
+    /// keep key order. Hidden keys are left out.
+    pub fn search<M>(&self, query: &str, matcher: &M) -> Vec<SearchHit>
+    where
+        M: Matcher<K> + ?Sized,
+    {
+        let mut hits: Vec<SearchHit> = (0..self.len())
+            .filter(|&i| !self.slots[i].is_hidden())
+            .filter_map(|index| {
+                let score = matcher.score(query, index, &self.keys[index])?;
+                Some(SearchHit { index, score })
+            })
+            .collect();
+        hits.sort_by_key(|h| Reverse(h.score));
+        hits
+    }
+
+    /// Select the best enabled match for `query`. Returns its index, or
+    /// `None`, changing nothing, if nothing matches.
+    pub fn search_select<M>(&mut self, query: &str, matcher: &M) -> Option<usize>
+    where
+        M: Matcher<K> + ?Sized,
+    {
+        let hits = self.search(query, matcher);
+        let best = hits.iter().find(|h| self.selectable(h.index))?.index;
+        self.change(best);
+        Some(best)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_ranks_word_starts_and_runs() {
+        let score = |q: &str, k: &str| FuzzyMatcher.score(q, 0, &k);
+        assert_eq!(score("", "x"), Some(0));
+        assert_eq!(score("xz", "abc"), None);
+        assert!(score("ab", "abc") > score("ab", "axb"));
+        assert!(score("b", "a b") > score("b", "ab"));
+
+        #[cfg(all(feature = "disable", feature = "tombstone"))]
+        {
+            let mut ka = KeyArray::new(["Dark", "Dim", "Default"]);
+            ka.disable(1);
+            let found: Vec<_> = ka.search("d", &FuzzyMatcher).iter().map(|h| h.index).collect();
+            assert_eq!(found, [0, 1, 2]);
+            ka.soft_remove(0);
+            assert_eq!(ka.search_select("d", &FuzzyMatcher), Some(2));
+            assert_eq!(ka.search_select("q", &FuzzyMatcher), None);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! `Serialize`/`Deserialize` for KeyArray.
+//!
+//! The keys, the current index and bookmarks (by index) are written; ids,
+//! attached data and per-instance settings are not. Deserializing checks
+//! what `new_with` would assert, so bad input is an error rather than a
+//! KeyArray that panics later.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//!
+//! let mut mode = KeyArray::new(["On", "Off", "Auto"]);
+//! mode.change(2);
+//! let json = serde_json::to_string(&mode).unwrap();
+//! assert_eq!(json, r#"{"keys":["On","Off","Auto"],"current":2}"#);
+//!
+//! let back: KeyArray<String> = serde_json::from_str(&json).unwrap();
+//! assert_eq!(back.current(), "Auto");
+//! assert!(serde_json::from_str::<KeyArray<String>>(r#"{"keys":[],"current":0}"#).is_err());
+//! ```
+
+use crate::KeyArray;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+#[derive(Serialize)]
+#[serde(rename = "KeyArray")]
+struct Ser<'a, K> {
+    keys: &'a [K],
+    current: usize,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    bookmarks: BTreeMap<&'a str, usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "KeyArray", deny_unknown_fields)]
+struct De<K> {
+    keys: Vec<K>,
+    current: usize,
+    #[serde(default)]
+    bookmarks: BTreeMap<String, usize>,
+}
+
+/// Bookmarks whose key was removed are left out.
+impl<K> Serialize for KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display + Serialize,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let bookmarks = self
+            .bookmarks()
+            .filter_map(|(name, id)| Some((name, self.index_of_id(id)?)))
+            .collect();
+        Ser {
+            keys: &self.keys,
+            current: self.idx,
+            bookmarks,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de, K> Deserialize<'de> for KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display + Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let De {
+            keys,
+            current,
+            bookmarks,
+        } = De::deserialize(deserializer)?;
+        let mut ka = KeyArray::try_new_with(keys, current).map_err(D::Error::custom)?;
+        let mut marks = BTreeMap::new();
+        for (name, i) in bookmarks {
+            let id = ka.id_at(i).ok_or_else(|| {
+                D::Error::custom(format_args!(
+                    "bookmark {:?}: index {} out of bounds",
+                    name, i
+                ))
+            })?;
+            marks.insert(name, id);
+        }
+        ka.bookmarks = Arc::new(marks);
+        Ok(ka)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChangeEvent;
+
+    #[test]
+    fn bookmarks_and_invariants() {
+        let mut ka = KeyArray::new([10u8, 20, 30]);
+        ka.change(1);
+        ka.bookmark("mid");
+        ka.change(2);
+        let json = serde_json::to_string(&ka).unwrap();
+        assert_eq!(
+            json,
+            r#"{"keys":[10,20,30],"current":2,"bookmarks":{"mid":1}}"#
+        );
+        let mut back: KeyArray<u8> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.jump_to_bookmark("mid"), Some(1));
+
+        for bad in [
+            r#"{"keys":[1],"current":1}"#,
+            r#"{"keys":[1],"current":0,"bookmarks":{"x":3}}"#,
+            r#"{"keys":[1]}"#,
+        ] {
+            assert!(
+                serde_json::from_str::<KeyArray<u8>>(bad).is_err(),
+                "{}",
+                bad
+            );
+        }
+
+        let ev = back.change_with_reason(0, "sync").unwrap();
+        let json = serde_json::to_string(&ev).unwrap();
+        assert_eq!(
+            json,
+            r#"{"previous":1,"current":0,"key":10,"reason":"sync"}"#
+        );
+        assert_eq!(serde_json::from_str::<ChangeEvent<u8>>(&json).unwrap(), ev);
+    }
+}
@@ -0,0 +1,151 @@
+//! A key array that interrupt handlers and the main loop can share.
+//!
+//! Every access runs inside a `critical-section`, so an ISR (say, a rotary
+//! encoder) can move the selection while the main loop reads it, with no
+//! RTOS and no `static mut`. It holds any [`SelectionCursor`], so on a
+//! target without a heap it holds a [`KeyArrayFixed`](crate::KeyArrayFixed):
+//!
+//! ```
+//! use keyarray::shared::SharedKeyArray;
+//! use keyarray::KeyArrayFixed;
+//!
+//! static MODE: SharedKeyArray<KeyArrayFixed<&str, 3>> = SharedKeyArray::new();
+//!
+//! MODE.init(KeyArrayFixed::new(&["Off", "Low", "High"]));
+//! // in the encoder interrupt:
+//! MODE.advance(-1);
+//! // in the main loop:
+//! assert_eq!(MODE.current(), Some("High"));
+//! ```
+//!
+//! A shared [`KeyArray`](crate::KeyArray) also offers
+//! [`take_dirty`](SharedKeyArray::take_dirty), for a main loop that
+//! redraws only after a change. Moving the cursor never allocates; only
+//! [`init`](SharedKeyArray::init) and [`with`](SharedKeyArray::with)
+//! closures that edit a KeyArray's keys do.
+
+use crate::cursor::SelectionCursor;
+#[cfg(feature = "std")]
+use crate::KeyArray;
+use core::cell::RefCell;
+#[cfg(feature = "std")]
+use core::fmt::{Debug, Display};
+use critical_section::Mutex;
+
+/// A `static`-friendly, interrupt-safe slot holding an optional key array
+/// `C`. Every accessor returns `None` (or does nothing) until
+/// [`init`](Self::init) is called.
+pub struct SharedKeyArray<C> {
+    inner: Mutex<RefCell<Option<C>>>,
+}
+
+impl<C> SharedKeyArray<C> {
+    /// An empty slot, usable in a `static` initializer.
+    pub const fn new() -> Self {
+        SharedKeyArray {
+            inner: Mutex::new(RefCell::new(None)),
+        }
+    }
+}
+
+impl<C> Default for SharedKeyArray<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: SelectionCursor> SharedKeyArray<C> {
+    /// Store `keys`, returning the array it replaces.
+    pub fn init(&self, keys: C) -> Option<C> {
+        critical_section::with(|cs| self.inner.borrow_ref_mut(cs).replace(keys))
+    }
+
+    /// Run `f` on the array inside one critical section. Keep `f` short:
+    /// interrupts are masked while it runs.
+    pub fn with<R>(&self, f: impl FnOnce(&mut C) -> R) -> Option<R> {
+        critical_section::with(|cs| self.inner.borrow_ref_mut(cs).as_mut().map(f))
+    }
+
+    /// Move `steps` keys (negative moves backwards); one encoder detent is
+    /// one step. A KeyArray moves as [`KeyArray::advance_by`] does,
+    /// skipping keys that cannot be selected and following its wrap mode;
+    /// past an end under [`WrapMode::Error`](crate::cursor::WrapMode::Error),
+    /// the selection stays put.
+    pub fn advance(&self, steps: isize) {
+        self.with(|keys| keys.advance(steps));
+    }
+
+    pub fn next(&self) {
+        self.with(C::next);
+    }
+
+    pub fn prev(&self) {
+        self.with(C::prev);
+    }
+
+    /// A copy of the current key.
+    pub fn current(&self) -> Option<C::Item>
+    where
+        C::Item: Clone,
+    {
+        self.with(|keys| keys.selected_item().cloned()).flatten()
+    }
+
+    pub fn current_index(&self) -> Option<usize> {
+        self.with(|keys| keys.cursor())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K> SharedKeyArray<KeyArray<K>>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Whether anything changed since the last call; see
+    /// [`KeyArray::take_dirty`].
+    pub fn take_dirty(&self) -> bool {
+        self.with(KeyArray::take_dirty).unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KeyArrayFixed;
+    #[cfg(feature = "disable")]
+    use std::thread;
+
+    #[cfg(feature = "disable")]
+    static LEVEL: SharedKeyArray<KeyArray<u8>> = SharedKeyArray::new();
+
+    #[cfg(feature = "disable")]
+    #[test]
+    fn shared_across_threads() {
+        assert_eq!(LEVEL.current(), None);
+        assert!(!LEVEL.take_dirty());
+        LEVEL.init(KeyArray::new(0..10));
+
+        let encoder: Vec<_> = (0..4)
+            .map(|_| thread::spawn(|| (0..25).for_each(|_| LEVEL.advance(1))))
+            .collect();
+        encoder.into_iter().for_each(|t| t.join().unwrap());
+
+        // 100 steps over 10 keys lands back on the first
+        assert_eq!(LEVEL.current(), Some(0));
+        assert!(LEVEL.take_dirty());
+        LEVEL.prev();
+        assert_eq!(LEVEL.current_index(), Some(9));
+        LEVEL.with(|keys| keys.disable(0));
+        LEVEL.advance(1);
+        assert_eq!(LEVEL.current_index(), Some(1));
+    }
+
+    #[test]
+    fn holds_a_fixed_array() {
+        let shared: SharedKeyArray<KeyArrayFixed<u8, 4>> = SharedKeyArray::new();
+        shared.init(KeyArrayFixed::new(&[1, 2, 3]));
+        shared.with(|keys| keys.push(4));
+        shared.advance(-1);
+        assert_eq!((shared.current(), shared.current_index()), (Some(4), Some(3)));
+    }
+}
@@ -0,0 +1,80 @@
+//! Dry runs of message sequences.
+//!
+//! [`simulate`](KeyArray::simulate) applies messages to a scratch copy and
+//! reports what would happen, leaving the live KeyArray and its observers
+//! untouched. The copy shares storage with the original until the first
+//! structural message, so validating a user's macro is cheap.
+//!
+//! ```
+//! use keyarray::{KeyArray, Msg};
+//!
+//! let live = KeyArray::new(["A", "B"]);
+//! let run = live.simulate([Msg::Next, Msg::Remove(5), Msg::Insert(0, "Z")]);
+//! assert!(!run.is_ok());
+//! assert_eq!(run.errors[0].0, 1);
+//! assert_eq!(run.state.keys(), ["Z", "A", "B"]);
+//! assert_eq!(live.current(), &"A");
+//! ```
+
+use crate::error::KeyArrayError;
+use crate::event::ChangeEvent;
+use crate::{KeyArray, Msg};
+use std::fmt::{Debug, Display};
+
+/// What a [`simulate`](KeyArray::simulate) run would do.
+#[derive(Clone)]
+pub struct SimulationResult<K> {
+    /// The state after every message that could be applied.
+    pub state: KeyArray<K>,
+    /// One event per message that moved the selection, in order.
+    pub events: Vec<ChangeEvent<K>>,
+    /// Messages that could not be applied, by position in the input, with
+    /// why. They were skipped and the run went on.
+    pub errors: Vec<(usize, KeyArrayError)>,
+}
+
+impl<K> SimulationResult<K> {
+    /// Whether every message could be applied.
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Apply `msgs` to a copy of `self`, as by
+    /// [`try_update`](Self::try_update), and report the outcome.
+    pub fn simulate(&self, msgs: impl IntoIterator<Item = Msg<K>>) -> SimulationResult<K> {
+        let mut result = SimulationResult {
+            state: self.clone(),
+            events: Vec::new(),
+            errors: Vec::new(),
+        };
+        for (n, msg) in msgs.into_iter().enumerate() {
+            match result.state.try_update(msg) {
+                Ok(event) => result.events.extend(event),
+                Err(e) => result.errors.push((n, e)),
+            }
+        }
+        result
+    }
+}
+
+#[cfg(all(test, feature = "observers"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn live_array_is_untouched() {
+        let mut live = KeyArray::new([1, 2, 3]);
+        live.on_change(|_, _, _| panic!("observer saw a simulation"));
+        let run = live.simulate([Msg::Select(2), Msg::Remove(2), Msg::Select(3)]);
+        let oob = KeyArrayError::IndexOutOfBounds { index: 3, len: 2 };
+        assert_eq!(run.errors, [(2, oob)]);
+        assert_eq!(run.events.len(), 2);
+        assert_eq!((run.state.keys(), run.state.current()), (&[1, 2][..], &2));
+        assert_eq!(live.current_index(), 0);
+    }
+}
@@ -0,0 +1,101 @@
+//! Percentage traffic splitting for rollout switches.
+//!
+//! [`set_split`](KeyArray::set_split) stores each key's share as its
+//! rotation [`weight`](KeyArray::weight); [`assign`](KeyArray::assign) then
+//! maps an input onto a key with probability proportional to its share.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//!
+//! let mut flag = KeyArray::new(["on", "off"]);
+//! flag.set_split(&[("on", 10), ("off", 90)]);
+//! let on = (0..10_000u32).filter(|u| flag.assign(u) == Some(0)).count();
+//! assert!((800..1200).contains(&on));
+//! ```
+//!
+//! Shares are relative, so they need not add up to 100. Inputs are hashed,
+//! so a user keeps their assignment while the split is unchanged; pass a
+//! random number instead for per-request splitting. Growing the share of
+//! the first key keeps everyone who already had it.
+
+use crate::sticky::{mix, StableHasher};
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+
+/// The split changed. Both lists hold one share per key, in key order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitEvent {
+    pub previous: Vec<u32>,
+    pub current: Vec<u32>,
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Set every key's share: listed keys get theirs, the rest 0. Returns
+    /// the change, or `None` if the split was already this. Panics if a
+    /// listed key is not in the array.
+    pub fn set_split(&mut self, split: &[(K, u32)]) -> Option<SplitEvent> {
+        let mut current = vec![0; self.len()];
+        for (key, share) in split {
+            let i = self.keys.iter().position(|k| k == key);
+            let i = i.unwrap_or_else(|| panic!("KeyArray::set_split: no key {:?}", key));
+            current[i] = *share;
+        }
+        let previous = self.split();
+        if previous == current {
+            return None;
+        }
+        for (i, &share) in current.iter().enumerate() {
+            self.set_weight(i, share);
+        }
+        Some(SplitEvent { previous, current })
+    }
+
+    /// Every key's share, in key order.
+    pub fn split(&self) -> Vec<u32> {
+        (0..self.len()).filter_map(|i| self.weight(i)).collect()
+    }
+
+    /// Index of the key `input` falls on. `None` if every share is 0.
+    pub fn assign<H: Hash + ?Sized>(&self, input: &H) -> Option<usize> {
+        let split = self.split();
+        let total: u64 = split.iter().map(|&w| w as u64).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut point = mix(StableHasher::hash(input)) % total;
+        split.iter().position(|&share| {
+            let share = share as u64;
+            if point < share {
+                return true;
+            }
+            point -= share;
+            false
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rollout() {
+        let mut ka = KeyArray::new(["on", "off"]);
+        let ev = ka.set_split(&[("on", 25), ("off", 75)]).unwrap();
+        assert_eq!((ev.previous, ev.current), (vec![1, 1], vec![25, 75]));
+        assert_eq!(ka.set_split(&[("off", 75), ("on", 25)]), None);
+        let had_on: Vec<u32> = (0..4000).filter(|u| ka.assign(u) == Some(0)).collect();
+        assert!((800..1200).contains(&had_on.len()));
+
+        // widening the rollout keeps everyone already on
+        ka.set_split(&[("on", 50), ("off", 50)]);
+        assert!(had_on.iter().all(|u| ka.assign(u) == Some(0)));
+
+        ka.set_split(&[]);
+        assert_eq!(ka.assign("anyone"), None);
+    }
+}
@@ -0,0 +1,140 @@
+//! Standard library traits, so KeyArray composes with iterators and
+//! indexing like any other collection.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//!
+//! let mut menu: KeyArray<&str> = ["New", "Open"].into_iter().collect();
+//! menu.extend(["Save", "Quit"]);
+//! menu.change(1);
+//! assert_eq!(menu[2], "Save");
+//!
+//! let lines: Vec<String> = menu
+//!     .items()
+//!     .map(|(i, key, current)| format!("{}{} {}", if current { ">" } else { " " }, i, key))
+//!     .collect();
+//! assert_eq!(lines[1], ">1 Open");
+//!
+//! for key in &menu {
+//!     assert!(!key.is_empty());
+//! }
+//! assert_eq!(menu.into_iter().last(), Some("Quit"));
+//! ```
+
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+use std::ops::Index;
+use std::sync::Arc;
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Iterate over the keys.
+    pub fn iter(&self) -> std::slice::Iter<'_, K> {
+        self.keys.iter()
+    }
+
+    /// Every key as `(index, key, is_current)`, for rendering menus.
+    pub fn items(&self) -> impl Iterator<Item = (usize, &K, bool)> + '_ {
+        self.keys
+            .iter()
+            .enumerate()
+            .map(move |(i, k)| (i, k, i == self.idx))
+    }
+}
+
+impl<'a, K> IntoIterator for &'a KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    type Item = &'a K;
+    type IntoIter = std::slice::Iter<'a, K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// The keys, without copying them unless a clone still shares them.
+impl<K> IntoIterator for KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    type Item = K;
+    type IntoIter = std::vec::IntoIter<K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Arc::try_unwrap(self.keys)
+            .unwrap_or_else(|shared| (*shared).clone())
+            .into_iter()
+    }
+}
+
+/// Panics if `i` is out of bounds, like slice indexing.
+impl<K> Index<usize> for KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    type Output = K;
+
+    fn index(&self, i: usize) -> &K {
+        &self.keys[i]
+    }
+}
+
+/// [`push`](KeyArray::push)es each key.
+impl<K> Extend<K> for KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    fn extend<I: IntoIterator<Item = K>>(&mut self, keys: I) {
+        for key in keys {
+            self.push(key);
+        }
+    }
+}
+
+/// As [`KeyArray::new`]: the first key is current. Panics if the iterator
+/// is empty.
+impl<K> FromIterator<K> for KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    fn from_iter<I: IntoIterator<Item = K>>(keys: I) -> Self {
+        KeyArray::new(keys)
+    }
+}
+
+/// Equal when the keys, in order, and the current index are; ids, attached
+/// data and settings are not compared.
+impl<K> PartialEq for KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.idx == other.idx && self.keys == other.keys
+    }
+}
+
+impl<K> Eq for KeyArray<K> where K: Clone + Eq + Debug + Display {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equality_ignores_ids() {
+        let a: KeyArray<u8> = (1..=3).collect();
+        let mut b = KeyArray::new([0, 1, 2, 3]);
+        b.remove(0);
+        assert_eq!(a, b);
+        b.change(1);
+        assert_ne!(a, b);
+
+        let shared = b.clone();
+        assert_eq!(b.into_iter().sum::<u8>(), 6);
+        assert_eq!((&shared).into_iter().count(), 3);
+        assert_eq!(shared.items().filter(|item| item.2).count(), 1);
+    }
+}
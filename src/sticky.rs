@@ -0,0 +1,173 @@
+//! Sticky selection: map any hashable input (a user id, a session token)
+//! onto a key, the same key every time.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//!
+//! let mut bucket = KeyArray::new(["control", "variant-a", "variant-b"]);
+//! let first = bucket.select_for("user-1842");
+//! assert_eq!(bucket.select_for("user-1842"), first);
+//! ```
+//!
+//! The hash is FNV-1a rather than the std hasher, so assignments are the
+//! same in every process and across Rust releases (for inputs whose `Hash`
+//! impl is platform independent, such as strings). They depend on the key
+//! list: adding or removing a key reshuffles most inputs.
+//!
+//! When the list changes at runtime (shards joining and leaving), use
+//! [`select_consistent_for`](KeyArray::select_consistent_for) instead. It
+//! uses rendezvous hashing over the key values, so removing a key only moves
+//! the inputs that were on it, and adding one only takes its fair share:
+//!
+//! ```
+//! use keyarray::KeyArray;
+//!
+//! let mut shards = KeyArray::new(["db-1", "db-2", "db-3"]);
+//! let before = shards.keys()[shards.consistent_index_for("user-7")];
+//! shards.push("db-4");
+//! let after = shards.keys()[shards.consistent_index_for("user-7")];
+//! assert!(after == before || after == "db-4");
+//! ```
+
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+use std::hash::{Hash, Hasher};
+
+// 64-bit FNV-1a.
+pub(crate) struct StableHasher(u64);
+
+impl StableHasher {
+    pub(crate) fn new() -> Self {
+        StableHasher(0xcbf2_9ce4_8422_2325)
+    }
+
+    pub(crate) fn hash<H: Hash + ?Sized>(input: &H) -> u64 {
+        let mut hasher = StableHasher::new();
+        input.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= b as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Index of the key `input` is assigned to. Panics if there are no keys.
+    pub fn index_for<H: Hash + ?Sized>(&self, input: &H) -> usize {
+        assert!(!self.is_empty(), "KeyArray::index_for: no keys");
+        (StableHasher::hash(input) % self.len() as u64) as usize
+    }
+
+    /// Make the key `input` is assigned to current and return the index now
+    /// current, which is its replacement's if it is deprecated under strict
+    /// mode; `None`, changing nothing, if that key cannot be selected (disabled
+    /// or locked). Panics if there are no keys.
+    pub fn select_for<H: Hash + ?Sized>(&mut self, input: &H) -> Option<usize> {
+        let i = self.index_for(input);
+        self.try_change(i).ok()?;
+        Some(self.current_index())
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display + Hash,
+{
+    /// Index of the key `input` is assigned to by rendezvous hashing: the key
+    /// whose value scores highest together with `input`. Depends on key
+    /// values, not positions, so it survives reordering. O(n). Panics if
+    /// there are no keys.
+    pub fn consistent_index_for<H: Hash + ?Sized>(&self, input: &H) -> usize {
+        assert!(!self.is_empty(), "KeyArray::consistent_index_for: no keys");
+        let score = |key: &K| {
+            let mut hasher = StableHasher::new();
+            key.hash(&mut hasher);
+            input.hash(&mut hasher);
+            mix(hasher.finish())
+        };
+        // ties (equal keys) go to the lowest index
+        let mut best = (0, score(&self.keys[0]));
+        for (i, key) in self.keys.iter().enumerate().skip(1) {
+            let s = score(key);
+            if s > best.1 {
+                best = (i, s);
+            }
+        }
+        best.0
+    }
+
+    /// Make the [`consistent_index_for`](Self::consistent_index_for) key
+    /// current and return the index now current, as for
+    /// [`select_for`](Self::select_for); `None` as for
+    /// [`select_for`](Self::select_for). Panics if there are no keys.
+    pub fn select_consistent_for<H: Hash + ?Sized>(&mut self, input: &H) -> Option<usize> {
+        let i = self.consistent_index_for(input);
+        self.try_change(i).ok()?;
+        Some(self.current_index())
+    }
+}
+
+// splitmix64 finalizer: FNV's low bits are too regular to compare scores.
+pub(crate) fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stable_and_spread() {
+        // FNV-1a test vector
+        let mut h = StableHasher::new();
+        h.write(b"a");
+        assert_eq!(h.finish(), 0xaf63_dc4c_8601_ec8c);
+
+        let ka = KeyArray::new(0..4);
+        let mut counts = [0; 4];
+        for user in 0..400u32 {
+            let i = ka.index_for(&format!("user-{}", user));
+            assert_eq!(ka.index_for(&format!("user-{}", user)), i);
+            counts[i] += 1;
+        }
+        assert!(counts.iter().all(|&c| c > 60), "{:?}", counts);
+    }
+
+    #[test]
+    fn consistent_assignment_moves_little() {
+        let mut ka = KeyArray::new(["a", "b", "c", "d"]);
+        let assign = |ka: &KeyArray<&'static str>| -> Vec<&str> {
+            (0..1000u32)
+                .map(|u| ka.keys()[ka.consistent_index_for(&u)])
+                .collect()
+        };
+        let before = assign(&ka);
+        ka.push("e");
+        let grown = assign(&ka);
+        let moved = before.iter().zip(&grown).filter(|(b, g)| b != g).count();
+        // every move goes to the new key, about a fifth of inputs
+        assert!(grown.iter().zip(&before).all(|(g, b)| g == b || *g == "e"));
+        assert!((120..280).contains(&moved), "{}", moved);
+
+        ka.remove(0);
+        let shrunk = assign(&ka);
+        assert!(grown.iter().zip(&shrunk).all(|(g, s)| g == s || *g == "a"));
+        ka.sort_by(|x, y| y.cmp(x));
+        assert_eq!(assign(&ka), shrunk);
+    }
+}
@@ -0,0 +1,435 @@
+//! Async change notification: a shared KeyArray whose changes can be
+//! awaited as a [`Stream`], and which accepts [`Msg`]s as a [`Sink`].
+//!
+//! Works with any executor; the stream is backed by `futures` channels.
+//!
+//! ```
+//! use futures::StreamExt;
+//! use keyarray::stream::AsyncKeyArray;
+//! use keyarray::KeyArray;
+//!
+//! # futures::executor::block_on(async {
+//! let mode = AsyncKeyArray::new(KeyArray::new(["On", "Off", "Auto"]));
+//! let mut changes = mode.changes();
+//! mode.next();
+//! mode.change(2);
+//! drop(mode);
+//!
+//! let mut seen = Vec::new();
+//! while let Some(ev) = changes.next().await {
+//!     seen.push(ev.key);
+//! }
+//! assert_eq!(seen, ["Off", "Auto"]);
+//! # });
+//! ```
+//!
+//! To remote-control the array from another stream of messages, feed it to
+//! [`apply_stream`](AsyncKeyArray::apply_stream), or `forward` a
+//! `Result<Msg<K>, KeyArrayError>` stream into a handle.
+//!
+//! When changes arrive faster than they can be rendered, wrap the stream in
+//! [`coalesce`] or [`coalesce_with`] to receive one net event per burst.
+
+use crate::error::KeyArrayError;
+use crate::event::{ChangeEvent, ChangeReason};
+use crate::{KeyArray, Msg};
+use futures::channel::mpsc::{self, UnboundedSender};
+use futures::future::{self, Ready};
+use futures::{Sink, Stream, StreamExt};
+use std::fmt::{Debug, Display};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll};
+
+struct State<K> {
+    keys: KeyArray<K>,
+    subscribers: Vec<UnboundedSender<ChangeEvent<K>>>,
+}
+
+/// A cloneable handle to one KeyArray. Every change made through any handle
+/// is delivered to every stream from [`changes`](Self::changes).
+pub struct AsyncKeyArray<K> {
+    state: Arc<Mutex<State<K>>>,
+}
+
+impl<K> Clone for AsyncKeyArray<K> {
+    fn clone(&self) -> Self {
+        AsyncKeyArray {
+            state: Arc::clone(&self.state),
+        }
+    }
+}
+
+impl<K> AsyncKeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    pub fn new(keys: KeyArray<K>) -> Self {
+        AsyncKeyArray {
+            state: Arc::new(Mutex::new(State {
+                keys,
+                subscribers: Vec::new(),
+            })),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State<K>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// A copy of the array as it is now; cheap, as keys are shared.
+    pub fn snapshot(&self) -> KeyArray<K> {
+        self.lock().keys.clone()
+    }
+
+    pub fn current(&self) -> K {
+        self.lock().keys.current().clone()
+    }
+
+    /// Apply `msg` and notify every stream if the selection changed.
+    /// Never waits: streams buffer events until they are polled. Panics
+    /// where [`try_update`](Self::try_update) fails, without poisoning the
+    /// lock.
+    pub fn update(&self, msg: Msg<K>) -> Option<ChangeEvent<K>> {
+        self.apply(msg, None)
+            .unwrap_or_else(|e| panic!("AsyncKeyArray::update: {}", e))
+    }
+
+    /// [`update`](Self::update), attaching `reason` to the event every
+    /// stream receives.
+    pub fn update_with_reason(
+        &self,
+        msg: Msg<K>,
+        reason: impl Into<ChangeReason>,
+    ) -> Option<ChangeEvent<K>> {
+        self.apply(msg, Some(reason.into()))
+            .unwrap_or_else(|e| panic!("AsyncKeyArray::update_with_reason: {}", e))
+    }
+
+    /// [`update`](Self::update), failing with [`KeyArrayError`] instead of
+    /// panicking. The message is checked and applied under one lock, so no
+    /// other handle can change the array in between.
+    pub fn try_update(&self, msg: Msg<K>) -> Result<Option<ChangeEvent<K>>, KeyArrayError> {
+        self.apply(msg, None)
+    }
+
+    /// [`try_update`](Self::try_update), attaching `reason` to the event
+    /// every stream receives.
+    pub fn try_update_with_reason(
+        &self,
+        msg: Msg<K>,
+        reason: impl Into<ChangeReason>,
+    ) -> Result<Option<ChangeEvent<K>>, KeyArrayError> {
+        self.apply(msg, Some(reason.into()))
+    }
+
+    fn apply(
+        &self,
+        msg: Msg<K>,
+        reason: Option<ChangeReason>,
+    ) -> Result<Option<ChangeEvent<K>>, KeyArrayError> {
+        let mut state = self.lock();
+        let Some(mut ev) = state.keys.try_update(msg)? else {
+            return Ok(None);
+        };
+        ev.reason = reason;
+        state
+            .subscribers
+            .retain(|tx| tx.unbounded_send(ev.clone()).is_ok());
+        Ok(Some(ev))
+    }
+
+    pub fn change(&self, i: usize) -> Option<ChangeEvent<K>> {
+        self.update(Msg::Select(i))
+    }
+
+    pub fn next(&self) -> Option<ChangeEvent<K>> {
+        self.update(Msg::Next)
+    }
+
+    pub fn prev(&self) -> Option<ChangeEvent<K>> {
+        self.update(Msg::Prev)
+    }
+
+    /// Every change from now on, in order. The stream ends once all handles
+    /// are dropped; dropping the stream unsubscribes it.
+    pub fn changes(&self) -> impl Stream<Item = ChangeEvent<K>> + Unpin {
+        let (tx, rx) = mpsc::unbounded();
+        self.lock().subscribers.push(tx);
+        rx
+    }
+
+    /// Apply every message from `msgs` in order with
+    /// [`try_update`](Self::try_update), pulling the next one only after
+    /// the previous was applied, and return how many changed the selection.
+    /// Completes when `msgs` ends, or fails at the first message that is
+    /// refused; the messages before it stay applied.
+    pub async fn apply_stream<S>(&self, msgs: S) -> Result<usize, KeyArrayError>
+    where
+        S: Stream<Item = Msg<K>>,
+    {
+        let mut msgs = std::pin::pin!(msgs);
+        let mut changed = 0;
+        while let Some(msg) = msgs.next().await {
+            changed += usize::from(self.try_update(msg)?.is_some());
+        }
+        Ok(changed)
+    }
+}
+
+/// Messages sent into a handle are applied with
+/// [`try_update`](AsyncKeyArray::try_update), a refused one failing the
+/// send. Applying never waits, so the sink is always ready.
+impl<K> Sink<Msg<K>> for AsyncKeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    type Error = KeyArrayError;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), KeyArrayError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, msg: Msg<K>) -> Result<(), KeyArrayError> {
+        self.try_update(msg).map(drop)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), KeyArrayError>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), KeyArrayError>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Stream returned by [`coalesce`] and [`coalesce_with`].
+pub struct Coalesce<S, F, Fut>
+where
+    S: Stream,
+{
+    changes: S,
+    timer: F,
+    pending: Option<S::Item>,
+    // the last event seen, and the one before the pending burst, whose key
+    // the burst started from
+    last: Option<S::Item>,
+    start: Option<S::Item>,
+    delay: Option<Pin<Box<Fut>>>,
+    done: bool,
+}
+
+/// Merge events that are already queued into one: whenever the consumer
+/// polls, it gets a single event covering everything that happened since.
+pub fn coalesce<S, K>(changes: S) -> Coalesce<S, fn() -> Ready<()>, Ready<()>>
+where
+    S: Stream<Item = ChangeEvent<K>> + Unpin,
+{
+    coalesce_with(changes, || future::ready(()))
+}
+
+/// Merge bursts: the first event of a burst starts the future returned by
+/// `timer` (e.g. `|| tokio::time::sleep(Duration::from_millis(50))`), and
+/// one event is emitted when it completes. The merged event runs from the
+/// burst's first `previous` to its last `current`, `key` and `reason`. A burst
+/// that ends on the key it started from, such as A → B → A, is dropped; for
+/// the first burst, whose starting key is not known, ending on the index it
+/// started from is enough. A pending burst is flushed early when `changes`
+/// ends.
+pub fn coalesce_with<S, K, F, Fut>(changes: S, timer: F) -> Coalesce<S, F, Fut>
+where
+    S: Stream<Item = ChangeEvent<K>> + Unpin,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = ()>,
+{
+    Coalesce {
+        changes,
+        timer,
+        pending: None,
+        last: None,
+        start: None,
+        delay: None,
+        done: false,
+    }
+}
+
+// Neither the buffered event nor the boxed timer is ever pinned in place.
+impl<S, F, Fut> Unpin for Coalesce<S, F, Fut>
+where
+    S: Stream + Unpin,
+    F: Unpin,
+{
+}
+
+impl<S, K, F, Fut> Stream for Coalesce<S, F, Fut>
+where
+    S: Stream<Item = ChangeEvent<K>> + Unpin,
+    K: Clone + PartialEq,
+    F: FnMut() -> Fut + Unpin,
+    Fut: Future<Output = ()>,
+{
+    type Item = ChangeEvent<K>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<ChangeEvent<K>>> {
+        let this = self.get_mut();
+        // drain everything that is ready before looking at the timer
+        while !this.done {
+            match this.changes.poll_next_unpin(cx) {
+                Poll::Ready(Some(ev)) => {
+                    let before = this.last.replace(ev.clone());
+                    match &mut this.pending {
+                        Some(burst) => {
+                            burst.current = ev.current;
+                            burst.key = ev.key;
+                            burst.reason = ev.reason;
+                        }
+                        None => {
+                            this.start = before;
+                            this.pending = Some(ev);
+                            this.delay = Some(Box::pin((this.timer)()));
+                        }
+                    }
+                }
+                Poll::Ready(None) => this.done = true,
+                Poll::Pending => break,
+            }
+        }
+        if this.pending.is_none() {
+            return if this.done {
+                Poll::Ready(None)
+            } else {
+                Poll::Pending
+            };
+        }
+        if !this.done {
+            if let Some(delay) = &mut this.delay {
+                if delay.as_mut().poll(cx).is_pending() {
+                    return Poll::Pending;
+                }
+            }
+        }
+        this.delay = None;
+        let start = this.start.take();
+        match this.pending.take() {
+            Some(ev)
+                if ev.previous == ev.current
+                    && start.is_none_or(|before| before.key == ev.key) =>
+            {
+                if this.done {
+                    Poll::Ready(None)
+                } else {
+                    Poll::Pending
+                }
+            }
+            burst => Poll::Ready(burst),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::{stream, FutureExt, SinkExt};
+
+    #[test]
+    fn every_subscriber_sees_changes() {
+        let ka = AsyncKeyArray::new(KeyArray::new(["A", "B", "C"]));
+        let first = ka.changes();
+        ka.next();
+        let second = ka.changes();
+        let other = ka.clone();
+        other.prev();
+        assert!(other.change(0).is_none());
+        drop((ka, other));
+
+        let keys =
+            |s: Vec<ChangeEvent<&'static str>>| s.into_iter().map(|e| e.key).collect::<Vec<_>>();
+        assert_eq!(keys(block_on(first.collect())), ["B", "A"]);
+        assert_eq!(keys(block_on(second.collect())), ["A"]);
+    }
+
+    #[test]
+    fn dropped_stream_unsubscribes() {
+        let ka = AsyncKeyArray::new(KeyArray::new([1, 2]));
+        drop(ka.changes());
+        ka.next();
+        assert!(ka.lock().subscribers.is_empty());
+    }
+
+    #[test]
+    fn remote_control() {
+        let ka = AsyncKeyArray::new(KeyArray::new(["A", "B", "C"]));
+        let msgs = stream::iter([Msg::Next, Msg::Select(1), Msg::Remove(0)]);
+        assert_eq!(block_on(ka.apply_stream(msgs)), Ok(2));
+        assert_eq!(ka.current(), "C");
+        let msgs = stream::iter([Msg::Prev, Msg::Select(5), Msg::Next]);
+        let oob = KeyArrayError::IndexOutOfBounds { index: 5, len: 2 };
+        assert_eq!(block_on(ka.apply_stream(msgs)), Err(oob));
+        assert_eq!(ka.current(), "B");
+
+        let mut sink = ka.clone();
+        let changes = ka.changes();
+        block_on(sink.send_all(&mut stream::iter([Ok(Msg::Next), Ok(Msg::Next)]))).unwrap();
+        assert_eq!(block_on(sink.send(Msg::Select(5))), Err(oob));
+        drop((ka, sink));
+        assert_eq!(
+            block_on(changes.map(|ev| ev.key).collect::<Vec<_>>()),
+            ["C", "B"]
+        );
+    }
+
+    #[test]
+    fn bursts_collapse() {
+        let ka = AsyncKeyArray::new(KeyArray::new(["A", "B", "C", "D"]));
+        let mut ready = coalesce(ka.changes());
+        // a timer that never fires: only the end of the stream flushes
+        let slow = coalesce_with(ka.changes(), future::pending::<()>);
+        ka.next();
+        ka.next();
+        ka.next();
+        let ev = block_on(ready.next()).unwrap();
+        assert_eq!((ev.previous, ev.current, ev.key), (0, 3, "D"));
+
+        ka.prev();
+        drop(ka);
+        assert_eq!(block_on(ready.next()).unwrap().key, "C");
+        let all: Vec<_> = block_on(slow.collect());
+        assert_eq!(
+            all,
+            [ChangeEvent {
+                previous: 0,
+                current: 2,
+                key: "C",
+                reason: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn round_trips_are_dropped() {
+        let ev = |previous, current, key| ChangeEvent {
+            previous,
+            current,
+            key,
+            reason: None,
+        };
+        let (tx, rx) = mpsc::unbounded();
+        let mut merged = coalesce(rx);
+        tx.unbounded_send(ev(0, 1, "B")).unwrap();
+        assert_eq!(block_on(merged.next()), Some(ev(0, 1, "B")));
+        tx.unbounded_send(ev(1, 2, "C")).unwrap();
+        tx.unbounded_send(ev(2, 1, "B")).unwrap();
+        assert_eq!(merged.next().now_or_never(), None);
+        // back on the same index, but the keys were edited meanwhile
+        tx.unbounded_send(ev(1, 2, "C")).unwrap();
+        tx.unbounded_send(ev(2, 1, "X")).unwrap();
+        assert_eq!(merged.next().now_or_never(), Some(Some(ev(1, 1, "X"))));
+        drop(tx);
+        assert_eq!(block_on(merged.next()), None);
+
+        let there_and_back = stream::iter([ev(0, 1, "B"), ev(1, 0, "A")]);
+        assert_eq!(block_on(coalesce(there_and_back).collect::<Vec<_>>()), []);
+    }
+}
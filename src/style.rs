@@ -0,0 +1,86 @@
+//! How a key is drawn: colors and text attributes.
+//!
+//! These are plain values with no allocation, so the `no_std` drawing
+//! helpers in `graphics` share them with the `ui` feature, which stores a
+//! [`KeyStyle`] with each key as part of its `KeyUi`.
+
+/// Semantic color for a key; toolkits map these onto their own palette.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ColorHint {
+    #[default]
+    Default,
+    Accent,
+    Success,
+    Warning,
+    Danger,
+    Rgb(u8, u8, u8),
+}
+
+impl ColorHint {
+    /// The shared palette: the color every renderer uses for the hint, or
+    /// `None` for [`Default`](ColorHint::Default), meaning the frontend's
+    /// own color.
+    pub fn to_rgb(self) -> Option<(u8, u8, u8)> {
+        match self {
+            ColorHint::Default => None,
+            ColorHint::Accent => Some((17, 168, 205)),
+            ColorHint::Success => Some((13, 188, 121)),
+            ColorHint::Warning => Some((229, 229, 16)),
+            ColorHint::Danger => Some((205, 49, 49)),
+            ColorHint::Rgb(r, g, b) => Some((r, g, b)),
+        }
+    }
+}
+
+/// Text attributes for a key. Renderers skip those they cannot show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Emphasis {
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub dim: bool,
+}
+
+impl Emphasis {
+    pub const NONE: Emphasis = Emphasis {
+        bold: false,
+        italic: false,
+        underline: false,
+        dim: false,
+    };
+    pub const BOLD: Emphasis = Emphasis {
+        bold: true,
+        ..Emphasis::NONE
+    };
+    pub const ITALIC: Emphasis = Emphasis {
+        italic: true,
+        ..Emphasis::NONE
+    };
+    pub const UNDERLINE: Emphasis = Emphasis {
+        underline: true,
+        ..Emphasis::NONE
+    };
+    pub const DIM: Emphasis = Emphasis {
+        dim: true,
+        ..Emphasis::NONE
+    };
+
+    /// Every attribute set in either.
+    pub fn union(self, other: Emphasis) -> Emphasis {
+        Emphasis {
+            bold: self.bold || other.bold,
+            italic: self.italic || other.italic,
+            underline: self.underline || other.underline,
+            dim: self.dim || other.dim,
+        }
+    }
+}
+
+/// How a key is drawn: the style part of its `KeyUi`, as returned by
+/// `KeyArray::style` with the `ui` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct KeyStyle {
+    pub foreground: ColorHint,
+    pub background: ColorHint,
+    pub emphasis: Emphasis,
+}
@@ -0,0 +1,342 @@
+//! A KeyArray shared between threads.
+//!
+//! [`SyncKeyArray`] is a cloneable handle: every clone sees the same array,
+//! guarded by a lock. The current index is also kept in an atomic, so the
+//! common read path of a render thread does not take the lock at all.
+//! [`watch`](SyncKeyArray::watch) hands out a channel receiving the new
+//! current key on every change, whichever thread made it; with the `tokio`
+//! feature, [`watch_async`](SyncKeyArray::watch_async) returns a
+//! `tokio::sync::watch` receiver instead.
+//!
+//! To rebuild a large key list, [`begin_reload`](SyncKeyArray::begin_reload)
+//! hands out a [`ReloadHandle`] that collects the new keys without holding
+//! the lock. [`commit`](ReloadHandle::commit) matches them against the old
+//! ones, still without the lock, then swaps the result in at once, so
+//! readers see either the old list or the new one, never a half-built one.
+//!
+//! ```
+//! use keyarray::sync::SyncKeyArray;
+//! use keyarray::KeyArray;
+//! use std::thread;
+//!
+//! let mode = SyncKeyArray::new(KeyArray::new(["Walk", "Run", "Crouch"]));
+//! let changes = mode.watch();
+//!
+//! let input = mode.clone();
+//! thread::spawn(move || {
+//!     input.next();
+//!     input.change(2);
+//! })
+//! .join()
+//! .unwrap();
+//!
+//! assert_eq!(mode.current_index(), 2);
+//! assert_eq!(changes.try_iter().collect::<Vec<_>>(), ["Run", "Crouch"]);
+//!
+//! let mut reload = mode.begin_reload();
+//! reload.extend(["Walk", "Crouch", "Prone"]);
+//! reload.commit().unwrap();
+//! assert_eq!((mode.current_index(), mode.current()), (1, "Crouch"));
+//! ```
+
+use crate::error::KeyArrayError;
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+struct Shared<K> {
+    keys: RwLock<KeyArray<K>>,
+    // mirrors `keys.current_index()`, written under the write lock
+    idx: AtomicUsize,
+    // locked after `keys`, so watchers see changes in order
+    watchers: Mutex<Vec<Sender<K>>>,
+    #[cfg(feature = "tokio")]
+    watch: tokio::sync::watch::Sender<Option<K>>,
+}
+
+/// A cloneable, thread-safe handle to one KeyArray.
+pub struct SyncKeyArray<K> {
+    shared: Arc<Shared<K>>,
+}
+
+impl<K> Clone for SyncKeyArray<K> {
+    fn clone(&self) -> Self {
+        SyncKeyArray {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+impl<K> SyncKeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    pub fn new(keys: KeyArray<K>) -> Self {
+        SyncKeyArray {
+            shared: Arc::new(Shared {
+                idx: AtomicUsize::new(keys.current_index()),
+                #[cfg(feature = "tokio")]
+                watch: tokio::sync::watch::channel(current_of(&keys)).0,
+                keys: RwLock::new(keys),
+                watchers: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Lock the array for reading. Writers wait until the guard is dropped.
+    pub fn read(&self) -> RwLockReadGuard<'_, KeyArray<K>> {
+        self.shared.keys.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write_lock(&self) -> RwLockWriteGuard<'_, KeyArray<K>> {
+        self.shared.keys.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn watchers(&self) -> MutexGuard<'_, Vec<Sender<K>>> {
+        self.shared.watchers.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Run `f` on the array under the write lock, then tell every watcher
+    /// if the current key changed. Anything KeyArray can do can be done
+    /// this way.
+    pub fn write<R>(&self, f: impl FnOnce(&mut KeyArray<K>) -> R) -> R {
+        let mut keys = self.write_lock();
+        let before = (keys.current_index(), current_of(&keys));
+        let result = f(&mut keys);
+        self.shared.idx.store(keys.current_index(), Ordering::Release);
+        let after = current_of(&keys);
+        if (keys.current_index(), &after) != (before.0, &before.1) {
+            if let Some(key) = &after {
+                self.watchers().retain(|tx| tx.send(key.clone()).is_ok());
+            }
+            #[cfg(feature = "tokio")]
+            self.shared.watch.send_replace(after);
+        }
+        result
+    }
+
+    /// Index of the current key, read without locking.
+    pub fn current_index(&self) -> usize {
+        self.shared.idx.load(Ordering::Acquire)
+    }
+
+    pub fn current(&self) -> K {
+        self.read().current().clone()
+    }
+
+    /// A copy of the array as it is now; cheap, as keys are shared.
+    pub fn snapshot(&self) -> KeyArray<K> {
+        self.read().clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// See [`KeyArray::change`]. Panics as it does, after releasing the
+    /// lock, so the lock is not poisoned.
+    pub fn change(&self, i: usize) {
+        self.try_change(i)
+            .unwrap_or_else(|e| panic!("SyncKeyArray::change: {}", e))
+    }
+
+    pub fn try_change(&self, i: usize) -> Result<(), KeyArrayError> {
+        self.write(|keys| keys.try_change(i))
+    }
+
+    pub fn next(&self) {
+        self.write(KeyArray::next)
+    }
+
+    pub fn prev(&self) {
+        self.write(KeyArray::prev)
+    }
+
+    pub fn push(&self, key: K) {
+        self.write(|keys| {
+            keys.push(key);
+        })
+    }
+
+    pub fn try_insert(&self, i: usize, key: K) -> Result<(), KeyArrayError> {
+        self.write(|keys| keys.try_insert(i, key).map(drop))
+    }
+
+    pub fn try_remove(&self, i: usize) -> Result<K, KeyArrayError> {
+        self.write(|keys| keys.try_remove(i))
+    }
+
+    /// Start rebuilding the key list off to the side; see
+    /// [`ReloadHandle`].
+    pub fn begin_reload(&self) -> ReloadHandle<K> {
+        // the swap keeps the live array's history and activity
+        let base = KeyArray {
+            #[cfg(feature = "history")]
+            history: None,
+            #[cfg(feature = "activity")]
+            activity: None,
+            ..self.snapshot()
+        };
+        ReloadHandle {
+            target: self.clone(),
+            base,
+            keys: Vec::new(),
+            select: None,
+        }
+    }
+
+    /// A receiver getting the new current key after every change from now
+    /// on. Dropping it unsubscribes.
+    pub fn watch(&self) -> Receiver<K> {
+        let (tx, rx) = mpsc::channel();
+        self.watchers().push(tx);
+        rx
+    }
+
+    /// A `tokio::sync::watch` receiver holding the current key, or `None`
+    /// while the array is empty. Unlike [`watch`](Self::watch) it only
+    /// keeps the latest value, so slow readers skip intermediate keys.
+    #[cfg(feature = "tokio")]
+    pub fn watch_async(&self) -> tokio::sync::watch::Receiver<Option<K>> {
+        self.shared.watch.subscribe()
+    }
+}
+
+/// A replacement key list being built for a [`SyncKeyArray`]; see
+/// [`begin_reload`](SyncKeyArray::begin_reload). Dropping it abandons the
+/// reload.
+#[must_use = "the keys are only replaced when the reload is committed"]
+pub struct ReloadHandle<K> {
+    target: SyncKeyArray<K>,
+    // the array as it was when the reload began
+    base: KeyArray<K>,
+    keys: Vec<K>,
+    select: Option<K>,
+}
+
+impl<K> ReloadHandle<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    pub fn push(&mut self, key: K) {
+        self.keys.push(key);
+    }
+
+    pub fn extend(&mut self, keys: impl IntoIterator<Item = K>) {
+        self.keys.extend(keys);
+    }
+
+    /// Keys collected so far.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// After the reload, select the first key equal to `key` if there is
+    /// one and it is enabled, instead of keeping the current key.
+    pub fn select(&mut self, key: K) {
+        self.select = Some(key);
+    }
+
+    /// Replace the keys as by [`KeyArray::set_keys`], in one step as far as
+    /// readers can tell. The new list is matched against the array as it
+    /// was when the reload began; if it changed since, the match is redone
+    /// under the lock. Fails with [`KeyArrayError::Empty`], changing
+    /// nothing, if no keys were collected.
+    pub fn commit(self) -> Result<(), KeyArrayError> {
+        if self.keys.is_empty() {
+            return Err(KeyArrayError::Empty);
+        }
+        let ReloadHandle {
+            target,
+            mut base,
+            keys,
+            select,
+        } = self;
+        let generation = base.generation();
+        let reselect = |keys: &mut KeyArray<K>| {
+            let found = select.as_ref().and_then(|k| keys.keys().iter().position(|x| x == k));
+            if let Some(i) = found {
+                keys.try_change(i).ok();
+            }
+        };
+        base.set_keys(keys);
+        reselect(&mut base);
+        target.write(|keys| {
+            if keys.generation() == generation {
+                keys.keys = base.keys;
+                keys.slots = base.slots;
+                keys.idx = base.idx;
+                keys.next_id = base.next_id;
+                keys.touch();
+            } else {
+                keys.set_keys(base.keys.iter().cloned());
+                reselect(keys);
+            }
+        });
+        Ok(())
+    }
+}
+
+fn current_of<K>(keys: &KeyArray<K>) -> Option<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    keys.keys().get(keys.current_index()).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn watchers_see_changes_from_any_handle() {
+        let ka = SyncKeyArray::new(KeyArray::new(["A", "B", "C"]));
+        let early = ka.watch();
+        let other = ka.clone();
+        other.next();
+        let late = ka.watch();
+        assert_eq!(ka.try_remove(0), Ok("A"));
+        ka.write(|keys| keys.sort_by(|a, b| b.cmp(a)));
+        let oob = KeyArrayError::IndexOutOfBounds { index: 5, len: 2 };
+        assert_eq!(other.try_change(5), Err(oob));
+        drop(early);
+        ka.prev();
+
+        // the sort moved the current key, so it is reported again
+        assert_eq!(late.try_iter().collect::<Vec<_>>(), ["C", "C", "B"]);
+        assert_eq!((ka.current_index(), other.current()), (1, "B"));
+        assert_eq!(ka.watchers().len(), 1);
+    }
+
+    #[test]
+    fn reloads_swap_in_whole() {
+        let ka = SyncKeyArray::new(KeyArray::new_with(["A", "B", "C"], 1));
+        let id = ka.read().id_at(1);
+        let changes = ka.watch();
+        let mut reload = ka.begin_reload();
+        reload.extend(["C", "B", "D"]);
+        assert_eq!(ka.len(), 3);
+        reload.commit().unwrap();
+        assert_eq!((ka.current_index(), ka.read().id_at(1)), (1, id));
+
+        // a change made meanwhile is matched again under the lock
+        let mut reload = ka.begin_reload();
+        reload.extend(["E", "D"]);
+        reload.select("E");
+        ka.change(2);
+        reload.commit().unwrap();
+        assert_eq!((ka.current(), ka.len()), ("E", 2));
+        assert_eq!(ka.begin_reload().commit(), Err(KeyArrayError::Empty));
+        assert_eq!(changes.try_iter().collect::<Vec<_>>(), ["D", "E"]);
+    }
+}
@@ -0,0 +1,200 @@
+//! Tauri helpers: expose a backend KeyArray to the JS frontend.
+//!
+//! Manage a [`TauriKeyArray`] and generate the commands in your app crate:
+//!
+//! ```ignore
+//! keyarray::tauri_commands!(String);
+//!
+//! tauri::Builder::default()
+//!     .manage(keyarray::tauri::TauriKeyArray::new(KeyArray::new(
+//!         ["On", "Off", "Auto"].map(String::from),
+//!     )))
+//!     .invoke_handler(tauri::generate_handler![
+//!         keyarray_get_state,
+//!         keyarray_change,
+//!         keyarray_next,
+//!         keyarray_prev,
+//!     ])
+//! ```
+//!
+//! The frontend calls `invoke("keyarray_next")` and friends, each returning a
+//! [`KeyArrayPayload`], and subscribes with `listen("keyarray://changed", ..)`
+//! to hear about every change, including ones made from Rust via
+//! [`update`].
+
+use crate::KeyArray;
+use serde::Serialize;
+use std::fmt::{Debug, Display};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use tauri::{AppHandle, Emitter, Runtime};
+
+/// Event emitted with a [`KeyArrayPayload`] after every change.
+pub const CHANGED_EVENT: &str = "keyarray://changed";
+
+/// Snapshot sent to the frontend.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyArrayPayload<K> {
+    pub keys: Vec<K>,
+    pub current_index: usize,
+    pub current: K,
+    pub generation: u64,
+}
+
+/// Tauri managed state wrapping a KeyArray.
+pub struct TauriKeyArray<K> {
+    inner: Mutex<KeyArray<K>>,
+}
+
+impl<K> TauriKeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    pub fn new(keys: KeyArray<K>) -> Self {
+        TauriKeyArray {
+            inner: Mutex::new(keys),
+        }
+    }
+
+    /// Lock the array for direct access. Changes made this way are not
+    /// emitted; prefer [`update`].
+    pub fn lock(&self) -> MutexGuard<'_, KeyArray<K>> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn payload<K>(ka: &KeyArray<K>) -> KeyArrayPayload<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    KeyArrayPayload {
+        keys: ka.keys().to_vec(),
+        current_index: ka.current_index(),
+        current: ka.current().clone(),
+        generation: ka.generation(),
+    }
+}
+
+/// Current state of the array.
+pub fn get_state<K>(state: &TauriKeyArray<K>) -> KeyArrayPayload<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    payload(&state.lock())
+}
+
+/// Apply `f` and, if it changed anything, emit [`CHANGED_EVENT`].
+pub fn update<R, K, F>(
+    app: &AppHandle<R>,
+    state: &TauriKeyArray<K>,
+    f: F,
+) -> tauri::Result<KeyArrayPayload<K>>
+where
+    R: Runtime,
+    K: Clone + PartialEq + Debug + Display + Serialize,
+    F: FnOnce(&mut KeyArray<K>),
+{
+    let snapshot = {
+        let mut ka = state.lock();
+        let before = ka.generation();
+        f(&mut ka);
+        let changed = ka.generation() != before;
+        (payload(&ka), changed)
+    };
+    if snapshot.1 {
+        app.emit(CHANGED_EVENT, snapshot.0.clone())?;
+    }
+    Ok(snapshot.0)
+}
+
+/// Select `index`, failing with a message if it is out of bounds or cannot
+/// be selected.
+pub fn change<R, K>(
+    app: &AppHandle<R>,
+    state: &TauriKeyArray<K>,
+    index: usize,
+) -> Result<KeyArrayPayload<K>, String>
+where
+    R: Runtime,
+    K: Clone + PartialEq + Debug + Display + Serialize,
+{
+    let mut changed = Ok(());
+    let payload = update(app, state, |ka| changed = ka.try_change(index));
+    changed.map_err(|e| e.to_string())?;
+    payload.map_err(|e| e.to_string())
+}
+
+/// Select the following key, wrapping.
+pub fn next<R, K>(
+    app: &AppHandle<R>,
+    state: &TauriKeyArray<K>,
+) -> Result<KeyArrayPayload<K>, String>
+where
+    R: Runtime,
+    K: Clone + PartialEq + Debug + Display + Serialize,
+{
+    update(app, state, |ka| ka.next()).map_err(|e| e.to_string())
+}
+
+/// Select the preceding key, wrapping.
+pub fn prev<R, K>(
+    app: &AppHandle<R>,
+    state: &TauriKeyArray<K>,
+) -> Result<KeyArrayPayload<K>, String>
+where
+    R: Runtime,
+    K: Clone + PartialEq + Debug + Display + Serialize,
+{
+    update(app, state, |ka| ka.prev()).map_err(|e| e.to_string())
+}
+
+/// Define `keyarray_get_state`, `keyarray_change`, `keyarray_next` and
+/// `keyarray_prev` Tauri commands for a managed `TauriKeyArray<$key>`.
+#[macro_export]
+macro_rules! tauri_commands {
+    ($key:ty) => {
+        #[::tauri::command]
+        fn keyarray_get_state(
+            state: ::tauri::State<'_, $crate::tauri::TauriKeyArray<$key>>,
+        ) -> $crate::tauri::KeyArrayPayload<$key> {
+            $crate::tauri::get_state(state.inner())
+        }
+
+        #[::tauri::command]
+        fn keyarray_change<R: ::tauri::Runtime>(
+            app: ::tauri::AppHandle<R>,
+            state: ::tauri::State<'_, $crate::tauri::TauriKeyArray<$key>>,
+            index: usize,
+        ) -> ::std::result::Result<$crate::tauri::KeyArrayPayload<$key>, String> {
+            $crate::tauri::change(&app, state.inner(), index)
+        }
+
+        #[::tauri::command]
+        fn keyarray_next<R: ::tauri::Runtime>(
+            app: ::tauri::AppHandle<R>,
+            state: ::tauri::State<'_, $crate::tauri::TauriKeyArray<$key>>,
+        ) -> ::std::result::Result<$crate::tauri::KeyArrayPayload<$key>, String> {
+            $crate::tauri::next(&app, state.inner())
+        }
+
+        #[::tauri::command]
+        fn keyarray_prev<R: ::tauri::Runtime>(
+            app: ::tauri::AppHandle<R>,
+            state: ::tauri::State<'_, $crate::tauri::TauriKeyArray<$key>>,
+        ) -> ::std::result::Result<$crate::tauri::KeyArrayPayload<$key>, String> {
+            $crate::tauri::prev(&app, state.inner())
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_shape() {
+        let state = TauriKeyArray::new(KeyArray::new_with(["On", "Off"], 1));
+        let p = get_state(&state);
+        assert_eq!((p.keys, p.current_index, p.current), (vec!["On", "Off"], 1, "Off"));
+    }
+}
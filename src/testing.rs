@@ -0,0 +1,238 @@
+//! Scripted behavioral tests of menu flows.
+//!
+//! A [`TestDriver`] feeds a KeyArray the inputs a user would produce
+//! ([`Msg`]s, mnemonic letters, [script](crate::script) steps), records the
+//! change event of each and, with the `render` feature, what a renderer
+//! drew after it, and checks the outcome with chainable assertions that
+//! panic with the failing input's position. It drives a real KeyArray, so
+//! the test exercises the same semantics as the application.
+//!
+//! ```
+//! use keyarray::testing::{Input, TestDriver};
+//! use keyarray::{KeyArray, Msg};
+//!
+//! let mut menu = TestDriver::new(KeyArray::new(["New", "Open", "Quit"]));
+//! menu.feed(Msg::Next)
+//!     .feed(Input::Letter('q'))
+//!     .refuse(Msg::Select(7))
+//!     .assert_current(&"Quit")
+//!     .assert_events(&["Open", "Quit"]);
+//! ```
+
+use crate::event::ChangeEvent;
+#[cfg(feature = "render")]
+use crate::render::{KeyArrayRenderer, RenderOptions};
+use crate::{KeyArray, Msg};
+use std::fmt::{Debug, Display};
+
+/// One scripted user action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input<K> {
+    /// Applied with [`try_update`](KeyArray::try_update).
+    Msg(Msg<K>),
+    /// A letter key, handled by [`handle_key`](KeyArray::handle_key).
+    Letter(char),
+    /// An expression run by [`execute`](KeyArray::execute).
+    Script(String),
+}
+
+impl<K> From<Msg<K>> for Input<K> {
+    fn from(msg: Msg<K>) -> Self {
+        Input::Msg(msg)
+    }
+}
+
+/// Drives a KeyArray through scripted inputs; see the
+/// [module docs](self).
+pub struct TestDriver<K> {
+    keys: KeyArray<K>,
+    fed: usize,
+    events: Vec<ChangeEvent<K>>,
+    #[cfg(feature = "render")]
+    renderer: Option<(Box<dyn KeyArrayRenderer<K>>, RenderOptions)>,
+    #[cfg(feature = "render")]
+    frames: Vec<String>,
+}
+
+impl<K> TestDriver<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    pub fn new(keys: KeyArray<K>) -> Self {
+        TestDriver {
+            keys,
+            fed: 0,
+            events: Vec::new(),
+            #[cfg(feature = "render")]
+            renderer: None,
+            #[cfg(feature = "render")]
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn keys(&self) -> &KeyArray<K> {
+        &self.keys
+    }
+
+    pub fn into_inner(self) -> KeyArray<K> {
+        self.keys
+    }
+
+    /// One event per input that changed the current index or key, in
+    /// order.
+    pub fn events(&self) -> &[ChangeEvent<K>] {
+        &self.events
+    }
+
+    /// Render with `renderer` after every input from now on; see
+    /// [`frames`](Self::frames).
+    #[cfg(feature = "render")]
+    pub fn capture_frames<R>(&mut self, renderer: R, options: RenderOptions) -> &mut Self
+    where
+        R: KeyArrayRenderer<K> + 'static,
+    {
+        self.renderer = Some((Box::new(renderer), options));
+        self
+    }
+
+    /// What the renderer drew after each input since
+    /// [`capture_frames`](Self::capture_frames).
+    #[cfg(feature = "render")]
+    pub fn frames(&self) -> &[String] {
+        &self.frames
+    }
+
+    // Apply `input`, recording its event and frame; the error says why it
+    // was refused.
+    fn apply(&mut self, input: Input<K>) -> Result<(), String> {
+        let previous = self.keys.current_index();
+        let before = self.keys.keys().get(previous).cloned();
+        self.fed += 1;
+        let outcome = match input {
+            Input::Msg(msg) => self.keys.try_update(msg).map(drop).map_err(|e| e.to_string()),
+            Input::Letter(c) => match self.keys.handle_key(c) {
+                Some(_) => Ok(()),
+                None => Err(format!("no enabled key has the mnemonic {:?}", c)),
+            },
+            Input::Script(expr) => self.keys.execute(&expr).map(drop).map_err(|e| e.to_string()),
+        };
+        let current = self.keys.current_index();
+        if let Some(key) = self.keys.keys().get(current) {
+            if current != previous || before.as_ref() != Some(key) {
+                self.events.push(ChangeEvent {
+                    previous,
+                    current,
+                    key: key.clone(),
+                    reason: None,
+                });
+            }
+        }
+        #[cfg(feature = "render")]
+        if let Some((renderer, options)) = &mut self.renderer {
+            self.frames.push(self.keys.render_with(renderer.as_mut(), *options));
+        }
+        outcome
+    }
+
+    /// Apply `input`. Panics if it is refused.
+    #[track_caller]
+    pub fn feed(&mut self, input: impl Into<Input<K>>) -> &mut Self {
+        let input = input.into();
+        let shown = format!("{:?}", input);
+        if let Err(why) = self.apply(input) {
+            panic!("TestDriver: input {} ({}) was refused: {}", self.fed, shown, why);
+        }
+        self
+    }
+
+    /// [`feed`](Self::feed) every input in order.
+    #[track_caller]
+    pub fn feed_all<I>(&mut self, inputs: impl IntoIterator<Item = I>) -> &mut Self
+    where
+        I: Into<Input<K>>,
+    {
+        for input in inputs {
+            self.feed(input);
+        }
+        self
+    }
+
+    /// Apply `input`, which is expected to be refused. Panics if it is
+    /// accepted.
+    #[track_caller]
+    pub fn refuse(&mut self, input: impl Into<Input<K>>) -> &mut Self {
+        let input = input.into();
+        let shown = format!("{:?}", input);
+        if self.apply(input).is_ok() {
+            panic!("TestDriver: input {} ({}) was accepted", self.fed, shown);
+        }
+        self
+    }
+
+    #[track_caller]
+    pub fn assert_current(&mut self, key: &K) -> &mut Self {
+        assert_eq!(self.keys.current(), key, "TestDriver: current key after {} inputs", self.fed);
+        self
+    }
+
+    #[track_caller]
+    pub fn assert_index(&mut self, i: usize) -> &mut Self {
+        assert_eq!(self.keys.current_index(), i, "TestDriver: index after {} inputs", self.fed);
+        self
+    }
+
+    #[track_caller]
+    pub fn assert_keys(&mut self, keys: &[K]) -> &mut Self {
+        assert_eq!(self.keys.keys(), keys, "TestDriver: keys after {} inputs", self.fed);
+        self
+    }
+
+    /// Check the keys of all events so far.
+    #[track_caller]
+    pub fn assert_events(&mut self, keys: &[K]) -> &mut Self {
+        let seen: Vec<&K> = self.events.iter().map(|e| &e.key).collect();
+        let keys: Vec<&K> = keys.iter().collect();
+        assert_eq!(seen, keys, "TestDriver: events after {} inputs", self.fed);
+        self
+    }
+
+    /// Check the latest frame.
+    #[cfg(feature = "render")]
+    #[track_caller]
+    pub fn assert_frame(&mut self, frame: &str) -> &mut Self {
+        let last = self.frames.last().map(String::as_str);
+        assert_eq!(last, Some(frame), "TestDriver: frame after {} inputs", self.fed);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_events_and_frames() {
+        let mut driver = TestDriver::new(KeyArray::new(["Low", "Mid", "High"]));
+        #[cfg(feature = "render")]
+        driver.capture_frames(crate::render::PlainRenderer, RenderOptions::all());
+        driver
+            .feed_all([Msg::Next, Msg::Insert(0, "Off")])
+            .feed(Input::Script("select 'High'".into()))
+            .refuse(Input::Letter('z'))
+            .assert_index(3)
+            .assert_keys(&["Off", "Low", "Mid", "High"]);
+        let moves: Vec<_> = driver.events().iter().map(|e| (e.previous, e.current)).collect();
+        assert_eq!(moves, [(0, 1), (1, 2), (2, 3)]);
+        #[cfg(feature = "render")]
+        {
+            assert_eq!(driver.frames().len(), 4);
+            driver.assert_frame("Off Low Mid [High]");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "TestDriver: input 2 (Msg(Remove(4))) was refused")]
+    fn refused_inputs_panic_with_their_position() {
+        TestDriver::new(KeyArray::new(["a", "b"])).feed(Msg::Prev).feed(Msg::Remove(4));
+    }
+}
@@ -0,0 +1,260 @@
+//! A stable textual form that parses back.
+//!
+//! [`KeyArrayFormat::TEXT`] writes the keys separated by `, ` with the
+//! current key in brackets: `On, [Off], Auto`. Backslashes, commas,
+//! brackets and whitespace at either end of a key are escaped with `\`, so
+//! every key survives the round trip through a config file or command line;
+//! parsing ignores whitespace around keys. `KeyArray<K>` implements
+//! `FromStr` for this form when `K` does. If no key is bracketed, the first
+//! is current.
+//!
+//! Other formats, such as [`KeyArrayFormat::MENU`] (`[On] Off Auto`), only
+//! change how [`format_with`](KeyArray::format_with) writes and
+//! [`KeyArrayFormat::parse`] reads.
+//!
+//! ```
+//! use keyarray::text::KeyArrayFormat;
+//! use keyarray::KeyArray;
+//!
+//! let ka: KeyArray<String> = "Off, [HDMI 1], A\\, B".parse().unwrap();
+//! assert_eq!(ka.keys(), ["Off", "HDMI 1", "A, B"]);
+//! assert_eq!(ka.current(), "HDMI 1");
+//!
+//! let text = ka.format_with(&KeyArrayFormat::TEXT);
+//! assert_eq!(text, "Off, [HDMI 1], A\\, B");
+//! assert_eq!(text.parse::<KeyArray<String>>().unwrap(), ka);
+//! assert_eq!(ka.format_with(&KeyArrayFormat::MENU), "Off [HDMI 1] A, B");
+//! ```
+
+use crate::KeyArray;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+use std::str::FromStr;
+
+/// How keys are written and read as text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyArrayFormat {
+    /// Written between keys. When reading, surrounding whitespace is
+    /// optional.
+    pub separator: &'static str,
+    /// Written before the current key.
+    pub open: &'static str,
+    /// Written after the current key.
+    pub close: &'static str,
+    /// Escape characters that would be misread with `\`. Without escaping
+    /// the output is for display only.
+    pub escape: bool,
+}
+
+impl KeyArrayFormat {
+    /// The stable, parseable form: `On, [Off], Auto`.
+    pub const TEXT: KeyArrayFormat = KeyArrayFormat {
+        separator: ", ",
+        open: "[",
+        close: "]",
+        escape: true,
+    };
+
+    /// For menus and logs: `On [Off] Auto`, unescaped.
+    pub const MENU: KeyArrayFormat = KeyArrayFormat {
+        separator: " ",
+        open: "[",
+        close: "]",
+        escape: false,
+    };
+
+    /// Read keys written in this format.
+    pub fn parse<K>(&self, s: &str) -> Result<KeyArray<K>, TextError<K::Err>>
+    where
+        K: Clone + PartialEq + Debug + Display + FromStr,
+    {
+        let trimmed = self.separator.trim();
+        let separator = if trimmed.is_empty() { self.separator } else { trimmed };
+        let mut keys = Vec::new();
+        let mut current = None;
+        for (index, chars) in self.split(s, separator)?.into_iter().enumerate() {
+            let mut chars = trim(&chars);
+            if let Some(inner) = self.strip_markers(chars) {
+                if current.replace(index).is_some() {
+                    return Err(TextError::MultipleCurrent { index });
+                }
+                chars = trim(inner);
+            }
+            if chars.is_empty() {
+                return Err(TextError::EmptyKey { index });
+            }
+            let text: String = chars.iter().map(|&(c, _)| c).collect();
+            let key = text.parse().map_err(|error| TextError::Key { index, error })?;
+            keys.push(key);
+        }
+        KeyArray::try_new_with(keys, current.unwrap_or(0)).map_err(|_| TextError::Empty)
+    }
+
+    // Split at unescaped separators into characters tagged with whether
+    // they were escaped.
+    fn split<E>(&self, s: &str, separator: &str) -> Result<Vec<Vec<(char, bool)>>, TextError<E>> {
+        if s.trim().is_empty() {
+            return Err(TextError::Empty);
+        }
+        let mut segments = vec![Vec::new()];
+        let mut rest = s;
+        while let Some(c) = rest.chars().next() {
+            let segment = segments.last_mut().expect("never empty");
+            if self.escape && c == '\\' {
+                let Some(escaped) = rest[1..].chars().next() else {
+                    return Err(TextError::TrailingEscape);
+                };
+                segment.push((escaped, true));
+                rest = &rest[1 + escaped.len_utf8()..];
+            } else if let Some(after) = rest.strip_prefix(separator) {
+                segments.push(Vec::new());
+                rest = after;
+            } else {
+                segment.push((c, false));
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+        Ok(segments)
+    }
+
+    // The characters between unescaped `open` and `close` markers, if the
+    // key has them.
+    fn strip_markers<'c>(&self, chars: &'c [(char, bool)]) -> Option<&'c [(char, bool)]> {
+        let unescaped = |marker: &str, part: &[(char, bool)]| {
+            marker.chars().eq(part.iter().map(|&(c, _)| c)) && part.iter().all(|&(_, esc)| !esc)
+        };
+        let (open, close) = (self.open.chars().count(), self.close.chars().count());
+        if open + close == 0 || chars.len() < open + close {
+            return None;
+        }
+        let (head, rest) = chars.split_at(open);
+        let (inner, tail) = rest.split_at(rest.len() - close);
+        (unescaped(self.open, head) && unescaped(self.close, tail)).then_some(inner)
+    }
+
+    fn write_key(&self, out: &mut String, key: &str) {
+        if !self.escape {
+            out.push_str(key);
+            return;
+        }
+        let special: Vec<char> = [self.separator.trim(), self.open, self.close]
+            .iter()
+            .filter_map(|s| s.chars().next())
+            .chain(['\\'])
+            .collect();
+        let last = key.chars().count().saturating_sub(1);
+        for (i, c) in key.chars().enumerate() {
+            let at_end = i == 0 || i == last;
+            if special.contains(&c) || (at_end && c.is_whitespace()) {
+                out.push('\\');
+            }
+            out.push(c);
+        }
+    }
+}
+
+/// [`KeyArrayFormat::TEXT`].
+impl Default for KeyArrayFormat {
+    fn default() -> Self {
+        KeyArrayFormat::TEXT
+    }
+}
+
+/// Why text could not be read as a KeyArray.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextError<E> {
+    /// The text has no keys.
+    Empty,
+    /// Key `index` is blank.
+    EmptyKey { index: usize },
+    /// The text ends with a lone `\`.
+    TrailingEscape,
+    /// Key `index` is the second one marked current.
+    MultipleCurrent { index: usize },
+    /// Key `index` did not parse as `K`.
+    Key { index: usize, error: E },
+}
+
+impl<E: Display> Display for TextError<E> {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            TextError::Empty => f.write_str("no keys"),
+            TextError::EmptyKey { index } => write!(f, "key {} is empty", index),
+            TextError::TrailingEscape => f.write_str("text ends with an escape"),
+            TextError::MultipleCurrent { index } => {
+                write!(f, "key {} is marked current, but an earlier key is too", index)
+            }
+            TextError::Key { index, error } => write!(f, "key {}: {}", index, error),
+        }
+    }
+}
+
+impl<E: Debug + Display> std::error::Error for TextError<E> {}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Write the keys in `format`, marking the current one. Soft-removed
+    /// keys are included.
+    pub fn format_with(&self, format: &KeyArrayFormat) -> String {
+        let mut out = String::new();
+        for (i, key) in self.keys.iter().enumerate() {
+            if i > 0 {
+                out.push_str(format.separator);
+            }
+            if i == self.idx {
+                out.push_str(format.open);
+            }
+            format.write_key(&mut out, &key.to_string());
+            if i == self.idx {
+                out.push_str(format.close);
+            }
+        }
+        out
+    }
+}
+
+// Drop unescaped whitespace from both ends.
+fn trim(chars: &[(char, bool)]) -> &[(char, bool)] {
+    let blank = |&(c, esc): &(char, bool)| !esc && c.is_whitespace();
+    let start = chars.iter().take_while(|c| blank(c)).count();
+    let end = chars.len() - chars[start..].iter().rev().take_while(|c| blank(c)).count();
+    &chars[start..end]
+}
+
+/// Parses [`KeyArrayFormat::TEXT`].
+impl<K> FromStr for KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display + FromStr,
+{
+    type Err = TextError<K::Err>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        KeyArrayFormat::TEXT.parse(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn awkward_keys_round_trip() {
+        let keys = [" lead", "a\\b", "[x]", "tail ", "c,d"].map(String::from);
+        let ka = KeyArray::new_with(keys, 2);
+        let text = ka.format_with(&KeyArrayFormat::default());
+        assert_eq!(text, r"\ lead, a\\b, [\[x\]], tail\ , c\,d");
+        assert_eq!(text.parse::<KeyArray<String>>(), Ok(ka));
+
+        let nums: KeyArray<u8> = " 1 ,2,[ 3 ]".parse().unwrap();
+        assert_eq!((nums.keys(), nums.current_index()), (&[1, 2, 3][..], 2));
+        assert_eq!(KeyArrayFormat::MENU.parse::<u8>("1 [2] 3").unwrap().current(), &2);
+
+        let parse = |s: &str| s.parse::<KeyArray<u8>>().map(|_| ()).map_err(|e| e.to_string());
+        assert_eq!(parse(" "), Err("no keys".into()));
+        assert_eq!(parse("1,,2"), Err("key 1 is empty".into()));
+        assert_eq!(parse("1\\"), Err("text ends with an escape".into()));
+        assert!(parse("[1], [2]").unwrap_err().contains("key 1 is marked current"));
+        assert!(parse("1, x").unwrap_err().starts_with("key 1: "));
+    }
+}
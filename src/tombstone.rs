@@ -0,0 +1,111 @@
+//! Soft deletion: hide a key without removing it.
+//!
+//! A soft-removed key keeps its index and [`KeyId`], so positions stay
+//! stable for audit logs and undo, but navigation and rendering skip it
+//! until it is [`restore`](KeyArray::restore)d.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//!
+//! let mut ka = KeyArray::new(["A", "B", "C"]);
+//! let b = ka.soft_remove(1);
+//! ka.next();
+//! assert_eq!(ka.current(), &"C");
+//! assert_eq!(ka.keys(), ["A", "B", "C"]);
+//!
+//! assert_eq!(ka.restore(b), Some(1));
+//! ka.prev();
+//! assert_eq!(ka.current(), &"B");
+//! ```
+
+use crate::id::KeyId;
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Hide key `i` and return its id. If it was current, the following
+    /// visible key becomes current; if no other key is visible, the
+    /// selection stays. Panics if `i` is out of bounds.
+    pub fn soft_remove(&mut self, i: usize) -> KeyId {
+        assert!(
+            i < self.keys.len(),
+            "KeyArray::soft_remove: index {} out of bounds",
+            i
+        );
+        if !self.slots[i].hidden {
+            Arc::make_mut(&mut self.slots)[i].hidden = true;
+            self.touch();
+            if i == self.idx {
+                self.step_visible(|keys, at| keys.wrapped(at, 1));
+            }
+        }
+        self.slots[i].id
+    }
+
+    /// Bring back the soft-removed key `id` and return its index. `None` if
+    /// there is no such key or it was not removed.
+    pub fn restore(&mut self, id: KeyId) -> Option<usize> {
+        let i = self.index_of_id(id)?;
+        if !self.slots[i].hidden {
+            return None;
+        }
+        Arc::make_mut(&mut self.slots)[i].hidden = false;
+        self.touch();
+        Some(i)
+    }
+
+    /// Whether key `i` is soft-removed; `false` if out of bounds.
+    pub fn is_hidden(&self, i: usize) -> bool {
+        self.slots.get(i).is_some_and(|s| s.hidden)
+    }
+
+    /// Indices and ids of every soft-removed key, ascending.
+    pub fn tombstones(&self) -> impl Iterator<Item = (usize, KeyId)> + '_ {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.hidden)
+            .map(|(i, s)| (i, s.id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hidden_keys_are_skipped_until_restored() {
+        let mut ka = KeyArray::new(["A", "B", "C", "D"]);
+        let a = ka.soft_remove(0);
+        assert_eq!(ka.current(), &"B");
+        ka.soft_remove(2);
+        assert_eq!(ka.soft_remove(2), ka.id_at(2).unwrap());
+        ka.next();
+        assert_eq!(ka.current(), &"D");
+        ka.next();
+        assert_eq!(ka.current(), &"B");
+        assert_eq!(ka.tombstones().map(|(i, _)| i).collect::<Vec<_>>(), [0, 2]);
+
+        assert_eq!(ka.restore(a), Some(0));
+        assert_eq!(ka.restore(a), None);
+        ka.prev();
+        assert_eq!(ka.current(), &"A");
+        assert!(ka.is_hidden(2) && !ka.is_hidden(0));
+    }
+
+    #[cfg(feature = "render")]
+    #[test]
+    fn hidden_keys_are_not_rendered() {
+        use crate::render::{PlainRenderer, RenderOptions};
+        let mut ka = KeyArray::new(["Low", "Med", "High"]);
+        ka.soft_remove(1);
+        assert_eq!(
+            ka.render_with(&mut PlainRenderer, RenderOptions::all()),
+            "[Low] High"
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! A bounded trace of recent operations, for debugging bad indices.
+//!
+//! Once [`enable_tracing`](KeyArray::enable_tracing) is called, the core
+//! operations (changing, stepping, inserting, removing and reordering keys)
+//! are recorded with their index and the length they saw, keeping the
+//! latest ones. [`recent_ops`](KeyArray::recent_ops) lists them; the
+//! panicking methods append them to their message; and
+//! [`traced`](KeyArray::traced) attaches them to an error from a `try_*`
+//! method. While tracing is off, nothing is recorded.
+//!
+//! ```
+//! use keyarray::KeyArray;
+//!
+//! let mut tabs = KeyArray::new(["Home", "Search", "Settings"]);
+//! tabs.enable_tracing(8);
+//! tabs.remove(2);
+//! tabs.next();
+//! let result = tabs.try_change(2);
+//! let err = tabs.traced(result).unwrap_err();
+//! assert_eq!(
+//!     err.to_string(),
+//!     "index 2 out of bounds for 2 keys; recent operations: \
+//!      remove(2) on 3 keys, next on 2 keys, change(2) on 2 keys"
+//! );
+//! ```
+
+use crate::error::KeyArrayError;
+use crate::KeyArray;
+use std::collections::VecDeque;
+use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
+
+/// One recorded operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TracedOp {
+    /// The method, without any `try_` prefix.
+    pub name: &'static str,
+    /// The index it was given, if any.
+    pub index: Option<usize>,
+    /// How many keys there were before it ran.
+    pub len: usize,
+}
+
+impl Display for TracedOp {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.write_str(self.name)?;
+        if let Some(i) = self.index {
+            write!(f, "({})", i)?;
+        }
+        write!(f, " on {} keys", self.len)
+    }
+}
+
+/// An error with the operations that led up to it; from
+/// [`traced`](KeyArray::traced).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TracedError {
+    pub error: KeyArrayError,
+    /// Oldest first.
+    pub recent_ops: Vec<TracedOp>,
+}
+
+impl Display for TracedError {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        Display::fmt(&self.error, f)?;
+        write_ops(f, &self.recent_ops)
+    }
+}
+
+impl std::error::Error for TracedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+fn write_ops<'a>(f: &mut Formatter, ops: impl IntoIterator<Item = &'a TracedOp>) -> FmtResult {
+    for (n, op) in ops.into_iter().enumerate() {
+        let separator = if n == 0 { "; recent operations: " } else { ", " };
+        write!(f, "{}{}", separator, op)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Trace {
+    capacity: usize,
+    ops: VecDeque<TracedOp>,
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Start recording the latest `capacity` operations, forgetting any
+    /// recorded before. Panics if `capacity` is zero.
+    pub fn enable_tracing(&mut self, capacity: usize) {
+        assert!(capacity > 0, "KeyArray::enable_tracing: capacity must be positive");
+        self.trace = Some(Box::new(Trace {
+            capacity,
+            ops: VecDeque::with_capacity(capacity),
+        }));
+    }
+
+    /// Stop recording and forget the trace.
+    pub fn disable_tracing(&mut self) {
+        self.trace = None;
+    }
+
+    /// The recorded operations, oldest first; empty while tracing is off.
+    pub fn recent_ops(&self) -> Vec<TracedOp> {
+        self.trace.iter().flat_map(|t| t.ops.iter().copied()).collect()
+    }
+
+    /// Attach [`recent_ops`](Self::recent_ops) to the error of `result`.
+    pub fn traced<T>(&self, result: Result<T, KeyArrayError>) -> Result<T, TracedError> {
+        result.map_err(|error| TracedError {
+            error,
+            recent_ops: self.recent_ops(),
+        })
+    }
+
+    // Record operation `name` on `index`, if tracing.
+    pub(crate) fn trace_op(&mut self, name: &'static str, index: Option<usize>) {
+        let len = self.len();
+        if let Some(trace) = &mut self.trace {
+            if trace.ops.len() == trace.capacity {
+                trace.ops.pop_front();
+            }
+            trace.ops.push_back(TracedOp { name, index, len });
+        }
+    }
+
+    // Panic from `method` with `error` and the recorded operations.
+    pub(crate) fn fail(&self, method: &str, error: KeyArrayError) -> ! {
+        struct Ops<'a>(&'a VecDeque<TracedOp>);
+        impl Display for Ops<'_> {
+            fn fmt(&self, f: &mut Formatter) -> FmtResult {
+                write_ops(f, self.0)
+            }
+        }
+        match &self.trace {
+            Some(trace) => panic!("KeyArray::{}: {}{}", method, error, Ops(&trace.ops)),
+            None => panic!("KeyArray::{}: {}", method, error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_latest_ops() {
+        let mut ka = KeyArray::new(["a", "b"]);
+        ka.next();
+        assert!(ka.recent_ops().is_empty());
+        ka.enable_tracing(2);
+        ka.push("c");
+        ka.insert(0, "z");
+        ka.swap(0, 3);
+        let ops: Vec<_> = ka.recent_ops().iter().map(ToString::to_string).collect();
+        assert_eq!(ops, ["insert(0) on 3 keys", "swap(0) on 4 keys"]);
+        assert_eq!(ka.traced(Ok(1)), Ok(1));
+        ka.disable_tracing();
+        assert!(ka.recent_ops().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "KeyArray::change: index 5 out of bounds for 2 keys; \
+                               recent operations: prev on 2 keys, change(5) on 2 keys")]
+    fn panics_list_recent_ops() {
+        let mut ka = KeyArray::new(["a", "b"]);
+        ka.enable_tracing(4);
+        ka.prev();
+        ka.change(5);
+    }
+}
@@ -0,0 +1,190 @@
+//! Opt-in transition table for guarded selection changes.
+//!
+//! By default any key may follow any other. Once a key has an allowed
+//! transition, it may only move to the keys it is allowed to reach; a
+//! denied transition is refused whatever else is allowed. Rules follow the
+//! keys' [`KeyId`]s, so they survive inserts, removals and sorts.
+//! [`guarded_change`](KeyArray::guarded_change) checks the table; plain
+//! [`change`](KeyArray::change) ignores it.
+//!
+//! ```
+//! use keyarray::{KeyArray, KeyArrayError};
+//!
+//! let mut power = KeyArray::new_with(["On", "Off", "Auto"], 1);
+//! power.allow_transition(1, 0);
+//! assert_eq!(
+//!     power.guarded_change(2),
+//!     Err(KeyArrayError::TransitionDenied { from: 1, to: 2 })
+//! );
+//! power.guarded_change(0).unwrap();
+//! assert_eq!(power.current(), &"On");
+//! ```
+
+use crate::describe::RuleKind;
+use crate::error::KeyArrayError;
+use crate::id::KeyId;
+use crate::{check_index, KeyArray};
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+type Guard<K> = Arc<dyn Fn(&K, &K) -> bool + Send + Sync>;
+
+#[derive(Clone)]
+enum Rule<K> {
+    // with an optional guard called with the old and new key
+    Allow(Option<Guard<K>>),
+    Deny,
+}
+
+// Guards are shared, not copied, between clones.
+#[derive(Clone)]
+pub(crate) struct Transitions<K> {
+    rules: Arc<BTreeMap<(KeyId, KeyId), Rule<K>>>,
+}
+
+impl<K> Default for Transitions<K> {
+    fn default() -> Self {
+        Transitions {
+            rules: Arc::default(),
+        }
+    }
+}
+
+impl<K> Transitions<K> {
+    // guards are not counted: their size is not known
+    pub(crate) fn heap_bytes(&self) -> usize {
+        self.rules.len() * std::mem::size_of::<((KeyId, KeyId), Rule<K>)>()
+    }
+
+    // every rule, by pair, for `describe` and `to_canonical_string`
+    pub(crate) fn describe(&self) -> impl Iterator<Item = (KeyId, KeyId, RuleKind)> + '_ {
+        self.rules.iter().map(|(&(from, to), rule)| {
+            let kind = match rule {
+                Rule::Allow(None) => RuleKind::Allow,
+                Rule::Allow(Some(_)) => RuleKind::Guarded,
+                Rule::Deny => RuleKind::Deny,
+            };
+            (from, to, kind)
+        })
+    }
+
+    fn permits(&self, from: KeyId, to: KeyId, keys: (&K, &K)) -> bool {
+        if from == to {
+            return true;
+        }
+        match self.rules.get(&(from, to)) {
+            Some(Rule::Deny) => false,
+            Some(Rule::Allow(Some(guard))) => guard(keys.0, keys.1),
+            Some(Rule::Allow(None)) => true,
+            None => !self
+                .rules
+                .range((from, KeyId(0))..=(from, KeyId(u64::MAX)))
+                .any(|(_, rule)| matches!(rule, Rule::Allow(_))),
+        }
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Allow moving from key `from` to key `to`. From then on `from` may
+    /// only move to keys it is allowed to reach. Replaces any earlier rule
+    /// for the pair. Panics if either index is out of bounds.
+    pub fn allow_transition(&mut self, from: usize, to: usize) {
+        self.set_transition("allow_transition", from, to, Rule::Allow(None));
+    }
+
+    /// Refuse moving from key `from` to key `to`. Replaces any earlier rule
+    /// for the pair. Panics if either index is out of bounds.
+    pub fn deny_transition(&mut self, from: usize, to: usize) {
+        self.set_transition("deny_transition", from, to, Rule::Deny);
+    }
+
+    /// [`allow_transition`](Self::allow_transition), but only while
+    /// `guard(from_key, to_key)` returns true.
+    pub fn guard_transition<F>(&mut self, from: usize, to: usize, guard: F)
+    where
+        F: Fn(&K, &K) -> bool + Send + Sync + 'static,
+    {
+        let rule = Rule::Allow(Some(Arc::new(guard)));
+        self.set_transition("guard_transition", from, to, rule);
+    }
+
+    /// Forget every transition rule, allowing all moves again.
+    pub fn clear_transitions(&mut self) {
+        self.transitions = Transitions::default();
+    }
+
+    /// Whether the table permits moving from key `from` to key `to`.
+    /// Staying on a key is always permitted. Panics if either index is out
+    /// of bounds.
+    pub fn can_transition(&self, from: usize, to: usize) -> bool {
+        let (a, b) = (self.slots[from].id, self.slots[to].id);
+        self.transitions.permits(a, b, (&self.keys[from], &self.keys[to]))
+    }
+
+    /// [`try_change`](Self::try_change), also failing with
+    /// [`KeyArrayError::TransitionDenied`] if the table does not permit
+    /// moving from the current key to key `i`, or to the replacement a
+    /// [deprecated](Self::deprecate) key `i` would be swapped for.
+    pub fn guarded_change(&mut self, i: usize) -> Result<(), KeyArrayError> {
+        self.check_change(i)?;
+        self.try_change(i)
+    }
+
+    /// Whether [`guarded_change(i)`](Self::guarded_change) would succeed,
+    /// without changing anything.
+    pub fn check_change(&self, i: usize) -> Result<(), KeyArrayError> {
+        check_index(i, self.len())?;
+        self.check_selectable(i)?;
+        #[cfg(feature = "deprecation")]
+        let to = self.deprecation_target(i);
+        #[cfg(not(feature = "deprecation"))]
+        let to = i;
+        if !self.can_transition(self.idx, to) {
+            return Err(KeyArrayError::TransitionDenied { from: self.idx, to });
+        }
+        Ok(())
+    }
+
+    fn set_transition(&mut self, method: &str, from: usize, to: usize, rule: Rule<K>) {
+        for i in [from, to] {
+            check_index(i, self.len())
+                .unwrap_or_else(|e| panic!("KeyArray::{}: {}", method, e));
+        }
+        let key = (self.slots[from].id, self.slots[to].id);
+        Arc::make_mut(&mut self.transitions.rules).insert(key, rule);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    fn table_follows_keys_and_guards() {
+        let mut ka = KeyArray::new(["Off", "On", "Auto"]);
+        ka.deny_transition(0, 2);
+        assert!(ka.can_transition(0, 1) && !ka.can_transition(0, 2));
+
+        // the rule moves with the keys
+        ka.insert(0, "Eco");
+        assert!(!ka.can_transition(1, 3) && ka.can_transition(3, 1));
+
+        let armed = Arc::new(AtomicBool::new(false));
+        let flag = Arc::clone(&armed);
+        ka.guard_transition(1, 2, move |_, _| flag.load(Ordering::Relaxed));
+        assert!(!ka.can_transition(1, 0), "Off now only reaches On");
+        let denied = KeyArrayError::TransitionDenied { from: 1, to: 2 };
+        assert_eq!(ka.guarded_change(2), Err(denied));
+        armed.store(true, Ordering::Relaxed);
+        assert_eq!(ka.guarded_change(2), Ok(()));
+        assert_eq!(ka.current(), &"On");
+
+        ka.clear_transitions();
+        assert!(ka.can_transition(2, 0));
+    }
+}
@@ -0,0 +1,94 @@
+//! Safe-order traversal: changes that pass through the keys in between.
+//!
+//! Some hardware may only be switched between adjacent modes, like a
+//! selector whose positions must be passed one by one. Under
+//! [`Traversal::Adjacent`], [`change`](KeyArray::change) and everything
+//! built on it move along the [`transition_plan`](KeyArray::transition_plan)
+//! to the target, one key per hop, and observers are told about every hop,
+//! so each can be applied to the device in turn. Hidden and disabled keys
+//! are passed over as they are by the plan. Unlike
+//! [`gradual_change`](KeyArray::gradual_change), all hops happen at once.
+//!
+//! ```
+//! use keyarray::traversal::Traversal;
+//! use keyarray::KeyArray;
+//! use std::sync::{Arc, Mutex};
+//!
+//! let mut gear = KeyArray::new(["P", "R", "N", "D"]);
+//! gear.set_traversal(Traversal::Adjacent);
+//! let hops = Arc::new(Mutex::new(Vec::new()));
+//! let seen = hops.clone();
+//! gear.on_change(move |_, _, key| seen.lock().unwrap().push(*key));
+//!
+//! gear.change(3);
+//! assert_eq!(*hops.lock().unwrap(), ["R", "N", "D"]);
+//! ```
+//!
+//! [`next`](KeyArray::next) and [`prev`](KeyArray::prev) already move to a
+//! neighbour, except when wrapping from one end to the other; use
+//! [`WrapMode::Saturate`](crate::cursor::WrapMode::Saturate) to rule that
+//! out.
+
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+
+/// How [`change`](KeyArray::change) reaches its target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Traversal {
+    /// Straight to the target.
+    #[default]
+    Direct,
+    /// Through every selectable key in between, one hop at a time.
+    Adjacent,
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    pub fn traversal(&self) -> Traversal {
+        self.traversal
+    }
+
+    pub fn set_traversal(&mut self, traversal: Traversal) {
+        self.traversal = traversal;
+    }
+
+    // Make key `i`, already checked, current as the traversal mode says.
+    pub(crate) fn move_to(&mut self, i: usize) {
+        match self.traversal {
+            Traversal::Direct => {
+                self.idx = i;
+                self.touch();
+            }
+            Traversal::Adjacent => {
+                for hop in self.transition_plan(self.idx, i) {
+                    self.idx = hop;
+                    self.touch();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "disable"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hops_skip_unselectable_keys() {
+        let mut ka = KeyArray::new(["0", "1", "2", "3", "4"]);
+        ka.set_traversal(Traversal::Adjacent);
+        ka.disable(2);
+        let generation = ka.generation();
+        ka.change(4);
+        assert_eq!(ka.generation() - generation, 3);
+        ka.change(0);
+        assert_eq!(ka.generation() - generation, 6);
+
+        ka.set_traversal(Traversal::Direct);
+        ka.change(4);
+        assert_eq!(ka.generation() - generation, 7);
+    }
+}
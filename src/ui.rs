@@ -0,0 +1,158 @@
+//! Per-key presentation hints shared by every renderer and widget adapter.
+//!
+//! A [`KeyUi`] travels with its key through inserts, removals and sorts, so a
+//! menu built from the KeyArray never shows the wrong icon next to a label.
+//!
+//! Its colors and [`Emphasis`] form the key's [`KeyStyle`], which the ANSI
+//! and embedded-graphics renderers both honour, so a key marked
+//! [`ColorHint::Danger`] is red in every frontend without restyling it in
+//! each. Frontends without a renderer here can do the same with
+//! [`KeyArray::style`] and [`ColorHint::to_rgb`].
+//!
+//! ```
+//! use keyarray::ui::{ColorHint, Emphasis, KeyUi};
+//! use keyarray::KeyArray;
+//!
+//! let mut menu = KeyArray::new(["Save", "Erase All"]);
+//! menu.set_ui(1, KeyUi::new().color(ColorHint::Danger).emphasis(Emphasis::BOLD));
+//! let style = menu.style(1);
+//! assert_eq!(style.foreground.to_rgb(), Some((205, 49, 49)));
+//! assert!(style.emphasis.bold && !menu.style(0).emphasis.bold);
+//! ```
+
+use crate::KeyArray;
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+pub use crate::style::{ColorHint, Emphasis, KeyStyle};
+
+/// How a single key should be presented.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct KeyUi {
+    /// Toolkit-specific icon identifier (e.g. `"media-playback-start"`).
+    pub icon: Option<String>,
+    /// Hover/help text.
+    pub tooltip: Option<String>,
+    /// Short text shown beside the label, such as a count or "New".
+    pub badge: Option<String>,
+    /// Text color.
+    pub color: ColorHint,
+    pub background: ColorHint,
+    pub emphasis: Emphasis,
+}
+
+impl KeyUi {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn icon(mut self, icon: impl Into<String>) -> Self {
+        self.icon = Some(icon.into());
+        self
+    }
+
+    pub fn tooltip(mut self, tooltip: impl Into<String>) -> Self {
+        self.tooltip = Some(tooltip.into());
+        self
+    }
+
+    pub fn badge(mut self, badge: impl Into<String>) -> Self {
+        self.badge = Some(badge.into());
+        self
+    }
+
+    pub fn color(mut self, color: ColorHint) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn background(mut self, background: ColorHint) -> Self {
+        self.background = background;
+        self
+    }
+
+    /// Add `emphasis` to any set before.
+    pub fn emphasis(mut self, emphasis: Emphasis) -> Self {
+        self.emphasis = self.emphasis.union(emphasis);
+        self
+    }
+
+    pub fn style(&self) -> KeyStyle {
+        KeyStyle {
+            foreground: self.color,
+            background: self.background,
+            emphasis: self.emphasis,
+        }
+    }
+}
+
+impl<K> KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    /// Attach presentation hints to key `i`, replacing any previous ones.
+    /// Panics if `i` is out of bounds.
+    pub fn set_ui(&mut self, i: usize, ui: KeyUi) {
+        assert!(
+            i < self.keys.len(),
+            "KeyArray::set_ui: index {} out of bounds",
+            i
+        );
+        Arc::make_mut(&mut self.slots)[i].ui = Some(ui);
+        self.touch();
+    }
+
+    /// Presentation hints of key `i`, if any were attached.
+    pub fn ui(&self, i: usize) -> Option<&KeyUi> {
+        self.slots.get(i)?.ui.as_ref()
+    }
+
+    /// Colors and emphasis of key `i`; the default style if it has no
+    /// hints or `i` is out of bounds.
+    pub fn style(&self, i: usize) -> KeyStyle {
+        self.ui(i).map(KeyUi::style).unwrap_or_default()
+    }
+
+    /// Presentation hints of the current key.
+    pub fn current_ui(&self) -> Option<&KeyUi> {
+        self.ui(self.idx)
+    }
+
+    /// Detach and return the hints of key `i`.
+    pub fn clear_ui(&mut self, i: usize) -> Option<KeyUi> {
+        self.slots.get(i)?.ui.as_ref()?;
+        let ui = Arc::make_mut(&mut self.slots)[i].ui.take();
+        self.touch();
+        ui
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ui_follows_its_key() {
+        let mut ka = KeyArray::new(["Play", "Stop"]);
+        ka.set_ui(1, KeyUi::new().icon("stop").color(ColorHint::Danger));
+        ka.insert(0, "Rewind");
+        assert_eq!(ka.ui(2).unwrap().icon.as_deref(), Some("stop"));
+        ka.sort_by(|a, b| b.cmp(a));
+        assert_eq!(ka.keys(), &["Stop", "Rewind", "Play"]);
+        assert_eq!(ka.ui(0).unwrap().color, ColorHint::Danger);
+        assert_eq!(ka.clear_ui(0).unwrap().icon.as_deref(), Some("stop"));
+        assert!(ka.ui(0).is_none());
+    }
+
+    #[cfg(feature = "history")]
+    #[test]
+    fn undo_keeps_a_cleared_ui_cleared() {
+        let mut ka = KeyArray::new(["Play", "Stop"]);
+        ka.set_ui(0, KeyUi::new().icon("play"));
+        ka.enable_history(8);
+        ka.clear_ui(0);
+        ka.change(1);
+        assert!(ka.undo());
+        assert_eq!((ka.current_index(), ka.ui(0)), (0, None));
+    }
+}
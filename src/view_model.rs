@@ -0,0 +1,91 @@
+//! A minimal "anything selectable" interface for MVU-style views.
+//!
+//! Views written against [`SelectionViewModel`] accept a KeyArray in
+//! production and a hand-rolled double in tests.
+
+use crate::{KeyArray, Msg};
+use std::fmt::{Debug, Display};
+
+/// Read side of a single-selection list, plus the message a view should emit
+/// when the user picks an item.
+pub trait SelectionViewModel {
+    type Item;
+    type Message;
+
+    /// All items, in display order.
+    fn items(&self) -> &[Self::Item];
+
+    /// Index of the selected item, if any.
+    fn selected(&self) -> Option<usize>;
+
+    /// Message to dispatch when the item at `index` is chosen.
+    fn on_select(&self, index: usize) -> Self::Message;
+
+    /// Whether the item at `index` is the selected one.
+    fn is_selected(&self, index: usize) -> bool {
+        self.selected() == Some(index)
+    }
+}
+
+impl<K> SelectionViewModel for KeyArray<K>
+where
+    K: Clone + PartialEq + Debug + Display,
+{
+    type Item = K;
+    type Message = Msg<K>;
+
+    fn items(&self) -> &[K] {
+        self.keys()
+    }
+
+    fn selected(&self) -> Option<usize> {
+        Some(self.current_index())
+    }
+
+    fn on_select(&self, index: usize) -> Msg<K> {
+        Msg::Select(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view<V>(vm: &V) -> Vec<(String, V::Message)>
+    where
+        V: SelectionViewModel,
+        V::Item: Display,
+    {
+        vm.items()
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let mark = if vm.is_selected(i) { "*" } else { "" };
+                (format!("{}{}", mark, item), vm.on_select(i))
+            })
+            .collect()
+    }
+
+    struct Nothing;
+    impl SelectionViewModel for Nothing {
+        type Item = String;
+        type Message = ();
+        fn items(&self) -> &[String] {
+            &[]
+        }
+        fn selected(&self) -> Option<usize> {
+            None
+        }
+        fn on_select(&self, _index: usize) {}
+    }
+
+    #[test]
+    fn key_array_and_double() {
+        let ka = KeyArray::new_with(["On", "Off"], 1);
+        assert_eq!(
+            view(&ka),
+            vec![("On".into(), Msg::Select(0)), ("*Off".into(), Msg::Select(1))]
+        );
+        assert!(view(&Nothing).is_empty());
+    }
+}
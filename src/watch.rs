@@ -0,0 +1,214 @@
+//! Reload the key list from a file whenever it changes on disk.
+//!
+//! The file holds one key per line (parsed with `FromStr`); a leading `* `
+//! marks the key to select, and blank lines and `#` comments are skipped:
+//!
+//! ```text
+//! # power profiles
+//! Eco
+//! * Balanced
+//! Boost
+//! ```
+//!
+//! A reload goes through [`KeyArray::set_keys`], so keys that survive keep
+//! their ids and attached data, and the current key stays current unless the
+//! file selects another. However many writes landed since the last poll,
+//! they produce one reload and at most one [`ChangeEvent`].
+//!
+//! ```no_run
+//! use keyarray::watch::KeyFileWatcher;
+//! use keyarray::KeyArray;
+//!
+//! let watcher = KeyFileWatcher::new("profiles.txt")?;
+//! let mut profiles = KeyArray::new(["Balanced".to_string()]);
+//! keyarray::watch::reload_from(watcher.path(), &mut profiles)?;
+//! loop {
+//!     if let Some(ev) = watcher.poll(&mut profiles)? {
+//!         println!("switched to {}", ev.key);
+//!     }
+//!     # break;
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::event::ChangeEvent;
+use crate::KeyArray;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::fmt::{Debug, Display};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver};
+
+/// Reason attached to events caused by a reload.
+pub const REASON: &str = "reload";
+
+/// Parse a key file: the keys, and the index of the line marked `* `.
+pub fn parse_key_file<K: FromStr>(text: &str) -> io::Result<(Vec<K>, Option<usize>)> {
+    let mut keys = Vec::new();
+    let mut selected = None;
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let text = match line.strip_prefix("* ") {
+            Some(rest) => {
+                selected = Some(keys.len());
+                rest.trim_start()
+            }
+            None => line,
+        };
+        let key = text.parse().map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid key {:?}", text),
+            )
+        })?;
+        keys.push(key);
+    }
+    if keys.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "key file lists no keys",
+        ));
+    }
+    Ok((keys, selected))
+}
+
+/// Replace the keys of `keys` with those listed in the file at `path`.
+/// Returns an event, with [`REASON`], if the current index or key changed.
+/// On a read or parse error `keys` is left untouched.
+pub fn reload_from<K>(path: &Path, keys: &mut KeyArray<K>) -> io::Result<Option<ChangeEvent<K>>>
+where
+    K: Clone + PartialEq + Debug + Display + FromStr,
+{
+    let (list, selected) = parse_key_file(&fs::read_to_string(path)?)?;
+    let previous = keys.current_index();
+    let before = keys.current().clone();
+    keys.set_keys(list);
+    // a selected key that cannot be selected here keeps the selection
+    if let Some(i) = selected {
+        let _ = keys.try_change(i);
+    }
+    if keys.current_index() == previous && *keys.current() == before {
+        return Ok(None);
+    }
+    Ok(Some(ChangeEvent {
+        previous,
+        current: keys.current_index(),
+        key: keys.current().clone(),
+        reason: Some(REASON.into()),
+    }))
+}
+
+/// Watches one key file; see the module docs.
+pub struct KeyFileWatcher {
+    path: PathBuf,
+    // dropping the watcher stops notifications
+    _watcher: RecommendedWatcher,
+    changed: Receiver<()>,
+}
+
+impl KeyFileWatcher {
+    /// Start watching `path`. Its directory is watched rather than the file,
+    /// so editors and tools that replace the file atomically are seen too.
+    pub fn new(path: impl Into<PathBuf>) -> notify::Result<Self> {
+        let path = path.into();
+        let name = path.file_name().map(ToOwned::to_owned);
+        let (tx, changed) = mpsc::channel();
+        let mut watcher =
+            notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+                if let Ok(event) = res {
+                    if event.paths.iter().any(|p| p.file_name() == name.as_deref()) {
+                        let _ = tx.send(());
+                    }
+                }
+            })?;
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+        Ok(KeyFileWatcher {
+            path,
+            _watcher: watcher,
+            changed,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the file changed since the last call (or the last
+    /// [`poll`](Self::poll)). Never blocks.
+    pub fn take_changed(&self) -> bool {
+        self.changed.try_iter().count() > 0
+    }
+
+    /// If the file changed since the last poll, reload it once into `keys`
+    /// (see [`reload_from`]). A file that is momentarily missing is skipped.
+    pub fn poll<K>(&self, keys: &mut KeyArray<K>) -> io::Result<Option<ChangeEvent<K>>>
+    where
+        K: Clone + PartialEq + Debug + Display + FromStr,
+    {
+        if !self.take_changed() {
+            return Ok(None);
+        }
+        match reload_from(&self.path, keys) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn parse_and_reload() {
+        assert!(parse_key_file::<String>("# nothing\n\n").is_err());
+        assert!(parse_key_file::<u8>("1\nx\n").is_err());
+
+        let dir = std::env::temp_dir().join(format!("keyarray-watch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("levels");
+        fs::write(&path, "10\n20\n").unwrap();
+        let mut ka = KeyArray::new_with([5u8, 20], 1);
+        // 20 survives and stays current: no event
+        assert_eq!(reload_from(&path, &mut ka).unwrap(), None);
+        assert_eq!((ka.keys(), ka.current_index()), (&[10, 20][..], 1));
+
+        fs::write(&path, "# levels\n* 10\n30\n").unwrap();
+        let ev = reload_from(&path, &mut ka).unwrap().unwrap();
+        assert_eq!((ev.previous, ev.current, ev.key), (1, 0, 10));
+        assert_eq!(ev.reason.unwrap().as_str(), REASON);
+    }
+
+    #[test]
+    fn watcher_coalesces_writes() {
+        let dir = std::env::temp_dir().join(format!("keyarray-watch-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("modes");
+        fs::write(&path, "On\nOff\n").unwrap();
+        let watcher = KeyFileWatcher::new(&path).unwrap();
+        let mut ka = KeyArray::new(["On".to_string(), "Off".to_string()]);
+
+        fs::write(&path, "On\nOff\nAuto\n").unwrap();
+        fs::write(&path, "On\nOff\n* Auto\n").unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let ev = loop {
+            if let Some(ev) = watcher.poll(&mut ka).unwrap() {
+                break ev;
+            }
+            assert!(Instant::now() < deadline, "no change notification");
+            thread::sleep(Duration::from_millis(20));
+        };
+        assert_eq!(ev.key, "Auto");
+        assert_eq!(ka.len(), 3);
+    }
+}